@@ -0,0 +1,208 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::thread;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{external, history, paths, settings};
+
+pub const APP_LOG_FILE: &str = "app.log";
+const LOG_LEVEL_KEY: &str = "logLevel";
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+const MAX_LOG_BYTES: u64 = 4 * 1024 * 1024;
+// app.log.1 is the most recent rotated file, app.log.5 the oldest
+const GENERATIONS: usize = 5;
+const DEFAULT_LINES: usize = 200;
+
+// One JSON object per line, like the audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogLine {
+    pub at: u64,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+}
+
+struct Sink {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl Sink {
+    fn open(dir: &Path) -> std::io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(APP_LOG_FILE))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            size: file.metadata()?.len(),
+            file,
+        })
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let _ = fs::remove_file(generation(&self.dir, GENERATIONS));
+        for n in (1..GENERATIONS).rev() {
+            let _ = fs::rename(generation(&self.dir, n), generation(&self.dir, n + 1));
+        }
+        fs::rename(self.dir.join(APP_LOG_FILE), generation(&self.dir, 1))?;
+        *self = Self::open(&self.dir)?;
+        Ok(())
+    }
+}
+
+// Until init runs there is nowhere to write, and records are dropped
+struct AppLogger {
+    sink: Mutex<Option<Sink>>,
+}
+
+static LOGGER: AppLogger = AppLogger {
+    sink: Mutex::new(None),
+};
+
+fn generation(dir: &Path, n: usize) -> PathBuf {
+    dir.join(format!("{}.{}", APP_LOG_FILE, n))
+}
+
+impl Log for AppLogger {
+    // Dependencies only get through with warnings and errors
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+            && (metadata.target().starts_with(env!("CARGO_CRATE_NAME"))
+                || metadata.level() <= Level::Warn)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = LogLine {
+            at: history::now_ms(),
+            level: record.level().to_string(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        };
+        let Ok(mut json) = serde_json::to_string(&line) else {
+            return;
+        };
+        json.push('\n');
+        let Ok(mut sink) = self.sink.lock() else {
+            return;
+        };
+        let Some(sink) = sink.as_mut() else {
+            return;
+        };
+        if sink.size + json.len() as u64 > MAX_LOG_BYTES && sink.rotate().is_err() {
+            return;
+        }
+        if sink.file.write_all(json.as_bytes()).is_ok() {
+            sink.size += json.len() as u64;
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(Some(sink)) = self.sink.lock().as_deref_mut() {
+            let _ = sink.file.flush();
+        }
+    }
+}
+
+fn stored_level(app: &AppHandle) -> LevelFilter {
+    settings::get::<String>(app, LOG_LEVEL_KEY)
+        .and_then(|level| LevelFilter::from_str(&level).ok())
+        .unwrap_or(DEFAULT_LEVEL)
+}
+
+// Called first in setup. Panics are logged before the default hook runs, so a background
+// thread that dies leaves a trace.
+pub fn init(app: &AppHandle) {
+    if let Ok(dir) = paths::log_dir(app) {
+        if let (Ok(sink), Ok(mut slot)) = (Sink::open(&dir), LOGGER.sink.lock()) {
+            *slot = Some(sink);
+        }
+    }
+    if log::set_logger(&LOGGER).is_err() {
+        return;
+    }
+    log::set_max_level(stored_level(app));
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let thread = thread::current();
+        log::error!(
+            "Panic in thread {}: {}",
+            thread.name().unwrap_or("unnamed"),
+            info
+        );
+        previous(info);
+    }));
+    log::info!("sh_runner {} started", app.package_info().version);
+}
+
+fn read_lines(path: &Path) -> Vec<String> {
+    File::open(path)
+        .map(|file| BufReader::new(file).lines().map_while(Result::ok).collect())
+        .unwrap_or_default()
+}
+
+// The last `lines` records (default 200) at `level_filter` or more severe, oldest first
+#[tauri::command]
+pub fn get_app_logs(
+    app: AppHandle,
+    lines: Option<usize>,
+    level_filter: Option<String>,
+) -> Result<Vec<LogLine>, String> {
+    let limit = lines.unwrap_or(DEFAULT_LINES);
+    let min = match level_filter.as_deref() {
+        Some(level) => {
+            LevelFilter::from_str(level).map_err(|_| format!("Unknown log level: {}", level))?
+        }
+        None => LevelFilter::Trace,
+    };
+    LOGGER.flush();
+    let dir = paths::log_dir(&app)?;
+    let files = std::iter::once(dir.join(APP_LOG_FILE))
+        .chain((1..=GENERATIONS).map(|n| generation(&dir, n)));
+    let mut found = Vec::new();
+    for file in files {
+        for line in read_lines(&file).iter().rev() {
+            let Ok(entry) = serde_json::from_str::<LogLine>(line) else {
+                continue;
+            };
+            if Level::from_str(&entry.level).is_ok_and(|level| level <= min) {
+                found.push(entry);
+                if found.len() >= limit {
+                    found.reverse();
+                    return Ok(found);
+                }
+            }
+        }
+    }
+    found.reverse();
+    Ok(found)
+}
+
+#[tauri::command]
+pub fn open_app_log_folder(app: AppHandle) -> Result<(), String> {
+    let dir = paths::log_dir(&app)?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    external::open_with_default_app(&dir.to_string_lossy())
+}
+
+// error, warn, info, debug, trace or off
+#[tauri::command]
+pub fn set_log_level(app: AppHandle, level: String) -> Result<(), String> {
+    let filter =
+        LevelFilter::from_str(&level).map_err(|_| format!("Unknown log level: {}", level))?;
+    settings::set(&app, LOG_LEVEL_KEY, &filter.to_string().to_lowercase())?;
+    log::set_max_level(filter);
+    Ok(())
+}
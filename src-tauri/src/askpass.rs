@@ -0,0 +1,71 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::sandbox;
+
+// sudo runs this with its prompt as $1 and reads the password from stdout, so the
+// password goes straight from the dialog to sudo and never passes through the app
+const HELPER: &str = r#"#!/bin/sh
+prompt="${1:-Password:}"
+if command -v osascript >/dev/null 2>&1; then
+    exec osascript \
+        -e 'on run argv' \
+        -e 'text returned of (display dialog (item 1 of argv) with title "Scripts Runner" default answer "" with hidden answer with icon caution)' \
+        -e 'end run' "$prompt"
+elif command -v zenity >/dev/null 2>&1; then
+    exec zenity --password --title="Scripts Runner: $prompt"
+elif command -v kdialog >/dev/null 2>&1; then
+    exec kdialog --title "Scripts Runner" --password "$prompt"
+fi
+echo "No graphical password prompt available" >&2
+exit 1
+"#;
+
+const PROMPT_TOOLS: &[&str] = &["osascript", "zenity", "kdialog"];
+
+// The helper script for one run, removed again when dropped
+pub struct Askpass {
+    dir: PathBuf,
+    pub helper: PathBuf,
+}
+
+impl Drop for Askpass {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+pub fn has_prompt_tool() -> bool {
+    PROMPT_TOOLS
+        .iter()
+        .any(|tool| sandbox::find_program(tool).is_some())
+}
+
+#[cfg(unix)]
+pub fn prepare(run_id: &str) -> Result<Askpass, String> {
+    use std::io::Write;
+    use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+
+    let dir = std::env::temp_dir().join(format!("sh-runner-askpass-{}", run_id));
+    fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&dir)
+        .map_err(|e| format!("Failed to create askpass directory: {}", e))?;
+    let askpass = Askpass {
+        helper: dir.join("askpass.sh"),
+        dir,
+    };
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o700)
+        .open(&askpass.helper)
+        .and_then(|mut file| file.write_all(HELPER.as_bytes()))
+        .map_err(|e| format!("Failed to write askpass helper: {}", e))?;
+    Ok(askpass)
+}
+
+#[cfg(not(unix))]
+pub fn prepare(_run_id: &str) -> Result<Askpass, String> {
+    Err("GUI sudo prompts are not supported on this platform".to_string())
+}
@@ -0,0 +1,316 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::AppHandle;
+
+use crate::{history, index, paths, settings};
+
+pub const AUDIT_FILE: &str = "audit.log";
+// `prev` of the first chained line
+const GENESIS_HASH: &str = "0";
+// Hash of the last line, kept outside the log so dropping lines off its end is noticed too
+const HEAD_KEY: &str = "auditLogHead";
+
+// Hash of the last line written, so appends don't have to re-read the file; None until the
+// first append loads it
+static LAST_HASH: Mutex<Option<String>> = Mutex::new(None);
+
+// What the hash covers: everything on the line except the hash itself
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Chained<'a> {
+    at: u64,
+    action: &'a str,
+    details: &'a Value,
+    prev: &'a str,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AuditLine {
+    at: u64,
+    action: String,
+    details: Value,
+    // Missing on lines written before the log was chained
+    #[serde(default)]
+    prev: Option<String>,
+    #[serde(default)]
+    hash: Option<String>,
+}
+
+impl AuditLine {
+    fn expected_hash(&self, prev: &str) -> Result<String, String> {
+        let chained = serde_json::to_string(&Chained {
+            at: self.at,
+            action: &self.action,
+            details: &self.details,
+            prev,
+        })
+        .map_err(|e| e.to_string())?;
+        Ok(index::hash_bytes(chained.as_bytes()))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditVerification {
+    pub valid: bool,
+    pub lines: usize,
+    // Leading lines from before chaining, which can't be checked
+    pub unchained: usize,
+    // 1-based line number of the first line that doesn't fit the chain
+    pub first_invalid_line: Option<usize>,
+    pub reason: Option<String>,
+}
+
+// Both ends are inclusive epoch milliseconds; a missing end is open
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditRange {
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+}
+
+impl AuditRange {
+    fn contains(&self, raw: &str) -> bool {
+        serde_json::from_str::<AuditLine>(raw).is_ok_and(|line| {
+            self.from.is_none_or(|from| line.at >= from) && self.to.is_none_or(|to| line.at <= to)
+        })
+    }
+}
+
+fn audit_file(app: &AppHandle) -> Result<PathBuf, String> {
+    paths::data_dir(app).map(|dir| dir.join(AUDIT_FILE))
+}
+
+fn read_lines(app: &AppHandle) -> Result<Vec<String>, String> {
+    match fs::read_to_string(audit_file(app)?) {
+        Ok(content) => Ok(content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(str::to_string)
+            .collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn last_hash(app: &AppHandle) -> Result<String, String> {
+    let lines = read_lines(app)?;
+    Ok(lines
+        .last()
+        .and_then(|line| serde_json::from_str::<AuditLine>(line).ok())
+        .and_then(|line| line.hash)
+        .unwrap_or_else(|| GENESIS_HASH.to_string()))
+}
+
+// The line to append after the one hashed `prev`, without its newline, and its own hash
+fn chain_line(
+    at: u64,
+    action: &str,
+    details: &Value,
+    prev: &str,
+) -> Result<(String, String), String> {
+    let chained = Chained {
+        at,
+        action,
+        details,
+        prev,
+    };
+    let hash = index::hash_bytes(
+        serde_json::to_string(&chained)
+            .map_err(|e| e.to_string())?
+            .as_bytes(),
+    );
+    let mut line = serde_json::to_value(&chained).map_err(|e| e.to_string())?;
+    line["hash"] = Value::String(hash.clone());
+    let line = serde_json::to_string(&line).map_err(|e| e.to_string())?;
+    Ok((line, hash))
+}
+
+// Walks the chain from the top; `head` is the hash of the last line written, when known
+fn verify(lines: &[String], head: Option<&str>) -> Result<AuditVerification, String> {
+    let mut report = AuditVerification {
+        lines: lines.len(),
+        ..AuditVerification::default()
+    };
+    let mut prev: Option<String> = None;
+    for (index, raw) in lines.iter().enumerate() {
+        let problem = match serde_json::from_str::<AuditLine>(raw) {
+            Err(_) => Some("not a valid audit entry"),
+            Ok(AuditLine {
+                prev: None,
+                hash: None,
+                ..
+            }) if prev.is_none() => {
+                report.unchained += 1;
+                None
+            }
+            Ok(line) => match (&line.prev, &line.hash) {
+                (Some(line_prev), Some(hash)) => {
+                    if *line_prev != prev.as_deref().unwrap_or(GENESIS_HASH) {
+                        Some("does not follow the previous line")
+                    } else if line.expected_hash(line_prev)? != *hash {
+                        Some("contents do not match the hash")
+                    } else {
+                        prev = Some(hash.clone());
+                        None
+                    }
+                }
+                _ => Some("missing hash after the chain started"),
+            },
+        };
+        if let Some(reason) = problem {
+            report.first_invalid_line = Some(index + 1);
+            report.reason = Some(reason.to_string());
+            return Ok(report);
+        }
+    }
+    if head.is_some_and(|head| prev.as_deref() != Some(head)) {
+        report.reason = Some("the log ends before the last entry written".to_string());
+        return Ok(report);
+    }
+    report.valid = true;
+    Ok(report)
+}
+
+// The OS account the app runs as
+pub fn current_user() -> Option<String> {
+    ["USER", "USERNAME", "LOGNAME"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+}
+
+// Changes the backend makes on the user's behalf and every run, one JSON object per line in the
+// app data dir. Never pruned; each line's hash covers the previous one, so edits and truncation
+// show up in verify_audit_log.
+pub fn record(app: &AppHandle, action: &str, details: Value) -> Result<(), String> {
+    let dir = paths::data_dir(app)?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let mut last = LAST_HASH.lock().map_err(|e| e.to_string())?;
+    let prev = match last.take() {
+        Some(hash) => hash,
+        None => last_hash(app)?,
+    };
+    let (mut line, hash) = chain_line(history::now_ms(), action, &details, &prev)?;
+    line.push('\n');
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(AUDIT_FILE))
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| e.to_string());
+    // On failure the next append re-reads the file instead of chaining onto a line never written
+    if written.is_ok() {
+        let _ = settings::set(app, HEAD_KEY, &hash);
+        *last = Some(hash);
+    }
+    written
+}
+
+#[tauri::command]
+pub fn verify_audit_log(app: AppHandle) -> Result<AuditVerification, String> {
+    let _guard = LAST_HASH.lock().map_err(|e| e.to_string())?;
+    let lines = read_lines(&app)?;
+    // Unknown after a settings reset, in which case only the chain itself can be checked
+    let head: Option<String> = settings::get(&app, HEAD_KEY);
+    verify(&lines, head.as_deref())
+}
+
+// Copies the lines in `range` as written, hashes included; returns how many were exported
+#[tauri::command]
+pub fn export_audit_log(
+    app: AppHandle,
+    range: Option<AuditRange>,
+    path: String,
+) -> Result<usize, String> {
+    let range = range.unwrap_or_default();
+    let lines: Vec<String> = read_lines(&app)?
+        .into_iter()
+        .filter(|raw| range.contains(raw))
+        .collect();
+    let dest = settings::expand_path(&path);
+    if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let mut content = lines.join("\n");
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    fs::write(&dest, content).map_err(|e| format!("Failed to write {}: {}", dest.display(), e))?;
+    Ok(lines.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // Three chained lines written at 100, 200 and 300, and the hash of the last
+    fn chain() -> (Vec<String>, String) {
+        let mut lines = Vec::new();
+        let mut prev = GENESIS_HASH.to_string();
+        for (at, run) in [(100, "a"), (200, "b"), (300, "c")] {
+            let (line, hash) =
+                chain_line(at, "run-started", &json!({ "runId": run }), &prev).unwrap();
+            lines.push(line);
+            prev = hash;
+        }
+        (lines, prev)
+    }
+
+    #[test]
+    fn a_clean_chain_verifies() {
+        let (lines, head) = chain();
+        let report = verify(&lines, Some(&head)).unwrap();
+        assert!(report.valid);
+        assert_eq!(report.lines, 3);
+        assert_eq!(report.first_invalid_line, None);
+        // Without a known head only the chain is checked
+        assert!(verify(&lines, None).unwrap().valid);
+    }
+
+    #[test]
+    fn an_edited_line_is_reported() {
+        let (mut lines, head) = chain();
+        lines[1] = lines[1].replace("\"b\"", "\"x\"");
+        let report = verify(&lines, Some(&head)).unwrap();
+        assert!(!report.valid);
+        assert_eq!(report.first_invalid_line, Some(2));
+        assert_eq!(
+            report.reason.as_deref(),
+            Some("contents do not match the hash")
+        );
+    }
+
+    #[test]
+    fn a_dropped_last_line_is_reported() {
+        let (mut lines, head) = chain();
+        lines.pop();
+        let report = verify(&lines, Some(&head)).unwrap();
+        assert!(!report.valid);
+        assert_eq!(report.first_invalid_line, None);
+        assert_eq!(
+            report.reason.as_deref(),
+            Some("the log ends before the last entry written")
+        );
+    }
+
+    #[test]
+    fn export_ranges_include_both_ends() {
+        let (lines, _) = chain();
+        let count = |from, to| {
+            let range = AuditRange { from, to };
+            lines.iter().filter(|raw| range.contains(raw)).count()
+        };
+        assert_eq!(count(Some(100), Some(200)), 2);
+        assert_eq!(count(Some(200), Some(200)), 1);
+        assert_eq!(count(Some(201), None), 1);
+        assert_eq!(count(None, Some(99)), 0);
+        assert_eq!(count(None, None), 3);
+    }
+}
@@ -0,0 +1,55 @@
+use tauri::AppHandle;
+
+#[cfg(desktop)]
+use tauri_plugin_autostart::ManagerExt;
+
+use crate::tray;
+
+// Passed to the app by the login item so launches at login stay in the tray
+pub const AUTOSTART_ARG: &str = "--autostart";
+
+pub fn launched_at_login() -> bool {
+    std::env::args().any(|arg| arg == AUTOSTART_ARG)
+}
+
+// Probes the OS (LaunchAgent, registry entry, .desktop file) rather than a stored flag
+#[cfg(desktop)]
+pub fn is_enabled(app: &AppHandle) -> bool {
+    app.autolaunch().is_enabled().unwrap_or(false)
+}
+
+#[cfg(mobile)]
+pub fn is_enabled(_app: &AppHandle) -> bool {
+    false
+}
+
+#[cfg(desktop)]
+fn apply(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    let launcher = app.autolaunch();
+    // Disabling removes the login item the plugin created
+    let result = if enabled {
+        launcher.enable()
+    } else {
+        launcher.disable()
+    };
+    result.map_err(|e| e.to_string())
+}
+
+#[cfg(mobile)]
+fn apply(_app: &AppHandle, _enabled: bool) -> Result<(), String> {
+    Err("Launch at login is not supported on this platform".to_string())
+}
+
+#[tauri::command]
+pub fn get_autostart(app: AppHandle) -> Result<bool, String> {
+    Ok(is_enabled(&app))
+}
+
+#[tauri::command]
+pub fn set_autostart(app: AppHandle, enabled: bool) -> Result<bool, String> {
+    let result = apply(&app, enabled);
+    // The tray checkbox always reflects what the OS reports, even after a failure
+    tray::rebuild_menu(&app).map_err(|e| e.to_string())?;
+    result?;
+    Ok(is_enabled(&app))
+}
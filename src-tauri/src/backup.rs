@@ -0,0 +1,576 @@
+use std::collections::HashSet;
+use std::fs::{self, File, Metadata};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{scripts, settings};
+
+const BACKUP_EXCLUDES_KEY: &str = "backupExcludes";
+const DEFAULT_EXCLUDES: &[&str] = &[".git", "node_modules", ".DS_Store"];
+const PROGRESS_EVERY: usize = 25;
+
+#[derive(Default)]
+pub struct BackupState {
+    running: AtomicBool,
+    cancel: AtomicBool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupResult {
+    pub archive_path: String,
+    pub file_count: usize,
+    pub total_bytes: u64,
+    pub archive_bytes: u64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BackupProgress {
+    done: usize,
+    total: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveFormat {
+    Zip,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if name.ends_with(".zip") {
+            Ok(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Ok(Self::TarGz)
+        } else {
+            Err("Archive must end in .zip, .tar.gz or .tgz".to_string())
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum ItemKind {
+    Dir,
+    File,
+    Symlink,
+}
+
+struct BackupItem {
+    source: PathBuf,
+    name: String,
+    kind: ItemKind,
+    size: u64,
+    mode: u32,
+}
+
+#[cfg(unix)]
+fn file_mode(meta: &Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o7777
+}
+
+#[cfg(not(unix))]
+fn file_mode(meta: &Metadata) -> u32 {
+    if meta.is_dir() {
+        0o755
+    } else {
+        0o644
+    }
+}
+
+fn is_excluded(relative: &str, excludes: &[Pattern]) -> bool {
+    let file_name = relative.rsplit('/').next().unwrap_or(relative);
+    excludes
+        .iter()
+        .any(|p| p.matches(relative) || p.matches(file_name))
+}
+
+fn walk(
+    dir: &Path,
+    prefix: &str,
+    relative: &str,
+    excludes: &[Pattern],
+    items: &mut Vec<BackupItem>,
+) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries: Vec<_> = entries.flatten().collect();
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let relative = if relative.is_empty() {
+            file_name
+        } else {
+            format!("{}/{}", relative, file_name)
+        };
+        if is_excluded(&relative, excludes) {
+            continue;
+        }
+        // symlink_metadata so links are archived as links instead of followed
+        let Ok(meta) = fs::symlink_metadata(entry.path()) else {
+            continue;
+        };
+        let kind = if meta.file_type().is_symlink() {
+            ItemKind::Symlink
+        } else if meta.is_dir() {
+            ItemKind::Dir
+        } else {
+            ItemKind::File
+        };
+        let is_dir = kind == ItemKind::Dir;
+        items.push(BackupItem {
+            source: entry.path(),
+            name: format!("{}/{}", prefix, relative),
+            size: if kind == ItemKind::File {
+                meta.len()
+            } else {
+                0
+            },
+            mode: file_mode(&meta),
+            kind,
+        });
+        if is_dir {
+            walk(&entry.path(), prefix, &relative, excludes, items);
+        }
+    }
+}
+
+// Each root becomes a top-level folder in the archive, named after the root
+fn collect_items(roots: &[PathBuf], excludes: &[Pattern]) -> Vec<BackupItem> {
+    let mut used = HashSet::new();
+    let mut items = Vec::new();
+    for root in roots.iter().filter(|r| r.is_dir()) {
+        let base = root
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "scripts".to_string());
+        let mut prefix = base.clone();
+        let mut suffix = 2;
+        while !used.insert(prefix.clone()) {
+            prefix = format!("{}-{}", base, suffix);
+            suffix += 1;
+        }
+        walk(root, &prefix, "", excludes, &mut items);
+    }
+    items
+}
+
+fn write_zip(
+    dest: &Path,
+    items: &[BackupItem],
+    on_item: &mut dyn FnMut(usize) -> Result<(), String>,
+) -> Result<(), String> {
+    let file = File::create(dest).map_err(|e| e.to_string())?;
+    let mut zip = ZipWriter::new(file);
+    append_zip(&mut zip, items, on_item)?;
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn append_zip(
+    zip: &mut ZipWriter<File>,
+    items: &[BackupItem],
+    on_item: &mut dyn FnMut(usize) -> Result<(), String>,
+) -> Result<(), String> {
+    let base = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (index, item) in items.iter().enumerate() {
+        let options = base.unix_permissions(item.mode);
+        match item.kind {
+            ItemKind::Dir => zip
+                .add_directory(format!("{}/", item.name), options)
+                .map_err(|e| e.to_string())?,
+            ItemKind::Symlink => {
+                let target = fs::read_link(&item.source).map_err(|e| e.to_string())?;
+                zip.add_symlink(&item.name, target.to_string_lossy(), options)
+                    .map_err(|e| e.to_string())?;
+            }
+            ItemKind::File => {
+                zip.start_file(&item.name, options)
+                    .map_err(|e| e.to_string())?;
+                let mut source = File::open(&item.source).map_err(|e| e.to_string())?;
+                io::copy(&mut source, zip).map_err(|e| e.to_string())?;
+            }
+        }
+        on_item(index + 1)?;
+    }
+    Ok(())
+}
+
+// Adds `root` to an archive under `prefix`, leaving out the default excludes; returns the
+// number of files added
+pub(crate) fn append_folder(
+    zip: &mut ZipWriter<File>,
+    root: &Path,
+    prefix: &str,
+) -> Result<usize, String> {
+    let excludes: Vec<Pattern> = DEFAULT_EXCLUDES
+        .iter()
+        .filter_map(|p| Pattern::new(p).ok())
+        .collect();
+    let mut items = Vec::new();
+    walk(root, prefix, "", &excludes, &mut items);
+    append_zip(zip, &items, &mut |_| Ok(()))?;
+    Ok(items.iter().filter(|i| i.kind != ItemKind::Dir).count())
+}
+
+fn write_tar_gz(
+    dest: &Path,
+    items: &[BackupItem],
+    on_item: &mut dyn FnMut(usize) -> Result<(), String>,
+) -> Result<(), String> {
+    let file = File::create(dest).map_err(|e| e.to_string())?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    builder.follow_symlinks(false);
+    for (index, item) in items.iter().enumerate() {
+        builder
+            .append_path_with_name(&item.source, &item.name)
+            .map_err(|e| format!("{}: {}", item.source.display(), e))?;
+        on_item(index + 1)?;
+    }
+    builder
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn run_backup(app: &AppHandle, dest: &Path, excludes: &[Pattern]) -> Result<BackupResult, String> {
+    let format = ArchiveFormat::from_path(dest)?;
+    let items = collect_items(&settings::scripts_roots(app), excludes);
+    let total = items.iter().filter(|i| i.kind != ItemKind::Dir).count();
+    let total_bytes = items.iter().map(|i| i.size).sum();
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    let state = app.state::<BackupState>();
+    let mut done = 0;
+    let mut on_item = |index: usize| {
+        if state.cancel.load(Ordering::Relaxed) {
+            return Err("Backup cancelled".to_string());
+        }
+        if items[index - 1].kind != ItemKind::Dir {
+            done += 1;
+            if done % PROGRESS_EVERY == 0 || done == total {
+                let _ = app.emit("backup-progress", BackupProgress { done, total });
+            }
+        }
+        Ok(())
+    };
+    let written = match format {
+        ArchiveFormat::Zip => write_zip(dest, &items, &mut on_item),
+        ArchiveFormat::TarGz => write_tar_gz(dest, &items, &mut on_item),
+    };
+    if let Err(e) = written {
+        // Don't leave a truncated archive behind
+        let _ = fs::remove_file(dest);
+        return Err(e);
+    }
+
+    Ok(BackupResult {
+        archive_path: dest.to_string_lossy().to_string(),
+        file_count: total,
+        total_bytes,
+        archive_bytes: fs::metadata(dest).map(|m| m.len()).unwrap_or(0),
+    })
+}
+
+#[tauri::command]
+pub async fn backup_scripts(
+    app: AppHandle,
+    dest_path: String,
+    exclude: Option<Vec<String>>,
+) -> Result<BackupResult, String> {
+    let patterns = exclude
+        .or_else(|| settings::get(&app, BACKUP_EXCLUDES_KEY))
+        .unwrap_or_else(|| DEFAULT_EXCLUDES.iter().map(|s| s.to_string()).collect());
+    let excludes = patterns
+        .iter()
+        .map(|p| Pattern::new(p).map_err(|e| format!("Invalid pattern {}: {}", p, e)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let dest = settings::expand_path(&dest_path);
+
+    let state = app.state::<BackupState>();
+    if state.running.swap(true, Ordering::SeqCst) {
+        return Err("A backup is already running".to_string());
+    }
+    state.cancel.store(false, Ordering::SeqCst);
+    let worker = app.clone();
+    let result =
+        tauri::async_runtime::spawn_blocking(move || run_backup(&worker, &dest, &excludes))
+            .await
+            .map_err(|e| e.to_string());
+    state.running.store(false, Ordering::SeqCst);
+    result?
+}
+
+#[tauri::command]
+pub fn cancel_backup(app: AppHandle) {
+    app.state::<BackupState>()
+        .cancel
+        .store(true, Ordering::SeqCst);
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictPolicy {
+    Skip,
+    Overwrite,
+    Rename,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenamedFile {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub created: Vec<String>,
+    pub skipped: Vec<String>,
+    pub renamed: Vec<RenamedFile>,
+    pub rejected: Vec<String>,
+}
+
+fn renamed_target(target: &Path) -> PathBuf {
+    let stem = target
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = target
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    let mut counter = 2;
+    loop {
+        let candidate = target.with_file_name(format!("{}-{}{}", stem, counter, extension));
+        if fs::symlink_metadata(&candidate).is_err() {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o777))
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(not(unix))]
+fn create_symlink(_target: &Path, _link: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "symlinks are not supported",
+    ))
+}
+
+enum ImportKind<'a> {
+    Dir,
+    File(&'a mut dyn Read),
+    Symlink(PathBuf),
+}
+
+struct Importer<'a> {
+    dest_root: &'a Path,
+    policy: ConflictPolicy,
+    summary: ImportSummary,
+}
+
+impl Importer<'_> {
+    fn place(&mut self, name: &Path, kind: ImportKind, mode: Option<u32>) -> Result<(), String> {
+        let display = name.to_string_lossy().to_string();
+        let Some(relative) = scripts::safe_relative(name) else {
+            self.summary.rejected.push(display);
+            return Ok(());
+        };
+        let mut target = self.dest_root.join(&relative);
+        if let ImportKind::Dir = kind {
+            return fs::create_dir_all(&target).map_err(|e| e.to_string());
+        }
+        if let ImportKind::Symlink(link) = &kind {
+            // A link may only point at something inside the archive's own tree
+            let resolved = relative.parent().unwrap_or(Path::new("")).join(link);
+            if link.is_absolute() || scripts::safe_relative(&resolved).is_none() {
+                self.summary.rejected.push(display);
+                return Ok(());
+            }
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        if fs::symlink_metadata(&target).is_ok() {
+            match self.policy {
+                ConflictPolicy::Skip => {
+                    self.summary
+                        .skipped
+                        .push(target.to_string_lossy().to_string());
+                    return Ok(());
+                }
+                ConflictPolicy::Overwrite => {
+                    let _ = fs::remove_file(&target);
+                }
+                ConflictPolicy::Rename => {
+                    let renamed = renamed_target(&target);
+                    self.summary.renamed.push(RenamedFile {
+                        from: target.to_string_lossy().to_string(),
+                        to: renamed.to_string_lossy().to_string(),
+                    });
+                    target = renamed;
+                }
+            }
+        }
+        match kind {
+            ImportKind::File(source) => {
+                let mut file = File::create(&target).map_err(|e| e.to_string())?;
+                io::copy(source, &mut file).map_err(|e| e.to_string())?;
+                if let Some(mode) = mode.filter(|m| m & 0o777 != 0) {
+                    set_mode(&target, mode).map_err(|e| e.to_string())?;
+                }
+            }
+            ImportKind::Symlink(link) => {
+                create_symlink(&link, &target).map_err(|e| e.to_string())?;
+            }
+            ImportKind::Dir => {}
+        }
+        self.summary
+            .created
+            .push(target.to_string_lossy().to_string());
+        Ok(())
+    }
+}
+
+fn import_zip(archive: &Path, importer: &mut Importer) -> Result<(), String> {
+    import_zip_under(archive, "", importer)
+}
+
+// Only entries below `prefix`, placed relative to it; an empty prefix takes everything
+fn import_zip_under(archive: &Path, prefix: &str, importer: &mut Importer) -> Result<(), String> {
+    let file = File::open(archive).map_err(|e| e.to_string())?;
+    let mut zip = ZipArchive::new(file).map_err(|e| e.to_string())?;
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index).map_err(|e| e.to_string())?;
+        let Ok(name) = Path::new(entry.name())
+            .strip_prefix(prefix)
+            .map(Path::to_path_buf)
+        else {
+            continue;
+        };
+        if name.as_os_str().is_empty() {
+            continue;
+        }
+        let mode = entry.unix_mode();
+        if entry.is_dir() {
+            importer.place(&name, ImportKind::Dir, mode)?;
+        } else if entry.is_symlink() {
+            let mut link = String::new();
+            entry.read_to_string(&mut link).map_err(|e| e.to_string())?;
+            importer.place(&name, ImportKind::Symlink(PathBuf::from(link)), mode)?;
+        } else {
+            importer.place(&name, ImportKind::File(&mut entry), mode)?;
+        }
+    }
+    Ok(())
+}
+
+fn import_tar_gz(archive: &Path, importer: &mut Importer) -> Result<(), String> {
+    let file = File::open(archive).map_err(|e| e.to_string())?;
+    let mut tar = tar::Archive::new(GzDecoder::new(file));
+    for entry in tar.entries().map_err(|e| e.to_string())? {
+        let mut entry = entry.map_err(|e| e.to_string())?;
+        let name = entry.path().map_err(|e| e.to_string())?.into_owned();
+        let mode = entry.header().mode().ok();
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_dir() {
+            importer.place(&name, ImportKind::Dir, mode)?;
+        } else if entry_type.is_symlink() {
+            let link = entry
+                .link_name()
+                .map_err(|e| e.to_string())?
+                .map(|l| l.into_owned())
+                .unwrap_or_default();
+            importer.place(&name, ImportKind::Symlink(link), mode)?;
+        } else if entry_type.is_file() {
+            importer.place(&name, ImportKind::File(&mut entry), mode)?;
+        } else {
+            importer
+                .summary
+                .rejected
+                .push(name.to_string_lossy().to_string());
+        }
+    }
+    Ok(())
+}
+
+// Extracts the part of a zip below `prefix` into `dest_root`
+pub(crate) fn import_zip_folder(
+    archive: &Path,
+    prefix: &str,
+    dest_root: &Path,
+    policy: ConflictPolicy,
+) -> Result<ImportSummary, String> {
+    fs::create_dir_all(dest_root).map_err(|e| e.to_string())?;
+    let mut importer = Importer {
+        dest_root,
+        policy,
+        summary: ImportSummary::default(),
+    };
+    import_zip_under(archive, prefix, &mut importer)?;
+    Ok(importer.summary)
+}
+
+#[tauri::command]
+pub async fn import_scripts(
+    app: AppHandle,
+    archive_path: String,
+    dest_root: String,
+    conflict_policy: ConflictPolicy,
+) -> Result<ImportSummary, String> {
+    let archive = settings::expand_path(&archive_path);
+    let dest = settings::expand_path(&dest_root);
+    let format = ArchiveFormat::from_path(&archive)?;
+    let summary = tauri::async_runtime::spawn_blocking(move || {
+        fs::create_dir_all(&dest).map_err(|e| e.to_string())?;
+        let mut importer = Importer {
+            dest_root: &dest,
+            policy: conflict_policy,
+            summary: ImportSummary::default(),
+        };
+        match format {
+            ArchiveFormat::Zip => import_zip(&archive, &mut importer)?,
+            ArchiveFormat::TarGz => import_tar_gz(&archive, &mut importer)?,
+        }
+        Ok::<_, String>(importer.summary)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    scripts::notify_scripts_changed(&app, summary.created.clone());
+    Ok(summary)
+}
@@ -0,0 +1,349 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{LazyLock, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::runner::{self, ActiveRuns, RunOptions};
+use crate::{collections, danger, notifications, readonly, settings, tags, triggers};
+
+// Error of a member that was still waiting for a slot when its batch was cancelled
+pub const BATCH_CANCELLED_ERROR: &str = "batch-cancelled";
+
+// Ids of batches that are still running, with whether they were cancelled
+static BATCHES: LazyLock<Mutex<HashMap<String, bool>>> = LazyLock::new(Mutex::default);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchMember {
+    pub path: String,
+    pub name: String,
+    // None when the run never started
+    pub run_id: Option<String>,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    pub cancelled: bool,
+    // Why the run didn't start, e.g. a missing script or the batch being cancelled first
+    pub error: Option<String>,
+}
+
+// A script left out before the batch started
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedScript {
+    pub path: String,
+    pub name: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchSummary {
+    pub batch_id: String,
+    // Every member succeeded and nothing was skipped
+    pub success: bool,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+    pub duration_ms: u64,
+    // In the order the scripts were given
+    pub members: Vec<BatchMember>,
+    pub skipped: Vec<SkippedScript>,
+}
+
+pub fn is_cancelled(batch_id: &str) -> bool {
+    BATCHES
+        .lock()
+        .ok()
+        .and_then(|batches| batches.get(batch_id).copied())
+        .unwrap_or(false)
+}
+
+fn name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn not_started(path: String, error: String) -> BatchMember {
+    BatchMember {
+        name: name(&path),
+        path,
+        run_id: None,
+        success: false,
+        exit_code: None,
+        duration_ms: 0,
+        cancelled: error == BATCH_CANCELLED_ERROR,
+        error: Some(error),
+    }
+}
+
+fn run_member(
+    app: &AppHandle,
+    batch_id: &str,
+    path: String,
+    args: Vec<String>,
+    confirmed: bool,
+) -> BatchMember {
+    let options = RunOptions {
+        args,
+        confirmed,
+        env: settings::script_env(app, &path).into_iter().collect(),
+        trigger: Some(triggers::BATCH.to_string()),
+        batch_id: Some(batch_id.to_string()),
+        ..RunOptions::default()
+    };
+    let started = Instant::now();
+    match runner::execute(app, &path, &options) {
+        Ok(result) => BatchMember {
+            name: name(&path),
+            path,
+            run_id: Some(result.run_id),
+            success: result.success,
+            exit_code: result.exit_code,
+            duration_ms: result.duration_ms,
+            cancelled: result.cancelled,
+            error: None,
+        },
+        Err(error) => BatchMember {
+            name: name(&path),
+            path,
+            run_id: None,
+            success: false,
+            exit_code: None,
+            duration_ms: started.elapsed().as_millis() as u64,
+            cancelled: error == BATCH_CANCELLED_ERROR,
+            error: Some(error),
+        },
+    }
+}
+
+fn summarize(
+    batch_id: String,
+    members: Vec<BatchMember>,
+    skipped: Vec<SkippedScript>,
+    started: Instant,
+) -> BatchSummary {
+    let cancelled = members.iter().filter(|m| m.cancelled).count();
+    let succeeded = members.iter().filter(|m| m.success).count();
+    BatchSummary {
+        batch_id,
+        success: succeeded == members.len() && skipped.is_empty(),
+        succeeded,
+        failed: members.len() - succeeded - cancelled,
+        cancelled,
+        duration_ms: started.elapsed().as_millis() as u64,
+        members,
+        skipped,
+    }
+}
+
+fn begin(script_count: usize) -> Result<String, String> {
+    let batch_id = format!("batch-{}", runner::next_run_id());
+    BATCHES
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(batch_id.clone(), false);
+    log::info!("Starting batch {} of {} script(s)", batch_id, script_count);
+    Ok(batch_id)
+}
+
+// Parallel members all start at once and wait for slots under the concurrency limit;
+// sequential ones start after the previous one exits and stop starting once cancelled.
+// None when a member's thread panicked.
+fn run_members(
+    app: &AppHandle,
+    batch_id: &str,
+    scripts: Vec<(String, Vec<String>)>,
+    parallel: bool,
+    confirmed: bool,
+) -> Option<Vec<BatchMember>> {
+    if !parallel {
+        let members = scripts
+            .into_iter()
+            .map(|(path, args)| {
+                if is_cancelled(batch_id) {
+                    not_started(path, BATCH_CANCELLED_ERROR.to_string())
+                } else {
+                    run_member(app, batch_id, path, args, confirmed)
+                }
+            })
+            .collect();
+        return Some(members);
+    }
+    let handles: Vec<_> = scripts
+        .into_iter()
+        .map(|(path, args)| {
+            let app = app.clone();
+            let id = batch_id.to_string();
+            thread::spawn(move || run_member(&app, &id, path, args, confirmed))
+        })
+        .collect();
+    handles
+        .into_iter()
+        .map(|handle| handle.join())
+        .collect::<Result<Vec<_>, _>>()
+        .ok()
+}
+
+fn finish(
+    app: &AppHandle,
+    batch_id: String,
+    members: Option<Vec<BatchMember>>,
+    skipped: Vec<SkippedScript>,
+    started: Instant,
+) -> Result<BatchSummary, String> {
+    if let Ok(mut batches) = BATCHES.lock() {
+        batches.remove(&batch_id);
+    }
+    let Some(members) = members else {
+        log::error!("A run in batch {} panicked", batch_id);
+        return Err(format!("A run in batch {} panicked", batch_id));
+    };
+    let summary = summarize(batch_id, members, skipped, started);
+    notifications::notify_batch_finished(app, &summary);
+    let _ = app.emit("batch-finished", &summary);
+    Ok(summary)
+}
+
+// Starts every script at once, each waiting for a free slot under the concurrency limit,
+// and returns the batch id right away. Runs carry the id in their history entries; a
+// batch-finished event with the summary follows the last one.
+#[tauri::command]
+pub fn run_scripts(
+    app: AppHandle,
+    script_ids: Vec<String>,
+    args_map: Option<HashMap<String, Vec<String>>>,
+) -> Result<String, String> {
+    readonly::guard()?;
+    let mut seen = HashSet::new();
+    let script_ids: Vec<String> = script_ids
+        .into_iter()
+        .filter(|id| seen.insert(id.clone()))
+        .collect();
+    if script_ids.is_empty() {
+        return Err("No scripts to run".to_string());
+    }
+    let batch_id = begin(script_ids.len())?;
+    let mut args_map = args_map.unwrap_or_default();
+    let scripts = script_ids
+        .into_iter()
+        .map(|path| {
+            let args = args_map.remove(&path).unwrap_or_default();
+            (path, args)
+        })
+        .collect();
+
+    let id = batch_id.clone();
+    thread::spawn(move || {
+        let started = Instant::now();
+        let members = run_members(&app, &id, scripts, true, false);
+        let _ = finish(&app, id, members, Vec::new(), started);
+    });
+    Ok(batch_id)
+}
+
+// Dangerous scripts are skipped unless `confirmed`, missing files always are
+async fn run_resolved(
+    app: AppHandle,
+    paths: Vec<String>,
+    parallel: bool,
+    confirmed: bool,
+) -> Result<BatchSummary, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let started = Instant::now();
+        let mut skipped = Vec::new();
+        let mut scripts = Vec::new();
+        for path in paths {
+            let reason = if !Path::new(&path).is_file() {
+                Some("The script no longer exists")
+            } else if !confirmed && danger::is_dangerous(&app, &path) {
+                Some("Marked dangerous; run again with confirmed to include it")
+            } else {
+                None
+            };
+            match reason {
+                Some(reason) => skipped.push(SkippedScript {
+                    name: name(&path),
+                    path,
+                    reason: reason.to_string(),
+                }),
+                None => scripts.push((path, Vec::new())),
+            }
+        }
+        let batch_id = begin(scripts.len())?;
+        let members = run_members(&app, &batch_id, scripts, parallel, confirmed);
+        finish(&app, batch_id, members, skipped, started)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Runs every script carrying `tag`, from its header or assigned in the app, and waits for
+// them; the summary also arrives as a batch-finished event, and cancel_batch works on it
+#[tauri::command]
+pub async fn run_tag(
+    app: AppHandle,
+    tag: String,
+    parallel: bool,
+    confirmed: Option<bool>,
+) -> Result<BatchSummary, String> {
+    readonly::guard()?;
+    let wanted = tags::normalize(&tag);
+    let mut paths: Vec<String> = tags::tags_by_path(&app)
+        .into_iter()
+        .filter(|(_, tags)| tags.contains(&wanted))
+        .map(|(path, _)| path)
+        .collect();
+    if paths.is_empty() {
+        return Err(format!("No scripts are tagged {}", tag));
+    }
+    paths.sort();
+    run_resolved(app, paths, parallel, confirmed.unwrap_or(false)).await
+}
+
+// Like run_tag, in the collection's order
+#[tauri::command]
+pub async fn run_collection(
+    app: AppHandle,
+    name: String,
+    parallel: bool,
+    confirmed: Option<bool>,
+) -> Result<BatchSummary, String> {
+    readonly::guard()?;
+    let paths = collections::scripts(&app, &name)?;
+    if paths.is_empty() {
+        return Err(format!("Collection {} is empty", name));
+    }
+    run_resolved(app, paths, parallel, confirmed.unwrap_or(false)).await
+}
+
+// Members still waiting for a slot don't start, running ones are cancelled
+#[tauri::command]
+pub fn cancel_batch(app: AppHandle, batch_id: String) -> Result<(), String> {
+    {
+        let mut batches = BATCHES.lock().map_err(|e| e.to_string())?;
+        let cancelled = batches
+            .get_mut(&batch_id)
+            .ok_or_else(|| format!("Batch {} is not running", batch_id))?;
+        *cancelled = true;
+    }
+    let running: Vec<String> = app
+        .state::<ActiveRuns>()
+        .list()
+        .into_iter()
+        .filter(|run| run.batch_id.as_deref() == Some(batch_id.as_str()))
+        .map(|run| run.run_id)
+        .collect();
+    for run_id in running {
+        if let Err(e) = runner::cancel_script(app.clone(), run_id.clone(), None) {
+            log::warn!("Failed to cancel {} of batch {}: {}", run_id, batch_id, e);
+        }
+    }
+    Ok(())
+}
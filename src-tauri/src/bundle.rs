@@ -0,0 +1,124 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+use tauri::AppHandle;
+
+use crate::metadata::{self, Directive};
+use crate::{scripts, settings};
+
+// Header directives written first, in this order; anything else keeps its original order after them
+const BUNDLE_KEYS: &[&str] = &["name", "description", "icon", "tags", "args", "timeout"];
+
+fn bundle_directives(
+    content: &str,
+    stored: Option<&serde_json::Map<String, Value>>,
+) -> Vec<Directive> {
+    let meta = metadata::parse(content.lines());
+    let stored_str = |key: &str| {
+        stored
+            .and_then(|s| s.get(key))
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+
+    let mut tags = meta.tags.clone();
+    if let Some(Value::Array(stored_tags)) = stored.and_then(|s| s.get("tags")) {
+        for tag in stored_tags.iter().filter_map(Value::as_str) {
+            if !tags.iter().any(|t| t == tag) {
+                tags.push(tag.to_string());
+            }
+        }
+    }
+    let timeout = stored
+        .and_then(|s| s.get("timeoutSeconds"))
+        .and_then(Value::as_u64)
+        .filter(|t| *t > 0)
+        .or(meta.timeout_secs);
+
+    let mut directives = Vec::new();
+    let mut push = |key: &str, value: Option<String>| {
+        if let Some(value) = value {
+            directives.push(Directive::new(key, value));
+        }
+    };
+    push("name", meta.name.clone());
+    push("description", meta.description.clone());
+    push("icon", stored_str("icon").or(meta.icon.clone()));
+    push("tags", (!tags.is_empty()).then(|| tags.join(", ")));
+    push("args", stored_str("args").or(meta.args.clone()));
+    push("timeout", timeout.map(|t| t.to_string()));
+    directives.extend(
+        meta.directives
+            .into_iter()
+            .filter(|d| !BUNDLE_KEYS.contains(&d.key.as_str())),
+    );
+    directives
+}
+
+fn file_name(path: &Path) -> Result<&std::ffi::OsStr, String> {
+    path.file_name()
+        .ok_or_else(|| format!("Invalid script path: {}", path.display()))
+}
+
+#[tauri::command]
+pub fn export_script_bundle(app: AppHandle, path: String, dest: String) -> Result<String, String> {
+    let source = settings::expand_path(&path);
+    let content = fs::read_to_string(&source).map_err(|e| e.to_string())?;
+    let stored = settings::script_data(&app, &path);
+    let bundled =
+        metadata::with_directives(&content, &bundle_directives(&content, stored.as_ref()));
+
+    let mut target = settings::expand_path(&dest);
+    if target.is_dir() {
+        target = target.join(file_name(&source)?);
+    }
+    fs::write(&target, bundled).map_err(|e| e.to_string())?;
+    scripts::set_user_executable(&target).map_err(|e| e.to_string())?;
+    Ok(target.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn import_script_bundle(
+    app: AppHandle,
+    src: String,
+    dest_dir: Option<String>,
+) -> Result<String, String> {
+    let source = settings::expand_path(&src);
+    let content = fs::read_to_string(&source).map_err(|e| e.to_string())?;
+    let dir: PathBuf = match dest_dir {
+        Some(dir) => settings::expand_path(&dir),
+        None => settings::scripts_roots(&app)
+            .into_iter()
+            .next()
+            .ok_or_else(|| "No scripts folder configured".to_string())?,
+    };
+    let target = dir.join(file_name(&source)?);
+    if target.exists() {
+        return Err(format!("Script already exists: {}", target.display()));
+    }
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    fs::write(&target, &content).map_err(|e| e.to_string())?;
+    scripts::set_user_executable(&target).map_err(|e| e.to_string())?;
+
+    let meta = metadata::parse(content.lines());
+    let target_path = target.to_string_lossy().to_string();
+    settings::update_script_data(&app, &target_path, |data| {
+        if let Some(args) = &meta.args {
+            data.insert("args".to_string(), json!(args));
+        }
+        if !meta.tags.is_empty() {
+            data.insert("tags".to_string(), json!(meta.tags));
+        }
+        if let Some(icon) = &meta.icon {
+            data.insert("icon".to_string(), json!(icon));
+        }
+        if let Some(timeout) = meta.timeout_secs {
+            data.insert("timeoutSeconds".to_string(), json!(timeout));
+        }
+    })?;
+    scripts::notify_scripts_changed(&app, vec![target_path.clone()]);
+    Ok(target_path)
+}
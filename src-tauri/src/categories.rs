@@ -0,0 +1,201 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::{roots, scripts, settings};
+
+// Folders the user collapsed in the window's grouped view, a field of the frontend settings
+// keyed by category
+const COLLAPSED_FIELD: &str = "collapsedFolders";
+// Where collapsed folders were kept by path before they moved into the frontend settings
+const LEGACY_COLLAPSED_KEY: &str = "collapsedCategories";
+
+// A scripts root or one of its folders; only folders holding scripts somewhere below show up
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptFolder {
+    pub path: String,
+    pub name: String,
+    // How the window names the folder's group: the root's name, then the path below it
+    pub category: String,
+    pub collapsed: bool,
+    pub folders: Vec<ScriptFolder>,
+    pub scripts: Vec<String>,
+}
+
+fn name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string())
+}
+
+// Matches getScriptFolder in src/lib/scripts.ts
+fn category(root: &Path, dir: &Path) -> String {
+    let below: Vec<String> = dir
+        .strip_prefix(root)
+        .map(|relative| {
+            relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    if below.is_empty() {
+        return name(root);
+    }
+    format!("{}/{}", name(root), below.join("/"))
+}
+
+fn save(app: &AppHandle, collapsed: &BTreeSet<String>) -> Result<(), String> {
+    settings::set_frontend_field(app, COLLAPSED_FIELD, collapsed)
+}
+
+// Moves folders collapsed under the old key over as categories, under the root they're in
+fn migrate(app: &AppHandle, collapsed: &mut BTreeSet<String>) -> Result<(), String> {
+    let Some(legacy) = settings::get::<BTreeSet<String>>(app, LEGACY_COLLAPSED_KEY) else {
+        return Ok(());
+    };
+    let roots = settings::scripts_roots(app);
+    for path in legacy {
+        let path = Path::new(&path);
+        let root = roots
+            .iter()
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.as_os_str().len());
+        if let Some(root) = root {
+            collapsed.insert(category(root, path));
+        }
+    }
+    save(app, collapsed)?;
+    settings::delete(app, LEGACY_COLLAPSED_KEY)
+}
+
+fn load(app: &AppHandle) -> Result<BTreeSet<String>, String> {
+    let mut collapsed: BTreeSet<String> =
+        serde_json::from_value(settings::frontend_field(app, COLLAPSED_FIELD)).unwrap_or_default();
+    migrate(app, &mut collapsed)?;
+    Ok(collapsed)
+}
+
+// `relative` are the scripts below `dir`, as paths relative to it
+fn build(
+    root: &Path,
+    dir: &Path,
+    relative: Vec<PathBuf>,
+    collapsed: &BTreeSet<String>,
+) -> ScriptFolder {
+    let mut scripts = Vec::new();
+    let mut children: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
+    for path in relative {
+        let mut components = path.components();
+        let Some(first) = components.next() else {
+            continue;
+        };
+        let rest = components.as_path();
+        if rest.as_os_str().is_empty() {
+            scripts.push(dir.join(first).to_string_lossy().to_string());
+        } else {
+            children
+                .entry(PathBuf::from(first.as_os_str()))
+                .or_default()
+                .push(rest.to_path_buf());
+        }
+    }
+    scripts.sort();
+    let category = category(root, dir);
+    ScriptFolder {
+        name: name(dir),
+        collapsed: collapsed.contains(&category),
+        folders: children
+            .into_iter()
+            .map(|(child, relative)| build(root, &dir.join(child), relative, collapsed))
+            .collect(),
+        scripts,
+        path: dir.to_string_lossy().to_string(),
+        category,
+    }
+}
+
+fn folder_categories<'a>(folder: &'a ScriptFolder, categories: &mut BTreeSet<&'a str>) {
+    categories.insert(&folder.category);
+    for child in &folder.folders {
+        folder_categories(child, categories);
+    }
+}
+
+// The scripts roots as folder trees, each folder a category the UI can collapse. Collapsed
+// categories that no longer hold any script are dropped from the frontend settings.
+#[tauri::command]
+pub async fn list_scripts_tree(app: AppHandle) -> Result<Vec<ScriptFolder>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let stored = load(&app)?;
+        let configured = settings::scripts_roots(&app);
+        let tree: Vec<ScriptFolder> = configured
+            .iter()
+            .map(|root| {
+                let relative = scripts::all_scripts(vec![root.clone()])
+                    .into_iter()
+                    .filter_map(|script| {
+                        Path::new(&script)
+                            .strip_prefix(root)
+                            .ok()
+                            .map(Path::to_path_buf)
+                    })
+                    .collect();
+                build(root, root, relative, &stored)
+            })
+            .collect();
+        let mut existing = BTreeSet::new();
+        for folder in &tree {
+            folder_categories(folder, &mut existing);
+        }
+        // A folder on an unreachable root may only be missing from a stale listing
+        let stale: BTreeSet<String> = configured
+            .iter()
+            .filter(|root| roots::is_stale(&root.to_string_lossy()))
+            .map(|root| name(root))
+            .collect();
+        let kept: BTreeSet<String> = stored
+            .iter()
+            .filter(|category| {
+                existing.contains(category.as_str())
+                    || category
+                        .split('/')
+                        .next()
+                        .is_some_and(|root| stale.contains(root))
+            })
+            .cloned()
+            .collect();
+        if kept.len() != stored.len() {
+            save(&app, &kept)?;
+        }
+        Ok(tree)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn get_collapsed_categories(app: AppHandle) -> Result<Vec<String>, String> {
+    Ok(load(&app)?.into_iter().collect())
+}
+
+#[tauri::command]
+pub fn set_category_collapsed(
+    app: AppHandle,
+    category: String,
+    collapsed: bool,
+) -> Result<(), String> {
+    let mut stored = load(&app)?;
+    let changed = if collapsed {
+        stored.insert(category)
+    } else {
+        stored.remove(&category)
+    };
+    if !changed {
+        return Ok(());
+    }
+    save(&app, &stored)
+}
@@ -0,0 +1,180 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::settings;
+
+const COLLECTIONS_KEY: &str = "collections";
+
+// A named set of scripts, independent of the folders they live in
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Collection {
+    pub name: String,
+    // Script ids in the order they were added
+    pub scripts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionScript {
+    pub path: String,
+    pub name: String,
+    // The file is gone; it stays in the collection until removed
+    pub missing: bool,
+}
+
+fn load(app: &AppHandle) -> Vec<Collection> {
+    settings::get(app, COLLECTIONS_KEY).unwrap_or_default()
+}
+
+fn update<T>(
+    app: &AppHandle,
+    name: &str,
+    change: impl FnOnce(&mut Collection) -> T,
+) -> Result<T, String> {
+    let mut collections = load(app);
+    let collection = collections
+        .iter_mut()
+        .find(|c| c.name == name)
+        .ok_or_else(|| format!("Collection not found: {}", name))?;
+    let result = change(collection);
+    settings::set(app, COLLECTIONS_KEY, &collections)?;
+    Ok(result)
+}
+
+pub fn scripts(app: &AppHandle, name: &str) -> Result<Vec<String>, String> {
+    load(app)
+        .into_iter()
+        .find(|c| c.name == name)
+        .map(|c| c.scripts)
+        .ok_or_else(|| format!("Collection not found: {}", name))
+}
+
+pub fn names(app: &AppHandle) -> Vec<String> {
+    load(app).into_iter().map(|c| c.name).collect()
+}
+
+// Every script id in any collection, with the collection's name
+pub fn members(app: &AppHandle) -> Vec<(String, String)> {
+    load(app)
+        .into_iter()
+        .flat_map(|c| {
+            let name = c.name;
+            c.scripts.into_iter().map(move |s| (name.clone(), s))
+        })
+        .collect()
+}
+
+pub fn contains_script(app: &AppHandle, path: &str) -> bool {
+    load(app)
+        .iter()
+        .any(|c| c.scripts.iter().any(|s| s == path))
+}
+
+// Membership follows a renamed script, and is dropped for a deleted one
+pub fn move_script(app: &AppHandle, from: &str, to: Option<&str>) -> Result<(), String> {
+    let mut collections = load(app);
+    let mut changed = false;
+    for collection in collections.iter_mut() {
+        let Some(index) = collection.scripts.iter().position(|s| s == from) else {
+            continue;
+        };
+        changed = true;
+        match to {
+            Some(to) if !collection.scripts.iter().any(|s| s == to) => {
+                collection.scripts[index] = to.to_string();
+            }
+            _ => {
+                collection.scripts.remove(index);
+            }
+        }
+    }
+    if !changed {
+        return Ok(());
+    }
+    settings::set(app, COLLECTIONS_KEY, &collections)
+}
+
+#[tauri::command]
+pub fn list_collections(app: AppHandle) -> Result<Vec<Collection>, String> {
+    Ok(load(&app))
+}
+
+#[tauri::command]
+pub fn list_collection(app: AppHandle, name: String) -> Result<Vec<CollectionScript>, String> {
+    let collection = load(&app)
+        .into_iter()
+        .find(|c| c.name == name)
+        .ok_or_else(|| format!("Collection not found: {}", name))?;
+    Ok(collection
+        .scripts
+        .into_iter()
+        .map(|path| CollectionScript {
+            name: Path::new(&path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.clone()),
+            missing: !Path::new(&path).is_file(),
+            path,
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub fn create_collection(app: AppHandle, name: String) -> Result<Collection, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Collection name cannot be empty".to_string());
+    }
+    let mut collections = load(&app);
+    if collections.iter().any(|c| c.name == name) {
+        return Err(format!("Collection already exists: {}", name));
+    }
+    let collection = Collection {
+        name,
+        scripts: Vec::new(),
+    };
+    collections.push(collection.clone());
+    settings::set(&app, COLLECTIONS_KEY, &collections)?;
+    Ok(collection)
+}
+
+// Adding a script that's already in the collection does nothing
+#[tauri::command]
+pub fn add_to_collection(app: AppHandle, name: String, path: String) -> Result<Collection, String> {
+    if !Path::new(&path).is_file() {
+        return Err(format!("Script not found: {}", path));
+    }
+    update(&app, &name, |collection| {
+        if !collection.scripts.contains(&path) {
+            collection.scripts.push(path);
+        }
+        collection.clone()
+    })
+}
+
+#[tauri::command]
+pub fn remove_from_collection(
+    app: AppHandle,
+    name: String,
+    path: String,
+) -> Result<Collection, String> {
+    update(&app, &name, |collection| {
+        collection.scripts.retain(|s| *s != path);
+        collection.clone()
+    })
+}
+
+// The scripts themselves are left alone
+#[tauri::command]
+pub fn delete_collection(app: AppHandle, name: String) -> Result<(), String> {
+    let mut collections = load(&app);
+    let before = collections.len();
+    collections.retain(|c| c.name != name);
+    if collections.len() == before {
+        return Err(format!("Collection not found: {}", name));
+    }
+    settings::set(&app, COLLECTIONS_KEY, &collections)
+}
@@ -0,0 +1,146 @@
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::runner::RunOptions;
+use crate::{batch, queue, settings};
+
+// The frontend's own limit, so the window and the backend count against the same number
+const MAX_CONCURRENT_FIELD: &str = "maxConcurrent";
+// Must match maxConcurrent in DEFAULT_SETTINGS, src/hooks/useStore.ts
+const DEFAULT_MAX_CONCURRENT: usize = 2;
+const POLICY_KEY: &str = "concurrencyPolicy";
+pub const CONCURRENCY_LIMIT_ERROR: &str = "concurrency limit reached";
+// Prefix of the run error when the run went to the queue instead, followed by the queue item id
+pub const QUEUED_ERROR: &str = "queued";
+
+// Script runs holding a slot, counted from before the checks so two runs can't both see room
+static RUNNING: Mutex<usize> = Mutex::new(0);
+static FREED: Condvar = Condvar::new();
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConcurrencyPolicy {
+    // Refuse with CONCURRENCY_LIMIT_ERROR
+    #[default]
+    Reject,
+    // Hand the run to the queue, which starts it when a slot frees up
+    Queue,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConcurrencyStatus {
+    pub running: usize,
+    pub limit: usize,
+    pub policy: ConcurrencyPolicy,
+}
+
+// Held for the length of a run; dropping it frees the slot
+pub struct Slot;
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let mut running = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+        *running = running.saturating_sub(1);
+        FREED.notify_all();
+    }
+}
+
+// Always set, like in the frontend, which treats anything below 1 as 1
+fn limit(app: &AppHandle) -> usize {
+    let stored = settings::frontend_field(app, MAX_CONCURRENT_FIELD).as_u64();
+    stored.map_or(DEFAULT_MAX_CONCURRENT, |n| (n as usize).max(1))
+}
+
+fn policy(app: &AppHandle) -> ConcurrencyPolicy {
+    settings::get(app, POLICY_KEY).unwrap_or_default()
+}
+
+fn try_slot(app: &AppHandle) -> Option<Slot> {
+    let limit = limit(app);
+    let mut running = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+    if *running >= limit {
+        return None;
+    }
+    *running += 1;
+    Some(Slot)
+}
+
+// The queue worker waits its turn instead of going back into the queue
+fn wait_for_slot(app: &AppHandle) -> Slot {
+    let mut running = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+    // Re-read on every wake-up so a raised limit lets waiting runs through
+    while *running >= limit(app) {
+        running = FREED.wait(running).unwrap_or_else(|e| e.into_inner());
+    }
+    *running += 1;
+    Slot
+}
+
+// Batch members wait like queued runs, but give up when their batch is cancelled
+fn wait_for_batch_slot(app: &AppHandle, batch_id: &str) -> Result<Slot, String> {
+    const RECHECK: Duration = Duration::from_millis(250);
+    let mut running = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+    loop {
+        if batch::is_cancelled(batch_id) {
+            return Err(batch::BATCH_CANCELLED_ERROR.to_string());
+        }
+        if *running < limit(app) {
+            *running += 1;
+            return Ok(Slot);
+        }
+        running = FREED
+            .wait_timeout(running, RECHECK)
+            .unwrap_or_else(|e| e.into_inner())
+            .0;
+    }
+}
+
+// A slot for a run that is about to start, or the error to return when there is none. A
+// queued run keeps all of its options.
+pub fn acquire(app: &AppHandle, path: &str, options: &RunOptions) -> Result<Slot, String> {
+    if options.from_queue {
+        return Ok(wait_for_slot(app));
+    }
+    if let Some(batch_id) = &options.batch_id {
+        return wait_for_batch_slot(app, batch_id);
+    }
+    if let Some(slot) = try_slot(app) {
+        return Ok(slot);
+    }
+    match policy(app) {
+        ConcurrencyPolicy::Queue if !options.no_queue => {
+            let item = queue::enqueue_with_options(app, path.to_string(), options.clone())?;
+            Err(format!("{}: {}", QUEUED_ERROR, item.id))
+        }
+        _ => Err(CONCURRENCY_LIMIT_ERROR.to_string()),
+    }
+}
+
+#[tauri::command]
+pub fn get_concurrency_status(app: AppHandle) -> Result<ConcurrencyStatus, String> {
+    Ok(ConcurrencyStatus {
+        running: *RUNNING.lock().map_err(|e| e.to_string())?,
+        limit: limit(&app),
+        policy: policy(&app),
+    })
+}
+
+// Sets the same limit the window's own queue uses
+#[tauri::command]
+pub fn set_max_concurrent_runs(app: AppHandle, n: usize) -> Result<(), String> {
+    if n == 0 {
+        return Err("At least one run must be allowed".to_string());
+    }
+    settings::set_frontend_field(&app, MAX_CONCURRENT_FIELD, &n)?;
+    FREED.notify_all();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_concurrency_policy(app: AppHandle, policy: ConcurrencyPolicy) -> Result<(), String> {
+    settings::set(&app, POLICY_KEY, &policy)
+}
@@ -0,0 +1,323 @@
+use std::net::{TcpStream, ToSocketAddrs};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::history::{self, HistoryEntry};
+use crate::runner::RunOptions;
+use crate::{settings, triggers};
+
+// `code` of the JSON error returned when a manual run's conditions aren't met
+pub const CONDITION_NOT_MET_ERROR: &str = "condition-not-met";
+const CONDITIONS_FIELD: &str = "runConditions";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Where a script may run; every condition that is set has to hold
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RunConditions {
+    // The Wi-Fi network the machine must be on
+    pub ssid: Option<String>,
+    // `host:port` that must accept a connection
+    pub reachable: Option<String>,
+    // Glob the machine's hostname must match, ignoring case
+    pub hostname: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConditionNotMet {
+    pub code: &'static str,
+    pub path: String,
+    // "ssid", "reachable" or "hostname"
+    pub condition: &'static str,
+    pub message: String,
+}
+
+fn set(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+pub fn for_script(app: &AppHandle, path: &str) -> RunConditions {
+    settings::script_data(app, path)
+        .and_then(|data| data.get(CONDITIONS_FIELD).cloned())
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+fn stdout_of(mut command: Command) -> Option<String> {
+    let output = command
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Wifi {
+    Connected(String),
+    // Wi-Fi is off, or on but not joined to a network
+    NotAssociated,
+    // No tool to ask, or it failed
+    Unknown,
+}
+
+// The device behind the "Wi-Fi" hardware port; it isn't always en0
+#[cfg(target_os = "macos")]
+fn wifi_device(ports: &str) -> Option<String> {
+    let mut lines = ports.lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        let port = line.strip_prefix("Hardware Port:").map(str::trim);
+        if matches!(port, Some("Wi-Fi" | "AirPort")) {
+            return lines
+                .next()
+                .and_then(|line| line.strip_prefix("Device:"))
+                .map(|device| device.trim().to_string());
+        }
+    }
+    None
+}
+
+#[cfg(target_os = "macos")]
+fn current_wifi() -> Wifi {
+    let mut command = Command::new("networksetup");
+    command.arg("-listallhardwareports");
+    let Some(ports) = stdout_of(command) else {
+        return Wifi::Unknown;
+    };
+    // A Mac without Wi-Fi hardware can't be on a Wi-Fi network
+    let Some(device) = wifi_device(&ports) else {
+        return Wifi::NotAssociated;
+    };
+    let mut command = Command::new("networksetup");
+    command.args(["-getairportnetwork", &device]);
+    match stdout_of(command) {
+        Some(out) => match out.strip_prefix("Current Wi-Fi Network:") {
+            Some(ssid) => Wifi::Connected(ssid.trim().to_string()),
+            // "You are not associated with an AirPort network." and the like
+            None => Wifi::NotAssociated,
+        },
+        None => Wifi::Unknown,
+    }
+}
+
+// Terse mode escapes `:` and `\` in values with a backslash
+#[cfg(all(unix, not(target_os = "macos")))]
+fn parse_nmcli(out: &str) -> Wifi {
+    let unescape = |field: &str| {
+        let mut value = String::new();
+        let mut chars = field.chars();
+        while let Some(c) = chars.next() {
+            value.push(if c == '\\' {
+                chars.next().unwrap_or(c)
+            } else {
+                c
+            });
+        }
+        value
+    };
+    out.lines()
+        .find_map(|line| line.strip_prefix("yes:"))
+        .map_or(Wifi::NotAssociated, |ssid| Wifi::Connected(unescape(ssid)))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn current_wifi() -> Wifi {
+    let mut command = Command::new("nmcli");
+    command.args(["-t", "-f", "active,ssid", "dev", "wifi"]);
+    stdout_of(command).map_or(Wifi::Unknown, |out| parse_nmcli(&out))
+}
+
+#[cfg(windows)]
+fn current_wifi() -> Wifi {
+    let mut command = Command::new("netsh");
+    command.args(["wlan", "show", "interfaces"]);
+    let Some(out) = stdout_of(command) else {
+        return Wifi::Unknown;
+    };
+    out.lines()
+        .find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            (key.trim() == "SSID").then(|| value.trim().to_string())
+        })
+        .map_or(Wifi::NotAssociated, Wifi::Connected)
+}
+
+#[cfg(unix)]
+fn hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    // SAFETY: gethostname writes at most buf.len() bytes into buf
+    let status = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+    if status != 0 {
+        return None;
+    }
+    let end = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+    Some(String::from_utf8_lossy(&buf[..end]).to_string())
+}
+
+#[cfg(not(unix))]
+fn hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok()
+}
+
+fn reachable(target: &str) -> bool {
+    target.to_socket_addrs().is_ok_and(|mut addrs| {
+        addrs.any(|addr| TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).is_ok())
+    })
+}
+
+fn validate(conditions: &RunConditions) -> Result<(), String> {
+    if let Some(target) = set(&conditions.reachable) {
+        let port = target.rsplit_once(':').map(|(_, port)| port.parse::<u16>());
+        if !matches!(port, Some(Ok(_))) {
+            return Err(format!("Expected host:port, got {}", target));
+        }
+    }
+    if let Some(pattern) = set(&conditions.hostname) {
+        Pattern::new(pattern).map_err(|e| format!("Invalid hostname pattern: {}", e))?;
+    }
+    Ok(())
+}
+
+// The first condition that doesn't hold, as (name, message). A network or hostname that
+// can't be read counts as met, so a missing tool doesn't block every run; being on no Wi-Fi
+// at all doesn't meet an SSID condition.
+fn unmet(conditions: &RunConditions) -> Option<(&'static str, String)> {
+    if let Some(wanted) = set(&conditions.ssid) {
+        match current_wifi() {
+            Wifi::Connected(ssid) if ssid != wanted => {
+                return Some(("ssid", format!("Not on Wi-Fi {} (on {})", wanted, ssid)));
+            }
+            Wifi::Connected(_) => {}
+            Wifi::NotAssociated => {
+                return Some(("ssid", format!("Not on Wi-Fi {} (on no Wi-Fi)", wanted)));
+            }
+            Wifi::Unknown => log::info!("Wi-Fi network unknown, allowing the run"),
+        }
+    }
+    if let Some(target) = set(&conditions.reachable) {
+        if !reachable(target) {
+            return Some(("reachable", format!("{} is not reachable", target)));
+        }
+    }
+    if let Some(pattern) = set(&conditions.hostname) {
+        let options = MatchOptions {
+            case_sensitive: false,
+            ..MatchOptions::default()
+        };
+        let matches = Pattern::new(pattern).ok().zip(hostname());
+        if let Some((pattern, name)) = matches {
+            if !pattern.matches_with(&name, options) {
+                return Some((
+                    "hostname",
+                    format!("Hostname {} doesn't match {}", name, pattern),
+                ));
+            }
+        }
+    }
+    None
+}
+
+// The message for the first of the script's conditions that doesn't hold
+pub fn unmet_for(app: &AppHandle, path: &str) -> Option<String> {
+    unmet(&for_script(app, path)).map(|(_, message)| message)
+}
+
+// Manual runs get a ConditionNotMet error they can retry with `ignore_conditions`.
+// Unattended ones are skipped, leaving a history record that says why.
+pub fn check(
+    app: &AppHandle,
+    run_id: &str,
+    path: &str,
+    options: &RunOptions,
+) -> Result<(), String> {
+    if options.ignore_conditions {
+        return Ok(());
+    }
+    let Some((condition, message)) = unmet(&for_script(app, path)) else {
+        return Ok(());
+    };
+    match options.trigger.as_deref() {
+        Some(trigger) if triggers::is_unattended(Some(trigger)) => {
+            log::info!("Skipped {} from {}: {}", path, trigger, message);
+            let _ = history::record(
+                app,
+                HistoryEntry {
+                    run_id: run_id.to_string(),
+                    path: path.to_string(),
+                    started_at: history::now_ms(),
+                    args: options.args.clone(),
+                    trigger: options.trigger.clone(),
+                    batch_id: options.batch_id.clone(),
+                    condition_not_met: Some(message.clone()),
+                    source: Some(triggers::source(options)),
+                    ..HistoryEntry::default()
+                },
+            );
+            Err(format!("{}: {}", CONDITION_NOT_MET_ERROR, message))
+        }
+        _ => Err(serde_json::to_string(&ConditionNotMet {
+            code: CONDITION_NOT_MET_ERROR,
+            path: path.to_string(),
+            condition,
+            message,
+        })
+        .map_err(|e| e.to_string())?),
+    }
+}
+
+#[tauri::command]
+pub fn get_run_conditions(app: AppHandle, path: String) -> Result<RunConditions, String> {
+    Ok(for_script(&app, &path))
+}
+
+// Conditions with nothing set remove the field
+#[tauri::command]
+pub fn set_run_conditions(
+    app: AppHandle,
+    path: String,
+    conditions: RunConditions,
+) -> Result<(), String> {
+    validate(&conditions)?;
+    let empty = set(&conditions.ssid).is_none()
+        && set(&conditions.reachable).is_none()
+        && set(&conditions.hostname).is_none();
+    let value = serde_json::to_value(&conditions).map_err(|e| e.to_string())?;
+    settings::update_script_data(&app, &path, |data| {
+        if empty {
+            data.remove(CONDITIONS_FIELD);
+        } else {
+            data.insert(CONDITIONS_FIELD.to_string(), value);
+        }
+    })
+}
+
+// Both parsers are per platform
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn reads_the_active_network_from_nmcli() {
+        assert_eq!(
+            parse_nmcli("no:Cafe\nyes:Home\\:5G\\\\x\n"),
+            Wifi::Connected("Home:5G\\x".to_string())
+        );
+        assert_eq!(parse_nmcli("no:Cafe\n"), Wifi::NotAssociated);
+        assert_eq!(parse_nmcli(""), Wifi::NotAssociated);
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn finds_the_wifi_device() {
+        let ports = "Hardware Port: Ethernet\nDevice: en0\n\nHardware Port: Wi-Fi\nDevice: en1\n";
+        assert_eq!(wifi_device(ports), Some("en1".to_string()));
+        assert_eq!(wifi_device("Hardware Port: Ethernet\nDevice: en0\n"), None);
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Condvar, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::json;
+use tauri::{AppHandle, Emitter};
+
+use crate::history::{self, HistoryEntry};
+use crate::runner::RunOptions;
+use crate::{notifications, settings, triggers};
+
+// Prefix of the run error when the user cancelled during the countdown
+pub const COUNTDOWN_CANCELLED_ERROR: &str = "countdown-cancelled";
+// Per-script field: how long an unattended run waits, announced, before it starts
+const CONFIRM_TIMEOUT_FIELD: &str = "confirmTimeoutMs";
+const MAX_CONFIRM_TIMEOUT_MS: u64 = 5 * 60 * 1000;
+
+// Set to true, with the waiting run woken, by cancel_run_countdown
+type Pending = Arc<(Mutex<bool>, Condvar)>;
+
+// Countdowns in progress by run id
+static PENDING: LazyLock<Mutex<HashMap<String, Pending>>> = LazyLock::new(Mutex::default);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CountdownEvent<'a> {
+    run_id: &'a str,
+    path: &'a str,
+    trigger: Option<&'a str>,
+    timeout_ms: u64,
+    // When the run starts unless cancel_run_countdown is called first
+    deadline_ms: u64,
+}
+
+fn timeout_for(app: &AppHandle, path: &str) -> Option<u64> {
+    settings::script_data(app, path)
+        .and_then(|data| data.get(CONFIRM_TIMEOUT_FIELD)?.as_u64())
+        .filter(|ms| *ms > 0)
+}
+
+fn name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+// Announces an unattended run with a countdown and waits it out. Runs go ahead unless
+// cancelled in time; a cancelled one leaves a history record and fails to start.
+pub fn wait(app: &AppHandle, run_id: &str, path: &str, options: &RunOptions) -> Result<(), String> {
+    if !triggers::is_unattended(options.trigger.as_deref()) {
+        return Ok(());
+    }
+    let Some(timeout_ms) = timeout_for(app, path) else {
+        return Ok(());
+    };
+    let pending: Pending = Arc::new((Mutex::new(false), Condvar::new()));
+    if let Ok(mut countdowns) = PENDING.lock() {
+        countdowns.insert(run_id.to_string(), pending.clone());
+    }
+    let _ = app.emit(
+        "run-countdown",
+        CountdownEvent {
+            run_id,
+            path,
+            trigger: options.trigger.as_deref(),
+            timeout_ms,
+            deadline_ms: history::now_ms() + timeout_ms,
+        },
+    );
+    notifications::show(
+        app,
+        "Script about to run",
+        &format!(
+            "{} runs in {} s unless cancelled",
+            name(path),
+            timeout_ms.div_ceil(1000)
+        ),
+    );
+
+    let (lock, cvar) = &*pending;
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+    let mut cancelled = lock.lock().map_err(|e| e.to_string())?;
+    while !*cancelled {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            break;
+        }
+        cancelled = cvar
+            .wait_timeout(cancelled, left)
+            .map_err(|e| e.to_string())?
+            .0;
+    }
+    let cancelled = *cancelled;
+    if let Ok(mut countdowns) = PENDING.lock() {
+        countdowns.remove(run_id);
+    }
+    let _ = app.emit(
+        "run-countdown-ended",
+        json!({ "runId": run_id, "cancelled": cancelled }),
+    );
+    if !cancelled {
+        return Ok(());
+    }
+    log::info!("Skipped {}: cancelled during its countdown", path);
+    let _ = history::record(
+        app,
+        HistoryEntry {
+            run_id: run_id.to_string(),
+            path: path.to_string(),
+            started_at: history::now_ms(),
+            args: options.args.clone(),
+            trigger: options.trigger.clone(),
+            batch_id: options.batch_id.clone(),
+            countdown_cancelled: true,
+            source: Some(triggers::source(options)),
+            ..HistoryEntry::default()
+        },
+    );
+    Err(format!(
+        "{}: {} was cancelled before it started",
+        COUNTDOWN_CANCELLED_ERROR, path
+    ))
+}
+
+// Stops a run announced by run-countdown; false when its countdown already ran out
+#[tauri::command]
+pub fn cancel_run_countdown(run_id: String) -> Result<bool, String> {
+    let pending = PENDING
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(&run_id)
+        .cloned();
+    let Some(pending) = pending else {
+        return Ok(false);
+    };
+    let (lock, cvar) = &*pending;
+    *lock.lock().map_err(|e| e.to_string())? = true;
+    cvar.notify_all();
+    Ok(true)
+}
+
+#[tauri::command]
+pub fn get_confirm_timeout(app: AppHandle, path: String) -> Result<Option<u64>, String> {
+    Ok(timeout_for(&app, &path))
+}
+
+// None or 0 lets unattended runs start right away again
+#[tauri::command]
+pub fn set_confirm_timeout(
+    app: AppHandle,
+    path: String,
+    timeout_ms: Option<u64>,
+) -> Result<(), String> {
+    let timeout_ms = timeout_ms.filter(|ms| *ms > 0);
+    if timeout_ms.is_some_and(|ms| ms > MAX_CONFIRM_TIMEOUT_MS) {
+        return Err(format!(
+            "The countdown can be at most {} s",
+            MAX_CONFIRM_TIMEOUT_MS / 1000
+        ));
+    }
+    settings::update_script_data(&app, &path, |data| match timeout_ms {
+        Some(ms) => {
+            data.insert(CONFIRM_TIMEOUT_FIELD.to_string(), json!(ms));
+        }
+        None => {
+            data.remove(CONFIRM_TIMEOUT_FIELD);
+        }
+    })
+}
@@ -0,0 +1,118 @@
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::Value;
+use tauri::AppHandle;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::runner::RunOptions;
+use crate::{metadata, settings, triggers};
+
+// `code` of the JSON error returned when a dangerous script is started without `confirmed`
+pub const CONFIRMATION_REQUIRED_ERROR: &str = "confirmation-required";
+// Unattended triggers allowed to start dangerous scripts anyway
+const OVERRIDES_KEY: &str = "dangerousTriggerOverrides";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmationRequired {
+    pub code: &'static str,
+    pub path: String,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+fn describe(path: &str) -> ConfirmationRequired {
+    let meta = fs::read_to_string(path)
+        .map(|content| metadata::parse_script(&content))
+        .unwrap_or_default();
+    ConfirmationRequired {
+        code: CONFIRMATION_REQUIRED_ERROR,
+        path: path.to_string(),
+        name: meta.name.unwrap_or_else(|| {
+            Path::new(path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.to_string())
+        }),
+        description: meta.description,
+    }
+}
+
+// `# @dangerous` in the header or the per-script `dangerous` flag
+pub fn is_dangerous(app: &AppHandle, path: &str) -> bool {
+    let flagged = settings::script_data(app, path)
+        .and_then(|data| data.get("dangerous").and_then(Value::as_bool))
+        .unwrap_or(false);
+    flagged
+        || fs::read_to_string(path).is_ok_and(|content| metadata::parse_script(&content).dangerous)
+}
+
+fn overrides(app: &AppHandle) -> Vec<String> {
+    settings::get(app, OVERRIDES_KEY).unwrap_or_default()
+}
+
+pub fn has_override(app: &AppHandle, trigger: &str) -> bool {
+    overrides(app).iter().any(|t| t == trigger)
+}
+
+// Manual and tray/hotkey runs need `confirmed`; webhook, deep-link, CLI, workflow and other
+// unattended runs are refused unless their trigger has an override
+pub fn check(app: &AppHandle, path: &str, options: &RunOptions) -> Result<(), String> {
+    if options.confirmed || !is_dangerous(app, path) {
+        return Ok(());
+    }
+    match options.trigger.as_deref() {
+        Some(trigger) if triggers::is_unattended(Some(trigger)) => {
+            if has_override(app, trigger) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "{} is marked dangerous and can't be started by {} without an override",
+                    path, trigger
+                ))
+            }
+        }
+        _ => Err(serde_json::to_string(&describe(path)).map_err(|e| e.to_string())?),
+    }
+}
+
+// Native confirm for tray and hotkey runs; blocks, so never call it on the event loop
+pub fn confirm(app: &AppHandle, path: &str) -> bool {
+    let info = describe(path);
+    let mut message = format!("{} is marked as dangerous.", info.name);
+    if let Some(description) = &info.description {
+        message.push_str(&format!("\n\n{}", description));
+    }
+    app.dialog()
+        .message(message)
+        .title("Run dangerous script?")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Run".to_string(),
+            "Cancel".to_string(),
+        ))
+        .blocking_show()
+}
+
+#[tauri::command]
+pub fn get_dangerous_trigger_overrides(app: AppHandle) -> Result<Vec<String>, String> {
+    Ok(overrides(&app))
+}
+
+// e.g. ["webhook"] lets webhook calls start dangerous scripts without confirmation
+#[tauri::command]
+pub fn set_dangerous_trigger_overrides(
+    app: AppHandle,
+    triggers: Vec<String>,
+) -> Result<(), String> {
+    let mut triggers: Vec<String> = triggers
+        .iter()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+    triggers.sort();
+    triggers.dedup();
+    settings::set(&app, OVERRIDES_KEY, &triggers)
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::thread;
+
+use percent_encoding::percent_decode_str;
+use tauri::{AppHandle, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::runner::{self, RunOptions};
+use crate::{notifications, scripts, settings, triggers};
+
+const SCHEME: &str = "sh-runner";
+const CONFIRM_KEY: &str = "deepLinkConfirm";
+// Query parameters other than `arg` reach the script as PARAM_<NAME> variables
+const PARAM_ENV_PREFIX: &str = "PARAM_";
+
+#[derive(Debug, Clone, PartialEq)]
+struct DeepLinkRun {
+    script: String,
+    args: Vec<String>,
+    env: HashMap<String, String>,
+}
+
+// sh-runner://run/<script>?arg=a&arg=b&mode=fast
+fn parse(url: &Url) -> Result<DeepLinkRun, String> {
+    if url.scheme() != SCHEME {
+        return Err(format!("Unsupported scheme: {}", url.scheme()));
+    }
+    if url.host_str() != Some("run") {
+        return Err(format!("Unsupported deep link action: {}", url));
+    }
+    let script = percent_decode_str(url.path().trim_matches('/'))
+        .decode_utf8_lossy()
+        .into_owned();
+    if script.is_empty() {
+        return Err("Deep link is missing a script name".to_string());
+    }
+    let mut args = Vec::new();
+    let mut env = HashMap::new();
+    for (key, value) in url.query_pairs() {
+        if key == "arg" {
+            args.push(value.into_owned());
+        } else {
+            env.insert(param_env_name(&key), value.into_owned());
+        }
+    }
+    Ok(DeepLinkRun { script, args, env })
+}
+
+// PARAM_ and the name uppercased with anything else turned into `_`, so a caller can't set
+// variables such as PATH or LD_PRELOAD; the webhook uses it for its env too
+pub fn param_env_name(key: &str) -> String {
+    let name: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}{}", PARAM_ENV_PREFIX, name)
+}
+
+fn confirm(app: &AppHandle, path: &str, run: &DeepLinkRun) -> bool {
+    if !settings::get::<bool>(app, CONFIRM_KEY).unwrap_or(true) {
+        return true;
+    }
+    let mut message = format!("A link wants to run {}", path);
+    if !run.args.is_empty() {
+        message.push_str(&format!("\n\nArguments: {}", run.args.join(" ")));
+    }
+    app.dialog()
+        .message(message)
+        .title("Run script from link?")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Run".to_string(),
+            "Cancel".to_string(),
+        ))
+        .blocking_show()
+}
+
+fn handle_url(app: &AppHandle, url: &Url) {
+    let run = match parse(url) {
+        Ok(run) => run,
+        Err(error) => return notifications::show(app, "Invalid link", &error),
+    };
+    let Some(path) = scripts::find_script(app, &run.script) else {
+        return notifications::show(
+            app,
+            "Script not found",
+            &format!("No script matches \"{}\"", run.script),
+        );
+    };
+    let app = app.clone();
+    // The dialog and the run both block, so neither may happen on the event loop
+    thread::spawn(move || {
+        if !confirm(&app, &path, &run) {
+            return;
+        }
+        let options = RunOptions {
+            args: run.args,
+            env: run.env,
+            trigger: Some(triggers::DEEP_LINK.to_string()),
+            trigger_detail: Some(format!("{}://run/{}", SCHEME, run.script)),
+            ..RunOptions::default()
+        };
+        if let Err(error) = runner::execute(&app, &path, &options) {
+            notifications::show(&app, "Failed to run script", &error);
+        }
+    });
+}
+
+// URLs arrive through the open-url event, including ones forwarded to this
+// instance by the single-instance plugin, or as launch arguments on a cold start
+pub fn install(app: &AppHandle) {
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
+    let _ = app.deep_link().register_all();
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            handle_url(&handle, &url);
+        }
+    });
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        for url in urls {
+            handle_url(app, &url);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn set_deep_link_confirm(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set(&app, CONFIRM_KEY, &enabled)
+}
@@ -0,0 +1,231 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{LazyLock, Mutex, OnceLock};
+
+use regex::Regex;
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::metadata::{self, Requirement};
+use crate::startup::{self, Phase};
+use crate::{index, scripts};
+
+// `code` of the JSON error returned when a run with `check_dependencies` is missing something
+pub const MISSING_DEPENDENCIES_ERROR: &str = "missing-dependencies";
+
+static VERSION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\d+(?:\.\d+)*").unwrap());
+
+// Resolved path and version per program, keyed by the hash of the PATH they were found on
+static RESOLVED: LazyLock<Mutex<HashMap<(String, String), Resolved>>> =
+    LazyLock::new(Mutex::default);
+
+#[derive(Debug, Clone, Default)]
+struct Resolved {
+    path: Option<PathBuf>,
+    version: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyStatus {
+    Found,
+    Missing,
+    // Found, but older than the `>=` version, or its version couldn't be read
+    Outdated,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Dependency {
+    pub name: String,
+    pub min_version: Option<String>,
+    pub status: DependencyStatus,
+    pub path: Option<String>,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyReport {
+    pub path: String,
+    pub ok: bool,
+    pub dependencies: Vec<Dependency>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MissingDependencies<'a> {
+    code: &'static str,
+    #[serde(flatten)]
+    report: &'a DependencyReport,
+}
+
+// What a login shell puts on the PATH, which GUI launches on macOS don't inherit; read once
+#[cfg(unix)]
+pub fn login_path() -> OsString {
+    static LOGIN_PATH: OnceLock<OsString> = OnceLock::new();
+    LOGIN_PATH
+        .get_or_init(|| {
+            let shell = std::env::var_os("SHELL").unwrap_or_else(|| "/bin/sh".into());
+            Command::new(shell)
+                .args(["-l", "-c", "printf %s \"$PATH\""])
+                .stdin(Stdio::null())
+                .stderr(Stdio::null())
+                .output()
+                .ok()
+                .filter(|output| output.status.success() && !output.stdout.is_empty())
+                .map(|output| String::from_utf8_lossy(&output.stdout).into_owned().into())
+                .or_else(|| std::env::var_os("PATH"))
+                .unwrap_or_default()
+        })
+        .clone()
+}
+
+#[cfg(not(unix))]
+pub fn login_path() -> OsString {
+    static LOGIN_PATH: OnceLock<OsString> = OnceLock::new();
+    LOGIN_PATH
+        .get_or_init(|| std::env::var_os("PATH").unwrap_or_default())
+        .clone()
+}
+
+#[cfg(windows)]
+fn candidates(dir: &Path, name: &str) -> Vec<PathBuf> {
+    ["", ".exe", ".cmd", ".bat"]
+        .iter()
+        .map(|ext| dir.join(format!("{}{}", name, ext)))
+        .collect()
+}
+
+#[cfg(not(windows))]
+fn candidates(dir: &Path, name: &str) -> Vec<PathBuf> {
+    vec![dir.join(name)]
+}
+
+fn find_in(path: &OsString, name: &str) -> Option<PathBuf> {
+    if name.contains(['/', '\\']) {
+        let program = PathBuf::from(name);
+        return (program.is_file() && scripts::is_executable(&program)).then_some(program);
+    }
+    std::env::split_paths(path)
+        .flat_map(|dir| candidates(&dir, name))
+        .find(|candidate| candidate.is_file() && scripts::is_executable(candidate))
+}
+
+// First dotted number in `--version` output, e.g. "aws-cli/2.15.0 Python/3.11" gives 2.15.0
+fn version_of(program: &Path) -> Option<String> {
+    let output = Command::new(program)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    let text = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    VERSION.find(&text).map(|m| m.as_str().to_string())
+}
+
+// Numeric, component by component; missing components count as 0
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |v: &str| -> Vec<u64> { v.split('.').map(|p| p.parse().unwrap_or(0)).collect() };
+    let (a, b) = (parts(a), parts(b));
+    (0..a.len().max(b.len()))
+        .map(|i| {
+            a.get(i)
+                .copied()
+                .unwrap_or(0)
+                .cmp(&b.get(i).copied().unwrap_or(0))
+        })
+        .find(|order| order.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+fn resolve(path_hash: &str, path: &OsString, requirement: &Requirement) -> Resolved {
+    let key = (path_hash.to_string(), requirement.name.clone());
+    if let Some(resolved) = RESOLVED
+        .lock()
+        .ok()
+        .and_then(|cache| cache.get(&key).cloned())
+    {
+        // A version found without asking for one may still be unread
+        if requirement.min_version.is_none() || resolved.version.is_some() {
+            return resolved;
+        }
+    }
+    let program = find_in(path, &requirement.name);
+    let resolved = Resolved {
+        version: program
+            .as_deref()
+            .filter(|_| requirement.min_version.is_some())
+            .and_then(version_of),
+        path: program,
+    };
+    if let Ok(mut cache) = RESOLVED.lock() {
+        cache.insert(key, resolved.clone());
+    }
+    resolved
+}
+
+pub fn check(script: &Path) -> Result<DependencyReport, String> {
+    let content = fs::read_to_string(script).map_err(|e| e.to_string())?;
+    let path = login_path();
+    let path_hash = index::hash_bytes(path.to_string_lossy().as_bytes());
+    let dependencies: Vec<Dependency> = metadata::parse_script(&content)
+        .requires
+        .into_iter()
+        .map(|requirement| {
+            let resolved = resolve(&path_hash, &path, &requirement);
+            let status = match (&resolved.path, &requirement.min_version, &resolved.version) {
+                (None, _, _) => DependencyStatus::Missing,
+                (Some(_), None, _) => DependencyStatus::Found,
+                (Some(_), Some(min), Some(version)) if compare_versions(version, min).is_ge() => {
+                    DependencyStatus::Found
+                }
+                (Some(_), Some(_), _) => DependencyStatus::Outdated,
+            };
+            Dependency {
+                name: requirement.name,
+                min_version: requirement.min_version,
+                status,
+                path: resolved.path.map(|p| p.to_string_lossy().to_string()),
+                version: resolved.version,
+            }
+        })
+        .collect();
+    Ok(DependencyReport {
+        path: script.to_string_lossy().to_string(),
+        ok: dependencies
+            .iter()
+            .all(|d| d.status == DependencyStatus::Found),
+        dependencies,
+    })
+}
+
+// For runs with `check_dependencies`: the report becomes the error when anything is missing
+pub fn require(script: &Path) -> Result<(), String> {
+    let report = check(script)?;
+    if report.ok {
+        return Ok(());
+    }
+    let error = MissingDependencies {
+        code: MISSING_DEPENDENCIES_ERROR,
+        report: &report,
+    };
+    Err(serde_json::to_string(&error).map_err(|e| e.to_string())?)
+}
+
+#[tauri::command]
+pub fn check_dependencies(app: AppHandle, script_id: String) -> Result<DependencyReport, String> {
+    startup::guard(Phase::LoginPath)?;
+    let report = check(&scripts::allowed_script(&app, &script_id)?)?;
+    Ok(DependencyReport {
+        path: script_id,
+        ..report
+    })
+}
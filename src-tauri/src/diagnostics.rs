@@ -0,0 +1,427 @@
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::runner::ActiveRuns;
+use crate::watcher::{self, WatcherStatus};
+use crate::{deps, queue, sandbox, scripts, settings};
+
+// Env var names containing any of these have their value replaced in system info
+const SECRET_NAME_PARTS: &[&str] = &[
+    "TOKEN",
+    "SECRET",
+    "PASSWORD",
+    "PASSWD",
+    "PASS",
+    "KEY",
+    "CREDENTIAL",
+    "AUTH",
+    "SESSION",
+    "COOKIE",
+];
+const REDACTED: &str = "[redacted]";
+
+// Probed in this order; the first name found on PATH is reported for each
+const INTERPRETERS: &[(&str, &[&str])] = &[
+    ("bash", &["bash"]),
+    ("zsh", &["zsh"]),
+    ("python", &["python3", "python"]),
+    ("node", &["node"]),
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Interpreter {
+    pub name: String,
+    // None when nothing by that name is on PATH
+    pub path: Option<String>,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OsInfo {
+    pub family: String,
+    pub arch: String,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvReport {
+    pub app_version: String,
+    // PATH as scripts inherit it, one entry per directory
+    pub path: Vec<String>,
+    pub shell: Option<String>,
+    pub home_dir: Option<String>,
+    pub scripts_path: Option<scripts::ScriptsPathInfo>,
+    pub scripts_roots: Vec<String>,
+    pub interpreters: Vec<Interpreter>,
+    pub os: OsInfo,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RootStatus {
+    pub path: String,
+    pub resolved: String,
+    pub exists: bool,
+    pub readable: bool,
+    pub writable: bool,
+}
+
+// Best effort, from the filesystem the path is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VolumeKind {
+    Local,
+    Network,
+    Removable,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvVar {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemInfo {
+    pub app_version: String,
+    pub os: OsInfo,
+    pub shell: Option<String>,
+    // PATH the app was launched with, which is what scripts inherit
+    pub gui_path: Vec<String>,
+    // PATH a login shell sets up, which is what a terminal sees
+    pub login_path: Vec<String>,
+    pub roots: Vec<RootStatus>,
+    pub watcher: WatcherStatus,
+    pub running_runs: usize,
+    pub queued_runs: usize,
+    // The app's environment, with secret-looking values redacted
+    pub env: Vec<EnvVar>,
+}
+
+// First non-empty line of `<program> --version`; python 2 prints it on stderr
+fn version_of(program: &Path) -> Option<String> {
+    let output = Command::new(program)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    let text = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    text.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+fn interpreter(name: &str, candidates: &[&str]) -> Interpreter {
+    let path = candidates
+        .iter()
+        .find_map(|candidate| sandbox::find_program(candidate));
+    Interpreter {
+        name: name.to_string(),
+        version: path.as_deref().and_then(version_of),
+        path: path.map(|p| p.to_string_lossy().to_string()),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn os_version() -> Option<String> {
+    let output = Command::new("sw_vers")
+        .arg("-productVersion")
+        .output()
+        .ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string()).filter(|v| !v.is_empty())
+}
+
+#[cfg(target_os = "linux")]
+fn os_version() -> Option<String> {
+    let release = std::fs::read_to_string("/etc/os-release").ok()?;
+    release
+        .lines()
+        .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+        .map(|name| name.trim_matches('"').to_string())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn os_version() -> Option<String> {
+    let output = Command::new("cmd").args(["/C", "ver"]).output().ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string()).filter(|v| !v.is_empty())
+}
+
+fn split_path(path: &OsString) -> Vec<String> {
+    std::env::split_paths(path)
+        .map(|dir| dir.to_string_lossy().to_string())
+        .collect()
+}
+
+#[cfg(unix)]
+fn can_access(path: &Path, mode: libc::c_int) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    let Ok(path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: access only reads the NUL-terminated path
+    unsafe { libc::access(path.as_ptr(), mode) == 0 }
+}
+
+#[cfg(unix)]
+pub fn is_writable(path: &Path) -> bool {
+    can_access(path, libc::W_OK)
+}
+
+#[cfg(not(unix))]
+pub fn is_writable(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|m| !m.permissions().readonly())
+}
+
+#[cfg(unix)]
+fn statfs(path: &Path) -> Option<libc::statfs> {
+    use std::os::unix::ffi::OsStrExt;
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    // SAFETY: statfs only reads the NUL-terminated path and fills the zeroed struct
+    unsafe {
+        let mut stat: libc::statfs = std::mem::zeroed();
+        (libc::statfs(path.as_ptr(), &mut stat) == 0).then_some(stat)
+    }
+}
+
+// Non-local mounts are network shares; anything else mounted under /Volumes is an external disk
+#[cfg(target_os = "macos")]
+pub fn volume_kind(path: &Path) -> VolumeKind {
+    let Some(stat) = statfs(path) else {
+        return VolumeKind::Unknown;
+    };
+    // SAFETY: the kernel NUL-terminates the mount point
+    let mount_point = unsafe { std::ffi::CStr::from_ptr(stat.f_mntonname.as_ptr()) };
+    if stat.f_flags & libc::MNT_LOCAL as u32 == 0 {
+        VolumeKind::Network
+    } else if mount_point.to_bytes().starts_with(b"/Volumes/") {
+        VolumeKind::Removable
+    } else {
+        VolumeKind::Local
+    }
+}
+
+// NFS, SMB/CIFS and FUSE (sshfs and most cloud drives) by filesystem magic; removable media by
+// where desktops mount it
+#[cfg(target_os = "linux")]
+pub fn volume_kind(path: &Path) -> VolumeKind {
+    const NETWORK_MAGIC: &[u32] = &[0x6969, 0x517b, 0xff53_4d42, 0xfe53_4d42, 0x6573_5546];
+    let Some(stat) = statfs(path) else {
+        return VolumeKind::Unknown;
+    };
+    if NETWORK_MAGIC.contains(&(stat.f_type as u32)) {
+        VolumeKind::Network
+    } else if path.starts_with("/media") || path.starts_with("/run/media") {
+        VolumeKind::Removable
+    } else {
+        VolumeKind::Local
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub fn volume_kind(_path: &Path) -> VolumeKind {
+    VolumeKind::Unknown
+}
+
+fn root_status(root: &Path) -> RootStatus {
+    let resolved = scripts::resolve_root(root);
+    RootStatus {
+        path: root.to_string_lossy().to_string(),
+        resolved: resolved.to_string_lossy().to_string(),
+        exists: resolved.is_dir(),
+        readable: fs::read_dir(&resolved).is_ok(),
+        writable: is_writable(&resolved),
+    }
+}
+
+pub fn is_secret_name(name: &str) -> bool {
+    let name = name.to_uppercase();
+    SECRET_NAME_PARTS.iter().any(|part| name.contains(part))
+}
+
+fn environment() -> Vec<EnvVar> {
+    let mut env: Vec<EnvVar> = std::env::vars()
+        .map(|(name, value)| EnvVar {
+            value: if is_secret_name(&name) {
+                REDACTED.to_string()
+            } else {
+                value
+            },
+            name,
+        })
+        .collect();
+    env.sort_by(|a, b| a.name.cmp(&b.name));
+    env
+}
+
+fn system_info(app: &AppHandle) -> SystemInfo {
+    SystemInfo {
+        app_version: app.package_info().version.to_string(),
+        os: OsInfo {
+            family: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            version: os_version(),
+        },
+        shell: std::env::var("SHELL").ok(),
+        gui_path: std::env::var_os("PATH")
+            .map(|path| split_path(&path))
+            .unwrap_or_default(),
+        login_path: split_path(&deps::login_path()),
+        roots: settings::scripts_roots(app)
+            .iter()
+            .map(|root| root_status(root))
+            .collect(),
+        watcher: watcher::status(app),
+        running_runs: app.state::<ActiveRuns>().list().len(),
+        queued_runs: queue::pending_count(app),
+        env: environment(),
+    }
+}
+
+// Home directory shown as `~`, so reports don't carry the user name
+fn abbreviate(text: &str) -> String {
+    match dirs::home_dir() {
+        Some(home) => {
+            let home = home.to_string_lossy();
+            if home.is_empty() {
+                text.to_string()
+            } else {
+                text.replace(home.as_ref(), "~")
+            }
+        }
+        None => text.to_string(),
+    }
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+fn markdown(info: &SystemInfo) -> String {
+    let mut out = String::from("## System info\n\n");
+    out.push_str(&format!("- App version: {}\n", info.app_version));
+    out.push_str(&format!(
+        "- OS: {} {} ({})\n",
+        info.os.family,
+        info.os.version.as_deref().unwrap_or("unknown version"),
+        info.os.arch
+    ));
+    out.push_str(&format!(
+        "- Shell: {}\n",
+        info.shell.as_deref().unwrap_or("unknown")
+    ));
+    out.push_str(&format!(
+        "- Watcher: {} ({} folders, {} ms debounce)\n",
+        if info.watcher.active {
+            "running"
+        } else {
+            "stopped"
+        },
+        info.watcher.watched.len(),
+        info.watcher.debounce_ms
+    ));
+    out.push_str(&format!(
+        "- Runs: {} running, {} queued\n",
+        info.running_runs, info.queued_runs
+    ));
+
+    out.push_str(
+        "\n### Scripts folders\n\n| Folder | Exists | Readable | Writable |\n|---|---|---|---|\n",
+    );
+    for root in &info.roots {
+        out.push_str(&format!(
+            "| `{}` | {} | {} | {} |\n",
+            root.path,
+            yes_no(root.exists),
+            yes_no(root.readable),
+            yes_no(root.writable)
+        ));
+    }
+    for (title, path) in [
+        ("PATH given to scripts", &info.gui_path),
+        ("Login shell PATH", &info.login_path),
+    ] {
+        out.push_str(&format!(
+            "\n### {}\n\n```\n{}\n```\n",
+            title,
+            path.join("\n")
+        ));
+    }
+    let env: Vec<String> = info
+        .env
+        .iter()
+        .map(|var| format!("{}={}", var.name, var.value))
+        .collect();
+    out.push_str(&format!(
+        "\n<details><summary>Environment</summary>\n\n```\n{}\n```\n\n</details>\n",
+        env.join("\n")
+    ));
+    abbreviate(&out)
+}
+
+#[tauri::command]
+pub async fn get_system_info(app: AppHandle) -> Result<SystemInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || system_info(&app))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Ready to paste into a bug report
+#[tauri::command]
+pub async fn get_system_info_markdown(app: AppHandle) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || markdown(&system_info(&app)))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Everything a "my script can't find X" report needs, in one copyable payload
+#[tauri::command]
+pub async fn environment_report(app: AppHandle) -> Result<EnvReport, String> {
+    tauri::async_runtime::spawn_blocking(move || EnvReport {
+        app_version: app.package_info().version.to_string(),
+        path: std::env::var_os("PATH")
+            .map(|path| {
+                std::env::split_paths(&path)
+                    .map(|dir| dir.to_string_lossy().to_string())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        shell: std::env::var("SHELL").ok(),
+        home_dir: dirs::home_dir().map(|p| p.to_string_lossy().to_string()),
+        scripts_path: scripts::get_scripts_path_info(app.clone()).ok(),
+        scripts_roots: settings::scripts_roots(&app)
+            .iter()
+            .map(|root| root.to_string_lossy().to_string())
+            .collect(),
+        interpreters: INTERPRETERS
+            .iter()
+            .map(|(name, candidates)| interpreter(name, candidates))
+            .collect(),
+        os: OsInfo {
+            family: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            version: os_version(),
+        },
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
@@ -0,0 +1,273 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{dnd, history, notifications, settings, triggers};
+
+const SETTINGS_KEY: &str = "weeklyDigest";
+const MINUTE_MS: u64 = 60 * 1000;
+const HOUR_MS: u64 = 60 * MINUTE_MS;
+const DAY_MS: u64 = 24 * HOUR_MS;
+const WEEK_MS: u64 = 7 * DAY_MS;
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const FAILURES_IN_NOTIFICATION: usize = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DigestSettings {
+    pub enabled: bool,
+    // 0 is Monday, in local time like hour and minute
+    pub weekday: u8,
+    pub hour: u8,
+    pub minute: u8,
+    // When the last digest went out; the next one covers the week before its due time
+    pub last_sent_ms: Option<u64>,
+}
+
+impl Default for DigestSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            weekday: 0,
+            hour: 9,
+            minute: 0,
+            last_sent_ms: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DigestRange {
+    // Defaults to a week before `to_ms`
+    pub from_ms: Option<u64>,
+    // Defaults to now
+    pub to_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DigestFailure {
+    pub path: String,
+    pub name: String,
+    pub failures: usize,
+    // Of the most recent failure
+    pub exit_code: Option<i32>,
+    pub last_failed_at: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Digest {
+    pub from_ms: u64,
+    pub to_ms: u64,
+    pub runs: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub total_duration_ms: u64,
+    pub by_trigger: BTreeMap<String, usize>,
+    // Most failures first
+    pub failures: Vec<DigestFailure>,
+}
+
+fn load(app: &AppHandle) -> DigestSettings {
+    settings::get(app, SETTINGS_KEY).unwrap_or_default()
+}
+
+// Runs someone started by hand don't belong in a digest of unattended runs
+fn unattended(entry: &history::HistoryEntry) -> bool {
+    triggers::is_unattended(entry.trigger.as_deref())
+}
+
+fn name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+// Unattended runs that started in [from, to), counted in place over the history
+pub fn compile(app: &AppHandle, from_ms: u64, to_ms: u64) -> Digest {
+    let mut digest = Digest {
+        from_ms,
+        to_ms,
+        ..Digest::default()
+    };
+    let mut failures: HashMap<String, DigestFailure> = HashMap::new();
+    history::visit(app, |entry| {
+        if entry.started_at < from_ms || entry.started_at >= to_ms || !unattended(entry) {
+            return;
+        }
+        digest.runs += 1;
+        digest.total_duration_ms += entry.duration_ms;
+        if let Some(trigger) = &entry.trigger {
+            *digest.by_trigger.entry(trigger.clone()).or_insert(0) += 1;
+        }
+        if entry.success {
+            digest.succeeded += 1;
+            return;
+        }
+        digest.failed += 1;
+        let failure = failures
+            .entry(entry.path.clone())
+            .or_insert_with(|| DigestFailure {
+                path: entry.path.clone(),
+                name: name(&entry.path),
+                failures: 0,
+                exit_code: None,
+                last_failed_at: 0,
+            });
+        failure.failures += 1;
+        if entry.started_at >= failure.last_failed_at {
+            failure.last_failed_at = entry.started_at;
+            failure.exit_code = entry.exit_code;
+        }
+    });
+    digest.failures = failures.into_values().collect();
+    digest.failures.sort_by(|a, b| {
+        b.failures
+            .cmp(&a.failures)
+            .then(b.last_failed_at.cmp(&a.last_failed_at))
+    });
+    digest
+}
+
+// Milliseconds east of UTC at `ms`, so the schedule follows the local clock
+#[cfg(unix)]
+fn local_offset_ms(ms: u64) -> i64 {
+    let time = (ms / 1000) as libc::time_t;
+    // SAFETY: localtime_r only writes the tm it's given
+    let offset = unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        if libc::localtime_r(&time, &mut tm).is_null() {
+            0
+        } else {
+            tm.tm_gmtoff as i64
+        }
+    };
+    offset * 1000
+}
+
+#[cfg(not(unix))]
+fn local_offset_ms(_ms: u64) -> i64 {
+    0
+}
+
+// The first time after `after_ms` the digest is due
+fn next_due(settings: &DigestSettings, after_ms: u64) -> u64 {
+    let offset = local_offset_ms(after_ms);
+    let local = after_ms as i64 + offset;
+    let today = local.div_euclid(DAY_MS as i64);
+    let time_of_day = (u64::from(settings.hour.min(23)) * HOUR_MS
+        + u64::from(settings.minute.min(59)) * MINUTE_MS) as i64;
+    (0..=7)
+        .map(|days| today + days)
+        // 1970-01-01 was a Thursday
+        .filter(|day| (day + 3).rem_euclid(7) == i64::from(settings.weekday % 7))
+        .map(|day| day * DAY_MS as i64 + time_of_day)
+        .find(|due| *due > local)
+        .map_or(after_ms + WEEK_MS, |due| (due - offset).max(0) as u64)
+}
+
+fn describe(digest: &Digest) -> String {
+    let minutes = digest.total_duration_ms / MINUTE_MS;
+    let mut body = format!(
+        "{} unattended run(s), {} failed, {} min in total",
+        digest.runs, digest.failed, minutes
+    );
+    if !digest.failures.is_empty() {
+        let failed: Vec<String> = digest
+            .failures
+            .iter()
+            .take(FAILURES_IN_NOTIFICATION)
+            .map(|f| match f.exit_code {
+                Some(code) => format!("{} (exit {})", f.name, code),
+                None => f.name.clone(),
+            })
+            .collect();
+        body.push_str(&format!(": {}", failed.join(", ")));
+        if digest.failures.len() > FAILURES_IN_NOTIFICATION {
+            body.push_str(&format!(
+                " and {} more",
+                digest.failures.len() - FAILURES_IN_NOTIFICATION
+            ));
+        }
+    }
+    body
+}
+
+fn send_if_due(app: &AppHandle) {
+    let mut prefs = load(app);
+    if !prefs.enabled {
+        return;
+    }
+    let now = history::now_ms();
+    let Some(last) = prefs.last_sent_ms else {
+        prefs.last_sent_ms = Some(now);
+        let _ = settings::set(app, SETTINGS_KEY, &prefs);
+        return;
+    };
+    let due = next_due(&prefs, last);
+    if now < due {
+        return;
+    }
+    // A digest missed while the app was closed goes out once, for the week before it was due
+    let digest = compile(app, due.saturating_sub(WEEK_MS), due);
+    log::info!("Sending the weekly digest of {} run(s)", digest.runs);
+    if !dnd::is_active(app) {
+        notifications::show(app, "Weekly digest", &describe(&digest));
+    }
+    prefs.last_sent_ms = Some(now);
+    let _ = settings::set(app, SETTINGS_KEY, &prefs);
+}
+
+// Checks once a minute; the last send time is stored, so restarts don't skip or repeat one
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    thread::spawn(move || loop {
+        send_if_due(&app);
+        thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+#[tauri::command]
+pub fn get_digest(app: AppHandle, range: Option<DigestRange>) -> Result<Digest, String> {
+    let range = range.unwrap_or_default();
+    let to_ms = range.to_ms.unwrap_or_else(history::now_ms);
+    let from_ms = range.from_ms.unwrap_or(to_ms.saturating_sub(WEEK_MS));
+    if from_ms > to_ms {
+        return Err("The range starts after it ends".to_string());
+    }
+    Ok(compile(&app, from_ms, to_ms))
+}
+
+#[tauri::command]
+pub fn get_digest_settings(app: AppHandle) -> Result<DigestSettings, String> {
+    Ok(load(&app))
+}
+
+// Turning the digest on starts the schedule from now rather than sending one right away
+#[tauri::command]
+pub fn set_digest_settings(app: AppHandle, digest: DigestSettings) -> Result<(), String> {
+    if digest.weekday > 6 || digest.hour > 23 || digest.minute > 59 {
+        return Err("weekday must be 0-6, hour 0-23 and minute 0-59".to_string());
+    }
+    let previous = load(&app);
+    let last_sent_ms = if digest.enabled && !previous.enabled {
+        Some(history::now_ms())
+    } else {
+        previous.last_sent_ms
+    };
+    settings::set(
+        &app,
+        SETTINGS_KEY,
+        &DigestSettings {
+            last_sent_ms,
+            ..digest
+        },
+    )
+}
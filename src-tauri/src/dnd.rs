@@ -0,0 +1,156 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{history, notifications, settings, tray};
+
+const DND_KEY: &str = "doNotDisturb";
+const SUMMARY_MAX_LINES: usize = 5;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct DndSetting {
+    // Epoch milliseconds at which DND turns itself off; None lasts until disabled
+    until: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DndStatus {
+    pub active: bool,
+    pub until: Option<u64>,
+    pub remaining_ms: Option<u64>,
+}
+
+// Failure notifications held back while DND is on, delivered as one summary at the end
+#[derive(Default)]
+pub struct DndState {
+    // Bumped on every change so stale expiry timers do nothing
+    generation: AtomicU64,
+    queued: Mutex<Vec<String>>,
+}
+
+fn setting(app: &AppHandle) -> Option<DndSetting> {
+    settings::get(app, DND_KEY)
+}
+
+pub fn status(app: &AppHandle) -> DndStatus {
+    let now = history::now_ms();
+    match setting(app) {
+        Some(DndSetting { until }) if until.is_none_or(|u| u > now) => DndStatus {
+            active: true,
+            until,
+            remaining_ms: until.map(|u| u - now),
+        },
+        _ => DndStatus {
+            active: false,
+            until: None,
+            remaining_ms: None,
+        },
+    }
+}
+
+pub fn is_active(app: &AppHandle) -> bool {
+    let status = status(app);
+    if !status.active && setting(app).is_some() {
+        // Expired but the timer hasn't fired yet
+        let _ = end(app);
+    }
+    status.active
+}
+
+pub fn queue_failure(app: &AppHandle, message: String) {
+    if let Ok(mut queued) = app.state::<DndState>().queued.lock() {
+        queued.push(message);
+    }
+}
+
+fn changed(app: &AppHandle) {
+    let _ = tray::rebuild_menu(app);
+    let _ = app.emit("dnd-changed", status(app));
+}
+
+fn send_summary(app: &AppHandle, failures: Vec<String>) {
+    if failures.is_empty() {
+        return;
+    }
+    let title = match failures.len() {
+        1 => "1 run failed during Do Not Disturb".to_string(),
+        n => format!("{} runs failed during Do Not Disturb", n),
+    };
+    let mut lines: Vec<&str> = failures
+        .iter()
+        .take(SUMMARY_MAX_LINES)
+        .map(String::as_str)
+        .collect();
+    let more = failures.len().saturating_sub(SUMMARY_MAX_LINES);
+    let more_line = format!("and {} more", more);
+    if more > 0 {
+        lines.push(&more_line);
+    }
+    notifications::show(app, &title, &lines.join("\n"));
+}
+
+fn end(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<DndState>();
+    state.generation.fetch_add(1, Ordering::Relaxed);
+    settings::delete(app, DND_KEY)?;
+    let failures = state
+        .queued
+        .lock()
+        .map(|mut queued| std::mem::take(&mut *queued))
+        .unwrap_or_default();
+    send_summary(app, failures);
+    changed(app);
+    Ok(())
+}
+
+fn schedule_expiry(app: &AppHandle, until: u64) {
+    let generation = app.state::<DndState>().generation.load(Ordering::Relaxed);
+    let app = app.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(
+            until.saturating_sub(history::now_ms()),
+        ));
+        if app.state::<DndState>().generation.load(Ordering::Relaxed) == generation {
+            let _ = end(&app);
+        }
+    });
+}
+
+// Picks up a timed DND that was still running when the app quit
+pub fn resume(app: &AppHandle) {
+    if let Some(DndSetting { until: Some(until) }) = setting(app) {
+        schedule_expiry(app, until);
+    }
+}
+
+#[tauri::command]
+pub fn get_dnd(app: AppHandle) -> Result<DndStatus, String> {
+    Ok(status(&app))
+}
+
+// Only signaling is muted; runs, including scheduled ones, still execute
+#[tauri::command]
+pub fn set_dnd(app: AppHandle, enabled: bool, until: Option<u64>) -> Result<DndStatus, String> {
+    if !enabled {
+        end(&app)?;
+        return Ok(status(&app));
+    }
+    if until.is_some_and(|u| u <= history::now_ms()) {
+        return Err("Do Not Disturb end time is in the past".to_string());
+    }
+    app.state::<DndState>()
+        .generation
+        .fetch_add(1, Ordering::Relaxed);
+    settings::set(&app, DND_KEY, &DndSetting { until })?;
+    if let Some(until) = until {
+        schedule_expiry(&app, until);
+    }
+    changed(&app);
+    Ok(status(&app))
+}
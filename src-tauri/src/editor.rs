@@ -0,0 +1,233 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::metadata::{self, Directive};
+use crate::{scripts, snapshots, tags};
+
+// Larger files are left to a real editor
+const MAX_EDIT_BYTES: u64 = 1024 * 1024;
+// Prefix of the save error when the file changed since get_script_content read it
+pub const MODIFIED_ON_DISK_ERROR: &str = "modified-on-disk";
+// Prefix of the error for files too large or not text
+pub const NOT_EDITABLE_ERROR: &str = "not-editable";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    // Both kinds, kept as they are on save
+    Mixed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptContent {
+    pub content: String,
+    pub size: u64,
+    // Passed back to save_script_content as `expected_mtime`
+    pub mtime_ms: Option<u64>,
+    pub line_ending: LineEnding,
+}
+
+fn line_ending(text: &str) -> LineEnding {
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+    match (crlf, lf) {
+        (0, _) => LineEnding::Lf,
+        (_, 0) => LineEnding::Crlf,
+        _ => LineEnding::Mixed,
+    }
+}
+
+fn with_line_ending(text: &str, ending: LineEnding) -> String {
+    let lf = text.replace("\r\n", "\n");
+    match ending {
+        LineEnding::Lf => lf,
+        LineEnding::Crlf => lf.replace('\n', "\r\n"),
+        LineEnding::Mixed => text.to_string(),
+    }
+}
+
+fn read_text(script: &Path) -> Result<String, String> {
+    let size = fs::metadata(script).map_err(|e| e.to_string())?.len();
+    if size > MAX_EDIT_BYTES {
+        return Err(format!(
+            "{}: {} is larger than {} KB",
+            NOT_EDITABLE_ERROR,
+            script.display(),
+            MAX_EDIT_BYTES / 1024
+        ));
+    }
+    let bytes = fs::read(script).map_err(|e| e.to_string())?;
+    if bytes.contains(&0) {
+        return Err(format!(
+            "{}: {} is binary",
+            NOT_EDITABLE_ERROR,
+            script.display()
+        ));
+    }
+    String::from_utf8(bytes).map_err(|_| {
+        format!(
+            "{}: {} is not UTF-8 text",
+            NOT_EDITABLE_ERROR,
+            script.display()
+        )
+    })
+}
+
+// Written next to the script and renamed over it, so a failed write never truncates it
+fn write_atomic(script: &Path, content: &str) -> Result<(), String> {
+    let permissions = fs::metadata(script)
+        .map_err(|e| e.to_string())?
+        .permissions();
+    let name = script
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp = script.with_file_name(format!(".{}.sh-runner-tmp", name));
+    let written = fs::File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(content.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::set_permissions(&temp, permissions))
+        .and_then(|()| fs::rename(&temp, script));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp);
+        return Err(format!("Failed to save {}: {}", script.display(), e));
+    }
+    Ok(())
+}
+
+pub fn check_content(content: &str) -> Result<(), String> {
+    if content.len() as u64 > MAX_EDIT_BYTES || content.contains('\0') {
+        return Err(format!(
+            "{}: content must be text under {} KB",
+            NOT_EDITABLE_ERROR,
+            MAX_EDIT_BYTES / 1024
+        ));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_script_content(app: AppHandle, script_id: String) -> Result<ScriptContent, String> {
+    let script = scripts::managed_script(&app, &script_id)?;
+    let content = read_text(&script)?;
+    Ok(ScriptContent {
+        size: content.len() as u64,
+        mtime_ms: snapshots::mtime_ms(&script),
+        line_ending: line_ending(&content),
+        content,
+    })
+}
+
+// Refuses to overwrite changes made on disk after `expected_mtime` was read. Keeps the file's
+// permissions and line endings; returns the new mtime for the next save.
+#[tauri::command]
+pub fn save_script_content(
+    app: AppHandle,
+    script_id: String,
+    content: String,
+    expected_mtime: Option<u64>,
+) -> Result<Option<u64>, String> {
+    let script = scripts::managed_script(&app, &script_id)?;
+    check_content(&content)?;
+    let current = read_text(&script)?;
+    if snapshots::mtime_ms(&script) != expected_mtime {
+        return Err(format!(
+            "{}: {} changed on disk since it was opened; reload it before saving",
+            MODIFIED_ON_DISK_ERROR, script_id
+        ));
+    }
+    write_atomic(&script, &with_line_ending(&content, line_ending(&current)))?;
+    scripts::notify_scripts_changed(&app, vec![script_id]);
+    Ok(snapshots::mtime_ms(&script))
+}
+
+// Sets `key` where it already is in the header, or adds it at the end; None or empty removes it
+fn set_directive(directives: &mut Vec<Directive>, key: &str, value: Option<String>) {
+    let value = value.filter(|v| !v.is_empty());
+    let Some(value) = value else {
+        directives.retain(|d| !d.key.eq_ignore_ascii_case(key));
+        return;
+    };
+    match directives
+        .iter_mut()
+        .find(|d| d.key.eq_ignore_ascii_case(key))
+    {
+        Some(existing) => existing.value = value,
+        None => directives.push(Directive::new(key, value)),
+    }
+}
+
+fn apply_to(
+    app: &AppHandle,
+    path: &str,
+    set_tags: Option<&[String]>,
+    set_description: Option<&str>,
+) -> Result<(), String> {
+    let script = scripts::allowed_script(app, path)?;
+    let current = read_text(&script)?;
+    let mut directives = metadata::parse(current.lines()).directives;
+    if let Some(set_tags) = set_tags {
+        let mut normalized: Vec<String> = Vec::new();
+        for tag in set_tags.iter().map(|t| tags::normalize(t)) {
+            if !tag.is_empty() && !normalized.contains(&tag) {
+                normalized.push(tag);
+            }
+        }
+        set_directive(&mut directives, "tags", Some(normalized.join(", ")));
+    }
+    if let Some(description) = set_description {
+        set_directive(
+            &mut directives,
+            "description",
+            Some(description.trim().to_string()),
+        );
+    }
+    let updated = with_line_ending(
+        &metadata::with_directives(&current, &directives),
+        line_ending(&current),
+    );
+    if updated != current {
+        write_atomic(&script, &updated)?;
+    }
+    Ok(())
+}
+
+// Rewrites the `@tags` and `@description` header directives of each script, keeping the
+// shebang first and the body untouched. An empty list or description removes the directive.
+// Returns the scripts that were updated; the others are logged and skipped.
+#[tauri::command]
+pub fn apply_metadata(
+    app: AppHandle,
+    paths: Vec<String>,
+    set_tags: Option<Vec<String>>,
+    set_description: Option<String>,
+) -> Result<Vec<String>, String> {
+    if set_tags.is_none() && set_description.is_none() {
+        return Err("Nothing to change".to_string());
+    }
+    let mut updated = Vec::new();
+    let mut errors = Vec::new();
+    for path in paths {
+        match apply_to(&app, &path, set_tags.as_deref(), set_description.as_deref()) {
+            Ok(()) => updated.push(path),
+            Err(e) => {
+                log::warn!("Failed to update the metadata of {}: {}", path, e);
+                errors.push(e);
+            }
+        }
+    }
+    if updated.is_empty() && !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    scripts::notify_scripts_changed(&app, updated.clone());
+    Ok(updated)
+}
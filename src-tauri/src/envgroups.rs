@@ -0,0 +1,165 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::AppHandle;
+
+use crate::settings;
+
+const ENV_GROUPS_KEY: &str = "envGroups";
+// Per-script field, an array of group names applied in order
+const SCRIPT_GROUPS_FIELD: &str = "envGroups";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EnvGroup {
+    pub name: String,
+    pub vars: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletedEnvGroup {
+    pub name: String,
+    // Scripts that still list the group; it is skipped, with a warning, when they run
+    pub still_referenced_by: Vec<String>,
+}
+
+// What a script's groups add up to, before its own variables are laid on top
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedGroups {
+    pub applied: Vec<String>,
+    pub missing: Vec<String>,
+    pub vars: HashMap<String, String>,
+}
+
+fn load(app: &AppHandle) -> Vec<EnvGroup> {
+    settings::get(app, ENV_GROUPS_KEY).unwrap_or_default()
+}
+
+fn script_groups(data: &serde_json::Map<String, Value>) -> Vec<String> {
+    data.get(SCRIPT_GROUPS_FIELD)
+        .and_then(Value::as_array)
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn referencing_scripts(app: &AppHandle, name: &str) -> Vec<String> {
+    let scripts: Vec<Value> = settings::get(app, "scripts").unwrap_or_default();
+    scripts
+        .iter()
+        .filter_map(|script| match script {
+            Value::Object(data) if script_groups(data).iter().any(|g| g == name) => {
+                data.get("path").and_then(Value::as_str).map(str::to_string)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+// Later groups win over earlier ones
+pub fn resolve(app: &AppHandle, path: &str) -> ResolvedGroups {
+    let names = settings::script_data(app, path)
+        .map(|data| script_groups(&data))
+        .unwrap_or_default();
+    if names.is_empty() {
+        return ResolvedGroups::default();
+    }
+    let groups = load(app);
+    let mut resolved = ResolvedGroups::default();
+    for name in names {
+        match groups.iter().find(|g| g.name == name) {
+            Some(group) => {
+                resolved.vars.extend(group.vars.clone());
+                resolved.applied.push(name);
+            }
+            None => resolved.missing.push(name),
+        }
+    }
+    resolved
+}
+
+fn validate(vars: &BTreeMap<String, String>) -> Result<(), String> {
+    match vars
+        .keys()
+        .find(|key| key.is_empty() || key.contains(['=', '\0']))
+    {
+        Some(key) => Err(format!("Invalid variable name: {:?}", key)),
+        None => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub fn list_env_groups(app: AppHandle) -> Result<Vec<EnvGroup>, String> {
+    Ok(load(&app))
+}
+
+#[tauri::command]
+pub fn create_env_group(
+    app: AppHandle,
+    name: String,
+    vars: BTreeMap<String, String>,
+) -> Result<EnvGroup, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Group name cannot be empty".to_string());
+    }
+    validate(&vars)?;
+    let mut groups = load(&app);
+    if groups.iter().any(|g| g.name == name) {
+        return Err(format!("Environment group already exists: {}", name));
+    }
+    let group = EnvGroup { name, vars };
+    groups.push(group.clone());
+    settings::set(&app, ENV_GROUPS_KEY, &groups)?;
+    Ok(group)
+}
+
+// Replaces the group's variables; scripts pick up the change on their next run
+#[tauri::command]
+pub fn update_env_group(
+    app: AppHandle,
+    name: String,
+    vars: BTreeMap<String, String>,
+) -> Result<EnvGroup, String> {
+    validate(&vars)?;
+    let mut groups = load(&app);
+    let group = groups
+        .iter_mut()
+        .find(|g| g.name == name)
+        .ok_or_else(|| format!("Environment group not found: {}", name))?;
+    group.vars = vars;
+    let group = group.clone();
+    settings::set(&app, ENV_GROUPS_KEY, &groups)?;
+    Ok(group)
+}
+
+// Scripts keep their reference so re-creating the group brings it back
+#[tauri::command]
+pub fn delete_env_group(app: AppHandle, name: String) -> Result<DeletedEnvGroup, String> {
+    let mut groups = load(&app);
+    let before = groups.len();
+    groups.retain(|g| g.name != name);
+    if groups.len() == before {
+        return Err(format!("Environment group not found: {}", name));
+    }
+    settings::set(&app, ENV_GROUPS_KEY, &groups)?;
+    let still_referenced_by = referencing_scripts(&app, &name);
+    if !still_referenced_by.is_empty() {
+        log::warn!(
+            "Deleted environment group {} is still used by {}",
+            name,
+            still_referenced_by.join(", ")
+        );
+    }
+    Ok(DeletedEnvGroup {
+        name,
+        still_referenced_by,
+    })
+}
@@ -0,0 +1,593 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::AppHandle;
+
+use crate::history::{self, HistoryEntry};
+use crate::triggers::{self, TriggerSource};
+use crate::{audit, envgroups, profiles, runner, scripts, settings};
+
+const EDITOR_KEY: &str = "editorCommand";
+const TERMINAL_KEY: &str = "terminal";
+
+// Editors that open their own window; anything else is assumed to need a terminal
+const GUI_EDITORS: &[&str] = &[
+    "code",
+    "codium",
+    "cursor",
+    "subl",
+    "zed",
+    "mate",
+    "atom",
+    "gedit",
+    "kate",
+    "gvim",
+    "mvim",
+    "idea",
+    "bbedit",
+    "gnome-text-editor",
+    "notepad",
+    "notepad++",
+];
+
+// Splits a command line on whitespace, honouring single and double quotes
+fn split_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    let mut in_word = false;
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+    words
+}
+
+fn shell_quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', r"'\''"))
+}
+
+fn describe(program: &str, args: &[String]) -> String {
+    std::iter::once(program.to_string())
+        .chain(args.iter().cloned())
+        .map(|w| if w.contains(' ') { shell_quote(&w) } else { w })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Starts a program that outlives the app: no inherited stdio, its own process group,
+// and a thread that reaps it so it never lingers as a zombie
+pub fn spawn_detached(program: &str, args: &[String]) -> Result<(), String> {
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to run `{}`: {}", describe(program, args), e))?;
+    thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+// Expands {path} and {line} in a command template, appending the path when absent
+fn editor_argv(template: &str, path: &str, line: u32) -> Vec<String> {
+    let mut words = split_words(template);
+    if !template.contains("{path}") {
+        words.push(path.to_string());
+    }
+    words
+        .into_iter()
+        .map(|w| {
+            w.replace("{path}", path)
+                .replace("{line}", &line.to_string())
+        })
+        .collect()
+}
+
+fn is_gui_editor(program: &str) -> bool {
+    let name = Path::new(program)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    GUI_EDITORS.contains(&name.as_str())
+}
+
+// Which terminal run_in_terminal and terminal editors open; `custom` uses a command
+// template where {cmd} is replaced by the shell command line
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TerminalApp {
+    #[default]
+    Auto,
+    Terminal,
+    Iterm,
+    WindowsTerminal,
+    Cmd,
+    Custom,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TerminalSettings {
+    pub app: TerminalApp,
+    // e.g. `alacritty -e sh -c {cmd}` or `kitty sh -c {cmd}`
+    pub command_template: Option<String>,
+}
+
+fn terminal_settings(app: &AppHandle) -> TerminalSettings {
+    settings::get(app, TERMINAL_KEY).unwrap_or_default()
+}
+
+#[cfg(target_os = "macos")]
+fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn custom_terminal(template: Option<&str>, line: &str) -> Result<(), String> {
+    let template = template
+        .filter(|t| t.contains("{cmd}"))
+        .ok_or_else(|| "The custom terminal command needs a {cmd} placeholder".to_string())?;
+    let argv: Vec<String> = split_words(template)
+        .into_iter()
+        .map(|w| w.replace("{cmd}", line))
+        .collect();
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| "Terminal command is empty".to_string())?;
+    spawn_detached(program, args)
+}
+
+// Opens a new terminal window running `line` through the platform shell
+#[cfg(target_os = "macos")]
+fn open_terminal(terminal: &TerminalSettings, line: &str) -> Result<(), String> {
+    let script = match terminal.app {
+        TerminalApp::Auto | TerminalApp::Terminal => format!(
+            "tell application \"Terminal\"\nactivate\ndo script {}\nend tell",
+            applescript_string(line)
+        ),
+        TerminalApp::Iterm => format!(
+            "tell application \"iTerm\"\nactivate\nset w to (create window with default profile)\n\
+             tell current session of w to write text {}\nend tell",
+            applescript_string(line)
+        ),
+        TerminalApp::Custom => return custom_terminal(terminal.command_template.as_deref(), line),
+        TerminalApp::WindowsTerminal | TerminalApp::Cmd => {
+            return Err("That terminal is only available on Windows".to_string())
+        }
+    };
+    spawn_detached("osascript", &["-e".to_string(), script])
+}
+
+#[cfg(target_os = "windows")]
+fn open_terminal(terminal: &TerminalSettings, line: &str) -> Result<(), String> {
+    let windows_terminal = match terminal.app {
+        TerminalApp::Auto => crate::sandbox::find_program("wt.exe").is_some(),
+        TerminalApp::WindowsTerminal => true,
+        TerminalApp::Cmd => false,
+        TerminalApp::Custom => return custom_terminal(terminal.command_template.as_deref(), line),
+        TerminalApp::Terminal | TerminalApp::Iterm => {
+            return Err("That terminal is only available on macOS".to_string())
+        }
+    };
+    let mut args: Vec<String> = if windows_terminal {
+        vec!["/c".to_string(), "wt".to_string()]
+    } else {
+        vec!["/c".to_string(), "start".to_string(), String::new()]
+    };
+    args.extend(["cmd".to_string(), "/k".to_string(), line.to_string()]);
+    spawn_detached("cmd", &args)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn open_terminal(terminal: &TerminalSettings, line: &str) -> Result<(), String> {
+    match terminal.app {
+        TerminalApp::Auto => {}
+        TerminalApp::Custom => return custom_terminal(terminal.command_template.as_deref(), line),
+        _ => return Err("That terminal is not available on this platform".to_string()),
+    }
+    let argv = ["sh".to_string(), "-c".to_string(), line.to_string()];
+    let mut args = vec!["-e".to_string()];
+    args.extend(argv.iter().cloned());
+    spawn_detached("x-terminal-emulator", &args).or_else(|first| {
+        let mut args = vec!["--".to_string()];
+        args.extend(argv.iter().cloned());
+        spawn_detached("gnome-terminal", &args).map_err(|_| first)
+    })
+}
+
+#[cfg(not(target_os = "windows"))]
+fn command_line(argv: &[String]) -> String {
+    argv.iter()
+        .map(|w| shell_quote(w))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(target_os = "windows")]
+fn command_line(argv: &[String]) -> String {
+    argv.iter()
+        .map(|w| {
+            if w.contains(' ') {
+                format!("\"{}\"", w)
+            } else {
+                w.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Terminal.app, iTerm and cmd /k leave an interactive shell behind; everything else would
+// close the window the moment the script exits
+fn hold_open(terminal: &TerminalSettings, line: String) -> String {
+    let closes = cfg!(not(any(target_os = "macos", target_os = "windows")))
+        || (terminal.app == TerminalApp::Custom && cfg!(not(target_os = "windows")));
+    if closes {
+        format!(
+            "{}; printf '\\n[exited with %s] Press Enter to close ' \"$?\"; read _",
+            line
+        )
+    } else {
+        line
+    }
+}
+
+// The script's own env vars from the store, applied in front of the command
+#[cfg(not(target_os = "windows"))]
+fn with_env(env: &[(String, String)], line: String) -> String {
+    if env.is_empty() {
+        return line;
+    }
+    let assignments: Vec<String> = env
+        .iter()
+        .map(|(key, value)| shell_quote(&format!("{}={}", key, value)))
+        .collect();
+    format!("env {} {}", assignments.join(" "), line)
+}
+
+#[cfg(target_os = "windows")]
+fn with_env(env: &[(String, String)], line: String) -> String {
+    env.iter().rev().fold(line, |line, (key, value)| {
+        format!("set \"{}={}\" && {}", key, value, line)
+    })
+}
+
+#[cfg(not(target_os = "windows"))]
+fn change_dir(dir: &Path) -> String {
+    format!("cd {}", shell_quote(&dir.to_string_lossy()))
+}
+
+#[cfg(target_os = "windows")]
+fn change_dir(dir: &Path) -> String {
+    format!("cd /d \"{}\"", dir.display())
+}
+
+// Runs a terminal editor in a new terminal window
+fn spawn_in_terminal(app: &AppHandle, argv: &[String]) -> Result<(), String> {
+    open_terminal(&terminal_settings(app), &command_line(argv))
+}
+
+fn launch_editor(app: &AppHandle, argv: Vec<String>) -> Result<(), String> {
+    let Some((program, args)) = argv.split_first() else {
+        return Err("Editor command is empty".to_string());
+    };
+    if is_gui_editor(program) {
+        spawn_detached(program, args)
+    } else {
+        spawn_in_terminal(app, &argv)
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn open_with_default_app(path: &str) -> Result<(), String> {
+    spawn_detached("open", &[path.to_string()])
+}
+
+#[cfg(target_os = "windows")]
+pub fn open_with_default_app(path: &str) -> Result<(), String> {
+    spawn_detached(
+        "cmd",
+        &[
+            "/c".to_string(),
+            "start".to_string(),
+            String::new(),
+            path.to_string(),
+        ],
+    )
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub fn open_with_default_app(path: &str) -> Result<(), String> {
+    spawn_detached("xdg-open", &[path.to_string()])
+}
+
+#[cfg(target_os = "macos")]
+const CLIPBOARD_COMMANDS: &[&[&str]] = &[&["pbcopy"]];
+#[cfg(target_os = "windows")]
+const CLIPBOARD_COMMANDS: &[&[&str]] = &[&["clip"]];
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const CLIPBOARD_COMMANDS: &[&[&str]] = &[
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+];
+
+#[cfg(target_os = "macos")]
+const PASTE_COMMANDS: &[&[&str]] = &[&["pbpaste"]];
+#[cfg(target_os = "windows")]
+const PASTE_COMMANDS: &[&[&str]] =
+    &[&["powershell", "-NoProfile", "-Command", "Get-Clipboard -Raw"]];
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const PASTE_COMMANDS: &[&[&str]] = &[
+    &["wl-paste", "--no-newline", "--type", "text"],
+    &[
+        "xclip",
+        "-selection",
+        "clipboard",
+        "-out",
+        "-target",
+        "UTF8_STRING",
+    ],
+    &["xsel", "--clipboard", "--output"],
+];
+
+// The clipboard's text from the first paste tool that starts. Those fail when the clipboard
+// holds only an image or files, which comes back as empty text.
+pub fn read_clipboard() -> Result<String, String> {
+    for argv in PASTE_COMMANDS {
+        let Ok(output) = Command::new(argv[0])
+            .args(&argv[1..])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+        else {
+            continue;
+        };
+        if !output.status.success() {
+            return Ok(String::new());
+        }
+        return String::from_utf8(output.stdout)
+            .map_err(|_| "The clipboard doesn't hold text".to_string());
+    }
+    Err("No clipboard tool found".to_string())
+}
+
+// Pipes the text into the platform's clipboard tool, the first one that starts
+pub fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    use std::io::Write;
+
+    for argv in CLIPBOARD_COMMANDS {
+        let Ok(mut child) = Command::new(argv[0])
+            .args(&argv[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .map_err(|e| e.to_string())?;
+        }
+        let status = child.wait().map_err(|e| e.to_string())?;
+        return if status.success() {
+            Ok(())
+        } else {
+            Err(format!("{} exited with {}", argv[0], status))
+        };
+    }
+    Err("No clipboard tool found".to_string())
+}
+
+// Configured editor command first, then $VISUAL / $EDITOR, then the OS default handler
+#[tauri::command]
+pub fn open_in_editor(app: AppHandle, script_id: String, line: Option<u32>) -> Result<(), String> {
+    if !Path::new(&script_id).is_file() {
+        return Err(format!("Script not found: {}", script_id));
+    }
+    let line = line.unwrap_or(1).max(1);
+    if let Some(template) =
+        settings::get::<String>(&app, EDITOR_KEY).filter(|t| !t.trim().is_empty())
+    {
+        return launch_editor(&app, editor_argv(&template, &script_id, line));
+    }
+    let env_editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty());
+    match env_editor {
+        Some(editor) => launch_editor(&app, editor_argv(&editor, &script_id, line)),
+        None => open_with_default_app(&script_id),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn reveal(path: &Path) -> Result<(), String> {
+    spawn_detached(
+        "open",
+        &["-R".to_string(), path.to_string_lossy().to_string()],
+    )
+}
+
+#[cfg(target_os = "windows")]
+fn reveal(path: &Path) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    // explorer parses its own command line, so the path is quoted by hand
+    let arg = format!("/select,\"{}\"", path.display());
+    Command::new("explorer")
+        .raw_arg(&arg)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to run `explorer {}`: {}", arg, e))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn reveal(path: &Path) -> Result<(), String> {
+    let uri = tauri::Url::from_file_path(path)
+        .map_err(|_| format!("Not an absolute path: {}", path.display()))?;
+    // Ask the file manager over D-Bus to select the item; not every desktop implements it
+    let shown = Command::new("dbus-send")
+        .args([
+            "--session",
+            "--print-reply",
+            "--dest=org.freedesktop.FileManager1",
+            "--type=method_call",
+            "/org/freedesktop/FileManager1",
+            "org.freedesktop.FileManager1.ShowItems",
+            &format!("array:string:{}", uri),
+            "string:",
+        ])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if shown {
+        return Ok(());
+    }
+    let parent = path.parent().unwrap_or(path);
+    spawn_detached("xdg-open", &[parent.to_string_lossy().to_string()])
+}
+
+// Shows the script selected in the file manager; `resolve_symlinks` reveals the
+// link's target instead of the link itself
+#[tauri::command]
+pub fn reveal_script(script_id: String, resolve_symlinks: Option<bool>) -> Result<(), String> {
+    let path = Path::new(&script_id);
+    if fs::symlink_metadata(path).is_err() {
+        return Err(format!("Script no longer exists: {}", script_id));
+    }
+    if resolve_symlinks.unwrap_or(false) {
+        let target = fs::canonicalize(path)
+            .map_err(|e| format!("Could not resolve {}: {}", script_id, e))?;
+        return reveal(&target);
+    }
+    reveal(path)
+}
+
+// Same interpreter and working directory as an in-app run, plus the script's env vars.
+// The app doesn't own the terminal's process, so the history entry has no outcome
+#[tauri::command]
+pub fn run_in_terminal(
+    app: AppHandle,
+    script_id: String,
+    args: Option<Vec<String>>,
+) -> Result<String, String> {
+    let script = Path::new(&script_id);
+    scripts::allowed_script(&app, &script_id)?;
+    let args = args.unwrap_or_default();
+    let mut argv = if runner::has_shebang(script) {
+        vec![script_id.clone()]
+    } else {
+        vec!["bash".to_string(), script_id.clone()]
+    };
+    argv.extend(args.iter().cloned());
+    let own = settings::script_env(&app, &script_id);
+    let groups = envgroups::resolve(&app, &script_id);
+    let mut env: Vec<(String, String)> = groups
+        .vars
+        .into_iter()
+        .filter(|(key, _)| !own.iter().any(|(var, _)| var == key))
+        .collect();
+    env.extend(own);
+
+    let terminal = terminal_settings(&app);
+    let mut line = with_env(&env, command_line(&argv));
+    if let Some(dir) = script.parent() {
+        line = format!("{} && {}", change_dir(dir), line);
+    }
+    open_terminal(&terminal, &hold_open(&terminal, line))?;
+
+    let run_id = runner::next_run_id();
+    // The terminal owns the process, so only the hand-off is audited
+    let _ = audit::record(
+        &app,
+        "run-started",
+        json!({
+            "runId": run_id,
+            "path": script_id,
+            "args": args,
+            "trigger": triggers::TERMINAL,
+            "user": audit::current_user(),
+            "external": true,
+        }),
+    );
+    history::record(
+        &app,
+        HistoryEntry {
+            run_id: run_id.clone(),
+            path: script_id,
+            started_at: history::now_ms(),
+            // Only the hand-off is known to have worked
+            success: true,
+            args,
+            profile: profiles::active_name(&app),
+            trigger: Some(triggers::TERMINAL.to_string()),
+            source: Some(TriggerSource {
+                kind: triggers::TERMINAL.to_string(),
+                detail: None,
+            }),
+            external: true,
+            env_groups: groups.applied,
+            ..HistoryEntry::default()
+        },
+    )?;
+    Ok(run_id)
+}
+
+#[tauri::command]
+pub fn get_terminal_settings(app: AppHandle) -> Result<TerminalSettings, String> {
+    Ok(terminal_settings(&app))
+}
+
+#[tauri::command]
+pub fn set_terminal_settings(app: AppHandle, terminal: TerminalSettings) -> Result<(), String> {
+    settings::set(&app, TERMINAL_KEY, &terminal)
+}
+
+// Opens the given (or saved) terminal with a short message, before the choice is saved
+#[tauri::command]
+pub fn test_terminal(app: AppHandle, terminal: Option<TerminalSettings>) -> Result<(), String> {
+    let terminal = terminal.unwrap_or_else(|| terminal_settings(&app));
+    let line = "echo Scripts Runner can open this terminal".to_string();
+    open_terminal(&terminal, &hold_open(&terminal, line))
+}
+
+// e.g. `code -g {path}:{line}` or `vim +{line} {path}`; None goes back to the fallbacks
+#[tauri::command]
+pub fn set_editor_command(app: AppHandle, command: Option<String>) -> Result<(), String> {
+    match command.filter(|c| !c.trim().is_empty()) {
+        Some(command) => settings::set(&app, EDITOR_KEY, &command),
+        None => settings::delete(&app, EDITOR_KEY),
+    }
+}
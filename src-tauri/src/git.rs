@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::runner::{self, RunOptions};
+use crate::{notifications, scripts, settings};
+
+const FETCH_TIMEOUT_SECS: u64 = 120;
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitStatus {
+    pub is_repo: bool,
+    // None when HEAD is detached
+    pub branch: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+    pub dirty: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitPullResult {
+    pub run_id: String,
+    // False when there was nothing new upstream
+    pub updated: bool,
+    pub status: GitStatus,
+}
+
+// Cheap enough for every tray rebuild: looks for .git without spawning git
+pub fn is_repo(root: &Path) -> bool {
+    scripts::resolve_root(root)
+        .ancestors()
+        .any(|dir| dir.join(".git").exists())
+}
+
+pub fn repo_roots(app: &AppHandle) -> Vec<PathBuf> {
+    settings::scripts_roots(app)
+        .into_iter()
+        .filter(|root| is_repo(root))
+        .collect()
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// Parses `git status --porcelain=v2 --branch`
+fn parse_status(porcelain: &str) -> GitStatus {
+    let mut status = GitStatus {
+        is_repo: true,
+        ..GitStatus::default()
+    };
+    for line in porcelain.lines() {
+        if let Some(head) = line.strip_prefix("# branch.head ") {
+            status.branch = (head != "(detached)").then(|| head.to_string());
+        } else if let Some(upstream) = line.strip_prefix("# branch.upstream ") {
+            status.upstream = Some(upstream.to_string());
+        } else if let Some(counts) = line.strip_prefix("# branch.ab ") {
+            for count in counts.split_whitespace() {
+                if let Some(n) = count.strip_prefix('+') {
+                    status.ahead = n.parse().unwrap_or(0);
+                } else if let Some(n) = count.strip_prefix('-') {
+                    status.behind = n.parse().unwrap_or(0);
+                }
+            }
+        } else {
+            // Ordinary, renamed, unmerged and untracked entries put the path after a fixed
+            // number of fields; renames append the original path after a tab
+            let path = match line.chars().next() {
+                Some('1') => line.splitn(9, ' ').nth(8),
+                Some('2') => line.splitn(10, ' ').nth(9),
+                Some('u') => line.splitn(11, ' ').nth(10),
+                Some('?') => line.get(2..),
+                _ => None,
+            };
+            if let Some(path) = path.and_then(|p| p.split('\t').next()) {
+                status.dirty.push(path.to_string());
+            }
+        }
+    }
+    status
+}
+
+pub fn status(root: &Path) -> Result<GitStatus, String> {
+    if !is_repo(root) {
+        return Ok(GitStatus::default());
+    }
+    let dir = scripts::resolve_root(root);
+    git(&dir, &["status", "--porcelain=v2", "--branch"]).map(|out| parse_status(&out))
+}
+
+// Turns git's refusals into something a user can act on
+fn describe_failure(step: &str, stderr: &str) -> String {
+    let lower = stderr.to_lowercase();
+    let reason = if lower.contains("not possible to fast-forward") || lower.contains("diverg") {
+        "local and remote history have diverged; merge or rebase manually".to_string()
+    } else if lower.contains("would be overwritten") {
+        "local changes would be overwritten; commit or stash them first".to_string()
+    } else if lower.contains("no upstream") || lower.contains("no tracking information") {
+        "the current branch has no upstream".to_string()
+    } else {
+        stderr
+            .trim()
+            .lines()
+            .last()
+            .unwrap_or("unknown error")
+            .to_string()
+    };
+    format!("git {} failed: {}", step, reason)
+}
+
+// Fetch, then a fast-forward only merge: git either moves HEAD or leaves the tree untouched,
+// so a conflict or divergence never leaves a half-applied pull behind
+pub fn pull(app: &AppHandle, root: &Path) -> Result<GitPullResult, String> {
+    let before = status(root)?;
+    if !before.is_repo {
+        return Err(format!("{} is not a git repository", root.display()));
+    }
+    if before.upstream.is_none() {
+        return Err(describe_failure("pull", "no upstream"));
+    }
+    let dir = scripts::resolve_root(root);
+    let head = git(&dir, &["rev-parse", "HEAD"])?;
+    let options = RunOptions {
+        cwd: Some(dir.to_string_lossy().to_string()),
+        // A GUI app has nowhere to answer a credential prompt
+        env: HashMap::from([("GIT_TERMINAL_PROMPT".to_string(), "0".to_string())]),
+        timeout_secs: Some(FETCH_TIMEOUT_SECS),
+        ..RunOptions::default()
+    };
+    let run_id = runner::next_run_id();
+    let label = root.to_string_lossy().to_string();
+    for (step, args) in [
+        ("fetch", vec!["fetch", "--prune"]),
+        ("merge", vec!["merge", "--ff-only", "@{upstream}"]),
+    ] {
+        let argv: Vec<OsString> = ["git"]
+            .into_iter()
+            .chain(args)
+            .map(OsString::from)
+            .collect();
+        let output = runner::stream_command(app, &run_id, &label, argv, &options)?;
+        if !output.success {
+            return Err(describe_failure(step, &output.stderr));
+        }
+    }
+
+    let updated = git(&dir, &["rev-parse", "HEAD"])? != head;
+    if updated {
+        scripts::notify_scripts_changed(app, Vec::new());
+    }
+    Ok(GitPullResult {
+        run_id,
+        updated,
+        status: status(root)?,
+    })
+}
+
+// Tray entry point; the outcome is reported as a notification
+pub fn pull_in_background(app: &AppHandle, root: PathBuf) {
+    let app = app.clone();
+    thread::spawn(move || {
+        let name = root.to_string_lossy().to_string();
+        match pull(&app, &root) {
+            Ok(result) if result.updated => notifications::show(&app, "Scripts updated", &name),
+            Ok(_) => notifications::show(&app, "Scripts already up to date", &name),
+            Err(error) => notifications::show(&app, "Updating scripts failed", &error),
+        }
+    });
+}
+
+#[tauri::command]
+pub fn git_status(app: AppHandle, root_id: String) -> Result<GitStatus, String> {
+    status(&settings::scripts_root(&app, &root_id)?)
+}
+
+#[tauri::command]
+pub async fn git_pull(app: AppHandle, root_id: String) -> Result<GitPullResult, String> {
+    let root = settings::scripts_root(&app, &root_id)?;
+    tauri::async_runtime::spawn_blocking(move || pull(&app, &root))
+        .await
+        .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,141 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::{scripts, settings};
+
+// Bigger files are skipped, they are unlikely to be scripts someone wrote by hand
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptEdge {
+    // The script that mentions `to`
+    pub from: String,
+    pub to: String,
+}
+
+// `a/./b/../c` to `a/c` without touching the disk, so paths compare with the listed ones
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+// The line up to a `#` that starts a word outside quotes
+fn strip_comment(line: &str) -> &str {
+    let mut quote: Option<char> = None;
+    let mut previous = ' ';
+    for (index, c) in line.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q && previous != '\\' => quote = None,
+            (None, '\'' | '"') if previous != '\\' => quote = Some(c),
+            (None, '#') if previous.is_whitespace() || matches!(previous, ';' | '&' | '|') => {
+                return &line[..index];
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+    line
+}
+
+// Shell words, split on whitespace, quotes and operators; good enough to spot file names.
+// Parentheses stay so `"$(dirname "$0")/x.sh"` leaves `)/x.sh` behind.
+fn words(line: &str) -> impl Iterator<Item = &str> {
+    line.split(|c: char| {
+        c.is_whitespace()
+            || matches!(
+                c,
+                '"' | '\'' | '`' | ';' | '|' | '&' | '<' | '>' | '{' | '}' | '='
+            )
+    })
+    .filter(|word| !word.is_empty())
+}
+
+// The scripts a word can refer to: a bare sibling name, a path relative to the script's folder
+// or an absolute one, or `$DIR/name.sh` where only the name is known
+fn resolve(word: &str, dir: &Path, by_path: &HashMap<PathBuf, String>) -> Option<String> {
+    let dynamic = word.contains('$') || word.starts_with(')');
+    let word = word.trim_start_matches('(').trim_end_matches([')', ',']);
+    if !word.contains('/') {
+        return by_path.get(&normalize(&dir.join(word))).cloned();
+    }
+    if dynamic {
+        let name = word.rsplit('/').next()?;
+        return (!name.is_empty() && !name.contains('$'))
+            .then(|| by_path.get(&normalize(&dir.join(name))).cloned())
+            .flatten();
+    }
+    let path = settings::expand_path(word);
+    let path = if path.is_absolute() {
+        path
+    } else {
+        dir.join(path)
+    };
+    by_path.get(&normalize(&path)).cloned()
+}
+
+fn references(script: &str, by_path: &HashMap<PathBuf, String>) -> BTreeSet<String> {
+    let mut found = BTreeSet::new();
+    let too_big = fs::metadata(script).map_or(true, |m| m.len() > MAX_FILE_BYTES);
+    if too_big {
+        return found;
+    }
+    // Non-UTF-8 files fail here and are skipped
+    let Ok(content) = fs::read_to_string(script) else {
+        return found;
+    };
+    let dir = Path::new(script).parent().unwrap_or(Path::new(""));
+    for line in content.lines() {
+        let code = strip_comment(line);
+        for word in words(code) {
+            if let Some(target) = resolve(word, dir, by_path).filter(|t| t != script) {
+                found.insert(target);
+            }
+        }
+    }
+    found
+}
+
+// Which scripts under `dir` mention which others, by name or path. Text in comments is
+// ignored; a name that only appears in a string (an echo, say) still counts.
+#[tauri::command]
+pub async fn script_dependency_graph(dir: String) -> Result<Vec<ScriptEdge>, String> {
+    let root = settings::expand_path(&dir);
+    if !root.is_dir() {
+        return Err(format!("Not a folder: {}", dir));
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let scripts = scripts::all_scripts(vec![root]);
+        let by_path: HashMap<PathBuf, String> = scripts
+            .iter()
+            .map(|script| (normalize(Path::new(script)), script.clone()))
+            .collect();
+        let mut edges: Vec<ScriptEdge> = scripts
+            .iter()
+            .flat_map(|script| {
+                references(script, &by_path)
+                    .into_iter()
+                    .map(|to| ScriptEdge {
+                        from: script.clone(),
+                        to,
+                    })
+            })
+            .collect();
+        edges.sort();
+        edges
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
@@ -0,0 +1,590 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::limits::ResourceUsage;
+use crate::matchers::MatcherHit;
+use crate::startup::{self, Phase};
+use crate::triggers::TriggerSource;
+use crate::{paths, search, settings, workflows};
+
+pub const HISTORY_FILE: &str = "history.json";
+pub const RUNS_DIR: &str = "runs";
+const STDOUT_LOG: &str = "stdout.log";
+const STDERR_LOG: &str = "stderr.log";
+const RETENTION_KEY: &str = "historyRetention";
+const PRUNE_EVERY: usize = 50;
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+// Fewer runs than this and one slow outlier would decide a script's average
+const MIN_TIMED_RUNS: usize = 3;
+const DEFAULT_SLOWEST_LIMIT: usize = 10;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HistoryEntry {
+    pub run_id: String,
+    pub path: String,
+    pub started_at: u64,
+    pub duration_ms: u64,
+    pub exit_code: Option<i32>,
+    pub success: bool,
+    pub timed_out: bool,
+    // Stopped with cancel_script
+    pub cancelled: bool,
+    pub args: Vec<String>,
+    pub profile: Option<String>,
+    pub label: Option<String>,
+    pub trigger: Option<String>,
+    // Handed off to an external terminal, so there's no exit code or duration
+    pub external: bool,
+    // Found unfinished after a crash or power loss; there's no exit code, duration or output
+    pub aborted: bool,
+    pub resource_usage: Option<ResourceUsage>,
+    // Environment groups merged into the run's variables, in the order they were applied
+    pub env_groups: Vec<String>,
+    // Output matcher rules that fired during the run
+    pub matcher_hits: Vec<MatcherHit>,
+    // The run_scripts batch the run belonged to
+    pub batch_id: Option<String>,
+    // Where the run's log files stop, when its output passed the log cap
+    pub log_truncated_at_bytes: Option<u64>,
+    // An unattended run skipped because a run condition failed, and which one
+    pub condition_not_met: Option<String>,
+    // Ran a staged edit rather than the script itself
+    pub staged: bool,
+    // How the run was started, for rerun_history_entry; None on entries from older versions
+    pub rerun: Option<RerunSpec>,
+    // What started the run, in more detail than `trigger`; None on entries from older versions
+    pub source: Option<TriggerSource>,
+    // Hash of the file that ran, the staged copy for staged runs; None on older entries
+    pub content_hash: Option<String>,
+    // Size of the clipboard text run_with_clipboard piped in; the text itself isn't kept
+    pub clipboard_stdin_bytes: Option<u64>,
+    // An unattended run the user cancelled during its confirmation countdown
+    pub countdown_cancelled: bool,
+}
+
+// The run options worth repeating. Env values aren't stored, only which variables were set;
+// a rerun takes their current values from the script's saved env.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RerunSpec {
+    pub env_keys: Vec<String>,
+    pub cwd: Option<String>,
+    pub timeout_secs: Option<u64>,
+    pub encoding: Option<String>,
+    pub run_in_pty: bool,
+    pub sandbox: bool,
+    pub merge_stderr: bool,
+    pub gui_sudo: bool,
+    pub umask: Option<u32>,
+    pub placeholders: HashMap<String, String>,
+    pub trace: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HistoryRetention {
+    pub max_records: Option<usize>,
+    pub max_per_script: Option<usize>,
+    pub max_age_days: Option<u64>,
+    pub keep_logs_longer_than_history: bool,
+}
+
+impl Default for HistoryRetention {
+    fn default() -> Self {
+        Self {
+            max_records: Some(5000),
+            max_per_script: Some(200),
+            max_age_days: Some(90),
+            keep_logs_longer_than_history: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptTiming {
+    pub path: String,
+    pub avg_ms: u64,
+    pub max_ms: u64,
+    pub run_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistorySize {
+    pub records: usize,
+    pub history_bytes: u64,
+    pub artifacts_bytes: u64,
+}
+
+pub struct History {
+    data_dir: Option<PathBuf>,
+    entries: Mutex<Vec<HistoryEntry>>,
+    // Set once the file has been read; until then runs are only kept in memory
+    loaded: AtomicBool,
+    recorded_since_prune: AtomicUsize,
+}
+
+impl History {
+    // Empty until load() reads the file, so setup doesn't wait on it
+    pub fn new(app: &AppHandle) -> Self {
+        Self {
+            data_dir: paths::data_dir(app).ok(),
+            entries: Mutex::new(Vec::new()),
+            loaded: AtomicBool::new(false),
+            recorded_since_prune: AtomicUsize::new(0),
+        }
+    }
+
+    // Runs recorded before the file was read go after the stored ones
+    fn load(&self) -> Result<(), String> {
+        let stored: Vec<HistoryEntry> = self
+            .data_dir
+            .as_ref()
+            .and_then(|dir| fs::read_to_string(dir.join(HISTORY_FILE)).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        let mut entries = self.entries.lock().map_err(|e| e.to_string())?;
+        let recorded = std::mem::replace(&mut *entries, stored);
+        let save = !recorded.is_empty();
+        entries.extend(recorded);
+        self.loaded.store(true, Ordering::SeqCst);
+        if save {
+            self.save(&entries)?;
+        }
+        Ok(())
+    }
+
+    fn save(&self, entries: &[HistoryEntry]) -> Result<(), String> {
+        let Some(dir) = &self.data_dir else {
+            return Err("Could not determine app data directory".to_string());
+        };
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        let json = serde_json::to_string(entries).map_err(|e| e.to_string())?;
+        fs::write(dir.join(HISTORY_FILE), json).map_err(|e| e.to_string())
+    }
+
+    // Per-run artifacts (logs, snapshots) live under runs/<run_id>
+    pub fn run_dir(&self, run_id: &str) -> Option<PathBuf> {
+        self.data_dir
+            .as_ref()
+            .map(|dir| dir.join(RUNS_DIR).join(run_id))
+    }
+}
+
+// Labels are trimmed; blank ones count as no label
+pub fn normalize_label(label: Option<&str>) -> Option<String> {
+    label
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+pub fn retention(app: &AppHandle) -> HistoryRetention {
+    settings::get(app, RETENTION_KEY).unwrap_or_default()
+}
+
+// Indices of entries to drop; the most recent run of every script is always kept
+fn select_pruned(
+    entries: &[HistoryEntry],
+    retention: &HistoryRetention,
+    now: u64,
+) -> HashSet<usize> {
+    let cutoff = retention
+        .max_age_days
+        .map(|days| now.saturating_sub(days * DAY_MS));
+    let mut per_script: HashMap<&str, usize> = HashMap::new();
+    let mut kept = 0;
+    let mut pruned = HashSet::new();
+    for (index, entry) in entries.iter().enumerate().rev() {
+        let count = per_script.entry(entry.path.as_str()).or_insert(0);
+        let expired = cutoff.is_some_and(|c| entry.started_at < c);
+        let over_script = retention.max_per_script.is_some_and(|max| *count >= max);
+        let over_total = retention.max_records.is_some_and(|max| kept >= max);
+        if *count > 0 && (expired || over_script || over_total) {
+            pruned.insert(index);
+        } else {
+            *count += 1;
+            kept += 1;
+        }
+    }
+    pruned
+}
+
+// Startup phase: reads the stored runs, then prunes them
+pub fn load(app: &AppHandle) {
+    if let Err(e) = app.state::<History>().load() {
+        log::error!("Failed to load run history: {}", e);
+    }
+    let _ = prune(app);
+}
+
+pub fn prune(app: &AppHandle) -> Result<usize, String> {
+    startup::guard(Phase::History)?;
+    let retention = retention(app);
+    let history = app.state::<History>();
+    let removed: Vec<HistoryEntry> = {
+        let mut entries = history.entries.lock().map_err(|e| e.to_string())?;
+        let pruned = select_pruned(&entries, &retention, now_ms());
+        if pruned.is_empty() {
+            return Ok(0);
+        }
+        let (removed, kept) = std::mem::take(&mut *entries)
+            .into_iter()
+            .enumerate()
+            .partition::<Vec<_>, _>(|(index, _)| pruned.contains(index));
+        *entries = kept.into_iter().map(|(_, e)| e).collect();
+        history.save(&entries)?;
+        removed.into_iter().map(|(_, e)| e).collect()
+    };
+    // Artifacts are deleted after the lock is released so runs can keep recording
+    if !retention.keep_logs_longer_than_history {
+        for entry in &removed {
+            if let Some(dir) = history.run_dir(&entry.run_id) {
+                let _ = fs::remove_dir_all(dir);
+            }
+        }
+    }
+    Ok(removed.len())
+}
+
+pub fn prune_in_background(app: &AppHandle) {
+    let app = app.clone();
+    thread::spawn(move || {
+        let _ = prune(&app);
+    });
+}
+
+pub fn record(app: &AppHandle, entry: HistoryEntry) -> Result<(), String> {
+    let history = app.state::<History>();
+    {
+        let mut entries = history.entries.lock().map_err(|e| e.to_string())?;
+        entries.push(entry);
+        if history.loaded.load(Ordering::SeqCst) {
+            history.save(&entries)?;
+        }
+    }
+    if history.recorded_since_prune.fetch_add(1, Ordering::Relaxed) + 1 >= PRUNE_EVERY {
+        history.recorded_since_prune.store(0, Ordering::Relaxed);
+        prune_in_background(app);
+    }
+    Ok(())
+}
+
+pub fn find(app: &AppHandle, run_id: &str) -> Option<HistoryEntry> {
+    let history = app.state::<History>();
+    let entries = history.entries.lock().ok()?;
+    entries.iter().rev().find(|e| e.run_id == run_id).cloned()
+}
+
+// The run's log files were written while it ran; this makes them searchable
+pub fn index_output(app: &AppHandle, run_id: &str) {
+    if let Some(dir) = run_dir(app, run_id) {
+        search::index_in_background(dir, log_files(app, run_id));
+    }
+}
+
+pub fn run_dir(app: &AppHandle, run_id: &str) -> Option<PathBuf> {
+    app.state::<History>().run_dir(run_id)
+}
+
+// A run's log files by stream, whether or not they exist
+pub fn log_files(app: &AppHandle, run_id: &str) -> Vec<(&'static str, PathBuf)> {
+    run_dir(app, run_id)
+        .map(|dir| {
+            vec![
+                ("stdout", dir.join(STDOUT_LOG)),
+                ("stderr", dir.join(STDERR_LOG)),
+            ]
+        })
+        .unwrap_or_default()
+}
+
+// None when the run kept no logs, e.g. terminal hand-offs or runs from before logs were kept
+pub fn read_output(app: &AppHandle, run_id: &str) -> Option<(String, String)> {
+    let dir = app.state::<History>().run_dir(run_id)?;
+    let stdout = fs::read_to_string(dir.join(STDOUT_LOG)).ok()?;
+    let stderr = fs::read_to_string(dir.join(STDERR_LOG)).unwrap_or_default();
+    Some((stdout, stderr))
+}
+
+pub fn latest_for(app: &AppHandle, path: &str) -> Option<HistoryEntry> {
+    let history = app.state::<History>();
+    let entries = history.entries.lock().ok()?;
+    entries.iter().rev().find(|e| e.path == path).cloned()
+}
+
+// Mean duration of the last few finished runs of `path`, for queue estimates
+pub fn average_duration(app: &AppHandle, path: &str) -> Option<u64> {
+    const SAMPLE: usize = 10;
+    let history = app.state::<History>();
+    let entries = history.entries.lock().ok()?;
+    let durations: Vec<u64> = entries
+        .iter()
+        .rev()
+        .filter(|e| e.path == path && !e.external)
+        .take(SAMPLE)
+        .map(|e| e.duration_ms)
+        .collect();
+    (!durations.is_empty()).then(|| durations.iter().sum::<u64>() / durations.len() as u64)
+}
+
+// Median duration of the recent successful runs of `path`, for remaining-time estimates.
+// Failed, timed-out and cancelled runs don't count; None with too few runs, or when they
+// vary too much for the median to mean anything.
+pub fn typical_duration(app: &AppHandle, path: &str) -> Option<u64> {
+    const SAMPLE: usize = 20;
+    const MIN_RUNS: usize = 3;
+    // Standard deviation over mean
+    const MAX_VARIATION: f64 = 0.5;
+    let history = app.state::<History>();
+    let entries = history.entries.lock().ok()?;
+    let mut durations: Vec<u64> = entries
+        .iter()
+        .rev()
+        .filter(|e| {
+            e.path == path && e.success && !e.timed_out && !e.cancelled && !e.external && !e.aborted
+        })
+        .take(SAMPLE)
+        .map(|e| e.duration_ms)
+        .collect();
+    drop(entries);
+    if durations.len() < MIN_RUNS {
+        return None;
+    }
+    let count = durations.len() as f64;
+    let mean = durations.iter().sum::<u64>() as f64 / count;
+    let variance = durations
+        .iter()
+        .map(|d| (*d as f64 - mean).powi(2))
+        .sum::<f64>()
+        / count;
+    if mean <= 0.0 || variance.sqrt() / mean > MAX_VARIATION {
+        return None;
+    }
+    durations.sort_unstable();
+    Some(durations[durations.len() / 2])
+}
+
+// Latest finished run per script, from a single pass; external runs have no outcome and are skipped
+pub fn latest_by_path(app: &AppHandle) -> HashMap<String, HistoryEntry> {
+    let history = app.state::<History>();
+    let Ok(entries) = history.entries.lock() else {
+        return HashMap::new();
+    };
+    let mut latest = HashMap::new();
+    for entry in entries.iter().rev().filter(|e| !e.external) {
+        latest
+            .entry(entry.path.clone())
+            .or_insert_with(|| entry.clone());
+    }
+    latest
+}
+
+// Keeps past runs attached to a script after it was renamed
+pub fn rename_path(app: &AppHandle, from: &str, to: &str) -> Result<(), String> {
+    rename_paths(app, |path| (path == from).then(|| to.to_string()))
+}
+
+// `rename` returns the new path for entries that should move
+pub fn rename_paths(
+    app: &AppHandle,
+    rename: impl Fn(&str) -> Option<String>,
+) -> Result<(), String> {
+    startup::guard(Phase::History)?;
+    let history = app.state::<History>();
+    let mut entries = history.entries.lock().map_err(|e| e.to_string())?;
+    let mut changed = false;
+    for entry in entries.iter_mut() {
+        if let Some(path) = rename(&entry.path) {
+            entry.path = path;
+            changed = true;
+        }
+    }
+    if changed {
+        history.save(&entries)?;
+    }
+    Ok(())
+}
+
+// Walks every entry under the lock, for summaries that shouldn't copy the whole history
+pub fn visit(app: &AppHandle, visit: impl FnMut(&HistoryEntry)) {
+    let history = app.state::<History>();
+    let Ok(entries) = history.entries.lock() else {
+        return;
+    };
+    entries.iter().for_each(visit);
+}
+
+// The run that started last among those matching `filter`
+pub fn last_started(
+    app: &AppHandle,
+    filter: impl Fn(&HistoryEntry) -> bool,
+) -> Option<HistoryEntry> {
+    let history = app.state::<History>();
+    let entries = history.entries.lock().ok()?;
+    entries
+        .iter()
+        .filter(|e| filter(e))
+        .max_by_key(|e| e.started_at)
+        .cloned()
+}
+
+pub fn clear(app: &AppHandle) -> Result<(), String> {
+    startup::guard(Phase::History)?;
+    let history = app.state::<History>();
+    let mut entries = history.entries.lock().map_err(|e| e.to_string())?;
+    entries.clear();
+    history.save(&entries)?;
+    if let Some(dir) = &history.data_dir {
+        let _ = fs::remove_dir_all(dir.join(RUNS_DIR));
+    }
+    Ok(())
+}
+
+// Adds runs from another install; a run id already here is replaced only with `overwrite`.
+// Returns how many were added or replaced and how many were skipped.
+pub fn merge(
+    app: &AppHandle,
+    incoming: Vec<HistoryEntry>,
+    overwrite: bool,
+) -> Result<(usize, usize), String> {
+    startup::guard(Phase::History)?;
+    let history = app.state::<History>();
+    let mut entries = history.entries.lock().map_err(|e| e.to_string())?;
+    let (mut imported, mut skipped) = (0, 0);
+    for entry in incoming {
+        match entries.iter().position(|e| e.run_id == entry.run_id) {
+            Some(index) if overwrite => {
+                entries[index] = entry;
+                imported += 1;
+            }
+            Some(_) => skipped += 1,
+            None => {
+                entries.push(entry);
+                imported += 1;
+            }
+        }
+    }
+    if imported > 0 {
+        entries.sort_by_key(|e| e.started_at);
+        history.save(&entries)?;
+    }
+    Ok((imported, skipped))
+}
+
+// Newest first, optionally narrowed to one script and/or one label
+#[tauri::command]
+pub fn get_run_history(
+    app: AppHandle,
+    path: Option<String>,
+    limit: Option<usize>,
+    label_filter: Option<String>,
+) -> Result<Vec<HistoryEntry>, String> {
+    startup::guard(Phase::History)?;
+    let label_filter = normalize_label(label_filter.as_deref());
+    let history = app.state::<History>();
+    let entries = history.entries.lock().map_err(|e| e.to_string())?;
+    Ok(entries
+        .iter()
+        .rev()
+        .filter(|e| path.as_ref().is_none_or(|p| &e.path == p))
+        .filter(|e| label_filter.is_none() || e.label == label_filter)
+        .take(limit.unwrap_or(usize::MAX))
+        .cloned()
+        .collect())
+}
+
+#[tauri::command]
+pub fn get_history_size(app: AppHandle) -> Result<HistorySize, String> {
+    startup::guard(Phase::History)?;
+    let history = app.state::<History>();
+    let records = history.entries.lock().map_err(|e| e.to_string())?.len();
+    let (history_bytes, artifacts_bytes) = match &history.data_dir {
+        Some(dir) => (
+            fs::metadata(dir.join(HISTORY_FILE))
+                .map(|m| m.len())
+                .unwrap_or(0),
+            dir_size(&dir.join(RUNS_DIR)),
+        ),
+        None => (0, 0),
+    };
+    Ok(HistorySize {
+        records,
+        history_bytes,
+        artifacts_bytes,
+    })
+}
+
+#[tauri::command]
+pub fn get_history_retention(app: AppHandle) -> Result<HistoryRetention, String> {
+    Ok(retention(&app))
+}
+
+#[tauri::command]
+pub fn set_history_retention(app: AppHandle, retention: HistoryRetention) -> Result<(), String> {
+    settings::set(&app, RETENTION_KEY, &retention)?;
+    prune_in_background(&app);
+    Ok(())
+}
+
+// Highest average duration first, over scripts with at least MIN_TIMED_RUNS runs. Terminal
+// hand-offs, aborted runs and workflows have no duration of their own and are left out.
+#[tauri::command]
+pub fn slowest_scripts(app: AppHandle, limit: Option<usize>) -> Result<Vec<ScriptTiming>, String> {
+    startup::guard(Phase::History)?;
+    // Total, longest and count per script
+    let mut totals: HashMap<String, (u64, u64, usize)> = HashMap::new();
+    visit(&app, |entry| {
+        if entry.external || entry.aborted || entry.path.starts_with(workflows::HISTORY_PREFIX) {
+            return;
+        }
+        let (total, max, count) = totals.entry(entry.path.clone()).or_default();
+        *total += entry.duration_ms;
+        *max = (*max).max(entry.duration_ms);
+        *count += 1;
+    });
+    let mut timings: Vec<ScriptTiming> = totals
+        .into_iter()
+        .filter(|(_, (_, _, count))| *count >= MIN_TIMED_RUNS)
+        .map(|(path, (total, max, count))| ScriptTiming {
+            path,
+            avg_ms: total / count as u64,
+            max_ms: max,
+            run_count: count,
+        })
+        .collect();
+    timings.sort_by(|a, b| b.avg_ms.cmp(&a.avg_ms).then_with(|| a.path.cmp(&b.path)));
+    timings.truncate(limit.unwrap_or(DEFAULT_SLOWEST_LIMIT));
+    Ok(timings)
+}
@@ -0,0 +1,257 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::thread;
+
+use serde_json::Value;
+use tauri::AppHandle;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::runner::{self, RunOptions};
+use crate::{danger, notifications, readonly, settings, triggers, window};
+
+// Must match the default in src/hooks/useStore.ts
+pub const DEFAULT_HOTKEY: &str = "CommandOrControl+Shift+R";
+const RUN_AGAIN_HOTKEY_KEY: &str = "runAgainHotkey";
+// Script path -> accelerator
+const SCRIPT_HOTKEYS_KEY: &str = "scriptHotkeys";
+// Prefix of the error returned when an accelerator is already bound
+pub const HOTKEY_CONFLICT_ERROR: &str = "hotkey-conflict";
+
+pub fn register_toggle_hotkey(app: &AppHandle, accelerator: &str) -> Result<(), String> {
+    let shortcuts = app.global_shortcut();
+    if shortcuts.is_registered(accelerator) {
+        shortcuts
+            .unregister(accelerator)
+            .map_err(|e| e.to_string())?;
+    }
+    shortcuts
+        .on_shortcut(accelerator, |app, _shortcut, event| {
+            if event.state == ShortcutState::Pressed {
+                window::toggle_main_window(app);
+            }
+        })
+        .map_err(|e| e.to_string())
+}
+
+fn register_run_again_hotkey(app: &AppHandle, accelerator: &str) -> Result<(), String> {
+    app.global_shortcut()
+        .on_shortcut(accelerator, |app, _shortcut, event| {
+            if event.state == ShortcutState::Pressed {
+                runner::run_last_again(app, triggers::HOTKEY);
+            }
+        })
+        .map_err(|e| e.to_string())
+}
+
+// Re-registers the stored run-again hotkey at startup
+pub fn restore_run_again_hotkey(app: &AppHandle) {
+    if let Some(accelerator) = settings::get::<String>(app, RUN_AGAIN_HOTKEY_KEY) {
+        let _ = register_run_again_hotkey(app, &accelerator);
+    }
+}
+
+fn unregister(app: &AppHandle, accelerator: &str) -> Result<(), String> {
+    let shortcuts = app.global_shortcut();
+    if shortcuts.is_registered(accelerator) {
+        shortcuts
+            .unregister(accelerator)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn parse(accelerator: &str) -> Result<Shortcut, String> {
+    accelerator
+        .parse()
+        .map_err(|e| format!("Invalid hotkey \"{}\": {}", accelerator, e))
+}
+
+fn script_hotkeys(app: &AppHandle) -> BTreeMap<String, String> {
+    settings::get(app, SCRIPT_HOTKEYS_KEY).unwrap_or_default()
+}
+
+fn toggle_hotkey(app: &AppHandle) -> String {
+    let frontend: Value = settings::get(app, "settings").unwrap_or_default();
+    frontend["globalHotkey"]
+        .as_str()
+        .unwrap_or(DEFAULT_HOTKEY)
+        .to_string()
+}
+
+// Compares parsed shortcuts, so "Ctrl+Shift+K" and "Control+Shift+K" count as the same binding
+fn check_conflict(app: &AppHandle, accelerator: &str, owner: Option<&str>) -> Result<(), String> {
+    let wanted = parse(accelerator)?;
+    let same = |other: &str| parse(other).is_ok_and(|other| other == wanted);
+    let conflict = if same(&toggle_hotkey(app)) {
+        Some("the show/hide window hotkey".to_string())
+    } else if owner.is_some()
+        && settings::get::<String>(app, RUN_AGAIN_HOTKEY_KEY).is_some_and(|a| same(&a))
+    {
+        Some("the run-again hotkey".to_string())
+    } else {
+        script_hotkeys(app)
+            .into_iter()
+            .find(|(path, other)| Some(path.as_str()) != owner && same(other))
+            .map(|(path, _)| format!("the hotkey for {}", path))
+    };
+    match conflict {
+        Some(binding) => Err(format!(
+            "{}: {} is already used by {}",
+            HOTKEY_CONFLICT_ERROR, accelerator, binding
+        )),
+        None => Ok(()),
+    }
+}
+
+// None removes the hotkey
+#[tauri::command]
+pub fn set_run_again_hotkey(app: AppHandle, accelerator: Option<String>) -> Result<(), String> {
+    let accelerator = accelerator.filter(|a| !a.trim().is_empty());
+    if let Some(accelerator) = &accelerator {
+        check_conflict(&app, accelerator, None)?;
+    }
+    if let Some(previous) = settings::get::<String>(&app, RUN_AGAIN_HOTKEY_KEY) {
+        unregister(&app, &previous)?;
+    }
+    match accelerator {
+        Some(accelerator) => {
+            register_run_again_hotkey(&app, &accelerator)?;
+            settings::set(&app, RUN_AGAIN_HOTKEY_KEY, &accelerator)
+        }
+        None => settings::delete(&app, RUN_AGAIN_HOTKEY_KEY),
+    }
+}
+
+// Looks the script up when the key is pressed, so a renamed script keeps its hotkey
+fn run_script_for(app: &AppHandle, pressed: &Shortcut) {
+    let Some((path, accelerator)) = script_hotkeys(app)
+        .into_iter()
+        .find(|(_, accelerator)| parse(accelerator).is_ok_and(|s| s == *pressed))
+    else {
+        return;
+    };
+    let app = app.clone();
+    thread::spawn(move || {
+        if !Path::new(&path).is_file() {
+            return notifications::show(&app, "Script not found", &path);
+        }
+        if let Err(error) = readonly::guard() {
+            return notifications::show(&app, "Failed to run script", &error);
+        }
+        let confirmed = danger::is_dangerous(&app, &path);
+        if confirmed && !danger::confirm(&app, &path) {
+            return;
+        }
+        let options = RunOptions {
+            env: settings::script_env(&app, &path).into_iter().collect(),
+            trigger: Some(triggers::HOTKEY.to_string()),
+            trigger_detail: Some(accelerator),
+            confirmed,
+            ..RunOptions::default()
+        };
+        if let Err(error) = runner::execute(&app, &path, &options) {
+            notifications::show(&app, "Failed to run script", &error);
+        }
+    });
+}
+
+fn register_script_hotkey(app: &AppHandle, accelerator: &str) -> Result<(), String> {
+    app.global_shortcut()
+        .on_shortcut(accelerator, |app, shortcut, event| {
+            if event.state == ShortcutState::Pressed {
+                run_script_for(app, shortcut);
+            }
+        })
+        .map_err(|e| e.to_string())
+}
+
+// Re-registers the stored per-script hotkeys at startup
+pub fn restore_script_hotkeys(app: &AppHandle) {
+    for accelerator in script_hotkeys(app).values() {
+        let _ = register_script_hotkey(app, accelerator);
+    }
+}
+
+// The accelerators the stored settings bind, taken before they're reloaded
+pub struct Bindings {
+    toggle: String,
+    run_again: Option<String>,
+    scripts: Vec<String>,
+}
+
+pub fn bindings(app: &AppHandle) -> Bindings {
+    Bindings {
+        toggle: toggle_hotkey(app),
+        run_again: run_again_hotkey(app),
+        scripts: script_hotkeys(app).into_values().collect(),
+    }
+}
+
+// After the settings were reloaded: drops the hotkeys bound `before` and binds the stored ones
+pub fn reapply(app: &AppHandle, before: Bindings) {
+    for accelerator in before.run_again.iter().chain(&before.scripts) {
+        let _ = unregister(app, accelerator);
+    }
+    restore_run_again_hotkey(app);
+    restore_script_hotkeys(app);
+    let toggle = toggle_hotkey(app);
+    if toggle != before.toggle {
+        let _ = unregister(app, &before.toggle);
+        if let Err(e) = register_toggle_hotkey(app, &toggle) {
+            log::warn!("Failed to register {}: {}", toggle, e);
+        }
+    }
+}
+
+pub fn has_script_hotkey(app: &AppHandle, path: &str) -> bool {
+    script_hotkeys(app).contains_key(path)
+}
+
+pub fn script_hotkey_paths(app: &AppHandle) -> Vec<String> {
+    script_hotkeys(app).into_keys().collect()
+}
+
+pub fn script_hotkey(app: &AppHandle, path: &str) -> Option<String> {
+    script_hotkeys(app).remove(path)
+}
+
+pub fn run_again_hotkey(app: &AppHandle) -> Option<String> {
+    settings::get(app, RUN_AGAIN_HOTKEY_KEY)
+}
+
+// Keeps a script's hotkey with it when it's renamed, or drops it with `to` None on delete
+pub fn move_script_hotkey(app: &AppHandle, from: &str, to: Option<&str>) -> Result<(), String> {
+    let mut hotkeys = script_hotkeys(app);
+    let Some(accelerator) = hotkeys.remove(from) else {
+        return Ok(());
+    };
+    match to {
+        Some(to) => {
+            hotkeys.insert(to.to_string(), accelerator);
+        }
+        None => unregister(app, &accelerator)?,
+    }
+    settings::set(app, SCRIPT_HOTKEYS_KEY, &hotkeys)
+}
+
+// Replaces any hotkey the script already had
+#[tauri::command]
+pub fn set_script_hotkey(app: AppHandle, path: String, accelerator: String) -> Result<(), String> {
+    let accelerator = accelerator.trim().to_string();
+    if !Path::new(&path).is_file() {
+        return Err(format!("Script not found: {}", path));
+    }
+    check_conflict(&app, &accelerator, Some(&path))?;
+    let mut hotkeys = script_hotkeys(&app);
+    if let Some(previous) = hotkeys.remove(&path) {
+        unregister(&app, &previous)?;
+    }
+    register_script_hotkey(&app, &accelerator)?;
+    hotkeys.insert(path, accelerator);
+    settings::set(&app, SCRIPT_HOTKEYS_KEY, &hotkeys)
+}
+
+#[tauri::command]
+pub fn clear_script_hotkey(app: AppHandle, path: String) -> Result<(), String> {
+    move_script_hotkey(&app, &path, None)
+}
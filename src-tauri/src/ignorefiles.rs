@@ -0,0 +1,233 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use glob::{MatchOptions, Pattern};
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::{scripts, settings, watcher};
+
+const ENABLED_KEY: &str = "respectIgnoreFiles";
+// Read in this order in every folder, so the app's own file has the last word
+const IGNORE_FILES: &[&str] = &[".gitignore", ".shrunnerignore"];
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+const OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+struct Rule {
+    pattern: Pattern,
+    negated: bool,
+    // A trailing slash only matches folders
+    dir_only: bool,
+    // A slash anywhere but the end ties the pattern to its file's folder; otherwise it
+    // matches the name at any depth
+    anchored: bool,
+    file: PathBuf,
+    line: usize,
+    text: String,
+}
+
+// The rules of one folder's ignore files
+struct Level {
+    dir: PathBuf,
+    rules: Vec<Rule>,
+}
+
+// Ignore files from the root of a scan down to the folder being read
+#[derive(Default)]
+pub struct IgnoreStack(Vec<Level>);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IgnoreMatch {
+    pub file: String,
+    pub line: usize,
+    pub pattern: String,
+    // A `!` rule that brought the path back
+    pub negated: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IgnoreCheck {
+    pub ignored: bool,
+    // The rule that decided, on the path or one of its folders; None when no rule matched
+    pub rule: Option<IgnoreMatch>,
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn restore(app: &AppHandle) {
+    let enabled = settings::get(app, ENABLED_KEY).unwrap_or(true);
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+fn parse_line(raw: &str) -> Option<(String, bool, bool, bool)> {
+    let line = raw.trim_end();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (line, negated) = match line.strip_prefix('!') {
+        Some(rest) => (rest, true),
+        None => (line.strip_prefix('\\').unwrap_or(line), false),
+    };
+    let (line, dir_only) = match line.strip_suffix('/') {
+        Some(rest) => (rest, true),
+        None => (line, false),
+    };
+    let anchored = line.contains('/');
+    let line = line.strip_prefix('/').unwrap_or(line);
+    (!line.is_empty()).then(|| (line.to_string(), negated, dir_only, anchored))
+}
+
+fn read_rules(dir: &Path) -> Vec<Rule> {
+    let mut rules = Vec::new();
+    for name in IGNORE_FILES {
+        let file = dir.join(name);
+        let Ok(content) = fs::read_to_string(&file) else {
+            continue;
+        };
+        for (index, raw) in content.lines().enumerate() {
+            let Some((text, negated, dir_only, anchored)) = parse_line(raw) else {
+                continue;
+            };
+            match Pattern::new(&text) {
+                Ok(pattern) => rules.push(Rule {
+                    pattern,
+                    negated,
+                    dir_only,
+                    anchored,
+                    file: file.clone(),
+                    line: index + 1,
+                    text: raw.trim_end().to_string(),
+                }),
+                Err(e) => log::warn!("Skipping {}:{}: {}", file.display(), index + 1, e),
+            }
+        }
+    }
+    rules
+}
+
+impl Rule {
+    fn matches(&self, relative: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        if self.anchored {
+            return self.pattern.matches_with(relative, OPTIONS);
+        }
+        let name = relative.rsplit('/').next().unwrap_or(relative);
+        self.pattern.matches_with(name, OPTIONS)
+    }
+}
+
+impl IgnoreStack {
+    // Called when the scan enters `dir`; pair with leave
+    pub fn enter(&mut self, dir: &Path) {
+        let rules = if is_enabled() {
+            read_rules(dir)
+        } else {
+            Vec::new()
+        };
+        self.0.push(Level {
+            dir: dir.to_path_buf(),
+            rules,
+        });
+    }
+
+    pub fn leave(&mut self) {
+        self.0.pop();
+    }
+
+    // The last matching rule wins, deeper files over shallower ones
+    fn decide(&self, path: &Path, is_dir: bool) -> Option<&Rule> {
+        let mut decided = None;
+        for level in &self.0 {
+            let Ok(relative) = path.strip_prefix(&level.dir) else {
+                continue;
+            };
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            if let Some(rule) = level
+                .rules
+                .iter()
+                .rev()
+                .find(|rule| rule.matches(&relative, is_dir))
+            {
+                decided = Some(rule);
+            }
+        }
+        decided
+    }
+
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.decide(path, is_dir).is_some_and(|rule| !rule.negated)
+    }
+}
+
+fn to_match(rule: &Rule) -> IgnoreMatch {
+    IgnoreMatch {
+        file: rule.file.to_string_lossy().to_string(),
+        line: rule.line,
+        pattern: rule.text.clone(),
+        negated: rule.negated,
+    }
+}
+
+// Walks from the scripts root holding `path` down to it, the way the scanner would, and
+// reports the rule that left it out. A folder that's ignored hides everything inside it,
+// whatever its contents' own rules say.
+#[tauri::command]
+pub fn test_ignore_path(app: AppHandle, path: String) -> Result<IgnoreCheck, String> {
+    let target = scripts::resolve_root(&settings::expand_path(&path));
+    let root = settings::scripts_roots(&app)
+        .into_iter()
+        .map(|root| scripts::resolve_root(&root))
+        .find(|root| target.starts_with(root))
+        .ok_or_else(|| format!("{} is not inside a scripts folder", path))?;
+    if !is_enabled() {
+        return Ok(IgnoreCheck {
+            ignored: false,
+            rule: None,
+        });
+    }
+    let mut stack = IgnoreStack::default();
+    let mut dir = root.clone();
+    stack.enter(&dir);
+    let relative = target.strip_prefix(&root).map_err(|e| e.to_string())?;
+    let mut components = relative.components().peekable();
+    while let Some(component) = components.next() {
+        let current = dir.join(component);
+        let is_dir = components.peek().is_some() || current.is_dir();
+        let rule = stack.decide(&current, is_dir);
+        if rule.is_some_and(|rule| !rule.negated) || components.peek().is_none() {
+            return Ok(IgnoreCheck {
+                ignored: rule.is_some_and(|rule| !rule.negated),
+                rule: rule.map(to_match),
+            });
+        }
+        dir = current;
+        stack.enter(&dir);
+    }
+    Ok(IgnoreCheck {
+        ignored: false,
+        rule: None,
+    })
+}
+
+// Off scans every folder, ignore files or not
+#[tauri::command]
+pub fn set_respect_ignore_files(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set(&app, ENABLED_KEY, &enabled)?;
+    ENABLED.store(enabled, Ordering::Relaxed);
+    let _ = watcher::restart(&app);
+    scripts::notify_scripts_changed(&app, Vec::new());
+    Ok(())
+}
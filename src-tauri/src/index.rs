@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use std::sync::{LazyLock, Mutex};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+
+use crate::metadata::{self, ArgSpec};
+use crate::orphans::{self, Relinked};
+use crate::startup::{self, Phase};
+use crate::{scripts, settings};
+
+const INDEX_KEY: &str = "scriptIndex";
+
+// Parsed headers by path, with the mtime and size they were read at; dropped for paths the
+// watcher reports
+static METADATA: LazyLock<Mutex<HashMap<String, Cached>>> = LazyLock::new(Mutex::default);
+
+struct Cached {
+    mtime_ms: u64,
+    size: u64,
+    script: IndexedScript,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexEntry {
+    pub mtime_ms: u64,
+    pub size: u64,
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptChanges {
+    pub added: Vec<String>,
+    pub modified: Vec<String>,
+    pub removed: Vec<String>,
+    // How many files had to be read, the rest were matched on mtime and size
+    pub hashed: usize,
+    // Removed scripts whose settings, favorites or hotkeys were left behind
+    pub orphaned: Vec<String>,
+    // Removed scripts whose state moved to an added file with the same content
+    pub relinked: Vec<Relinked>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedScript {
+    pub path: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub icon: Option<String>,
+    pub tags: Vec<String>,
+    // Program from the shebang, e.g. "bash" for `#!/usr/bin/env bash`; scripts without one run
+    // through bash
+    pub interpreter: String,
+    pub args: Vec<ArgSpec>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptIndex {
+    pub scripts: Vec<IndexedScript>,
+    // How many scripts had to be read, the rest came from the cache
+    pub read: usize,
+}
+
+fn hex_digest(hasher: Sha256) -> String {
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+pub fn hash_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hex_digest(hasher))
+}
+
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    hex_digest(Sha256::new_with_prefix(bytes))
+}
+
+fn stat(path: &Path) -> Option<(u64, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    let mtime_ms = meta
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_millis() as u64;
+    Some((mtime_ms, meta.len()))
+}
+
+pub fn interpreter(shebang: Option<&str>) -> String {
+    let Some(line) = shebang.and_then(|s| s.strip_prefix("#!")) else {
+        return "bash".to_string();
+    };
+    let mut words = line.split_whitespace();
+    let program = match words.next() {
+        Some(env) if env.ends_with("/env") => words.find(|w| !w.starts_with('-')),
+        program => program,
+    };
+    program
+        .and_then(|p| Path::new(p).file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "bash".to_string())
+}
+
+fn index_script(path: &str) -> Option<IndexedScript> {
+    let content = fs::read_to_string(path).ok()?;
+    let meta = metadata::parse(content.lines());
+    Some(IndexedScript {
+        path: path.to_string(),
+        interpreter: interpreter(meta.shebang.as_deref()),
+        args: metadata::script_args(&content),
+        name: meta.name,
+        description: meta.description,
+        icon: meta.icon,
+        tags: meta.tags,
+    })
+}
+
+// An empty list means anything may have changed
+pub fn invalidate_metadata(paths: &[String]) {
+    if let Ok(mut cache) = METADATA.lock() {
+        if paths.is_empty() {
+            cache.clear();
+        } else {
+            // Removing a folder reports only the folder
+            cache.retain(|path, _| !paths.iter().any(|p| Path::new(path).starts_with(p)));
+        }
+    }
+}
+
+// Header metadata for every script under `dir`, re-reading only what changed since the last call
+pub fn metadata_index(dir: &Path) -> ScriptIndex {
+    let mut index = ScriptIndex::default();
+    let mut cache = METADATA.lock().unwrap_or_else(|e| e.into_inner());
+    for path in scripts::all_scripts(vec![dir.to_path_buf()]) {
+        let Some((mtime_ms, size)) = stat(Path::new(&path)) else {
+            continue;
+        };
+        match cache.get(&path) {
+            Some(cached) if cached.mtime_ms == mtime_ms && cached.size == size => {
+                index.scripts.push(cached.script.clone());
+            }
+            _ => {
+                let Some(script) = index_script(&path) else {
+                    continue;
+                };
+                index.read += 1;
+                cache.insert(
+                    path,
+                    Cached {
+                        mtime_ms,
+                        size,
+                        script: script.clone(),
+                    },
+                );
+                index.scripts.push(script);
+            }
+        }
+    }
+    index
+}
+
+pub fn load(app: &AppHandle) -> HashMap<String, IndexEntry> {
+    settings::get(app, INDEX_KEY).unwrap_or_default()
+}
+
+// Brings the stored index in line with the scripts on disk, re-hashing only files
+// whose mtime or size changed (or every file when `full` is set)
+pub fn refresh(
+    app: &AppHandle,
+    full: bool,
+) -> Result<(HashMap<String, IndexEntry>, ScriptChanges), String> {
+    let mut previous = load(app);
+    let mut index = HashMap::new();
+    let mut changes = ScriptChanges::default();
+    for path in scripts::all_scripts(settings::scripts_roots(app)) {
+        let Some((mtime_ms, size)) = stat(Path::new(&path)) else {
+            continue;
+        };
+        let old = previous.remove(&path);
+        let entry = match old.as_ref() {
+            Some(old) if !full && old.mtime_ms == mtime_ms && old.size == size => old.clone(),
+            _ => {
+                let Ok(hash) = hash_file(Path::new(&path)) else {
+                    continue;
+                };
+                changes.hashed += 1;
+                IndexEntry {
+                    mtime_ms,
+                    size,
+                    hash,
+                }
+            }
+        };
+        match old {
+            None => changes.added.push(path.clone()),
+            Some(old) if old.hash != entry.hash => changes.modified.push(path.clone()),
+            Some(_) => {}
+        }
+        index.insert(path, entry);
+    }
+    changes.removed = previous.keys().cloned().collect();
+    changes.added.sort();
+    changes.modified.sort();
+    changes.removed.sort();
+
+    let added: Vec<(String, String)> = changes
+        .added
+        .iter()
+        .filter_map(|path| index.get(path).map(|e| (path.clone(), e.hash.clone())))
+        .collect();
+    let removed = previous
+        .into_iter()
+        .map(|(path, e)| (path, e.hash))
+        .collect();
+    let reconciled = orphans::reconcile(app, removed, &added);
+    changes.orphaned = reconciled.orphaned;
+    changes.relinked = reconciled.relinked;
+
+    let dirty = changes.hashed > 0 || !changes.removed.is_empty();
+    if dirty {
+        settings::set(app, INDEX_KEY, &index)?;
+    }
+    Ok((index, changes))
+}
+
+// Startup phase: catches up with whatever changed on disk while the app was closed
+pub fn initial_scan(app: &AppHandle) {
+    match refresh(app, false) {
+        Ok((_, changes)) => {
+            let mut paths = changes.added;
+            paths.extend(changes.modified);
+            paths.extend(changes.removed);
+            if !paths.is_empty() {
+                scripts::notify_scripts_changed(app, paths);
+            }
+        }
+        Err(e) => log::warn!("Initial script scan failed: {}", e),
+    }
+    orphans::prune_on_launch(app);
+}
+
+#[tauri::command]
+pub async fn rebuild_script_index(app: AppHandle) -> Result<ScriptChanges, String> {
+    startup::guard(Phase::Scripts)?;
+    tauri::async_runtime::spawn_blocking(move || refresh(&app, true).map(|(_, changes)| changes))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// One call for the whole list instead of metadata and argument lookups per script
+#[tauri::command]
+pub async fn index_scripts(dir: String) -> Result<ScriptIndex, String> {
+    let dir = settings::expand_path(&dir);
+    tauri::async_runtime::spawn_blocking(move || metadata_index(&dir))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Scripts added, edited or deleted since the index was last refreshed
+#[tauri::command]
+pub async fn detect_script_changes(app: AppHandle) -> Result<ScriptChanges, String> {
+    startup::guard(Phase::Scripts)?;
+    tauri::async_runtime::spawn_blocking(move || refresh(&app, false).map(|(_, changes)| changes))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// Groups of scripts with identical content, largest groups first
+#[tauri::command]
+pub async fn find_duplicate_scripts(app: AppHandle) -> Result<Vec<Vec<String>>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (index, _) = refresh(&app, false)?;
+        let mut by_hash: HashMap<String, Vec<String>> = HashMap::new();
+        for (path, entry) in index {
+            by_hash.entry(entry.hash).or_default().push(path);
+        }
+        let mut groups: Vec<Vec<String>> = by_hash
+            .into_values()
+            .filter(|paths| paths.len() > 1)
+            .map(|mut paths| {
+                paths.sort();
+                paths
+            })
+            .collect();
+        groups.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        Ok(groups)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
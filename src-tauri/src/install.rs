@@ -0,0 +1,164 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tauri::AppHandle;
+
+use crate::{index, scripts, settings};
+
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_REDIRECTS: u32 = 5;
+const MAX_SCRIPT_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstalledScript {
+    pub path: String,
+    // Always the hash of what was downloaded, whether or not one was expected
+    pub sha256: String,
+    pub replaced: bool,
+    pub entry: Map<String, Value>,
+}
+
+// HTTPS all the way, including every redirect, with a hard cap on time and size
+fn download(url: &str) -> Result<Vec<u8>, String> {
+    if !url.starts_with("https://") {
+        return Err("Only https:// URLs can be installed from".to_string());
+    }
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .https_only(true)
+        .max_redirects(MAX_REDIRECTS)
+        .timeout_global(Some(DOWNLOAD_TIMEOUT))
+        .build()
+        .into();
+    let mut response = agent
+        .get(url)
+        .call()
+        .map_err(|e| format!("Download failed: {}", e))?;
+    response
+        .body_mut()
+        .with_config()
+        .limit(MAX_SCRIPT_BYTES)
+        .read_to_vec()
+        .map_err(|e| match e {
+            ureq::Error::BodyExceedsLimit(_) => {
+                format!("Script is larger than {} KB", MAX_SCRIPT_BYTES / 1024)
+            }
+            e => format!("Download failed: {}", e),
+        })
+}
+
+// Written next to the target and renamed over it, so a failed replace leaves the old file
+fn replace_file(target: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let mut temp = target.as_os_str().to_owned();
+    temp.push(".download");
+    let temp = PathBuf::from(temp);
+    fs::write(&temp, bytes)?;
+    fs::rename(&temp, target).inspect_err(|_| {
+        let _ = fs::remove_file(&temp);
+    })
+}
+
+fn create_file(target: &Path, bytes: &[u8]) -> Result<(), String> {
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(target)
+        .and_then(|mut file| file.write_all(bytes))
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => {
+                format!("A file already exists at {}", target.display())
+            }
+            _ => e.to_string(),
+        })
+}
+
+fn install(
+    app: &AppHandle,
+    root: &Path,
+    url: &str,
+    dest_relative_path: &str,
+    expected_sha256: Option<&str>,
+    overwrite: bool,
+) -> Result<InstalledScript, String> {
+    let mut relative = scripts::safe_relative(Path::new(dest_relative_path))
+        .ok_or_else(|| format!("Invalid script path: {}", dest_relative_path))?;
+    if relative.extension().is_none() {
+        relative.set_extension("sh");
+    }
+    let target = root.join(&relative);
+    scripts::ensure_writable_under(root, &target)?;
+    let replaced = target.exists();
+    if replaced && !overwrite {
+        return Err(format!("A file already exists at {}", target.display()));
+    }
+
+    let bytes = download(url)?;
+    let sha256 = index::hash_bytes(&bytes);
+    if let Some(expected) = expected_sha256.map(str::trim).filter(|s| !s.is_empty()) {
+        if !expected.eq_ignore_ascii_case(&sha256) {
+            return Err(format!(
+                "Checksum mismatch: expected {}, got {}",
+                expected, sha256
+            ));
+        }
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    // Again, in case a folder on the way was swapped for a symlink during the download
+    scripts::ensure_writable_under(root, &target)?;
+    if overwrite {
+        replace_file(&target, &bytes).map_err(|e| e.to_string())?;
+    } else {
+        create_file(&target, &bytes)?;
+    }
+    scripts::set_user_executable(&target).map_err(|e| e.to_string())?;
+    // Files we write ourselves aren't normally quarantined, but Gatekeeper would block them if they were
+    let _ = scripts::strip_quarantine(&target);
+
+    let path = target.to_string_lossy().to_string();
+    settings::update_script_data(app, &path, |_| {})?;
+    scripts::notify_scripts_changed(app, vec![path.clone()]);
+    let entry =
+        settings::script_data(app, &path).ok_or_else(|| format!("Failed to register {}", path))?;
+    Ok(InstalledScript {
+        path,
+        sha256,
+        replaced,
+        entry,
+    })
+}
+
+// Installs into `root_id`, or the main scripts folder when none is given
+#[tauri::command]
+pub async fn install_script_from_url(
+    app: AppHandle,
+    url: String,
+    dest_relative_path: String,
+    expected_sha256: Option<String>,
+    overwrite: Option<bool>,
+    root_id: Option<String>,
+) -> Result<InstalledScript, String> {
+    let root = match root_id {
+        Some(root_id) => settings::scripts_root(&app, &root_id)?,
+        None => settings::scripts_folder(&app)
+            .ok_or_else(|| "Could not determine home directory".to_string())?,
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        install(
+            &app,
+            &root,
+            &url,
+            &dest_relative_path,
+            expected_sha256.as_deref(),
+            overwrite.unwrap_or(false),
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
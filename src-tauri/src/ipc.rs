@@ -0,0 +1,382 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Listener, Manager};
+
+use crate::runner::{self, ActiveRuns, RunOptions};
+use crate::{history, livestream, scripts, settings, triggers};
+
+const SOCKET_NAME: &str = ".sh_runner.sock";
+
+#[derive(Default)]
+pub struct IpcState {
+    stopping: AtomicBool,
+}
+
+// One request per line, either JSON ({"cmd": "run", "script": "backup", "args": []})
+// or plain words (`run backup.sh fast`) for use with nc
+#[derive(Debug, Deserialize)]
+#[serde(tag = "cmd", rename_all = "camelCase", rename_all_fields = "camelCase")]
+enum Request {
+    List,
+    Run {
+        script: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    Status {
+        #[serde(default)]
+        target: Option<String>,
+    },
+    Tail {
+        run_id: String,
+    },
+}
+
+fn parse_request(line: &str) -> Result<Request, String> {
+    if line.starts_with('{') {
+        return serde_json::from_str(line).map_err(|e| e.to_string());
+    }
+    let mut words = line.split_whitespace();
+    let command = words.next().unwrap_or_default();
+    let rest: Vec<String> = words.map(str::to_string).collect();
+    match (command, rest.as_slice()) {
+        ("list", []) => Ok(Request::List),
+        ("run", [script, args @ ..]) => Ok(Request::Run {
+            script: script.clone(),
+            args: args.to_vec(),
+        }),
+        ("status", []) => Ok(Request::Status { target: None }),
+        ("status", [target]) => Ok(Request::Status {
+            target: Some(target.clone()),
+        }),
+        ("tail", [run_id]) => Ok(Request::Tail {
+            run_id: run_id.clone(),
+        }),
+        _ => Err(format!("Unknown request: {}", line)),
+    }
+}
+
+pub fn socket_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(SOCKET_NAME))
+}
+
+fn respond(writer: &mut impl Write, value: Value) -> io::Result<()> {
+    writeln!(writer, "{}", value)?;
+    writer.flush()
+}
+
+fn error(message: impl Into<String>) -> Value {
+    json!({ "ok": false, "error": message.into() })
+}
+
+fn run(app: &AppHandle, script: &str, args: Vec<String>) -> Value {
+    let Some(path) = scripts::find_script(app, script) else {
+        return error(format!("No script matches \"{}\"", script));
+    };
+    let run_id = runner::next_run_id();
+    let app = app.clone();
+    let (id, script_path) = (run_id.clone(), path.clone());
+    thread::spawn(move || {
+        let options = RunOptions {
+            args,
+            trigger: Some(triggers::CLI.to_string()),
+            trigger_detail: Some(format!("run {}", script_path)),
+            ..RunOptions::default()
+        };
+        let _ = runner::execute_with_id(&app, id, &script_path, &options);
+    });
+    json!({ "ok": true, "runId": run_id, "path": path })
+}
+
+// Without a target lists live runs; a run id or script name reports that run or its latest one
+pub fn status(app: &AppHandle, target: Option<&str>) -> Value {
+    let running = app.state::<ActiveRuns>().list();
+    let Some(target) = target else {
+        return json!({ "ok": true, "running": running });
+    };
+    let path = scripts::find_script(app, target);
+    if let Some(run) = running
+        .iter()
+        .find(|r| r.run_id == target || path.as_deref() == Some(r.path.as_str()))
+    {
+        return json!({ "ok": true, "running": true, "runId": run.run_id, "run": run });
+    }
+    let last = history::find(app, target).or_else(|| {
+        path.as_deref()
+            .and_then(|path| history::latest_for(app, path))
+    });
+    match last {
+        Some(entry) => json!({ "ok": true, "running": false, "runId": entry.run_id, "run": entry }),
+        None => error(format!("No run found for \"{}\"", target)),
+    }
+}
+
+// Streams a live run's output line by line until it exits
+fn tail(app: &AppHandle, run_id: &str, writer: &mut impl Write) -> io::Result<()> {
+    let (tx, rx) = mpsc::channel::<Value>();
+    // Output comes straight from the run, since it isn't emitted while the window is hidden
+    let subscription = livestream::subscribe(run_id, tx.clone());
+    let exit_run_id = run_id.to_string();
+    let listener = app.listen("script-exited", move |event| {
+        let Ok(mut payload) = serde_json::from_str::<Value>(event.payload()) else {
+            return;
+        };
+        if payload["runId"] == exit_run_id.as_str() {
+            payload["event"] = json!("script-exited");
+            let _ = tx.send(payload);
+        }
+    });
+
+    // Checked after subscribing so an exit in between is not missed
+    let live = app
+        .state::<ActiveRuns>()
+        .list()
+        .iter()
+        .any(|r| r.run_id == run_id);
+    let result = if live {
+        stream_events(rx, writer)
+    } else {
+        respond(writer, status(app, Some(run_id)))
+    };
+    app.unlisten(listener);
+    if let Some(subscription) = subscription {
+        livestream::unsubscribe(subscription);
+    }
+    result
+}
+
+fn stream_events(rx: mpsc::Receiver<Value>, writer: &mut impl Write) -> io::Result<()> {
+    for mut payload in rx {
+        let exited = payload["event"] == "script-exited";
+        if !exited {
+            payload["event"] = json!("script-output");
+        }
+        respond(writer, payload)?;
+        if exited {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn handle(app: &AppHandle, line: &str, writer: &mut impl Write) -> io::Result<()> {
+    match parse_request(line) {
+        Ok(Request::List) => respond(
+            writer,
+            json!({ "ok": true, "scripts": scripts::all_scripts(settings::scripts_roots(app)) }),
+        ),
+        Ok(Request::Run { script, args }) => respond(writer, run(app, &script, args)),
+        Ok(Request::Status { target }) => respond(writer, status(app, target.as_deref())),
+        Ok(Request::Tail { run_id }) => tail(app, &run_id, writer),
+        Err(message) => {
+            log::debug!("Rejected CLI request: {}", message);
+            respond(writer, error(message))
+        }
+    }
+}
+
+// Answers one client connection until it closes
+fn serve(app: &AppHandle, reader: impl Read, mut writer: impl Write) {
+    for line in BufReader::new(reader).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let line = line.trim();
+        if !line.is_empty() && handle(app, line, &mut writer).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(unix)]
+pub fn start(app: &AppHandle) -> Result<(), String> {
+    use std::fs;
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    let path = socket_path().ok_or_else(|| "Could not determine home directory".to_string())?;
+    if path.exists() {
+        if UnixStream::connect(&path).is_ok() {
+            return Err(format!(
+                "Another instance is listening on {}",
+                path.display()
+            ));
+        }
+        // Left behind by a crash
+        fs::remove_file(&path).map_err(|e| e.to_string())?;
+    }
+    // Bound inside a private folder and moved into place, so the socket is never reachable
+    // by other users before it is 0600
+    let staging = path.with_file_name(format!("{}.{}", SOCKET_NAME, std::process::id()));
+    let _ = fs::remove_dir_all(&staging);
+    fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&staging)
+        .map_err(|e| e.to_string())?;
+    let bound = staging.join("sock");
+    let listener = UnixListener::bind(&bound).and_then(|listener| {
+        fs::set_permissions(&bound, fs::Permissions::from_mode(0o600))?;
+        fs::rename(&bound, &path)?;
+        Ok(listener)
+    });
+    let _ = fs::remove_dir_all(&staging);
+    let listener = listener.map_err(|e| e.to_string())?;
+
+    let app = app.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            if app.state::<IpcState>().stopping.load(Ordering::Relaxed) {
+                break;
+            }
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log::warn!("CLI socket connection failed: {}", e);
+                    continue;
+                }
+            };
+            let app = app.clone();
+            thread::spawn(move || {
+                if let Ok(reader) = stream.try_clone() {
+                    serve(&app, reader, stream);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+// Per user, since pipe names are shared by every session on the machine
+#[cfg(windows)]
+pub fn pipe_name() -> String {
+    format!(
+        r"\\.\pipe\sh_runner-{}",
+        std::env::var("USERNAME").unwrap_or_default()
+    )
+}
+
+// One pipe instance waiting for a client. The first instance claims the name, so a second
+// app fails here instead of sharing it.
+#[cfg(windows)]
+fn create_pipe(name: &str, first: bool) -> io::Result<std::fs::File> {
+    use std::os::windows::io::FromRawHandle;
+    use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+    use windows_sys::Win32::Storage::FileSystem::{
+        FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX,
+    };
+    use windows_sys::Win32::System::Pipes::{
+        CreateNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE,
+        PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+    };
+
+    let wide: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+    let open_mode = if first {
+        PIPE_ACCESS_DUPLEX | FILE_FLAG_FIRST_PIPE_INSTANCE
+    } else {
+        PIPE_ACCESS_DUPLEX
+    };
+    // The default security descriptor only lets the owner, SYSTEM and administrators write
+    let handle = unsafe {
+        CreateNamedPipeW(
+            wide.as_ptr(),
+            open_mode,
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+            PIPE_UNLIMITED_INSTANCES,
+            4096,
+            4096,
+            0,
+            std::ptr::null(),
+        )
+    };
+    if handle == INVALID_HANDLE_VALUE {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { std::fs::File::from_raw_handle(handle) })
+}
+
+// Blocks until a client opens the pipe
+#[cfg(windows)]
+fn wait_for_client(pipe: &std::fs::File) -> io::Result<()> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Foundation::ERROR_PIPE_CONNECTED;
+    use windows_sys::Win32::System::Pipes::ConnectNamedPipe;
+
+    if unsafe { ConnectNamedPipe(pipe.as_raw_handle(), std::ptr::null_mut()) } != 0 {
+        return Ok(());
+    }
+    let error = io::Error::last_os_error();
+    // The client connected between creating the instance and waiting on it
+    if error.raw_os_error() == Some(ERROR_PIPE_CONNECTED as i32) {
+        return Ok(());
+    }
+    Err(error)
+}
+
+#[cfg(windows)]
+pub fn start(app: &AppHandle) -> Result<(), String> {
+    let name = pipe_name();
+    let first = create_pipe(&name, true).map_err(|e| {
+        if e.kind() == io::ErrorKind::PermissionDenied {
+            format!("Another instance is listening on {}", name)
+        } else {
+            e.to_string()
+        }
+    })?;
+
+    let app = app.clone();
+    thread::spawn(move || {
+        let mut next = Some(first);
+        loop {
+            let pipe = match next.take().map_or_else(|| create_pipe(&name, false), Ok) {
+                Ok(pipe) => pipe,
+                Err(e) => {
+                    log::warn!("CLI pipe could not be created: {}", e);
+                    break;
+                }
+            };
+            if let Err(e) = wait_for_client(&pipe) {
+                log::warn!("CLI pipe connection failed: {}", e);
+                continue;
+            }
+            if app.state::<IpcState>().stopping.load(Ordering::Relaxed) {
+                break;
+            }
+            let app = app.clone();
+            thread::spawn(move || {
+                if let Ok(reader) = pipe.try_clone() {
+                    serve(&app, reader, pipe);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn start(_app: &AppHandle) -> Result<(), String> {
+    Err("The CLI socket is not supported on this platform".to_string())
+}
+
+pub fn shutdown(app: &AppHandle) {
+    app.state::<IpcState>()
+        .stopping
+        .store(true, Ordering::Relaxed);
+    // Wakes the accept loop so it sees the flag, then removes the socket file
+    #[cfg(windows)]
+    let _ = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(pipe_name());
+    let Some(path) = socket_path() else {
+        return;
+    };
+    #[cfg(unix)]
+    let _ = std::os::unix::net::UnixStream::connect(&path);
+    let _ = std::fs::remove_file(path);
+}
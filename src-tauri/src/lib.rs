@@ -1,9 +1,81 @@
-use tauri::{
-    utils::config::Color,
-    menu::{Menu, MenuItem},
-    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    Manager, Runtime, PhysicalPosition, Position,
-};
+mod applog;
+mod askpass;
+mod audit;
+mod autostart;
+mod backup;
+mod batch;
+mod bundle;
+mod categories;
+mod collections;
+mod concurrency;
+mod conditions;
+mod countdown;
+mod danger;
+mod deps;
+mod deeplink;
+mod diagnostics;
+mod digest;
+mod dnd;
+mod editor;
+mod envgroups;
+mod external;
+mod git;
+mod graph;
+mod history;
+mod hotkeys;
+mod ignorefiles;
+mod index;
+mod install;
+mod ipc;
+mod limits;
+mod livestream;
+mod logcap;
+mod matchers;
+mod metadata;
+mod metrics;
+mod migrate;
+mod notifications;
+mod onboarding;
+mod orphans;
+mod outputfilter;
+mod palette;
+mod paths;
+mod pause;
+mod placeholders;
+mod preconditions;
+mod profiles;
+mod prometheus;
+mod queue;
+mod readonly;
+mod recovery;
+mod report;
+mod roots;
+mod runner;
+mod runstate;
+mod runwindows;
+mod sandbox;
+mod scripts;
+mod screen;
+mod search;
+mod secrets;
+mod settings;
+mod snapshots;
+mod staging;
+mod startup;
+mod storewatch;
+mod templates;
+mod tags;
+mod transfer;
+mod tray;
+mod triggers;
+mod update;
+mod watcher;
+mod webhook;
+mod workflows;
+mod window;
+
+use startup::Phase;
+use tauri::{utils::config::Color, Manager};
 
 #[tauri::command]
 fn get_home_dir() -> Result<String, String> {
@@ -14,124 +86,333 @@ fn get_home_dir() -> Result<String, String> {
 
 #[tauri::command]
 fn get_default_scripts_path() -> Result<String, String> {
-    dirs::home_dir()
-        .map(|p| p.join("scripts").to_string_lossy().to_string())
+    settings::default_scripts_dir()
+        .map(|p| p.to_string_lossy().to_string())
         .ok_or_else(|| "Could not determine home directory".to_string())
 }
 
 #[tauri::command]
 fn set_tray_tooltip(app: tauri::AppHandle, tooltip: String) -> Result<(), String> {
-    if let Some(tray) = app.tray_by_id("main-tray") {
+    let tooltip = tray::with_status(tooltip);
+    if let Some(tray) = app.tray_by_id(tray::TRAY_ID) {
         tray.set_tooltip(Some(&tooltip)).map_err(|e| e.to_string())?;
     }
     Ok(())
 }
 
-fn create_tray<R: Runtime>(app: &tauri::AppHandle<R>) -> tauri::Result<()> {
-    let menu = Menu::new(app)?;
-    let toggle = MenuItem::new(app, "Show/Hide", true, None::<&str>)?;
-    let quit = MenuItem::new(app, "Quit", true, None::<&str>)?;
-    menu.append(&toggle)?;
-    menu.append(&quit)?;
-
-    let _tray = TrayIconBuilder::with_id("main-tray")
-        .icon(app.default_window_icon().unwrap().clone())
-        .icon_as_template(true)
-        .tooltip("Scripts Runner")
-        .menu(&menu)
-        .show_menu_on_left_click(false)
-        .on_menu_event(move |app, event| {
-            if event.id() == quit.id() {
-                app.exit(0);
-                return;
-            }
-            if event.id() == toggle.id() {
-                if let Some(window) = app.get_webview_window("main") {
-                    if window.is_visible().unwrap_or(false) {
-                        let _ = window.hide();
-                    } else {
-                        let _ = window.show();
-                        let _ = window.set_focus();
-                    }
-                }
-            }
-        })
-        .on_tray_icon_event(|tray, event| {
-            if let TrayIconEvent::Click {
-                button: MouseButton::Left,
-                button_state: MouseButtonState::Up,
-                ..
-            } = event
-            {
-                let app = tray.app_handle();
-                if let Some(window) = app.get_webview_window("main") {
-                    if window.is_visible().unwrap_or(false) {
-                        let _ = window.hide();
-                    } else {
-                        // Position window below tray icon
-                        if let Ok(Some(rect)) = tray.rect() {
-                            let pos_x = match rect.position {
-                                Position::Physical(p) => p.x,
-                                Position::Logical(l) => l.x as i32,
-                            };
-                            let pos_y = match rect.position {
-                                Position::Physical(p) => p.y,
-                                Position::Logical(l) => l.y as i32,
-                            };
-                            let size_h = match rect.size {
-                                tauri::Size::Physical(s) => s.height as i32,
-                                tauri::Size::Logical(s) => s.height as i32,
-                            };
-
-                            let x = pos_x - 140;
-                            let y = pos_y + size_h + 5;
-                            let _ = window.set_position(Position::Physical(
-                                PhysicalPosition { x, y },
-                            ));
-                        }
-                        let _ = window.show();
-                        let _ = window.set_focus();
-                    }
-                }
-            }
-        })
-        .build(app)?;
-
-    Ok(())
-}
-
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    startup::begin();
+    let builder = tauri::Builder::default();
+    // Registered first so a second launch hands its arguments (and deep links) over and exits
+    #[cfg(desktop)]
+    let builder = builder.plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
+        window::handle_second_instance(app, argv, cwd);
+    }));
+    builder
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_store::Builder::default().build())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_dialog::init())
         .setup(|app| {
+            applog::init(app.handle());
+
             // Hide from Dock on macOS
             #[cfg(target_os = "macos")]
             app.handle().set_activation_policy(tauri::ActivationPolicy::Accessory)?;
 
-            create_tray(app.handle())?;
+            #[cfg(desktop)]
+            app.handle().plugin(tauri_plugin_autostart::init(
+                tauri_plugin_autostart::MacosLauncher::LaunchAgent,
+                Some(vec![autostart::AUTOSTART_ARG]),
+            ))?;
 
-            // Hide window when it loses focus
-            let handle = app.handle().clone();
-            if let Some(window) = handle.get_webview_window("main") {
-                let _ = window.set_shadow(false);
-                let _ = window.set_background_color(Some(Color(0, 0, 0, 0)));
-                let window_clone = window.clone();
-                window.on_window_event(move |event| {
-                    if let tauri::WindowEvent::Focused(false) = event {
-                        let _ = window_clone.hide();
-                    }
-                });
+            // Before the store or history are first opened from their new homes
+            paths::migrate_legacy(app.handle());
+            app.manage(history::History::new(app.handle()));
+            app.manage(backup::BackupState::default());
+            app.manage(dnd::DndState::default());
+            app.manage(queue::QueueState::default());
+            app.manage(runner::ActiveRuns::default());
+            app.manage(ipc::IpcState::default());
+            app.manage(watcher::WatchState::default());
+            app.manage(storewatch::StoreWatchState::default());
+            app.manage(webhook::WebhookState::default());
+            startup::measure(Phase::Tray, || tray::create_tray(app.handle()))?;
+            // Before the watcher and anything else that scans the scripts tree
+            ignorefiles::restore(app.handle());
+            roots::start_prober(app.handle());
+            if let Err(e) = watcher::restart(app.handle()) {
+                log::error!("Failed to start the scripts watcher: {}", e);
+            }
+            if let Err(e) = storewatch::restart(app.handle()) {
+                log::warn!("Settings watcher not started: {}", e);
+            }
+            runstate::start_emitter(app.handle());
+            hotkeys::restore_run_again_hotkey(app.handle());
+            hotkeys::restore_script_hotkeys(app.handle());
+            readonly::restore(app.handle());
+            recovery::startup(app.handle());
+            update::start_background_check(app.handle());
+            deeplink::install(app.handle());
+            if let Err(e) = ipc::start(app.handle()) {
+                log::warn!("CLI socket not started: {}", e);
             }
+            if let Err(e) = webhook::restart(app.handle()) {
+                log::warn!("Webhook server not started: {}", e);
+            }
+
+            startup::measure(Phase::Window, || {
+                if let Some(window) = window::main_window(app.handle()) {
+                    let _ = window.set_shadow(false);
+                    let _ = window.set_background_color(Some(Color(0, 0, 0, 0)));
+                    window::install_auto_hide(&window);
+                    window::apply_stored_size(app.handle(), &window);
+                }
+                // Launches at login stay in the tray regardless of the show-on-launch setting
+                if !autostart::launched_at_login() {
+                    window::show_on_launch_if_enabled(app.handle());
+                }
+            });
+
+            // Off the main thread so the tray and window don't wait; commands that need one of
+            // these return an initializing error until its startup-phase-ready event
+            startup::defer(app.handle(), Phase::History, history::load);
+            startup::defer(app.handle(), Phase::Scripts, index::initial_scan);
+            startup::defer(app.handle(), Phase::LoginPath, |_| {
+                deps::login_path();
+            });
+            startup::defer(app.handle(), Phase::Scheduler, |app| {
+                dnd::resume(app);
+                queue::start_worker(app);
+                digest::start(app);
+            });
 
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![get_home_dir, get_default_scripts_path, set_tray_tooltip])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .invoke_handler(readonly::guard_commands(tauri::generate_handler![
+            get_home_dir,
+            get_default_scripts_path,
+            paths::get_app_paths,
+            set_tray_tooltip,
+            diagnostics::environment_report,
+            diagnostics::get_system_info,
+            diagnostics::get_system_info_markdown,
+            applog::get_app_logs,
+            applog::open_app_log_folder,
+            applog::set_log_level,
+            update::check_for_updates,
+            update::check_for_update,
+            update::install_update,
+            update::get_app_version,
+            update::get_update_settings,
+            update::set_update_settings,
+            runner::run_script,
+            runner::run_script_in_picked_dir,
+            runner::run_with_clipboard,
+            runner::preview_changes,
+            runner::confirm_after_preview,
+            runner::rerun_history_entry,
+            staging::stage_script_edit,
+            storewatch::reload_settings,
+            storewatch::set_settings_auto_reload,
+            staging::run_staged,
+            staging::promote_staged,
+            staging::discard_staged,
+            runner::set_output_encoding,
+            runner::set_output_marker,
+            runner::get_run_progress,
+            pause::pause_all,
+            pause::resume_all,
+            runner::run_last_script,
+            hotkeys::set_run_again_hotkey,
+            hotkeys::set_script_hotkey,
+            hotkeys::clear_script_hotkey,
+            runner::set_auto_chmod,
+            danger::get_dangerous_trigger_overrides,
+            danger::set_dangerous_trigger_overrides,
+            conditions::get_run_conditions,
+            conditions::set_run_conditions,
+            countdown::cancel_run_countdown,
+            countdown::get_confirm_timeout,
+            countdown::set_confirm_timeout,
+            runner::set_auto_strip_quarantine,
+            runner::set_block_writable_scripts,
+            runner::set_capture_command,
+            runner::cancel_script,
+            runner::set_cancel_grace_ms,
+            metrics::set_metrics_interval_ms,
+            prometheus::metrics_prometheus,
+            matchers::get_script_matchers,
+            matchers::set_script_matchers,
+            outputfilter::get_output_filter,
+            outputfilter::set_output_filter,
+            logcap::get_log_cap,
+            logcap::set_log_cap,
+            history::get_run_history,
+            report::save_run_report,
+            report::export_run_report,
+            history::get_history_size,
+            history::slowest_scripts,
+            livestream::get_output_tail,
+            palette::palette_search,
+            palette::record_palette_use,
+            triggers::explain_triggers,
+            search::search_output,
+            search::cancel_output_search,
+            search::search_run_output,
+            history::get_history_retention,
+            history::set_history_retention,
+            profiles::list_profiles,
+            profiles::get_active_profile,
+            profiles::save_profile,
+            profiles::delete_profile,
+            profiles::switch_profile,
+            scripts::grep_scripts,
+            graph::script_dependency_graph,
+            ignorefiles::test_ignore_path,
+            ignorefiles::set_respect_ignore_files,
+            scripts::get_scripts_path,
+            scripts::get_scripts_path_info,
+            scripts::validate_scripts_path,
+            scripts::create_scripts_path,
+            scripts::list_scripts,
+            scripts::list_scripts_with_status,
+            categories::list_scripts_tree,
+            categories::get_collapsed_categories,
+            categories::set_category_collapsed,
+            editor::get_script_content,
+            editor::save_script_content,
+            editor::apply_metadata,
+            onboarding::needs_default_setup,
+            onboarding::initialize_default_setup,
+            scripts::get_script_metadata,
+            scripts::infer_script_args,
+            scripts::get_script_placeholders,
+            deps::check_dependencies,
+            preconditions::check_preconditions,
+            scripts::get_allowed_script_paths,
+            scripts::set_allowed_script_paths,
+            scripts::get_allowed_dirs,
+            scripts::set_allowed_dirs,
+            scripts::set_last_selected,
+            scripts::get_last_selected,
+            scripts::rename_script,
+            scripts::duplicate_script,
+            scripts::delete_script,
+            migrate::migrate_scripts_path,
+            scripts::make_executable,
+            scripts::remove_quarantine,
+            snapshots::show_script_diff,
+            secrets::set_secret,
+            secrets::delete_secret,
+            secrets::list_secret_names,
+            window::set_show_on_launch,
+            window::set_window_size,
+            window::set_window_anchor,
+            backup::backup_scripts,
+            backup::cancel_backup,
+            backup::import_scripts,
+            transfer::export_profile,
+            transfer::import_profile,
+            bundle::export_script_bundle,
+            bundle::import_script_bundle,
+            audit::verify_audit_log,
+            audit::export_audit_log,
+            settings::reset_settings,
+            settings::list_settings_keys,
+            settings::get_setting,
+            settings::set_setting,
+            autostart::get_autostart,
+            autostart::set_autostart,
+            notifications::handle_notification_action,
+            notifications::get_notification_prefs,
+            notifications::set_notification_prefs,
+            notifications::set_script_notify_threshold,
+            notifications::set_script_notification_style,
+            notifications::list_notification_sounds,
+            runstate::get_runs_state,
+            recovery::run_health_check,
+            recovery::get_startup_recovery,
+            startup::get_startup_timings,
+            digest::get_digest,
+            digest::get_digest_settings,
+            digest::set_digest_settings,
+            runwindows::open_run_window,
+            runwindows::close_run_window,
+            runwindows::get_run_window_settings,
+            runwindows::set_run_window_settings,
+            watcher::set_watch_debounce_ms,
+            dnd::get_dnd,
+            dnd::set_dnd,
+            queue::enqueue_script,
+            queue::dequeue_script,
+            queue::get_queue,
+            queue::move_in_queue,
+            queue::reorder_queue,
+            queue::promote_to_front,
+            concurrency::get_concurrency_status,
+            concurrency::set_max_concurrent_runs,
+            concurrency::set_concurrency_policy,
+            batch::run_scripts,
+            batch::run_tag,
+            batch::run_collection,
+            batch::cancel_batch,
+            deeplink::set_deep_link_confirm,
+            index::rebuild_script_index,
+            index::index_scripts,
+            tags::get_tags,
+            tags::set_tray_tags_menu,
+            index::detect_script_changes,
+            index::find_duplicate_scripts,
+            install::install_script_from_url,
+            external::open_in_editor,
+            external::set_editor_command,
+            external::reveal_script,
+            external::run_in_terminal,
+            external::get_terminal_settings,
+            external::set_terminal_settings,
+            external::test_terminal,
+            git::git_status,
+            git::git_pull,
+            templates::list_script_templates,
+            templates::create_script,
+            webhook::get_webhook_settings,
+            webhook::set_webhook_settings,
+            webhook::set_http_trigger,
+            webhook::get_http_token,
+            workflows::list_workflows,
+            workflows::save_workflow,
+            workflows::delete_workflow,
+            envgroups::list_env_groups,
+            envgroups::create_env_group,
+            envgroups::update_env_group,
+            envgroups::delete_env_group,
+            collections::list_collections,
+            collections::list_collection,
+            collections::create_collection,
+            collections::add_to_collection,
+            collections::remove_from_collection,
+            collections::delete_collection,
+            orphans::list_orphaned_scripts,
+            orphans::purge_orphaned,
+            orphans::prune_missing_references,
+            orphans::set_auto_prune_on_launch,
+            workflows::run_workflow,
+            readonly::get_read_only,
+            readonly::set_read_only,
+        ]))
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                runwindows::close_all(app);
+                ipc::shutdown(app);
+                webhook::shutdown(app);
+            }
+        });
 }
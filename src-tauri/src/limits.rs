@@ -0,0 +1,193 @@
+use std::process::{Child, Command, ExitStatus};
+
+use serde::{Deserialize, Serialize};
+
+// Limits applied to the script process itself through setrlimit before exec
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ResourceLimits {
+    pub max_memory_mb: Option<u64>,
+    pub max_cpu_seconds: Option<u64>,
+}
+
+impl ResourceLimits {
+    pub fn is_empty(&self) -> bool {
+        self.max_memory_mb.is_none() && self.max_cpu_seconds.is_none()
+    }
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type Resource = libc::__rlimit_resource_t;
+#[cfg(all(unix, not(all(target_os = "linux", target_env = "gnu"))))]
+type Resource = libc::c_int;
+
+#[cfg(unix)]
+fn set_limit(resource: Resource, soft: u64, hard: u64) -> std::io::Result<()> {
+    let limit = libc::rlimit {
+        rlim_cur: soft as libc::rlim_t,
+        rlim_max: hard as libc::rlim_t,
+    };
+    // SAFETY: setrlimit only reads the struct we pass and is async-signal-safe
+    if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+pub fn apply(command: &mut Command, limits: ResourceLimits) -> Result<(), String> {
+    use std::os::unix::process::CommandExt;
+
+    if limits.is_empty() {
+        return Ok(());
+    }
+    let memory = limits
+        .max_memory_mb
+        .map(|mb| mb.saturating_mul(1024 * 1024));
+    let cpu = limits.max_cpu_seconds;
+    // SAFETY: the hook runs in the forked child and only calls setrlimit
+    unsafe {
+        command.pre_exec(move || {
+            if let Some(bytes) = memory {
+                set_limit(libc::RLIMIT_AS, bytes, bytes)?;
+            }
+            // SIGXCPU at the soft limit, SIGKILL a second later if it is ignored
+            if let Some(secs) = cpu {
+                set_limit(libc::RLIMIT_CPU, secs, secs.saturating_add(1))?;
+            }
+            Ok(())
+        });
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn apply(_command: &mut Command, limits: ResourceLimits) -> Result<(), String> {
+    if limits.is_empty() {
+        return Ok(());
+    }
+    Err("Resource limits are not supported on this platform".to_string())
+}
+
+// Peak memory and CPU time of a finished run, as reported by wait4
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceUsage {
+    pub max_rss_kb: u64,
+    pub user_cpu_ms: u64,
+    pub system_cpu_ms: u64,
+}
+
+#[cfg(unix)]
+fn timeval_ms(time: libc::timeval) -> u64 {
+    (time.tv_sec as u64) * 1000 + (time.tv_usec as u64) / 1000
+}
+
+// Child::try_wait, but reaping through wait4 so the child's rusage comes along
+#[cfg(unix)]
+pub fn try_wait(child: &mut Child) -> std::io::Result<Option<(ExitStatus, Option<ResourceUsage>)>> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let mut status = 0;
+    // SAFETY: rusage is plain data that wait4 fills in
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    // SAFETY: only waits for our own child; both out pointers are valid for the call
+    let pid = unsafe {
+        libc::wait4(
+            child.id() as libc::pid_t,
+            &mut status,
+            libc::WNOHANG,
+            &mut usage,
+        )
+    };
+    match pid {
+        0 => Ok(None),
+        -1 => {
+            let error = std::io::Error::last_os_error();
+            if error.kind() == std::io::ErrorKind::Interrupted {
+                Ok(None)
+            } else {
+                Err(error)
+            }
+        }
+        _ => {
+            // ru_maxrss is in kilobytes on Linux but in bytes on macOS
+            let max_rss = usage.ru_maxrss as u64;
+            let max_rss_kb = if cfg!(target_os = "macos") {
+                max_rss / 1024
+            } else {
+                max_rss
+            };
+            Ok(Some((
+                ExitStatus::from_raw(status),
+                Some(ResourceUsage {
+                    max_rss_kb,
+                    user_cpu_ms: timeval_ms(usage.ru_utime),
+                    system_cpu_ms: timeval_ms(usage.ru_stime),
+                }),
+            )))
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub fn try_wait(child: &mut Child) -> std::io::Result<Option<(ExitStatus, Option<ResourceUsage>)>> {
+    Ok(child.try_wait()?.map(|status| (status, None)))
+}
+
+#[cfg(unix)]
+pub fn exit_signal(status: &ExitStatus) -> Option<i32> {
+    std::os::unix::process::ExitStatusExt::signal(status)
+}
+
+#[cfg(not(unix))]
+pub fn exit_signal(_status: &ExitStatus) -> Option<i32> {
+    None
+}
+
+// Lowercased allocation failures from the shell, libc, Python, C++ and Rust runtimes
+#[cfg(unix)]
+const OUT_OF_MEMORY_MESSAGES: &[&str] = &[
+    "cannot allocate memory",
+    "out of memory",
+    "memory exhausted",
+    "memoryerror",
+    "bad_alloc",
+    "memory allocation of",
+];
+
+// Best guess at which limit ended the run: CPU exhaustion is signalled explicitly, while
+// running out of address space is only trusted from an allocation error, since a plain crash
+// has many other causes. A run the user cancelled was killed by us, not by a limit.
+#[cfg(unix)]
+pub fn exceeded(
+    limits: ResourceLimits,
+    signal: Option<i32>,
+    timed_out: bool,
+    cancelled: bool,
+    success: bool,
+    stderr: &str,
+) -> Option<String> {
+    if timed_out || cancelled || success {
+        return None;
+    }
+    if limits.max_cpu_seconds.is_some() && matches!(signal, Some(libc::SIGXCPU | libc::SIGKILL)) {
+        return Some("cpu".to_string());
+    }
+    let stderr = stderr.to_lowercase();
+    let out_of_memory = OUT_OF_MEMORY_MESSAGES
+        .iter()
+        .any(|needle| stderr.contains(needle));
+    (limits.max_memory_mb.is_some() && out_of_memory).then(|| "memory".to_string())
+}
+
+#[cfg(not(unix))]
+pub fn exceeded(
+    _limits: ResourceLimits,
+    _signal: Option<i32>,
+    _timed_out: bool,
+    _cancelled: bool,
+    _success: bool,
+    _stderr: &str,
+) -> Option<String> {
+    None
+}
@@ -0,0 +1,171 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{LazyLock, Mutex};
+
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, EventTarget};
+
+use crate::runwindows;
+
+// Lines kept per live run for the snapshot sent when the window is shown again
+const TAIL_LINES: usize = 200;
+
+// The main window starts hidden, see tauri.conf.json
+static VISIBLE: AtomicBool = AtomicBool::new(false);
+static STREAMS: LazyLock<Mutex<Streams>> = LazyLock::new(Mutex::default);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TailLine {
+    // Per run, counting from 0; live script-output events carry the same number
+    pub seq: u64,
+    pub stream: String,
+    pub line: String,
+}
+
+// Sent as script-output-snapshot for each run that printed while the window was hidden
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputSnapshot {
+    pub run_id: String,
+    // Those not streamed yet, oldest first, at most TAIL_LINES
+    pub lines: Vec<TailLine>,
+    // Live events continue from here
+    pub next_seq: u64,
+    // Lines held back that fell out of the tail before the window came back
+    pub skipped: u64,
+}
+
+#[derive(Default)]
+struct RunStream {
+    next_seq: u64,
+    tail: VecDeque<TailLine>,
+    // First line not streamed because the window was hidden
+    unsent_from: Option<u64>,
+}
+
+#[derive(Default)]
+struct Streams {
+    runs: HashMap<String, RunStream>,
+    // Backend readers such as the CLI's tail as (subscription, run id, sender); they get every line
+    subscribers: Vec<(u64, String, Sender<Value>)>,
+    next_subscription: u64,
+}
+
+impl RunStream {
+    fn snapshot(&self, run_id: &str, from: u64) -> OutputSnapshot {
+        let first = self.tail.front().map_or(self.next_seq, |line| line.seq);
+        OutputSnapshot {
+            run_id: run_id.to_string(),
+            lines: self
+                .tail
+                .iter()
+                .filter(|line| line.seq >= from)
+                .cloned()
+                .collect(),
+            next_seq: self.next_seq,
+            skipped: first.saturating_sub(from),
+        }
+    }
+}
+
+pub fn is_visible() -> bool {
+    VISIBLE.load(Ordering::SeqCst)
+}
+
+// Streams one output line. `payload` gets the line's seq; the lock is held while emitting so
+// a line is either in the snapshot or streamed live after it, never both or neither.
+pub fn output(app: &AppHandle, run_id: &str, stream: &str, line: &str, payload: Value) {
+    let Ok(mut streams) = STREAMS.lock() else {
+        return;
+    };
+    let run = streams.runs.entry(run_id.to_string()).or_default();
+    let seq = run.next_seq;
+    run.next_seq += 1;
+    if run.tail.len() == TAIL_LINES {
+        run.tail.pop_front();
+    }
+    run.tail.push_back(TailLine {
+        seq,
+        stream: stream.to_string(),
+        line: line.to_string(),
+    });
+    let mut payload = payload;
+    payload["seq"] = seq.into();
+    if is_visible() {
+        let _ = app.emit("script-output", &payload);
+    } else {
+        run.unsent_from.get_or_insert(seq);
+    }
+    // Pinned run windows listen to their own event, so they never see a line twice
+    if let Some(label) = runwindows::window_for(run_id) {
+        let _ = app.emit_to(
+            EventTarget::webview_window(label),
+            "run-window-output",
+            &payload,
+        );
+    }
+    streams
+        .subscribers
+        .retain(|(_, id, tx)| id != run_id || tx.send(payload.clone()).is_ok());
+}
+
+// For per-line events other than output, which are simply dropped while hidden
+pub fn emit_if_visible<S: Serialize + Clone>(app: &AppHandle, event: &str, payload: S) {
+    if is_visible() {
+        let _ = app.emit(event, payload);
+    }
+}
+
+// Output lines of `run_id` as script-output payloads, until the run finishes or the returned
+// subscription is passed to `unsubscribe`
+pub fn subscribe(run_id: &str, tx: Sender<Value>) -> Option<u64> {
+    let mut streams = STREAMS.lock().ok()?;
+    let subscription = streams.next_subscription;
+    streams.next_subscription += 1;
+    streams
+        .subscribers
+        .push((subscription, run_id.to_string(), tx));
+    Some(subscription)
+}
+
+pub fn unsubscribe(subscription: u64) {
+    if let Ok(mut streams) = STREAMS.lock() {
+        streams.subscribers.retain(|(id, _, _)| *id != subscription);
+    }
+}
+
+pub fn finished(run_id: &str) {
+    if let Ok(mut streams) = STREAMS.lock() {
+        streams.runs.remove(run_id);
+        streams.subscribers.retain(|(_, id, _)| id != run_id);
+    }
+}
+
+// Called whenever the main window is shown or hidden. Showing it sends a snapshot for each
+// run with held-back lines before live streaming resumes.
+pub fn set_visible(app: &AppHandle, visible: bool) {
+    let Ok(mut streams) = STREAMS.lock() else {
+        return;
+    };
+    if VISIBLE.swap(visible, Ordering::SeqCst) == visible || !visible {
+        return;
+    }
+    for (run_id, run) in streams.runs.iter_mut() {
+        if let Some(from) = run.unsent_from.take() {
+            let _ = app.emit("script-output-snapshot", run.snapshot(run_id, from));
+        }
+    }
+}
+
+// The kept tail of a live run, for views opened after it started; None once it finished
+#[tauri::command]
+pub fn get_output_tail(run_id: String) -> Result<Option<OutputSnapshot>, String> {
+    let streams = STREAMS.lock().map_err(|e| e.to_string())?;
+    Ok(streams.runs.get(&run_id).map(|run| {
+        let from = run.tail.front().map_or(run.next_seq, |line| line.seq);
+        run.snapshot(&run_id, from)
+    }))
+}
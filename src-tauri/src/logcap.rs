@@ -0,0 +1,216 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::runner::{self, WarningEvent};
+use crate::settings;
+
+const LOG_CAP_KEY: &str = "logCap";
+const DEFAULT_MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LogCap {
+    // Output past this many bytes isn't written to the run's log files; None keeps everything
+    pub max_bytes: Option<u64>,
+    // Cancels the script once its output passes this many bytes
+    pub hard_max_bytes: Option<u64>,
+}
+
+impl Default for LogCap {
+    fn default() -> Self {
+        Self {
+            max_bytes: Some(DEFAULT_MAX_BYTES),
+            hard_max_bytes: None,
+        }
+    }
+}
+
+// Bytes streamed by both of a run's readers against its cap
+pub struct LogBudget {
+    cap: LogCap,
+    streamed: AtomicU64,
+    warned: AtomicBool,
+    stopped: AtomicBool,
+    files: Option<Mutex<LogFiles>>,
+}
+
+// A run's log files, appended to as lines arrive so a crash still leaves what was printed
+struct LogFiles {
+    streams: Vec<(&'static str, File)>,
+    written: u64,
+}
+
+// The longest prefix of `text` that fits in `limit` bytes
+fn head(text: &str, limit: usize) -> &str {
+    if text.len() <= limit {
+        return text;
+    }
+    let mut cut = limit;
+    while !text.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    &text[..cut]
+}
+
+// The script's own cap (the per-script `logCap` field) or the global one
+pub fn for_script(app: &AppHandle, path: &str) -> LogCap {
+    settings::script_data(app, path)
+        .and_then(|data| data.get(LOG_CAP_KEY).cloned())
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_else(|| settings::get(app, LOG_CAP_KEY).unwrap_or_default())
+}
+
+impl LogBudget {
+    pub fn new(cap: LogCap) -> Self {
+        Self {
+            cap,
+            streamed: AtomicU64::new(0),
+            warned: AtomicBool::new(false),
+            stopped: AtomicBool::new(false),
+            files: None,
+        }
+    }
+
+    // Also writes each line to its stream's file, see history::log_files. A run whose files
+    // can't be created still runs, without logs.
+    pub fn with_logs(cap: LogCap, files: &[(&'static str, PathBuf)]) -> Self {
+        let opened: std::io::Result<Vec<(&'static str, File)>> = files
+            .iter()
+            .map(|(stream, path)| {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                Ok((*stream, File::create(path)?))
+            })
+            .collect();
+        let mut budget = Self::new(cap);
+        match opened {
+            Ok(streams) if !streams.is_empty() => {
+                budget.files = Some(Mutex::new(LogFiles {
+                    streams,
+                    written: 0,
+                }));
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Failed to create the run's log files: {}", e),
+        }
+        budget
+    }
+
+    // Until the cap, the line goes to disk straight away; past it nothing more is written
+    fn write(&self, stream: &str, line: &str) {
+        let Some(Ok(mut files)) = self.files.as_ref().map(Mutex::lock) else {
+            return;
+        };
+        let remaining = self
+            .cap
+            .max_bytes
+            .map_or(u64::MAX, |max| max.saturating_sub(files.written));
+        if remaining == 0 {
+            return;
+        }
+        let line = format!("{}\n", line);
+        let kept = head(&line, usize::try_from(remaining).unwrap_or(usize::MAX));
+        files.written += kept.len() as u64;
+        // A pty run's single stream is logged as stdout
+        let stream = if stream == "pty" { "stdout" } else { stream };
+        if let Some((_, file)) = files.streams.iter_mut().find(|(s, _)| *s == stream) {
+            if let Err(e) = file.write_all(kept.as_bytes()) {
+                log::warn!("Failed to write the {} log: {}", stream, e);
+            }
+        }
+    }
+
+    // For helper commands, which keep no logs
+    pub fn unlimited() -> Self {
+        Self::new(LogCap {
+            max_bytes: None,
+            hard_max_bytes: None,
+        })
+    }
+
+    // One atomic add per line; the event and the cancel only happen once
+    pub fn add(&self, app: &AppHandle, run_id: &str, stream: &str, line: &str) {
+        self.write(stream, line);
+        let bytes = line.len() + 1;
+        let total = self.streamed.fetch_add(bytes as u64, Ordering::Relaxed) + bytes as u64;
+        if let Some(max) = self.cap.max_bytes.filter(|max| total > *max) {
+            if !self.warned.swap(true, Ordering::Relaxed) {
+                let message = format!("Output passed {} bytes; the rest isn't logged", max);
+                let _ = app.emit(
+                    "script-warning",
+                    WarningEvent {
+                        run_id,
+                        message: &message,
+                    },
+                );
+            }
+        }
+        if self.cap.hard_max_bytes.is_some_and(|max| total > max)
+            && !self.stopped.swap(true, Ordering::Relaxed)
+        {
+            log::warn!("Run {} passed its hard output cap, cancelling it", run_id);
+            let _ = runner::cancel_script(app.clone(), run_id.to_string(), None);
+        }
+    }
+
+    // Where the log files were cut off, when they were
+    pub fn truncated_at(&self) -> Option<u64> {
+        self.cap
+            .max_bytes
+            .filter(|max| self.streamed.load(Ordering::Relaxed) > *max)
+    }
+
+    pub fn hit_hard_cap(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+}
+
+#[tauri::command]
+pub fn get_log_cap(app: AppHandle, path: Option<String>) -> Result<LogCap, String> {
+    Ok(match path {
+        Some(path) => for_script(&app, &path),
+        None => settings::get(&app, LOG_CAP_KEY).unwrap_or_default(),
+    })
+}
+
+// With a path it sets that script's override, and None for `cap` removes it
+#[tauri::command]
+pub fn set_log_cap(
+    app: AppHandle,
+    path: Option<String>,
+    cap: Option<LogCap>,
+) -> Result<(), String> {
+    if let Some(LogCap {
+        max_bytes: Some(max),
+        hard_max_bytes: Some(hard),
+    }) = cap
+    {
+        if hard < max {
+            return Err("The hard cap can't be below the log cap".to_string());
+        }
+    }
+    let Some(path) = path else {
+        return match cap {
+            Some(cap) => settings::set(&app, LOG_CAP_KEY, &cap),
+            None => settings::delete(&app, LOG_CAP_KEY),
+        };
+    };
+    let value = cap
+        .map(|cap| serde_json::to_value(cap).map_err(|e| e.to_string()))
+        .transpose()?;
+    settings::update_script_data(&app, &path, |data| match value {
+        Some(value) => {
+            data.insert(LOG_CAP_KEY.to_string(), value);
+        }
+        None => {
+            data.remove(LOG_CAP_KEY);
+        }
+    })
+}
@@ -0,0 +1,265 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{external, history, notifications, runner, settings};
+
+// Prefix of set_script_matchers' error for a pattern that doesn't compile
+pub const INVALID_MATCHER_ERROR: &str = "invalid-matcher";
+// Per-script field holding the rules
+const MATCHERS_FIELD: &str = "matchers";
+const DEFAULT_RATE_LIMIT_MS: u64 = 5000;
+// Hits past this are still acted on but no longer kept for history
+const MAX_RECORDED_HITS: usize = 100;
+const HIT_LINE_CHARS: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MatchAction {
+    Notify,
+    // Copies capture group `group` (the whole match for 0)
+    CopyCaptureGroup,
+    MarkRunFailed,
+    StopScript,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MatcherRule {
+    pub pattern: String,
+    pub action: MatchAction,
+    pub group: usize,
+    // The rule acts at most once per this many ms
+    pub rate_limit_ms: u64,
+}
+
+impl Default for MatcherRule {
+    fn default() -> Self {
+        Self {
+            pattern: String::new(),
+            action: MatchAction::Notify,
+            group: 1,
+            rate_limit_ms: DEFAULT_RATE_LIMIT_MS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MatcherHit {
+    // Position in the script's rule list
+    pub rule: usize,
+    pub action: Option<MatchAction>,
+    pub line: String,
+    pub capture: Option<String>,
+    pub at: u64,
+}
+
+struct Compiled {
+    regex: Regex,
+    rule: MatcherRule,
+    last_fired: Mutex<Option<Instant>>,
+}
+
+// A script's rules for one run, shared by its stdout and stderr readers
+pub struct Matchers {
+    path: String,
+    rules: Vec<Compiled>,
+    hits: Mutex<Vec<MatcherHit>>,
+    failed: AtomicBool,
+}
+
+// Byte offset of the problem, from regex-syntax since regex only has a formatted message
+pub fn describe_error(pattern: &str) -> String {
+    match regex_syntax::Parser::new().parse(pattern) {
+        Err(regex_syntax::Error::Parse(e)) => {
+            format!("{} at position {}", e.kind(), e.span().start.offset)
+        }
+        Err(regex_syntax::Error::Translate(e)) => {
+            format!("{} at position {}", e.kind(), e.span().start.offset)
+        }
+        Err(e) => e.to_string(),
+        Ok(_) => match Regex::new(pattern) {
+            Err(regex::Error::CompiledTooBig(limit)) => {
+                format!("pattern compiles to more than {} bytes", limit)
+            }
+            Err(e) => e.to_string(),
+            Ok(_) => "invalid pattern".to_string(),
+        },
+    }
+}
+
+fn validate(rules: &[MatcherRule]) -> Result<(), String> {
+    for (index, rule) in rules.iter().enumerate() {
+        if rule.pattern.is_empty() {
+            return Err(format!(
+                "{}: Rule {} has no pattern",
+                INVALID_MATCHER_ERROR,
+                index + 1
+            ));
+        }
+        let regex = Regex::new(&rule.pattern).map_err(|_| {
+            format!(
+                "{}: Rule {}: {}",
+                INVALID_MATCHER_ERROR,
+                index + 1,
+                describe_error(&rule.pattern)
+            )
+        })?;
+        if rule.action == MatchAction::CopyCaptureGroup && rule.group >= regex.captures_len() {
+            return Err(format!(
+                "{}: Rule {}: the pattern has no capture group {}",
+                INVALID_MATCHER_ERROR,
+                index + 1,
+                rule.group
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn stored_rules(app: &AppHandle, path: &str) -> Vec<MatcherRule> {
+    settings::script_data(app, path)
+        .and_then(|data| data.get(MATCHERS_FIELD).cloned())
+        .and_then(|rules| serde_json::from_value(rules).ok())
+        .unwrap_or_default()
+}
+
+// Compiled once per run; None when the script has no rules. A rule that no longer compiles
+// (edited outside the app) is skipped.
+pub fn compile(app: &AppHandle, path: &str) -> Option<Arc<Matchers>> {
+    let rules: Vec<Compiled> = stored_rules(app, path)
+        .into_iter()
+        .filter_map(|rule| match Regex::new(&rule.pattern) {
+            Ok(regex) => Some(Compiled {
+                regex,
+                rule,
+                last_fired: Mutex::new(None),
+            }),
+            Err(e) => {
+                log::warn!("Skipping matcher {:?} of {}: {}", rule.pattern, path, e);
+                None
+            }
+        })
+        .collect();
+    (!rules.is_empty()).then(|| {
+        Arc::new(Matchers {
+            path: path.to_string(),
+            rules,
+            hits: Mutex::default(),
+            failed: AtomicBool::new(false),
+        })
+    })
+}
+
+fn truncate(line: &str) -> String {
+    match line.char_indices().nth(HIT_LINE_CHARS) {
+        Some((end, _)) => line[..end].to_string(),
+        None => line.to_string(),
+    }
+}
+
+impl Matchers {
+    // Called by the readers for every output line
+    pub fn check(&self, app: &AppHandle, run_id: &str, line: &str) {
+        for (index, compiled) in self.rules.iter().enumerate() {
+            let Some(captures) = compiled.regex.captures(line) else {
+                continue;
+            };
+            let rate_limit = Duration::from_millis(compiled.rule.rate_limit_ms);
+            let Ok(mut last_fired) = compiled.last_fired.lock() else {
+                continue;
+            };
+            if last_fired.is_some_and(|at| at.elapsed() < rate_limit) {
+                continue;
+            }
+            *last_fired = Some(Instant::now());
+            drop(last_fired);
+            let capture = (compiled.rule.action == MatchAction::CopyCaptureGroup)
+                .then(|| captures.get(compiled.rule.group))
+                .flatten()
+                .map(|m| m.as_str().to_string());
+            self.act(app, run_id, &compiled.rule, line, capture.as_deref());
+            if let Ok(mut hits) = self.hits.lock() {
+                if hits.len() < MAX_RECORDED_HITS {
+                    hits.push(MatcherHit {
+                        rule: index,
+                        action: Some(compiled.rule.action),
+                        line: truncate(line),
+                        capture,
+                        at: history::now_ms(),
+                    });
+                }
+            }
+        }
+    }
+
+    fn act(
+        &self,
+        app: &AppHandle,
+        run_id: &str,
+        rule: &MatcherRule,
+        line: &str,
+        capture: Option<&str>,
+    ) {
+        let name = Path::new(&self.path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.path.clone());
+        match rule.action {
+            MatchAction::Notify => notifications::show(app, &name, &truncate(line)),
+            MatchAction::CopyCaptureGroup => {
+                if let Some(text) = capture {
+                    if let Err(e) = external::copy_to_clipboard(text) {
+                        log::warn!("Matcher in {} couldn't copy: {}", self.path, e);
+                    }
+                }
+            }
+            MatchAction::MarkRunFailed => self.failed.store(true, Ordering::Relaxed),
+            MatchAction::StopScript => {
+                log::info!("Matcher {:?} stopped run {}", rule.pattern, run_id);
+                let _ = runner::cancel_script(app.clone(), run_id.to_string(), None);
+            }
+        }
+    }
+
+    // A mark-run-failed rule matched
+    pub fn failed(&self) -> bool {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    pub fn hits(&self) -> Vec<MatcherHit> {
+        self.hits
+            .lock()
+            .map(|hits| hits.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[tauri::command]
+pub fn get_script_matchers(app: AppHandle, path: String) -> Result<Vec<MatcherRule>, String> {
+    Ok(stored_rules(&app, &path))
+}
+
+// Replaces the script's rules; nothing is saved when one of them doesn't compile
+#[tauri::command]
+pub fn set_script_matchers(
+    app: AppHandle,
+    path: String,
+    rules: Vec<MatcherRule>,
+) -> Result<(), String> {
+    validate(&rules)?;
+    let value = serde_json::to_value(&rules).map_err(|e| e.to_string())?;
+    settings::update_script_data(&app, &path, |data| {
+        if rules.is_empty() {
+            data.remove(MATCHERS_FIELD);
+        } else {
+            data.insert(MATCHERS_FIELD.to_string(), value);
+        }
+    })
+}
@@ -0,0 +1,536 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+
+// Only the leading comment block is ever parsed, so long scripts stay cheap
+const MAX_HEADER_LINES: usize = 64;
+
+static DIRECTIVE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*#\s*@([A-Za-z][\w-]*)\s*:?\s*(.*?)\s*$").unwrap());
+
+// Commands that expect to talk to a terminal
+static TTY_COMMAND: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|[\s;|&(`])(?:dialog|whiptail|select\s+\w+\s+in)\b").unwrap()
+});
+static READ_COMMAND: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|[\s;&(`])read\s").unwrap());
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Directive {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Requirement {
+    pub name: String,
+    pub min_version: Option<String>,
+}
+
+// `aws>=2` or a bare `jq`
+fn parse_requirement(spec: &str) -> Requirement {
+    match spec.split_once(">=") {
+        Some((name, version)) => Requirement {
+            name: name.trim().to_string(),
+            min_version: Some(version.trim().to_string()).filter(|v| !v.is_empty()),
+        },
+        None => Requirement {
+            name: spec.to_string(),
+            min_version: None,
+        },
+    }
+}
+
+impl Directive {
+    pub fn new(key: &str, value: impl Into<String>) -> Self {
+        Self {
+            key: key.to_string(),
+            value: value.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptMetadata {
+    pub shebang: Option<String>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub icon: Option<String>,
+    pub tags: Vec<String>,
+    pub args: Option<String>,
+    pub timeout_secs: Option<u64>,
+    // Set by `read`, dialog or whiptail, or forced with `# @tty: true|false`
+    pub needs_tty: bool,
+    // `# @dangerous`: runs need an explicit confirmation
+    pub dangerous: bool,
+    // `# @source: true`: sets variables or functions for the calling shell, so running it as a
+    // subprocess does nothing useful
+    pub sourceable: bool,
+    // `# @requires: jq, aws>=2`: programs the script expects on the PATH
+    pub requires: Vec<Requirement>,
+    // `# @accepts: stdin`: reads its input from stdin, so it can be run with the clipboard
+    pub accepts_stdin: bool,
+    // `# @port: 3000, 5173`: TCP ports the script listens on
+    pub ports: Vec<u16>,
+    // `# @lockfile: /tmp/dev.lock`: files whose presence means another copy is running;
+    // several are separated by commas, relative ones are against the script's folder
+    pub lockfiles: Vec<String>,
+    // `# @dry-run-flag: --dry-run`: arguments that make the script only report what it would do
+    pub dry_run_flag: Option<String>,
+    pub directives: Vec<Directive>,
+}
+
+impl ScriptMetadata {
+    pub fn directive(&self, key: &str) -> Option<&str> {
+        self.directives
+            .iter()
+            .find(|d| d.key.eq_ignore_ascii_case(key))
+            .map(|d| d.value.as_str())
+    }
+}
+
+pub fn parse_directive(line: &str) -> Option<Directive> {
+    DIRECTIVE
+        .captures(line)
+        .map(|caps| Directive::new(&caps[1].to_lowercase(), &caps[2]))
+}
+
+// `# @progress: 40` (or `40%`) anywhere in the output, clamped to 0–100
+pub fn parse_progress(line: &str) -> Option<f64> {
+    let directive = parse_directive(line)?;
+    if directive.key != "progress" {
+        return None;
+    }
+    let percent: f64 = directive.value.trim_end_matches('%').trim().parse().ok()?;
+    percent.is_finite().then(|| percent.clamp(0.0, 100.0))
+}
+
+pub fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn is_header_line(index: usize, line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.is_empty() || trimmed.starts_with('#') || (index == 0 && trimmed.starts_with("#!"))
+}
+
+pub fn parse<'a>(lines: impl IntoIterator<Item = &'a str>) -> ScriptMetadata {
+    let mut meta = ScriptMetadata::default();
+    let mut comments = Vec::new();
+    for (index, line) in lines.into_iter().take(MAX_HEADER_LINES).enumerate() {
+        if !is_header_line(index, line) {
+            break;
+        }
+        let trimmed = line.trim();
+        if index == 0 && trimmed.starts_with("#!") {
+            meta.shebang = Some(trimmed.to_string());
+        } else if let Some(directive) = parse_directive(line) {
+            meta.directives.push(directive);
+        } else if let Some(text) = trimmed.strip_prefix('#') {
+            let text = text.trim_start_matches('#').trim();
+            if !text.is_empty() && comments.len() < 2 {
+                comments.push(text.to_string());
+            }
+        }
+    }
+
+    meta.name = meta.directive("name").map(str::to_string);
+    meta.icon = meta.directive("icon").map(str::to_string);
+    meta.args = meta.directive("args").map(str::to_string);
+    meta.tags = meta.directive("tags").map(split_list).unwrap_or_default();
+    meta.timeout_secs = meta.directive("timeout").and_then(|v| v.parse().ok());
+    meta.requires = meta
+        .directive("requires")
+        .map(|v| split_list(v).iter().map(|s| parse_requirement(s)).collect())
+        .unwrap_or_default();
+    meta.dangerous = meta
+        .directive("dangerous")
+        .is_some_and(|v| !matches!(v.to_lowercase().as_str(), "false" | "no" | "0"));
+    meta.sourceable = meta
+        .directive("source")
+        .is_some_and(|v| !matches!(v.to_lowercase().as_str(), "false" | "no" | "0"));
+    meta.ports = meta
+        .directive("port")
+        .map(|v| {
+            split_list(v)
+                .iter()
+                .filter_map(|p| p.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    meta.lockfiles = meta
+        .directive("lockfile")
+        .map(|v| {
+            v.split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    meta.dry_run_flag = meta
+        .directive("dry-run-flag")
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string);
+    meta.accepts_stdin = meta.directive("accepts").is_some_and(|v| {
+        split_list(v)
+            .iter()
+            .any(|s| s.eq_ignore_ascii_case("stdin"))
+    });
+    // Same fallback as readScriptDescription in the frontend
+    meta.description = meta
+        .directive("description")
+        .map(str::to_string)
+        .or_else(|| (!comments.is_empty()).then(|| comments.join(" — ")));
+    meta
+}
+
+fn uses_tty(content: &str) -> bool {
+    content.lines().any(|line| {
+        let code = line.trim_start();
+        if code.starts_with('#') {
+            return false;
+        }
+        // `while read` loops and piped or redirected reads consume data, not keystrokes
+        let reads_input =
+            READ_COMMAND.is_match(code) && !code.contains("while") && !code.contains(['|', '<']);
+        reads_input || TTY_COMMAND.is_match(code)
+    })
+}
+
+// Parses the header, then scans the whole body for interactive commands
+pub fn parse_script(content: &str) -> ScriptMetadata {
+    let mut meta = parse(content.lines());
+    meta.needs_tty = match meta.directive("tty") {
+        Some(value) => !matches!(value.to_lowercase().as_str(), "false" | "no" | "0"),
+        None => uses_tty(content),
+    };
+    meta
+}
+
+// Replaces every directive in the header with `directives`, written right after the shebang
+pub fn with_directives(content: &str, directives: &[Directive]) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let header_len = lines
+        .iter()
+        .enumerate()
+        .take_while(|(index, line)| *index < MAX_HEADER_LINES && is_header_line(*index, line))
+        .count();
+    let has_shebang = lines
+        .first()
+        .is_some_and(|l| l.trim_start().starts_with("#!"));
+
+    let mut out = Vec::with_capacity(lines.len() + directives.len());
+    if has_shebang {
+        out.push(lines[0].to_string());
+    }
+    out.extend(directives.iter().map(|d| {
+        if d.value.is_empty() {
+            format!("# @{}", d.key)
+        } else {
+            format!("# @{}: {}", d.key, d.value)
+        }
+    }));
+    let start = usize::from(has_shebang);
+    out.extend(
+        lines[start..header_len]
+            .iter()
+            .filter(|line| parse_directive(line).is_none())
+            .map(|line| line.to_string()),
+    );
+    out.extend(lines[header_len..].iter().map(|line| line.to_string()));
+
+    let mut result = out.join("\n");
+    if content.ends_with('\n') || content.is_empty() {
+        result.push('\n');
+    }
+    result
+}
+
+static PLACEHOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{\s*([A-Za-z_][\w.-]*)\s*\}\}").unwrap());
+
+// Unique `{{NAME}}` tokens in the order they first appear
+pub fn placeholders(content: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for caps in PLACEHOLDER.captures_iter(content) {
+        if !names.iter().any(|name| name == &caps[1]) {
+            names.push(caps[1].to_string());
+        }
+    }
+    names
+}
+
+// Tokens without a value are left as they are
+pub fn fill_placeholders(content: &str, values: &HashMap<String, String>) -> String {
+    PLACEHOLDER
+        .replace_all(content, |caps: &regex::Captures| {
+            values
+                .get(&caps[1])
+                .cloned()
+                .unwrap_or_else(|| caps[0].to_string())
+        })
+        .into_owned()
+}
+
+static GETOPTS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\bgetopts\s+["']?(:?[A-Za-z0-9:]+)["']?"#).unwrap());
+static CASE_ON_FIRST_ARG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^\s*case\s+"?\$\{?1\}?"?\s+in\b"#).unwrap());
+static CASE_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*\(?\s*([^()#;]+?)\s*\)(.*)$").unwrap());
+static POSITIONAL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"^\s*(?:local\s+|readonly\s+|declare\s+)?([A-Za-z_]\w*)=["']?\$(?:([1-9])|\{([1-9])(?::?-([^}]*))?\})"#,
+    )
+    .unwrap()
+});
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArgSpec {
+    pub name: String,
+    // Empty for positional arguments
+    pub flags: Vec<String>,
+    pub takes_value: bool,
+    // 1-based, for positional arguments
+    pub position: Option<usize>,
+    pub default: Option<String>,
+    // Subcommands matched by a `case "$1"` block
+    pub choices: Vec<String>,
+    pub description: Option<String>,
+    // Guessed from the script body rather than declared with `# @arg`
+    pub inferred: bool,
+}
+
+impl ArgSpec {
+    fn flag(flags: Vec<String>, takes_value: bool) -> Self {
+        let name = flags
+            .iter()
+            .max_by_key(|f| f.len())
+            .map(|f| f.trim_start_matches('-').to_string())
+            .unwrap_or_default();
+        Self {
+            name,
+            flags,
+            takes_value,
+            inferred: true,
+            ..Self::default()
+        }
+    }
+}
+
+// `# @arg --name description`, `# @arg -n|--name <value> description` or `# @arg file description`
+pub fn declared_args(meta: &ScriptMetadata) -> Vec<ArgSpec> {
+    let mut position = 0;
+    meta.directives
+        .iter()
+        .filter(|d| d.key == "arg")
+        .filter_map(|d| {
+            let (spec, rest) = d
+                .value
+                .split_once(char::is_whitespace)
+                .unwrap_or((&d.value, ""));
+            let mut rest = rest.trim();
+            let takes_value = rest.starts_with('<');
+            if takes_value {
+                rest = rest.split_once('>').map_or("", |(_, r)| r.trim());
+            }
+            let description = (!rest.is_empty()).then(|| rest.to_string());
+            if spec.starts_with('-') {
+                let flags = spec.split('|').map(str::to_string).collect();
+                return Some(ArgSpec {
+                    description,
+                    inferred: false,
+                    ..ArgSpec::flag(flags, takes_value)
+                });
+            }
+            (!spec.is_empty()).then(|| {
+                position += 1;
+                ArgSpec {
+                    name: spec.to_string(),
+                    takes_value: true,
+                    position: Some(position),
+                    description,
+                    ..ArgSpec::default()
+                }
+            })
+        })
+        .collect()
+}
+
+fn getopts_args(optstring: &str) -> Vec<ArgSpec> {
+    let chars: Vec<char> = optstring.trim_start_matches(':').chars().collect();
+    chars
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.is_ascii_alphanumeric())
+        .map(|(i, c)| ArgSpec::flag(vec![format!("-{}", c)], chars.get(i + 1) == Some(&':')))
+        .collect()
+}
+
+// Walks `case "$1" in` blocks: flag patterns become flags, plain words become subcommands.
+// Returns which lines were inside such a block
+fn case_args(lines: &[&str], args: &mut Vec<ArgSpec>) -> Vec<bool> {
+    let mut inside = vec![false; lines.len()];
+    let mut choices: Vec<String> = Vec::new();
+    let mut depth = 0;
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index].trim();
+        inside[index] = depth > 0;
+        index += 1;
+        if depth == 0 {
+            if CASE_ON_FIRST_ARG.is_match(line) {
+                depth = 1;
+            }
+            continue;
+        }
+        if line.starts_with("case ") {
+            depth += 1;
+        } else if line == "esac" || line.starts_with("esac ") || line.starts_with("esac;") {
+            depth -= 1;
+            continue;
+        }
+        if depth != 1 || line.starts_with('#') {
+            continue;
+        }
+        let Some(caps) = CASE_PATTERN.captures(line) else {
+            continue;
+        };
+        // The branch body runs until `;;`, possibly on the pattern's own line
+        let mut body = caps[2].to_string();
+        while !body.contains(";;") && index < lines.len() && !lines[index].contains("esac") {
+            body.push('\n');
+            body.push_str(lines[index]);
+            inside[index] = true;
+            index += 1;
+        }
+        let patterns: Vec<&str> = caps[1]
+            .split('|')
+            .map(|p| p.trim().trim_matches(['"', '\'']))
+            .collect();
+        let flags: Vec<String> = patterns
+            .iter()
+            .filter(|p| p.starts_with('-') && *p != &"--" && !p.starts_with("-*"))
+            .map(|p| p.trim_end_matches("=*").to_string())
+            .collect();
+        if !flags.is_empty() {
+            let takes_value = patterns.iter().any(|p| p.ends_with("=*"))
+                || body.contains("$2")
+                || body.contains("${2")
+                || body.contains("shift 2");
+            args.push(ArgSpec::flag(flags, takes_value));
+        } else {
+            choices.extend(
+                patterns
+                    .iter()
+                    .filter(|p| !p.is_empty() && !p.starts_with('-'))
+                    .filter(|p| !p.contains(['*', '?', '[', '$']))
+                    .map(|p| p.to_string()),
+            );
+        }
+    }
+    if !choices.is_empty() {
+        args.push(ArgSpec {
+            name: "command".to_string(),
+            takes_value: true,
+            position: Some(1),
+            choices,
+            inferred: true,
+            ..ArgSpec::default()
+        });
+    }
+    inside
+}
+
+// Declared `# @arg` entries first, then guesses that don't clash with them
+pub fn script_args(content: &str) -> Vec<ArgSpec> {
+    let mut args = declared_args(&parse(content.lines()));
+    let inferred: Vec<ArgSpec> = infer_args(content)
+        .into_iter()
+        .filter(|guess| {
+            !args.iter().any(|arg| {
+                arg.flags.iter().any(|f| guess.flags.contains(f))
+                    || (arg.position.is_some() && arg.position == guess.position)
+            })
+        })
+        .collect();
+    args.extend(inferred);
+    args
+}
+
+// Best-effort guesses from getopts strings, `case "$1"` blocks and `name=${1:-default}`
+pub fn infer_args(content: &str) -> Vec<ArgSpec> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut args = Vec::new();
+    for caps in lines
+        .iter()
+        .filter(|l| !l.trim_start().starts_with('#'))
+        .filter_map(|l| GETOPTS.captures(l))
+    {
+        args.extend(getopts_args(&caps[1]));
+    }
+    let in_case = case_args(&lines, &mut args);
+    // `$2` inside a flag's branch is that flag's value, not a positional argument
+    for caps in lines
+        .iter()
+        .zip(in_case)
+        .filter(|(_, in_case)| !in_case)
+        .filter_map(|(l, _)| POSITIONAL.captures(l))
+    {
+        let Some(position) = caps
+            .get(2)
+            .or(caps.get(3))
+            .and_then(|m| m.as_str().parse().ok())
+        else {
+            continue;
+        };
+        if args.iter().any(|a| a.position == Some(position)) {
+            continue;
+        }
+        args.push(ArgSpec {
+            name: caps[1].to_lowercase(),
+            takes_value: true,
+            position: Some(position),
+            default: caps
+                .get(4)
+                .map(|m| m.as_str().trim_matches(['"', '\'']).to_string()),
+            inferred: true,
+            ..ArgSpec::default()
+        });
+    }
+
+    // The same flag is often matched by both getopts and a case block, e.g. -v and -v|--verbose
+    let mut merged: Vec<ArgSpec> = Vec::new();
+    for arg in args {
+        match merged
+            .iter_mut()
+            .find(|m| m.flags.iter().any(|f| arg.flags.contains(f)))
+        {
+            Some(existing) => {
+                let mut flags = existing.flags.clone();
+                flags.extend(
+                    arg.flags
+                        .into_iter()
+                        .filter(|f| !existing.flags.contains(f)),
+                );
+                *existing = ArgSpec::flag(flags, existing.takes_value || arg.takes_value);
+            }
+            None => merged.push(arg),
+        }
+    }
+    merged.sort_by_key(|arg| arg.position.unwrap_or(usize::MAX));
+    merged
+}
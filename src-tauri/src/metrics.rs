@@ -0,0 +1,155 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::runner::ActiveRuns;
+use crate::settings;
+
+const INTERVAL_KEY: &str = "metricsIntervalMs";
+const DEFAULT_INTERVAL_MS: u64 = 1000;
+const MIN_INTERVAL_MS: u64 = 100;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MetricsEvent<'a> {
+    run_id: &'a str,
+    // Across all cores, so a busy tree can go past 100
+    cpu_percent: f64,
+    rss_bytes: u64,
+    processes: usize,
+    elapsed_ms: u64,
+}
+
+// Totals for every process in the run's group
+#[derive(Debug, Clone, Copy, Default)]
+struct Sample {
+    cpu_ms: u64,
+    rss_bytes: u64,
+    processes: usize,
+}
+
+#[cfg(target_os = "linux")]
+fn sample(pgid: u32) -> Option<Sample> {
+    // SAFETY: sysconf only reads a system constant
+    let (ticks, page) = unsafe {
+        (
+            libc::sysconf(libc::_SC_CLK_TCK).max(1) as u64,
+            libc::sysconf(libc::_SC_PAGESIZE).max(1) as u64,
+        )
+    };
+    let mut total = Sample::default();
+    for entry in std::fs::read_dir("/proc").ok()?.flatten() {
+        let Ok(stat) = std::fs::read_to_string(entry.path().join("stat")) else {
+            continue;
+        };
+        // The command name is in parentheses and may contain spaces
+        let Some((_, fields)) = stat.rsplit_once(')') else {
+            continue;
+        };
+        let fields: Vec<&str> = fields.split_whitespace().collect();
+        let field = |n: usize| fields.get(n).and_then(|f| f.parse::<u64>().ok());
+        // Numbered from the state, field 3 in proc(5)
+        if field(2) != Some(pgid as u64) {
+            continue;
+        }
+        let cpu_ticks = field(11).unwrap_or(0) + field(12).unwrap_or(0);
+        total.cpu_ms += cpu_ticks * 1000 / ticks;
+        total.rss_bytes += field(21).unwrap_or(0) * page;
+        total.processes += 1;
+    }
+    Some(total)
+}
+
+// `time` is cumulative CPU as [[dd-]hh:]mm:ss.cc
+#[cfg(all(unix, not(target_os = "linux")))]
+fn parse_cpu_time(text: &str) -> Option<u64> {
+    let (days, rest) = match text.split_once('-') {
+        Some((days, rest)) => (days.parse::<u64>().ok()?, rest),
+        None => (0, text),
+    };
+    let seconds = rest.split(':').try_fold(0.0, |total, part| {
+        part.parse::<f64>().ok().map(|value| total * 60.0 + value)
+    })?;
+    Some(((days * 86_400) as f64 * 1000.0 + seconds * 1000.0) as u64)
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn sample(pgid: u32) -> Option<Sample> {
+    let output = std::process::Command::new("ps")
+        .args(["-A", "-o", "pgid=,rss=,time="])
+        .stdin(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    let mut total = Sample::default();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let mut columns = line.split_whitespace();
+        if columns.next().and_then(|g| g.parse::<u32>().ok()) != Some(pgid) {
+            continue;
+        }
+        let rss_kb: u64 = columns.next().and_then(|r| r.parse().ok()).unwrap_or(0);
+        total.rss_bytes += rss_kb * 1024;
+        total.cpu_ms += columns.next().and_then(parse_cpu_time).unwrap_or(0);
+        total.processes += 1;
+    }
+    Some(total)
+}
+
+#[cfg(not(unix))]
+fn sample(_pgid: u32) -> Option<Sample> {
+    None
+}
+
+fn interval(app: &AppHandle) -> Duration {
+    let ms = settings::get(app, INTERVAL_KEY).unwrap_or(DEFAULT_INTERVAL_MS);
+    Duration::from_millis(ms.max(MIN_INTERVAL_MS))
+}
+
+// Samples the run's process group until the run is gone; CPU is the share of the last interval
+pub fn start(app: &AppHandle, run_id: &str, pgid: u32) {
+    let app = app.clone();
+    let run_id = run_id.to_string();
+    let interval = interval(&app);
+    thread::spawn(move || {
+        let started = Instant::now();
+        let mut previous: Option<(Instant, u64)> = None;
+        while app.state::<ActiveRuns>().pid(&run_id) == Some(pgid) {
+            let Some(sample) = sample(pgid) else {
+                return;
+            };
+            let now = Instant::now();
+            let cpu_percent = previous.map_or(0.0, |(at, cpu_ms)| {
+                let wall_ms = now.duration_since(at).as_millis().max(1) as f64;
+                sample.cpu_ms.saturating_sub(cpu_ms) as f64 * 100.0 / wall_ms
+            });
+            previous = Some((now, sample.cpu_ms));
+            // The group can be empty for a moment after the last process exits
+            if sample.processes > 0 {
+                let _ = app.emit(
+                    "script-metrics",
+                    MetricsEvent {
+                        run_id: &run_id,
+                        cpu_percent: (cpu_percent * 10.0).round() / 10.0,
+                        rss_bytes: sample.rss_bytes,
+                        processes: sample.processes,
+                        elapsed_ms: started.elapsed().as_millis() as u64,
+                    },
+                );
+            }
+            thread::sleep(interval);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn set_metrics_interval_ms(app: AppHandle, ms: u64) -> Result<(), String> {
+    if ms < MIN_INTERVAL_MS {
+        return Err(format!(
+            "The metrics interval must be at least {} ms",
+            MIN_INTERVAL_MS
+        ));
+    }
+    settings::set(&app, INTERVAL_KEY, &ms)
+}
@@ -0,0 +1,220 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_store::StoreExt;
+
+use crate::{history, paths, scripts, settings, snapshots, watcher};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MigratedEvent<'a> {
+    from: &'a str,
+    to: &'a str,
+}
+
+// `value` moved from under one of `from` to under `to`, or None when it isn't below any of them
+pub(crate) fn rebase(value: &str, from: &[String], to: &str) -> Option<String> {
+    from.iter().find_map(|root| {
+        let rest = value.strip_prefix(root.as_str())?;
+        if rest.is_empty() {
+            Some(to.to_string())
+        } else if rest.starts_with(['/', '\\']) {
+            Some(format!("{}{}", to, rest))
+        } else {
+            None
+        }
+    })
+}
+
+// Rewrites string values and object keys alike, since some settings are maps keyed by path
+pub(crate) fn rewrite(value: &mut Value, from: &[String], to: &str) -> bool {
+    match value {
+        Value::String(text) => match rebase(text, from, to) {
+            Some(rebased) => {
+                *text = rebased;
+                true
+            }
+            None => false,
+        },
+        Value::Array(items) => items
+            .iter_mut()
+            .fold(false, |changed, item| rewrite(item, from, to) | changed),
+        Value::Object(fields) => {
+            let mut changed = false;
+            let rewritten: Map<String, Value> = std::mem::take(fields)
+                .into_iter()
+                .map(|(key, mut value)| {
+                    changed |= rewrite(&mut value, from, to);
+                    match rebase(&key, from, to) {
+                        Some(key) => {
+                            changed = true;
+                            (key, value)
+                        }
+                        None => (key, value),
+                    }
+                })
+                .collect();
+            *fields = rewritten;
+            changed
+        }
+        _ => false,
+    }
+}
+
+// A rename when both are on the same filesystem, otherwise a copy that is undone if it fails
+fn move_dir(from: &Path, to: &Path) -> Result<(), String> {
+    if to.is_dir() {
+        // Only an empty directory gets here, see migrate_scripts_path
+        fs::remove_dir(to).map_err(|e| e.to_string())?;
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    if let Err(e) = paths::copy_recursive(from, to) {
+        let _ = fs::remove_dir_all(to);
+        return Err(format!("Failed to copy scripts to {}: {}", to.display(), e));
+    }
+    if let Err(e) = fs::remove_dir_all(from) {
+        // Whatever could be removed is restored from the copy before the copy goes away
+        let _ = paths::copy_recursive(to, from);
+        let _ = fs::remove_dir_all(to);
+        return Err(format!("Failed to remove {}: {}", from.display(), e));
+    }
+    Ok(())
+}
+
+fn is_empty_dir(path: &Path) -> bool {
+    fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_none())
+}
+
+// Every store entry, so a failed rewrite can put them all back
+fn rewrite_store(
+    app: &AppHandle,
+    from: &[String],
+    to: &str,
+) -> Result<Vec<(String, Value)>, String> {
+    let store = app
+        .store(paths::store_file(app))
+        .map_err(|e| e.to_string())?;
+    let backup = store.entries();
+    for (key, mut value) in backup.clone() {
+        if rewrite(&mut value, from, to) {
+            store.set(key, value);
+        }
+    }
+    // The main folder may have been empty, meaning the default location
+    let mut frontend = store
+        .get("settings")
+        .unwrap_or_else(|| Value::Object(Map::new()));
+    if let Value::Object(fields) = &mut frontend {
+        fields.insert("scriptsFolder".to_string(), Value::String(to.to_string()));
+    }
+    store.set("settings", frontend);
+    store.save().map_err(|e| e.to_string())?;
+    Ok(backup)
+}
+
+fn restore_store(app: &AppHandle, backup: Vec<(String, Value)>) {
+    if let Ok(store) = app.store(paths::store_file(app)) {
+        store.clear();
+        for (key, value) in backup {
+            store.set(key, value);
+        }
+        let _ = store.save();
+    }
+}
+
+// Points the main scripts folder at `new_path`; with `move_files` the folder is moved there
+// first. Favorites, per-script settings, hotkeys, profiles, the index, history and snapshots
+// are rewritten to the new paths. A failed move or rewrite leaves files and settings as they were.
+#[tauri::command]
+pub fn migrate_scripts_path(
+    app: AppHandle,
+    new_path: String,
+    move_files: bool,
+) -> Result<(), String> {
+    let old = settings::scripts_folder(&app)
+        .ok_or_else(|| "No scripts folder is configured".to_string())?;
+    let new = settings::expand_path(new_path.trim());
+    if !new.is_absolute() {
+        return Err(format!("Not an absolute path: {}", new_path));
+    }
+    let old_resolved = fs::canonicalize(&old).unwrap_or_else(|_| old.clone());
+    // The new folder may not exist yet, so only its parent can be resolved
+    let new_resolved = match (
+        new.parent().and_then(|p| fs::canonicalize(p).ok()),
+        new.file_name(),
+    ) {
+        (Some(parent), Some(name)) => parent.join(name),
+        _ => new.clone(),
+    };
+    if scripts::is_within(&new_resolved, &old_resolved) {
+        return Err(format!(
+            "{} is inside the current scripts folder",
+            new.display()
+        ));
+    }
+    if move_files {
+        if !old.is_dir() {
+            return Err(format!("Scripts folder not found: {}", old.display()));
+        }
+        if new.exists() && !is_empty_dir(&new) {
+            return Err(format!("{} already exists and is not empty", new.display()));
+        }
+    } else if !new.is_dir() {
+        return Err(format!("Folder not found: {}", new.display()));
+    }
+
+    let display = |path: &PathBuf| path.to_string_lossy().to_string();
+    let to = display(&new);
+    // As configured (possibly `~/…`), expanded, and with symlinks resolved
+    let frontend: Value = settings::get(&app, "settings").unwrap_or_default();
+    let mut from: Vec<String> = frontend["scriptsFolder"]
+        .as_str()
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .into_iter()
+        .chain([display(&old), display(&old_resolved)])
+        .collect();
+    from.dedup();
+
+    if move_files {
+        move_dir(&old, &new)?;
+    }
+    let undo_move = || {
+        if move_files {
+            let _ = move_dir(&new, &old);
+        }
+    };
+    let backup = match rewrite_store(&app, &from, &to) {
+        Ok(backup) => backup,
+        Err(e) => {
+            undo_move();
+            return Err(e);
+        }
+    };
+    if let Err(e) = history::rename_paths(&app, |path| rebase(path, &from, &to)) {
+        restore_store(&app, backup);
+        undo_move();
+        return Err(e);
+    }
+    // Only costs a content-changed warning on the next run if it fails
+    let _ = snapshots::rename_paths(&app, |path| rebase(path, &from, &to));
+
+    let _ = watcher::restart(&app);
+    scripts::notify_scripts_changed(&app, Vec::new());
+    let _ = app.emit(
+        "scripts-path-migrated",
+        MigratedEvent {
+            from: &display(&old),
+            to: &to,
+        },
+    );
+    Ok(())
+}
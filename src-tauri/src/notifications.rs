@@ -0,0 +1,494 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::batch::BatchSummary;
+use crate::runner::{self, RunOptions, ScriptResult};
+use crate::{dnd, history, settings, window};
+
+// Action types are registered by the webview (registerActionTypes) with these ids:
+// RUN_SUCCEEDED offers "view-output", RUN_FAILED offers "view-output" and "rerun"
+const RUN_SUCCEEDED: &str = "run-succeeded";
+const RUN_FAILED: &str = "run-failed";
+const RERUN: &str = "rerun";
+// What XDG servers report for a click on the notification's body
+#[cfg(target_os = "linux")]
+const BODY_CLICK: &str = "default";
+const PREFS_KEY: &str = "notificationPrefs";
+const OUTPUT_LINES: usize = 3;
+const OUTPUT_LINE_CHARS: usize = 80;
+
+// CSI sequences (colors, cursor movement) and OSC sequences (titles, hyperlinks)
+pub static ANSI: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\x1b\[[0-?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(?:\x07|\x1b\\)|\x1b[@-_]").unwrap()
+});
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationUrgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+// Per-script overrides of the global sound, urgency and persistence; None keeps the global one
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationStyle {
+    // One of list_notification_sounds
+    pub sound: Option<String>,
+    pub urgency: Option<NotificationUrgency>,
+    // Stay on screen until dismissed
+    pub persistent: Option<bool>,
+}
+
+// The style a notification is shown with, after falling back to the globals
+struct Style {
+    sound: Option<String>,
+    urgency: NotificationUrgency,
+    persistent: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationPrefs {
+    // Runs shorter than this finish without a notification
+    pub min_duration_ms: u64,
+    pub notify_on_success: bool,
+    pub notify_on_failure: bool,
+    pub failures_bypass_threshold: bool,
+    // Per-script minimum duration, keyed by script path
+    pub script_min_duration_ms: HashMap<String, u64>,
+    // None plays the platform's default sound
+    pub sound: Option<String>,
+    // Urgency and persistence are only honoured by Linux notification servers
+    pub urgency: NotificationUrgency,
+    pub persistent: bool,
+    // Keyed by script path
+    pub script_styles: HashMap<String, NotificationStyle>,
+}
+
+impl Default for NotificationPrefs {
+    fn default() -> Self {
+        Self {
+            min_duration_ms: 0,
+            notify_on_success: true,
+            notify_on_failure: true,
+            failures_bypass_threshold: true,
+            script_min_duration_ms: HashMap::new(),
+            sound: None,
+            urgency: NotificationUrgency::Normal,
+            persistent: false,
+            script_styles: HashMap::new(),
+        }
+    }
+}
+
+impl NotificationPrefs {
+    // Uses the duration measured for the exit event, so every trigger is judged alike
+    fn should_notify(&self, result: &ScriptResult) -> bool {
+        if result.success {
+            self.notify_on_success && self.meets_threshold(result)
+        } else {
+            self.notify_on_failure
+                && (self.failures_bypass_threshold || self.meets_threshold(result))
+        }
+    }
+
+    fn meets_threshold(&self, result: &ScriptResult) -> bool {
+        let threshold = self
+            .script_min_duration_ms
+            .get(&result.path)
+            .copied()
+            .unwrap_or(self.min_duration_ms);
+        result.duration_ms >= threshold
+    }
+
+    // A sound that's no longer installed falls back to the global one, then to the default
+    fn style(&self, path: &str) -> Style {
+        let own = self.script_styles.get(path).cloned().unwrap_or_default();
+        let sounds = available_sounds();
+        let valid = |sound: Option<String>| {
+            sound.filter(|name| {
+                let known = sounds.contains(name);
+                if !known {
+                    log::warn!("Unknown notification sound {}, using the default", name);
+                }
+                known
+            })
+        };
+        Style {
+            sound: valid(own.sound).or_else(|| valid(self.sound.clone())),
+            urgency: own.urgency.unwrap_or(self.urgency),
+            persistent: own.persistent.unwrap_or(self.persistent),
+        }
+    }
+}
+
+// File stems in the given sound folders, which is how each platform names its sounds
+#[cfg(unix)]
+fn sounds_in(dirs: Vec<PathBuf>) -> BTreeSet<String> {
+    dirs.iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| {
+            let path = entry.path();
+            Some(path.file_stem()?.to_string_lossy().to_string())
+        })
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+fn available_sounds() -> BTreeSet<String> {
+    let mut dirs = vec![PathBuf::from("/System/Library/Sounds")];
+    dirs.extend(dirs::home_dir().map(|home| home.join("Library/Sounds")));
+    sounds_in(dirs)
+}
+
+// Names from the installed freedesktop sound themes
+#[cfg(all(unix, not(target_os = "macos")))]
+fn available_sounds() -> BTreeSet<String> {
+    let mut roots = vec![PathBuf::from("/usr/share/sounds")];
+    roots.extend(dirs::data_dir().map(|data| data.join("sounds")));
+    let themes = roots
+        .iter()
+        .filter_map(|root| fs::read_dir(root).ok())
+        .flatten()
+        .flatten()
+        .map(|theme| theme.path().join("stereo"))
+        .collect();
+    sounds_in(themes)
+}
+
+#[cfg(not(unix))]
+fn available_sounds() -> BTreeSet<String> {
+    let mut sounds: BTreeSet<String> = ["Default", "IM", "Mail", "Reminder", "SMS"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    for kind in ["Alarm", "Call"] {
+        sounds.insert(kind.to_string());
+        sounds.extend((2..=10).map(|n| format!("{}{}", kind, n)));
+    }
+    sounds
+}
+
+// The run a notification is about, so clicking it can open that run's output
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+struct ClickTarget {
+    app: AppHandle,
+    run_id: String,
+    failed: bool,
+}
+
+// The plugin has no way to set urgency or a timeout, so those go to the server directly. Its
+// desktop backend doesn't report clicks either, so run notifications always go there too.
+#[cfg(target_os = "linux")]
+fn show_on_server(title: &str, body: &str, style: &Style, click: Option<ClickTarget>) -> bool {
+    use notify_rust::{Timeout, Urgency};
+    if click.is_none() && style.urgency == NotificationUrgency::Normal && !style.persistent {
+        return false;
+    }
+    let mut notification = notify_rust::Notification::new();
+    notification
+        .summary(title)
+        .body(body)
+        .auto_icon()
+        .urgency(match style.urgency {
+            NotificationUrgency::Low => Urgency::Low,
+            NotificationUrgency::Normal => Urgency::Normal,
+            NotificationUrgency::Critical => Urgency::Critical,
+        });
+    if let Some(sound) = &style.sound {
+        notification.sound_name(sound);
+    }
+    if style.persistent {
+        notification.timeout(Timeout::Never);
+    }
+    if let Some(click) = &click {
+        notification.action(BODY_CLICK, "View output");
+        if click.failed {
+            notification.action(RERUN, "Rerun");
+        }
+    }
+    std::thread::spawn(move || {
+        let handle = match notification.show() {
+            Ok(handle) => handle,
+            Err(e) => {
+                log::warn!("Failed to show notification: {}", e);
+                return;
+            }
+        };
+        let Some(click) = click else {
+            return;
+        };
+        // Blocks until the notification is clicked or closed; "__closed" means the latter
+        handle.wait_for_action(|action| {
+            if action == "__closed" {
+                return;
+            }
+            if let Err(e) = perform_action(&click.app, &click.run_id, Some(action)) {
+                log::warn!("Notification action {} failed: {}", action, e);
+            }
+        });
+    });
+    true
+}
+
+#[cfg(not(target_os = "linux"))]
+fn show_on_server(_title: &str, _body: &str, _style: &Style, _click: Option<ClickTarget>) -> bool {
+    false
+}
+
+fn prefs(app: &AppHandle) -> NotificationPrefs {
+    settings::get(app, PREFS_KEY).unwrap_or_default()
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FocusRunEvent<'a> {
+    run_id: &'a str,
+    path: &'a str,
+}
+
+fn script_name(path: &str) -> String {
+    Path::new(path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn truncate(line: &str, max_chars: usize) -> String {
+    match line.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", line[..end].trim_end()),
+        None => line.to_string(),
+    }
+}
+
+// First few non-blank lines of stdout, without escape codes, for a notification body
+fn output_preview(stdout: &str) -> Option<String> {
+    let clean = ANSI.replace_all(stdout, "");
+    let mut lines = clean
+        .lines()
+        .map(|line| line.trim_end())
+        .filter(|line| !line.trim().is_empty());
+    let mut preview: Vec<String> = lines
+        .by_ref()
+        .take(OUTPUT_LINES)
+        .map(|line| truncate(line, OUTPUT_LINE_CHARS))
+        .collect();
+    if preview.is_empty() {
+        return None;
+    }
+    if lines.next().is_some() {
+        preview.push("…".to_string());
+    }
+    Some(preview.join("\n"))
+}
+
+// Runs that finish while the window is hidden are worth interrupting for, as are
+// runs that asked for their output to be shown
+pub fn notify_run_finished(app: &AppHandle, result: &ScriptResult, with_output: bool) {
+    let prefs = prefs(app);
+    if !with_output {
+        let visible = window::main_window(app)
+            .and_then(|w| w.is_visible().ok())
+            .unwrap_or(false);
+        if visible || !prefs.should_notify(result) {
+            return;
+        }
+    }
+
+    let name = script_name(&result.path);
+    let (title, body) = if result.timed_out {
+        ("Failed", format!("{} timed out", name))
+    } else if result.success {
+        ("Completed", format!("{} finished", name))
+    } else {
+        let code = result
+            .exit_code
+            .map_or_else(|| "signal".to_string(), |c| c.to_string());
+        ("Failed", format!("{} failed (exit {})", name, code))
+    };
+    if dnd::is_active(app) {
+        if !result.success {
+            dnd::queue_failure(app, body);
+        }
+        return;
+    }
+    let body = match with_output
+        .then(|| output_preview(&result.stdout))
+        .flatten()
+    {
+        Some(preview) => format!("{}\n{}", body, preview),
+        None => body,
+    };
+    let style = prefs.style(&result.path);
+    let click = ClickTarget {
+        app: app.clone(),
+        run_id: result.run_id.clone(),
+        failed: !result.success,
+    };
+    if show_on_server(title, &body, &style, Some(click)) {
+        return;
+    }
+    let mut builder = app.notification().builder();
+    if let Some(sound) = style.sound {
+        builder = builder.sound(sound);
+    }
+    // Desktop backends ignore the action type and show a plain notification
+    let _ = builder
+        .title(title)
+        .body(body)
+        .action_type_id(if result.success {
+            RUN_SUCCEEDED
+        } else {
+            RUN_FAILED
+        })
+        .extra("runId", &result.run_id)
+        .extra("path", &result.path)
+        .show();
+}
+
+// One notification for the whole batch, e.g. "4 succeeded, 1 failed: cleanup.sh"
+pub fn notify_batch_finished(app: &AppHandle, summary: &BatchSummary) {
+    let prefs = prefs(app);
+    let visible = window::main_window(app)
+        .and_then(|w| w.is_visible().ok())
+        .unwrap_or(false);
+    let wanted = if summary.success {
+        prefs.notify_on_success
+    } else {
+        prefs.notify_on_failure
+    };
+    if visible || !wanted {
+        return;
+    }
+    let mut body = format!("{} succeeded", summary.succeeded);
+    if summary.failed > 0 {
+        let failed: Vec<&str> = summary
+            .members
+            .iter()
+            .filter(|m| !m.success && !m.cancelled)
+            .map(|m| m.name.as_str())
+            .collect();
+        body.push_str(&format!(
+            ", {} failed: {}",
+            summary.failed,
+            failed.join(", ")
+        ));
+    }
+    if summary.cancelled > 0 {
+        body.push_str(&format!(", {} cancelled", summary.cancelled));
+    }
+    if !summary.skipped.is_empty() {
+        body.push_str(&format!(", {} skipped", summary.skipped.len()));
+    }
+    if dnd::is_active(app) {
+        if !summary.success {
+            dnd::queue_failure(app, body);
+        }
+        return;
+    }
+    let title = if summary.success {
+        "Batch completed"
+    } else {
+        "Batch failed"
+    };
+    show(app, title, &body);
+}
+
+pub fn show(app: &AppHandle, title: &str, body: &str) {
+    let _ = app.notification().builder().title(title).body(body).show();
+}
+
+// Shows the window where it normally opens, below the tray icon when hidden, then has the
+// frontend select the run
+fn view_output(app: &AppHandle, run_id: &str, path: &str) {
+    window::show_main_window(app);
+    let _ = app.emit("focus-run", FocusRunEvent { run_id, path });
+}
+
+// A tap on the body, or an unknown action, behaves like "View output". Blocks while a rerun goes.
+fn perform_action(app: &AppHandle, run_id: &str, action_id: Option<&str>) -> Result<(), String> {
+    let entry = history::find(app, run_id).ok_or_else(|| format!("Run not found: {}", run_id))?;
+    if action_id != Some(RERUN) {
+        view_output(app, &entry.run_id, &entry.path);
+        return Ok(());
+    }
+    let options = RunOptions {
+        args: entry.args,
+        ..RunOptions::default()
+    };
+    runner::execute(app, &entry.path, &options)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_notification_prefs(app: AppHandle) -> Result<NotificationPrefs, String> {
+    Ok(prefs(&app))
+}
+
+#[tauri::command]
+pub fn set_notification_prefs(app: AppHandle, prefs: NotificationPrefs) -> Result<(), String> {
+    settings::set(&app, PREFS_KEY, &prefs)
+}
+
+// None removes the override so the global threshold applies again
+#[tauri::command]
+pub fn set_script_notify_threshold(
+    app: AppHandle,
+    path: String,
+    min_duration_ms: Option<u64>,
+) -> Result<(), String> {
+    let mut prefs = prefs(&app);
+    match min_duration_ms {
+        Some(ms) => prefs.script_min_duration_ms.insert(path, ms),
+        None => prefs.script_min_duration_ms.remove(&path),
+    };
+    settings::set(&app, PREFS_KEY, &prefs)
+}
+
+#[tauri::command]
+pub fn list_notification_sounds() -> Result<Vec<String>, String> {
+    Ok(available_sounds().into_iter().collect())
+}
+
+// None removes the script's overrides
+#[tauri::command]
+pub fn set_script_notification_style(
+    app: AppHandle,
+    path: String,
+    style: Option<NotificationStyle>,
+) -> Result<(), String> {
+    let mut prefs = prefs(&app);
+    match style {
+        Some(style) => prefs.script_styles.insert(path, style),
+        None => prefs.script_styles.remove(&path),
+    };
+    settings::set(&app, PREFS_KEY, &prefs)
+}
+
+// Called with the action id from the webview's notification callback. On Linux the backend
+// hears clicks itself and calls the same handler.
+#[tauri::command]
+pub async fn handle_notification_action(
+    app: AppHandle,
+    run_id: String,
+    action_id: Option<String>,
+) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        perform_action(&app, &run_id, action_id.as_deref())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,136 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::Value;
+use tauri::AppHandle;
+
+use crate::{scripts, settings};
+
+const HELLO_WORLD: &str = r##"#!/usr/bin/env bash
+# @name: Hello World
+# @description: Prints a greeting and some facts about this machine
+# @icon: 👋
+# @tags: sample
+#
+# The `# @key: value` comments above are read by sh_runner to name and describe the
+# script in the list. Edit them, or anything below, and the list updates on save.
+set -euo pipefail
+
+echo "Hello from $(whoami) on $(hostname)"
+echo "Today is $(date '+%A, %B %d')"
+echo "This script lives in $(dirname "$0")"
+"##;
+
+const GREET: &str = r##"#!/usr/bin/env bash
+# @name: Greet Someone
+# @description: Shows how scripts take arguments
+# @icon: 💬
+# @tags: sample
+# @arg name Who to greet
+# @arg -t|--times <count> How many times to say it
+#
+# `# @arg` lines describe the arguments, so the app can ask for them before a run.
+# They arrive as regular positional arguments and flags.
+set -euo pipefail
+
+name="world"
+times=1
+while [ $# -gt 0 ]; do
+    case "$1" in
+        -t|--times) times="$2"; shift 2 ;;
+        *) name="$1"; shift ;;
+    esac
+done
+
+for _ in $(seq "$times"); do
+    echo "Hello, $name!"
+done
+"##;
+
+const LONG_TASK: &str = r##"#!/usr/bin/env bash
+# @name: Long Task
+# @description: Reports progress while it works through ten steps
+# @icon: ⏳
+# @tags: sample
+# @timeout: 120
+#
+# Printing `# @progress: N` (0-100) updates the progress bar and the time estimate.
+# `@timeout` above stops the run if it takes longer than that many seconds.
+set -euo pipefail
+
+steps=10
+for step in $(seq "$steps"); do
+    echo "Working on step $step of $steps"
+    sleep 1
+    echo "# @progress: $((step * 100 / steps))"
+done
+echo "Done"
+"##;
+
+const SAMPLES: &[(&str, &str)] = &[
+    ("hello-world.sh", HELLO_WORLD),
+    ("greet.sh", GREET),
+    ("long-task.sh", LONG_TASK),
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DefaultSetup {
+    pub scripts_dir: String,
+    pub created_dir: bool,
+    // Sample scripts that were written, so the UI can highlight them
+    pub created: Vec<String>,
+}
+
+fn is_empty_dir(path: &Path) -> bool {
+    fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_none())
+}
+
+// Nothing saved from the frontend yet, and no scripts where the list would look
+#[tauri::command]
+pub fn needs_default_setup(app: AppHandle) -> Result<bool, String> {
+    let configured = settings::get::<Value>(&app, "settings").is_some();
+    let folder = settings::scripts_folder(&app);
+    Ok(!configured && folder.is_none_or(|dir| !dir.exists() || is_empty_dir(&dir)))
+}
+
+// Creates the scripts folder with a few commented samples. A folder that already has something
+// in it is left alone, and existing files are never replaced.
+#[tauri::command]
+pub fn initialize_default_setup(app: AppHandle) -> Result<DefaultSetup, String> {
+    let dir = settings::scripts_folder(&app)
+        .ok_or_else(|| "Could not determine home directory".to_string())?;
+    let created_dir = !dir.exists();
+    if created_dir {
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let mut created = Vec::new();
+    if created_dir || is_empty_dir(&dir) {
+        for (name, content) in SAMPLES {
+            let target = dir.join(name);
+            let Ok(mut file) = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&target)
+            else {
+                continue;
+            };
+            file.write_all(content.as_bytes())
+                .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+            drop(file);
+            scripts::set_user_executable(&target).map_err(|e| e.to_string())?;
+            created.push(target.to_string_lossy().to_string());
+        }
+    }
+    if !created.is_empty() {
+        scripts::notify_scripts_changed(&app, created.clone());
+    }
+    Ok(DefaultSetup {
+        scripts_dir: dir.to_string_lossy().to_string(),
+        created_dir,
+        created,
+    })
+}
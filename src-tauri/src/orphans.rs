@@ -0,0 +1,327 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Emitter};
+
+use crate::startup::{self, Phase};
+use crate::{
+    collections, history, hotkeys, notifications, profiles, roots, scripts, settings, snapshots,
+};
+
+// Scripts whose files vanished while the app still holds state for them
+const ORPHANS_KEY: &str = "orphanedScripts";
+// Per-script field set while a script is orphaned
+const ORPHANED_FIELD: &str = "orphaned";
+// Run prune_missing_references once the first scan at launch is done
+const AUTO_PRUNE_KEY: &str = "autoPruneOnLaunch";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OrphanedScript {
+    pub id: String,
+    pub name: String,
+    // Content hash at the last scan that saw the file, for relinking
+    pub hash: String,
+    pub missing_since: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrunedMember {
+    pub collection: String,
+    pub path: String,
+}
+
+// Script ids removed from each place that referred to them
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PruneReport {
+    pub favorites: Vec<String>,
+    pub collections: Vec<PrunedMember>,
+    pub configs: Vec<String>,
+    pub checksums: Vec<String>,
+    pub hotkeys: Vec<String>,
+    // Under a folder that can't be reached right now, so left alone
+    pub skipped: Vec<String>,
+}
+
+impl PruneReport {
+    fn is_empty(&self) -> bool {
+        self.favorites.is_empty()
+            && self.collections.is_empty()
+            && self.configs.is_empty()
+            && self.checksums.is_empty()
+            && self.hotkeys.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Relinked {
+    pub from: String,
+    pub to: String,
+}
+
+// What a scan changed about orphaned scripts
+#[derive(Debug, Clone, Default)]
+pub struct Reconciled {
+    pub orphaned: Vec<String>,
+    pub relinked: Vec<Relinked>,
+}
+
+fn load(app: &AppHandle) -> Vec<OrphanedScript> {
+    settings::get(app, ORPHANS_KEY).unwrap_or_default()
+}
+
+fn save(app: &AppHandle, orphans: &[OrphanedScript]) -> Result<(), String> {
+    if orphans.is_empty() {
+        settings::delete(app, ORPHANS_KEY)
+    } else {
+        settings::set(app, ORPHANS_KEY, &orphans)
+    }
+}
+
+fn name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+// Anything that would otherwise linger once the file is gone
+fn has_state(app: &AppHandle, id: &str) -> bool {
+    settings::script_data(app, id).is_some()
+        || hotkeys::has_script_hotkey(app, id)
+        || collections::contains_script(app, id)
+        || profiles::load_profiles(app)
+            .iter()
+            .any(|p| p.favorites.iter().any(|f| f == id))
+}
+
+fn mark(app: &AppHandle, id: &str, orphaned: bool) {
+    if settings::script_data(app, id).is_none() {
+        return;
+    }
+    let _ = settings::update_script_data(app, id, |data| {
+        if orphaned {
+            data.insert(ORPHANED_FIELD.to_string(), json!(true));
+        } else {
+            data.remove(ORPHANED_FIELD);
+        }
+    });
+}
+
+// Called by index::refresh with the scripts that left the tree and the ones that appeared,
+// each with its content hash. A vanished script moves to an appeared one with the same
+// content when that's the only match and the new path has no state of its own; otherwise
+// it's recorded as orphaned, which only ever marks it.
+pub fn reconcile(
+    app: &AppHandle,
+    removed: Vec<(String, String)>,
+    added: &[(String, String)],
+) -> Reconciled {
+    let mut orphans = load(app);
+    let mut result = Reconciled::default();
+    let before = orphans.len();
+    // Moved out of the roots doesn't count when the file is still there, e.g. after a root was
+    // removed from the settings
+    for (id, hash) in removed {
+        if Path::new(&id).exists() || orphans.iter().any(|o| o.id == id) || !has_state(app, &id) {
+            continue;
+        }
+        orphans.push(OrphanedScript {
+            name: name(&id),
+            id: id.clone(),
+            hash,
+            missing_since: history::now_ms(),
+        });
+        result.orphaned.push(id);
+    }
+    // Restored to where it was, e.g. from the trash
+    let restored: Vec<String> = orphans
+        .iter()
+        .filter(|o| Path::new(&o.id).is_file())
+        .map(|o| o.id.clone())
+        .collect();
+    orphans.retain(|o| !restored.contains(&o.id));
+
+    for (path, hash) in added {
+        let matches: Vec<usize> = orphans
+            .iter()
+            .enumerate()
+            .filter(|(_, o)| o.hash == *hash)
+            .map(|(index, _)| index)
+            .collect();
+        let same_content = added.iter().filter(|(_, h)| h == hash).count();
+        let [index] = matches[..] else {
+            continue;
+        };
+        if same_content > 1 || settings::script_data(app, path).is_some() {
+            continue;
+        }
+        let orphan = orphans.remove(index);
+        if let Err(e) = scripts::move_script_state(app, &orphan.id, Some(path)) {
+            log::warn!("Failed to relink {} to {}: {}", orphan.id, path, e);
+            orphans.push(orphan);
+            continue;
+        }
+        log::info!("Relinked the state of {} to {}", orphan.id, path);
+        result.orphaned.retain(|id| *id != orphan.id);
+        result.relinked.push(Relinked {
+            from: orphan.id,
+            to: path.clone(),
+        });
+    }
+
+    if orphans.len() == before && restored.is_empty() && result.relinked.is_empty() {
+        return result;
+    }
+    if let Err(e) = save(app, &orphans) {
+        log::warn!("Failed to save orphaned scripts: {}", e);
+    }
+    for id in &result.orphaned {
+        mark(app, id, true);
+    }
+    for id in restored.iter().chain(result.relinked.iter().map(|r| &r.to)) {
+        mark(app, id, false);
+    }
+    if !result.orphaned.is_empty() {
+        let names: Vec<String> = result.orphaned.iter().map(|id| name(id)).collect();
+        log::warn!("Scripts missing on disk: {}", result.orphaned.join(", "));
+        notifications::show(
+            app,
+            "Scripts missing",
+            &format!("{} deleted or moved outside the app", names.join(", ")),
+        );
+    }
+    let _ = app.emit("orphans-changed", &orphans);
+    result
+}
+
+#[tauri::command]
+pub fn list_orphaned_scripts(app: AppHandle) -> Result<Vec<OrphanedScript>, String> {
+    startup::guard(Phase::Scripts)?;
+    Ok(load(&app))
+}
+
+// Drops the settings, favorites, hotkeys and collection entries left behind; past runs are
+// kept, as for a script deleted in the app. Returns the ids that were purged.
+#[tauri::command]
+pub fn purge_orphaned(app: AppHandle, ids: Vec<String>) -> Result<Vec<String>, String> {
+    let mut orphans = load(&app);
+    let mut purged = Vec::new();
+    for id in ids {
+        if !orphans.iter().any(|o| o.id == id) {
+            continue;
+        }
+        // It came back since the scan
+        if Path::new(&id).is_file() {
+            continue;
+        }
+        scripts::move_script_state(&app, &id, None)?;
+        orphans.retain(|o| o.id != id);
+        purged.push(id);
+    }
+    save(&app, &orphans)?;
+    if !purged.is_empty() {
+        scripts::notify_scripts_changed(&app, purged.clone());
+        let _ = app.emit("orphans-changed", &orphans);
+    }
+    Ok(purged)
+}
+
+fn prune(app: &AppHandle) -> Result<PruneReport, String> {
+    let mut report = PruneReport::default();
+    // Sorted and deduplicated, so the report lists each script once per place
+    let missing = |ids: Vec<String>, skipped: &mut Vec<String>| {
+        let mut ids: Vec<String> = ids
+            .into_iter()
+            .filter(|id| {
+                // Checked first, since looking for the file could hang on such a folder
+                let unreachable = roots::is_stale(id) || roots::ensure_available(id).is_err();
+                if unreachable && !skipped.contains(id) {
+                    skipped.push(id.clone());
+                }
+                !unreachable && !Path::new(id).exists()
+            })
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    };
+    let favorites = profiles::load_profiles(app)
+        .into_iter()
+        .flat_map(|p| p.favorites)
+        .collect();
+    report.favorites = missing(favorites, &mut report.skipped);
+    let members = collections::members(app);
+    let gone = missing(
+        members.iter().map(|(_, path)| path.clone()).collect(),
+        &mut report.skipped,
+    );
+    report.collections = members
+        .into_iter()
+        .filter(|(_, path)| gone.contains(path))
+        .map(|(collection, path)| PrunedMember { collection, path })
+        .collect();
+    report.configs = missing(settings::script_data_paths(app), &mut report.skipped);
+    report.checksums = missing(snapshots::paths(app)?, &mut report.skipped);
+    report.hotkeys = missing(hotkeys::script_hotkey_paths(app), &mut report.skipped);
+
+    let mut ids: Vec<String> = report
+        .favorites
+        .iter()
+        .chain(report.collections.iter().map(|m| &m.path))
+        .chain(&report.configs)
+        .chain(&report.checksums)
+        .chain(&report.hotkeys)
+        .cloned()
+        .collect();
+    ids.sort();
+    ids.dedup();
+    // Drops everything at once, as for a script deleted in the app; past runs are kept
+    for id in &ids {
+        scripts::move_script_state(app, id, None)?;
+    }
+    let mut orphans = load(app);
+    let before = orphans.len();
+    orphans.retain(|o| !ids.contains(&o.id));
+    if orphans.len() != before {
+        save(app, &orphans)?;
+        let _ = app.emit("orphans-changed", &orphans);
+    }
+    if !report.is_empty() {
+        log::info!("Pruned references to missing scripts: {}", ids.join(", "));
+        scripts::notify_scripts_changed(app, ids);
+    }
+    Ok(report)
+}
+
+// Removes favorites, collection entries, settings, checksums and hotkeys of scripts whose files
+// no longer exist. Scripts under a folder that can't be reached are skipped, so running it
+// again once it's back is safe.
+#[tauri::command]
+pub fn prune_missing_references(app: AppHandle) -> Result<PruneReport, String> {
+    startup::guard(Phase::Scripts)?;
+    prune(&app)
+}
+
+#[tauri::command]
+pub fn set_auto_prune_on_launch(app: AppHandle, enabled: bool) -> Result<(), String> {
+    if enabled {
+        settings::set(&app, AUTO_PRUNE_KEY, &true)
+    } else {
+        settings::delete(&app, AUTO_PRUNE_KEY)
+    }
+}
+
+// Called after the first scan, so scripts that only moved have been relinked first
+pub fn prune_on_launch(app: &AppHandle) {
+    if !settings::get::<bool>(app, AUTO_PRUNE_KEY).unwrap_or(false) {
+        return;
+    }
+    if let Err(e) = prune(app) {
+        log::warn!("Failed to prune missing script references: {}", e);
+    }
+}
@@ -0,0 +1,137 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{matchers, settings};
+
+// Prefix of set_output_filter's error for a pattern that doesn't compile
+pub const INVALID_FILTER_ERROR: &str = "invalid-filter";
+// Per-script field holding the filter
+const FILTER_FIELD: &str = "outputFilter";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OutputFilter {
+    // Removed from every line; a line left empty by it is dropped
+    pub strip_regex: Option<String>,
+    // Matches are sent as highlight ranges with the line
+    pub highlight_regex: Option<String>,
+}
+
+// Offsets are in UTF-16 code units, so the UI can slice the line with them directly
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Highlight {
+    pub start: usize,
+    pub end: usize,
+}
+
+// A script's filter for one run
+pub struct CompiledFilter {
+    strip: Option<Regex>,
+    highlight: Option<Regex>,
+}
+
+fn pattern(value: &Option<String>) -> Option<&str> {
+    value.as_deref().filter(|p| !p.is_empty())
+}
+
+fn validate(filter: &OutputFilter) -> Result<(), String> {
+    let fields = [
+        ("stripRegex", &filter.strip_regex),
+        ("highlightRegex", &filter.highlight_regex),
+    ];
+    for (field, value) in fields {
+        if let Some(p) = pattern(value) {
+            if Regex::new(p).is_err() {
+                return Err(format!(
+                    "{}: {}: {}",
+                    INVALID_FILTER_ERROR,
+                    field,
+                    matchers::describe_error(p)
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn stored_filter(app: &AppHandle, path: &str) -> OutputFilter {
+    settings::script_data(app, path)
+        .and_then(|data| data.get(FILTER_FIELD).cloned())
+        .and_then(|filter| serde_json::from_value(filter).ok())
+        .unwrap_or_default()
+}
+
+fn compile_one(path: &str, value: &Option<String>) -> Option<Regex> {
+    let p = pattern(value)?;
+    Regex::new(p)
+        .inspect_err(|e| log::warn!("Skipping output filter {:?} of {}: {}", p, path, e))
+        .ok()
+}
+
+// Compiled once per run; None when the script has no filter
+pub fn compile(app: &AppHandle, path: &str) -> Option<Arc<CompiledFilter>> {
+    let filter = stored_filter(app, path);
+    let compiled = CompiledFilter {
+        strip: compile_one(path, &filter.strip_regex),
+        highlight: compile_one(path, &filter.highlight_regex),
+    };
+    (compiled.strip.is_some() || compiled.highlight.is_some()).then(|| Arc::new(compiled))
+}
+
+fn utf16_offset(line: &str, byte: usize) -> usize {
+    line[..byte].encode_utf16().count()
+}
+
+impl CompiledFilter {
+    // None when stripping emptied a line that had something in it
+    pub fn strip<'a>(&self, line: &'a str) -> Option<Cow<'a, str>> {
+        let Some(strip) = &self.strip else {
+            return Some(Cow::Borrowed(line));
+        };
+        let stripped = strip.replace_all(line, "");
+        if stripped.trim().is_empty() && !line.trim().is_empty() {
+            return None;
+        }
+        Some(stripped)
+    }
+
+    pub fn highlights(&self, line: &str) -> Vec<Highlight> {
+        let Some(highlight) = &self.highlight else {
+            return Vec::new();
+        };
+        highlight
+            .find_iter(line)
+            .filter(|m| !m.is_empty())
+            .map(|m| Highlight {
+                start: utf16_offset(line, m.start()),
+                end: utf16_offset(line, m.end()),
+            })
+            .collect()
+    }
+}
+
+#[tauri::command]
+pub fn get_output_filter(app: AppHandle, path: String) -> Result<OutputFilter, String> {
+    Ok(stored_filter(&app, &path))
+}
+
+// Applies from the script's next run; an empty filter removes it
+#[tauri::command]
+pub fn set_output_filter(app: AppHandle, path: String, filter: OutputFilter) -> Result<(), String> {
+    validate(&filter)?;
+    let empty =
+        pattern(&filter.strip_regex).is_none() && pattern(&filter.highlight_regex).is_none();
+    let value = serde_json::to_value(&filter).map_err(|e| e.to_string())?;
+    settings::update_script_data(&app, &path, |data| {
+        if empty {
+            data.remove(FILTER_FIELD);
+        } else {
+            data.insert(FILTER_FIELD.to_string(), value);
+        }
+    })
+}
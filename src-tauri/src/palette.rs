@@ -0,0 +1,162 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{collections, history, scripts, settings, tags};
+
+// What was picked from the palette lately, most recent first
+const RECENT_KEY: &str = "paletteRecent";
+const MAX_RECENT: usize = 20;
+const MAX_RESULTS: usize = 50;
+// Added for the most recently used item, falling off for older ones
+const RECENT_BOOST: i64 = 40;
+// Built-in actions as (target, label); the frontend carries them out
+const ACTIONS: &[(&str, &str)] = &[
+    ("new-script", "New Script"),
+    ("settings", "Settings"),
+    ("reload", "Reload"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaletteKind {
+    Script,
+    Collection,
+    Tag,
+    Action,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaletteItem {
+    pub kind: PaletteKind,
+    pub label: String,
+    // The script id, collection name, tag or action id
+    pub target: String,
+}
+
+// None unless every query character appears in order. Matches at word starts and runs of
+// consecutive characters score higher, gaps and long labels a little lower.
+fn score(query: &str, label: &str) -> Option<i64> {
+    let label: Vec<char> = label.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+    for wanted in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (next..label.len()).find(|i| label[*i] == wanted)?;
+        let word_start = found == 0 || !label[found - 1].is_alphanumeric();
+        score += 10;
+        if word_start {
+            score += 8;
+        }
+        match previous {
+            Some(previous) if previous + 1 == found => score += 5,
+            Some(previous) => score -= (found - previous - 1).min(5) as i64,
+            None => score -= found.min(10) as i64,
+        }
+        previous = Some(found);
+        next = found + 1;
+    }
+    Some(score - (label.len() / 10) as i64)
+}
+
+fn recent(app: &AppHandle) -> Vec<PaletteItem> {
+    settings::get(app, RECENT_KEY).unwrap_or_default()
+}
+
+fn items(app: &AppHandle) -> Vec<PaletteItem> {
+    let item = |kind, label: String, target: String| PaletteItem {
+        kind,
+        label,
+        target,
+    };
+    let mut items: Vec<PaletteItem> = ACTIONS
+        .iter()
+        .map(|(target, label)| item(PaletteKind::Action, label.to_string(), target.to_string()))
+        .collect();
+    for path in scripts::all_scripts(settings::scripts_roots(app)) {
+        let label = Path::new(&path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.clone());
+        items.push(item(PaletteKind::Script, label, path));
+    }
+    for name in collections::names(app) {
+        items.push(item(PaletteKind::Collection, name.clone(), name));
+    }
+    let tags: BTreeSet<String> = tags::tags_by_path(app).into_values().flatten().collect();
+    for tag in tags {
+        items.push(item(PaletteKind::Tag, format!("#{}", tag), tag));
+    }
+    items
+}
+
+// Items picked from the palette lately come first, then scripts by their last run
+fn boosts(app: &AppHandle) -> Vec<(PaletteKind, String)> {
+    let mut boosted: Vec<(PaletteKind, String)> = recent(app)
+        .into_iter()
+        .map(|item| (item.kind, item.target))
+        .collect();
+    let mut runs: Vec<(String, u64)> = history::latest_by_path(app)
+        .into_iter()
+        .map(|(path, entry)| (path, entry.started_at))
+        .collect();
+    runs.sort_by_key(|(_, started_at)| std::cmp::Reverse(*started_at));
+    for (path, _) in runs {
+        if !boosted
+            .iter()
+            .any(|(kind, target)| *kind == PaletteKind::Script && *target == path)
+        {
+            boosted.push((PaletteKind::Script, path));
+        }
+    }
+    boosted
+}
+
+// Everything the palette can open, best match first. An empty query lists recently used
+// items and the built-in actions.
+#[tauri::command]
+pub async fn palette_search(app: AppHandle, query: String) -> Result<Vec<PaletteItem>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let boosted = boosts(&app);
+        let boost = |item: &PaletteItem| {
+            boosted
+                .iter()
+                .position(|(kind, target)| *kind == item.kind && *target == item.target)
+                .map_or(0, |rank| (RECENT_BOOST - 2 * rank as i64).max(0))
+        };
+        let query = query.trim();
+        let mut scored: Vec<(i64, PaletteItem)> = items(&app)
+            .into_iter()
+            .filter_map(|item| {
+                if query.is_empty() {
+                    let boost = boost(&item);
+                    return (boost > 0 || item.kind == PaletteKind::Action)
+                        .then_some((boost, item));
+                }
+                let score = score(query, &item.label)?;
+                Some((score + boost(&item), item))
+            })
+            .collect();
+        scored.sort_by(|(a, a_item), (b, b_item)| b.cmp(a).then(a_item.label.cmp(&b_item.label)));
+        Ok(scored
+            .into_iter()
+            .take(MAX_RESULTS)
+            .map(|(_, item)| item)
+            .collect())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Called when the user picks an item, so it ranks higher next time
+#[tauri::command]
+pub fn record_palette_use(app: AppHandle, item: PaletteItem) -> Result<(), String> {
+    let mut recent = recent(&app);
+    recent.retain(|r| !(r.kind == item.kind && r.target == item.target));
+    recent.insert(0, item);
+    recent.truncate(MAX_RECENT);
+    settings::set(&app, RECENT_KEY, &recent)
+}
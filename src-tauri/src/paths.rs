@@ -0,0 +1,154 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::{audit, history, settings};
+
+// Linux keeps everything under this name in the XDG base directories
+#[cfg(target_os = "linux")]
+const LINUX_DIR_NAME: &str = "sh_runner";
+const LOGS_DIR: &str = "logs";
+pub const TEMPLATES_DIR: &str = "templates";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppPaths {
+    pub config_dir: String,
+    pub data_dir: String,
+    pub log_dir: String,
+    pub cache_dir: String,
+    pub store_file: String,
+    pub templates_dir: String,
+    pub default_scripts_dir: Option<String>,
+}
+
+fn missing(kind: &str) -> String {
+    format!("Could not determine the {} directory", kind)
+}
+
+// $XDG_CONFIG_HOME/sh_runner on Linux, Application Support on macOS, AppData\Roaming on Windows
+#[cfg(target_os = "linux")]
+pub fn config_dir(_app: &AppHandle) -> Result<PathBuf, String> {
+    dirs::config_dir()
+        .map(|dir| dir.join(LINUX_DIR_NAME))
+        .ok_or_else(|| missing("config"))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn config_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path().app_config_dir().map_err(|_| missing("config"))
+}
+
+// $XDG_DATA_HOME/sh_runner on Linux; history and run artifacts live here
+#[cfg(target_os = "linux")]
+pub fn data_dir(_app: &AppHandle) -> Result<PathBuf, String> {
+    dirs::data_dir()
+        .map(|dir| dir.join(LINUX_DIR_NAME))
+        .ok_or_else(|| missing("data"))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path().app_data_dir().map_err(|_| missing("data"))
+}
+
+// $XDG_DATA_HOME/sh_runner/logs on Linux, ~/Library/Logs on macOS, AppData\Local on Windows
+#[cfg(target_os = "linux")]
+pub fn log_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    data_dir(app).map(|dir| dir.join(LOGS_DIR))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn log_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path().app_log_dir().map_err(|_| missing("log"))
+}
+
+#[cfg(target_os = "linux")]
+pub fn cache_dir(_app: &AppHandle) -> Result<PathBuf, String> {
+    dirs::cache_dir()
+        .map(|dir| dir.join(LINUX_DIR_NAME))
+        .ok_or_else(|| missing("cache"))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn cache_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path().app_cache_dir().map_err(|_| missing("cache"))
+}
+
+// Absolute, so the backend and the frontend open the same store wherever it lives;
+// falls back to the store plugin's own default location
+pub fn store_file(app: &AppHandle) -> PathBuf {
+    config_dir(app)
+        .map(|dir| dir.join(settings::STORE_FILE))
+        .unwrap_or_else(|_| PathBuf::from(settings::STORE_FILE))
+}
+
+pub fn copy_recursive(from: &Path, to: &Path) -> std::io::Result<()> {
+    if !from.is_dir() {
+        return fs::copy(from, to).map(|_| ());
+    }
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)?.flatten() {
+        copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+// Never overwrites: whatever is already at the new location wins
+fn migrate(from: &Path, to: &Path) {
+    if !from.exists() || to.exists() {
+        return;
+    }
+    if let Some(parent) = to.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    // A rename can't cross filesystems, so fall back to copying and removing the original
+    if fs::rename(from, to).is_err() && copy_recursive(from, to).is_ok() {
+        let _ = if from.is_dir() {
+            fs::remove_dir_all(from)
+        } else {
+            fs::remove_file(from)
+        };
+    }
+}
+
+// Earlier builds used Tauri's identifier-named dirs on Linux too; moves their files over on
+// the first launch with the XDG layout. Must run before anything opens the store or history.
+pub fn migrate_legacy(app: &AppHandle) {
+    let (Ok(config), Ok(data)) = (config_dir(app), data_dir(app)) else {
+        return;
+    };
+    let resolver = app.path();
+    if let Some(legacy_data) = resolver.app_data_dir().ok().filter(|dir| *dir != data) {
+        migrate(
+            &legacy_data.join(settings::STORE_FILE),
+            &config.join(settings::STORE_FILE),
+        );
+        for name in [history::HISTORY_FILE, history::RUNS_DIR, audit::AUDIT_FILE] {
+            migrate(&legacy_data.join(name), &data.join(name));
+        }
+    }
+    if let Some(legacy_config) = resolver.app_config_dir().ok().filter(|dir| *dir != config) {
+        migrate(
+            &legacy_config.join(TEMPLATES_DIR),
+            &config.join(TEMPLATES_DIR),
+        );
+    }
+}
+
+#[tauri::command]
+pub fn get_app_paths(app: AppHandle) -> Result<AppPaths, String> {
+    let display = |path: PathBuf| path.to_string_lossy().to_string();
+    let config = config_dir(&app)?;
+    Ok(AppPaths {
+        data_dir: display(data_dir(&app)?),
+        log_dir: display(log_dir(&app)?),
+        cache_dir: display(cache_dir(&app)?),
+        store_file: display(store_file(&app)),
+        templates_dir: display(config.join(TEMPLATES_DIR)),
+        config_dir: display(config),
+        default_scripts_dir: settings::default_scripts_dir().map(display),
+    })
+}
@@ -0,0 +1,89 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::runner::ActiveRuns;
+use crate::{runstate, tray};
+
+// Runs started while paused are stopped as soon as they spawn, until resume_all
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PauseSummary {
+    // False on platforms without job control signals; nothing was changed then
+    pub supported: bool,
+    pub paused: bool,
+    pub run_ids: Vec<String>,
+    // Runs whose process could not be signalled, usually because it just exited
+    pub failed: Vec<String>,
+}
+
+pub fn is_paused() -> bool {
+    PAUSED.load(Ordering::SeqCst)
+}
+
+// Scripts run in their own process group, so the signal reaches the whole tree
+#[cfg(unix)]
+fn signal(pid: u32, paused: bool) -> bool {
+    let signal = if paused { libc::SIGSTOP } else { libc::SIGCONT };
+    unsafe { libc::killpg(pid as libc::pid_t, signal) == 0 }
+}
+
+// Called once a run's process exists
+#[cfg(unix)]
+pub fn stop_if_paused(pid: u32) {
+    if is_paused() {
+        signal(pid, true);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn stop_if_paused(_pid: u32) {}
+
+#[cfg(unix)]
+fn set_paused(app: &AppHandle, paused: bool) -> PauseSummary {
+    PAUSED.store(paused, Ordering::SeqCst);
+    runstate::changed();
+    let mut summary = PauseSummary {
+        supported: true,
+        paused,
+        run_ids: Vec::new(),
+        failed: Vec::new(),
+    };
+    for run in app.state::<ActiveRuns>().list() {
+        match run.pid {
+            Some(pid) if signal(pid, paused) => summary.run_ids.push(run.run_id),
+            _ => summary.failed.push(run.run_id),
+        }
+    }
+    tray::refresh_status(app);
+    summary
+}
+
+#[cfg(not(unix))]
+fn set_paused(_app: &AppHandle, _paused: bool) -> PauseSummary {
+    PauseSummary {
+        supported: false,
+        paused: false,
+        run_ids: Vec::new(),
+        failed: Vec::new(),
+    }
+}
+
+// Tray entry point
+pub fn toggle(app: &AppHandle) {
+    set_paused(app, !is_paused());
+}
+
+// Timeouts keep counting while a run is stopped
+#[tauri::command]
+pub fn pause_all(app: AppHandle) -> Result<PauseSummary, String> {
+    Ok(set_paused(&app, true))
+}
+
+#[tauri::command]
+pub fn resume_all(app: AppHandle) -> Result<PauseSummary, String> {
+    Ok(set_paused(&app, false))
+}
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{metadata, scripts};
+
+// A filled-in copy of a script for one run, removed again when dropped; the original is
+// only ever read
+pub struct Filled {
+    dir: PathBuf,
+    pub script: PathBuf,
+}
+
+impl Drop for Filled {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[cfg(unix)]
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    fs::DirBuilder::new().mode(0o700).create(dir)
+}
+
+#[cfg(not(unix))]
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    fs::create_dir(dir)
+}
+
+// None when there is nothing to substitute; with `strict`, any token left without a value fails
+pub fn prepare(
+    run_id: &str,
+    script: &Path,
+    values: &HashMap<String, String>,
+    strict: bool,
+) -> Result<Option<Filled>, String> {
+    if values.is_empty() && !strict {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(script).map_err(|e| e.to_string())?;
+    let names = metadata::placeholders(&content);
+    if strict {
+        let unfilled: Vec<&str> = names
+            .iter()
+            .filter(|name| !values.contains_key(*name))
+            .map(String::as_str)
+            .collect();
+        if !unfilled.is_empty() {
+            return Err(format!("Unfilled placeholders: {}", unfilled.join(", ")));
+        }
+    }
+    if names.is_empty() {
+        return Ok(None);
+    }
+
+    let dir = std::env::temp_dir().join(format!("sh-runner-placeholders-{}", run_id));
+    create_private_dir(&dir)
+        .map_err(|e| format!("Failed to create placeholder directory: {}", e))?;
+    let filled = Filled {
+        script: dir.join(script.file_name().unwrap_or("script.sh".as_ref())),
+        dir,
+    };
+    fs::write(
+        &filled.script,
+        metadata::fill_placeholders(&content, values),
+    )
+    .and_then(|_| scripts::set_user_executable(&filled.script))
+    .map_err(|e| format!("Failed to write the filled-in script: {}", e))?;
+    Ok(Some(filled))
+}
@@ -0,0 +1,96 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::net::TcpListener;
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::{metadata, scripts, settings};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PreconditionKind {
+    Port,
+    Lockfile,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Precondition {
+    pub kind: PreconditionKind,
+    // The port number, or the lock file's resolved path
+    pub target: String,
+    // False when the run is likely to fail on it
+    pub ok: bool,
+    pub message: String,
+}
+
+// Binds the port on loopback and on every interface, dropping the listener right away.
+// A server on either address makes one of the binds fail.
+fn port(port: u16) -> Precondition {
+    let mut result = Precondition {
+        kind: PreconditionKind::Port,
+        target: port.to_string(),
+        ok: true,
+        message: format!("Port {} is free", port),
+    };
+    for host in ["127.0.0.1", "0.0.0.0"] {
+        match TcpListener::bind((host, port)) {
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::AddrInUse => {
+                result.ok = false;
+                result.message = format!("Port {} is already in use", port);
+                return result;
+            }
+            // e.g. a privileged port; the script may well be allowed what the app isn't
+            Err(e) => result.message = format!("Port {} couldn't be checked: {}", port, e),
+        }
+    }
+    result
+}
+
+fn lockfile(script: &Path, path: &str) -> Precondition {
+    let mut resolved = settings::expand_path(path);
+    if resolved.is_relative() {
+        if let Some(dir) = script.parent() {
+            resolved = dir.join(resolved);
+        }
+    }
+    let target = resolved.to_string_lossy().to_string();
+    let present = fs::symlink_metadata(&resolved).is_ok();
+    Precondition {
+        kind: PreconditionKind::Lockfile,
+        message: if present {
+            format!("{} exists, so another copy may be running", target)
+        } else {
+            format!("{} is not present", target)
+        },
+        ok: !present,
+        target,
+    }
+}
+
+fn check(script: &Path) -> Result<Vec<Precondition>, String> {
+    let content = fs::read_to_string(script).map_err(|e| e.to_string())?;
+    let meta = metadata::parse(content.lines());
+    Ok(meta
+        .ports
+        .iter()
+        .map(|p| port(*p))
+        .chain(meta.lockfiles.iter().map(|l| lockfile(script, l)))
+        .collect())
+}
+
+// What the script's `@port` and `@lockfile` directives say about launching it now, so the UI
+// can warn before a run that's bound to fail. Nothing is refused here.
+#[tauri::command]
+pub async fn check_preconditions(
+    app: AppHandle,
+    path: String,
+) -> Result<Vec<Precondition>, String> {
+    let script = scripts::allowed_script(&app, &path)?;
+    tauri::async_runtime::spawn_blocking(move || check(&script))
+        .await
+        .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::{settings, tray, watcher};
+
+const PROFILES_KEY: &str = "profiles";
+const ACTIVE_PROFILE_KEY: &str = "activeProfile";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Profile {
+    pub name: String,
+    pub roots: Vec<String>,
+    pub favorites: Vec<String>,
+}
+
+pub fn load_profiles(app: &AppHandle) -> Vec<Profile> {
+    settings::get(app, PROFILES_KEY).unwrap_or_default()
+}
+
+pub fn active_name(app: &AppHandle) -> Option<String> {
+    settings::get(app, ACTIVE_PROFILE_KEY)
+}
+
+pub fn active_profile(app: &AppHandle) -> Option<Profile> {
+    let name = active_name(app)?;
+    load_profiles(app).into_iter().find(|p| p.name == name)
+}
+
+// Applies `update` to every profile's favorites, saving only when something changed
+pub fn update_favorites(
+    app: &AppHandle,
+    mut update: impl FnMut(&mut Vec<String>),
+) -> Result<(), String> {
+    let mut profiles = load_profiles(app);
+    let mut changed = false;
+    for profile in &mut profiles {
+        let before = profile.favorites.clone();
+        update(&mut profile.favorites);
+        changed |= profile.favorites != before;
+    }
+    if changed {
+        settings::set(app, PROFILES_KEY, &profiles)?;
+    }
+    Ok(())
+}
+
+// Everything that depends on the active roots is refreshed from here
+fn apply_active_profile(app: &AppHandle) -> Result<(), String> {
+    tray::rebuild_menu(app).map_err(|e| e.to_string())?;
+    watcher::restart(app)?;
+    let _ = app.emit("profile-switched", active_profile(app));
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_profiles(app: AppHandle) -> Result<Vec<Profile>, String> {
+    Ok(load_profiles(&app))
+}
+
+#[tauri::command]
+pub fn get_active_profile(app: AppHandle) -> Result<Option<Profile>, String> {
+    Ok(active_profile(&app))
+}
+
+#[tauri::command]
+pub fn save_profile(app: AppHandle, profile: Profile) -> Result<(), String> {
+    let name = profile.name.trim().to_string();
+    if name.is_empty() {
+        return Err("Profile name cannot be empty".to_string());
+    }
+    let mut profiles = load_profiles(&app);
+    let profile = Profile { name, ..profile };
+    match profiles.iter_mut().find(|p| p.name == profile.name) {
+        Some(existing) => *existing = profile.clone(),
+        None => profiles.push(profile.clone()),
+    }
+    settings::set(&app, PROFILES_KEY, &profiles)?;
+    if active_name(&app).as_deref() == Some(profile.name.as_str()) {
+        apply_active_profile(&app)
+    } else {
+        tray::rebuild_menu(&app).map_err(|e| e.to_string())
+    }
+}
+
+#[tauri::command]
+pub fn delete_profile(app: AppHandle, name: String) -> Result<(), String> {
+    let mut profiles = load_profiles(&app);
+    profiles.retain(|p| p.name != name);
+    settings::set(&app, PROFILES_KEY, &profiles)?;
+    if active_name(&app).as_deref() == Some(name.as_str()) {
+        settings::delete(&app, ACTIVE_PROFILE_KEY)?;
+        return apply_active_profile(&app);
+    }
+    tray::rebuild_menu(&app).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn switch_profile(app: AppHandle, name: String) -> Result<Profile, String> {
+    let profile = load_profiles(&app)
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| format!("Profile not found: {}", name))?;
+    settings::set(&app, ACTIVE_PROFILE_KEY, &profile.name)?;
+    apply_active_profile(&app)?;
+    Ok(profile)
+}
@@ -0,0 +1,145 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use tauri::{AppHandle, Manager};
+
+use crate::history::{self, HistoryEntry};
+use crate::runner::ActiveRuns;
+
+// Upper bounds in seconds; +Inf is added after them
+const DURATION_BUCKETS: [f64; 10] = [0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 300.0, 900.0, 3600.0];
+
+#[derive(Default)]
+struct Counts {
+    runs: u64,
+    failures: u64,
+}
+
+// Handed off to a terminal, lost in a crash or never started, so there's no duration
+fn timed(entry: &HistoryEntry) -> bool {
+    !(entry.external
+        || entry.aborted
+        || entry.countdown_cancelled
+        || entry.condition_not_met.is_some())
+}
+
+fn failed(entry: &HistoryEntry) -> bool {
+    !entry.success && !entry.external && entry.condition_not_met.is_none()
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+// Counters are taken from the stored history, so they drop when retention prunes old runs;
+// Prometheus reads a drop as a counter reset
+pub fn render(app: &AppHandle) -> String {
+    let mut total = Counts::default();
+    let mut per_script: BTreeMap<String, Counts> = BTreeMap::new();
+    let mut buckets = [0u64; DURATION_BUCKETS.len()];
+    let (mut timed_runs, mut duration_sum) = (0u64, 0.0);
+    history::visit(app, |entry| {
+        let failure = failed(entry);
+        let script = per_script.entry(entry.path.clone()).or_default();
+        for counts in [&mut total, script] {
+            counts.runs += 1;
+            counts.failures += u64::from(failure);
+        }
+        if timed(entry) {
+            let seconds = entry.duration_ms as f64 / 1000.0;
+            for (count, bound) in buckets.iter_mut().zip(DURATION_BUCKETS) {
+                if seconds <= bound {
+                    *count += 1;
+                }
+            }
+            timed_runs += 1;
+            duration_sum += seconds;
+        }
+    });
+    let active = app.state::<ActiveRuns>().list();
+
+    let mut out = String::new();
+    header(
+        &mut out,
+        "sh_runner_runs_total",
+        "counter",
+        "Runs in history.",
+    );
+    let _ = writeln!(out, "sh_runner_runs_total {}", total.runs);
+    header(
+        &mut out,
+        "sh_runner_run_failures_total",
+        "counter",
+        "Runs in history that failed, timed out or were cancelled.",
+    );
+    let _ = writeln!(out, "sh_runner_run_failures_total {}", total.failures);
+    header(
+        &mut out,
+        "sh_runner_script_runs_total",
+        "counter",
+        "Runs in history per script.",
+    );
+    for (path, counts) in &per_script {
+        let _ = writeln!(
+            out,
+            "sh_runner_script_runs_total{{script=\"{}\"}} {}",
+            escape_label(path),
+            counts.runs
+        );
+    }
+    header(
+        &mut out,
+        "sh_runner_script_failures_total",
+        "counter",
+        "Failed runs in history per script.",
+    );
+    for (path, counts) in &per_script {
+        let _ = writeln!(
+            out,
+            "sh_runner_script_failures_total{{script=\"{}\"}} {}",
+            escape_label(path),
+            counts.failures
+        );
+    }
+    header(
+        &mut out,
+        "sh_runner_run_duration_seconds",
+        "histogram",
+        "How long finished runs took.",
+    );
+    for (count, bound) in buckets.iter().zip(DURATION_BUCKETS) {
+        let _ = writeln!(
+            out,
+            "sh_runner_run_duration_seconds_bucket{{le=\"{}\"}} {}",
+            bound, count
+        );
+    }
+    let _ = writeln!(
+        out,
+        "sh_runner_run_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+        timed_runs
+    );
+    let _ = writeln!(out, "sh_runner_run_duration_seconds_sum {}", duration_sum);
+    let _ = writeln!(out, "sh_runner_run_duration_seconds_count {}", timed_runs);
+    header(
+        &mut out,
+        "sh_runner_active_runs",
+        "gauge",
+        "Runs in progress.",
+    );
+    let _ = writeln!(out, "sh_runner_active_runs {}", active.len());
+    out
+}
+
+#[tauri::command]
+pub fn metrics_prometheus(app: AppHandle) -> Result<String, String> {
+    Ok(render(&app))
+}
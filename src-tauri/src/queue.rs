@@ -0,0 +1,265 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::thread;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::runner::{self, RunOptions};
+use crate::{history, runstate, triggers};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QueueStatus {
+    Pending,
+    Running,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueItem {
+    pub id: String,
+    pub path: String,
+    // File name, for display
+    pub name: String,
+    pub args: Vec<String>,
+    pub status: QueueStatus,
+    // What queued it, e.g. "manual", "webhook" or "cli"
+    pub trigger: Option<String>,
+    pub enqueued_at: u64,
+    // Set once the worker picks the item up
+    pub started_at: Option<u64>,
+    // From the average duration of earlier runs of everything ahead; None when one of them has
+    // never run. Only filled in by get_queue.
+    pub estimated_start_ms: Option<u64>,
+    // Everything the run was started with, sandbox, env and limits included, so it runs later
+    // exactly as it would have now
+    #[serde(skip)]
+    pub options: RunOptions,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct QueueItemFailedEvent<'a> {
+    id: &'a str,
+    path: &'a str,
+    error: String,
+}
+
+// Lives in the backend so the queue outlasts window reloads; the running item is
+// always first and stays in the list until it finishes
+#[derive(Default)]
+pub struct QueueState {
+    items: Mutex<Vec<QueueItem>>,
+    wake: Condvar,
+    counter: AtomicU64,
+}
+
+fn emit_changed(app: &AppHandle, items: &[QueueItem]) {
+    let _ = app.emit("queue-changed", items);
+    runstate::changed();
+}
+
+fn next_pending(app: &AppHandle) -> QueueItem {
+    let state = app.state::<QueueState>();
+    let mut items = state.items.lock().unwrap_or_else(|e| e.into_inner());
+    loop {
+        if let Some(item) = items.first_mut() {
+            item.status = QueueStatus::Running;
+            item.started_at = Some(history::now_ms());
+            let item = item.clone();
+            emit_changed(app, &items);
+            return item;
+        }
+        items = state.wake.wait(items).unwrap_or_else(|e| e.into_inner());
+    }
+}
+
+fn finish(app: &AppHandle, id: &str) {
+    let state = app.state::<QueueState>();
+    let mut items = state.items.lock().unwrap_or_else(|e| e.into_inner());
+    items.retain(|item| item.id != id);
+    emit_changed(app, &items);
+}
+
+// Runs queued items one after another through the normal run pipeline
+pub fn start_worker(app: &AppHandle) {
+    let app = app.clone();
+    thread::spawn(move || loop {
+        let item = next_pending(&app);
+        let options = RunOptions {
+            from_queue: true,
+            ..item.options.clone()
+        };
+        log::info!("Starting queued run of {}", item.path);
+        if let Err(error) = runner::execute(&app, &item.path, &options) {
+            log::warn!("Queued run of {} failed: {}", item.path, error);
+            let _ = app.emit(
+                "queue-item-failed",
+                QueueItemFailedEvent {
+                    id: &item.id,
+                    path: &item.path,
+                    error,
+                },
+            );
+        }
+        finish(&app, &item.id);
+    });
+}
+
+#[tauri::command]
+pub fn enqueue_script(
+    app: AppHandle,
+    path: String,
+    args: Option<Vec<String>>,
+    trigger: Option<String>,
+) -> Result<QueueItem, String> {
+    enqueue(&app, path, args.unwrap_or_default(), trigger)
+}
+
+pub fn enqueue(
+    app: &AppHandle,
+    path: String,
+    args: Vec<String>,
+    trigger: Option<String>,
+) -> Result<QueueItem, String> {
+    let options = RunOptions {
+        args,
+        trigger,
+        ..RunOptions::default()
+    };
+    enqueue_with_options(app, path, options)
+}
+
+// Queues a run with the options it was started with
+pub fn enqueue_with_options(
+    app: &AppHandle,
+    path: String,
+    mut options: RunOptions,
+) -> Result<QueueItem, String> {
+    let state = app.state::<QueueState>();
+    options
+        .trigger
+        .get_or_insert_with(|| triggers::MANUAL.to_string());
+    let item = QueueItem {
+        id: format!(
+            "q{}-{}",
+            history::now_ms(),
+            state.counter.fetch_add(1, Ordering::Relaxed)
+        ),
+        name: Path::new(&path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.clone()),
+        path,
+        args: options.args.clone(),
+        status: QueueStatus::Pending,
+        trigger: options.trigger.clone(),
+        enqueued_at: history::now_ms(),
+        started_at: None,
+        estimated_start_ms: None,
+        options,
+    };
+    let mut items = state.items.lock().map_err(|e| e.to_string())?;
+    items.push(item.clone());
+    emit_changed(app, &items);
+    state.wake.notify_one();
+    Ok(item)
+}
+
+#[tauri::command]
+pub fn dequeue_script(app: AppHandle, id: String) -> Result<(), String> {
+    let state = app.state::<QueueState>();
+    let mut items = state.items.lock().map_err(|e| e.to_string())?;
+    let index = items
+        .iter()
+        .position(|item| item.id == id)
+        .ok_or_else(|| format!("Queue item not found: {}", id))?;
+    if items[index].status == QueueStatus::Running {
+        return Err("Cannot remove the item that is currently running".to_string());
+    }
+    let item = items.remove(index);
+    emit_changed(&app, &items);
+    drop(items);
+    runner::emit_cancelled_before_start(&app, &item.id, &item.path);
+    Ok(())
+}
+
+// Items still waiting, not counting the one that is running
+pub fn pending_count(app: &AppHandle) -> usize {
+    let state = app.state::<QueueState>();
+    state.items.lock().map_or(0, |items| {
+        items
+            .iter()
+            .filter(|item| item.status == QueueStatus::Pending)
+            .count()
+    })
+}
+
+#[tauri::command]
+pub fn get_queue(app: AppHandle) -> Result<Vec<QueueItem>, String> {
+    let mut items = {
+        let state = app.state::<QueueState>();
+        let items = state.items.lock().map_err(|e| e.to_string())?;
+        items.clone()
+    };
+    let now = history::now_ms();
+    let mut cursor = Some(now);
+    for item in &mut items {
+        let average = history::average_duration(&app, &item.path);
+        match item.status {
+            QueueStatus::Running => {
+                cursor = item
+                    .started_at
+                    .zip(average)
+                    .map(|(started, average)| (started + average).max(now));
+            }
+            QueueStatus::Pending => {
+                item.estimated_start_ms = cursor;
+                cursor = cursor.zip(average).map(|(start, average)| start + average);
+            }
+        }
+    }
+    Ok(items)
+}
+
+// The worker takes items under the same lock, so an item is either moved before it starts or
+// refused as running
+fn move_item(app: &AppHandle, id: &str, new_index: usize) -> Result<(), String> {
+    let state = app.state::<QueueState>();
+    let mut items = state.items.lock().map_err(|e| e.to_string())?;
+    let index = items
+        .iter()
+        .position(|item| item.id == id)
+        .ok_or_else(|| format!("Queue item not found: {}", id))?;
+    if items[index].status == QueueStatus::Running {
+        return Err("Cannot move the item that is currently running".to_string());
+    }
+    let first_pending = items
+        .iter()
+        .take_while(|item| item.status == QueueStatus::Running)
+        .count();
+    let item = items.remove(index);
+    let new_index = new_index.clamp(first_pending, items.len());
+    items.insert(new_index, item);
+    emit_changed(app, &items);
+    Ok(())
+}
+
+// new_index is a position in get_queue's list; pending items can't move ahead of the running one
+#[tauri::command]
+pub fn move_in_queue(app: AppHandle, id: String, new_index: usize) -> Result<(), String> {
+    move_item(&app, &id, new_index)
+}
+
+#[tauri::command]
+pub fn reorder_queue(app: AppHandle, run_id: String, new_index: usize) -> Result<(), String> {
+    move_item(&app, &run_id, new_index)
+}
+
+// Next to run once the current item finishes
+#[tauri::command]
+pub fn promote_to_front(app: AppHandle, run_id: String) -> Result<(), String> {
+    move_item(&app, &run_id, 0)
+}
@@ -0,0 +1,210 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{Deserialize, Serialize};
+use tauri::ipc::Invoke;
+use tauri::{AppHandle, Emitter};
+
+use crate::{settings, tray};
+
+// Error prefix for everything refused while read-only mode is on
+pub const READ_ONLY_ERROR: &str = "read-only-mode";
+// Only stored when the mode should survive a restart
+const READ_ONLY_KEY: &str = "readOnlyMode";
+
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+// Commands that run scripts or change files or settings. Listing, history, logs and exports
+// keep working, as do window state, pausing and Do Not Disturb.
+const BLOCKED_COMMANDS: &[&str] = &[
+    "run_script",
+    "run_script_in_picked_dir",
+    "run_with_clipboard",
+    "preview_changes",
+    "confirm_after_preview",
+    "rerun_history_entry",
+    "stage_script_edit",
+    "run_staged",
+    "promote_staged",
+    "discard_staged",
+    "run_last_script",
+    "enqueue_script",
+    "run_workflow",
+    "run_scripts",
+    "run_tag",
+    "run_collection",
+    "run_in_terminal",
+    "test_terminal",
+    "handle_notification_action",
+    "open_in_editor",
+    "git_pull",
+    "install_script_from_url",
+    "create_script",
+    "initialize_default_setup",
+    "rename_script",
+    "duplicate_script",
+    "delete_script",
+    "save_script_content",
+    "apply_metadata",
+    "make_executable",
+    "remove_quarantine",
+    "import_scripts",
+    "import_script_bundle",
+    "import_profile",
+    "migrate_scripts_path",
+    "create_scripts_path",
+    "save_workflow",
+    "delete_workflow",
+    "create_env_group",
+    "update_env_group",
+    "delete_env_group",
+    "create_collection",
+    "add_to_collection",
+    "remove_from_collection",
+    "delete_collection",
+    "purge_orphaned",
+    "prune_missing_references",
+    "run_health_check",
+    "set_auto_prune_on_launch",
+    "save_profile",
+    "delete_profile",
+    "switch_profile",
+    "set_secret",
+    "delete_secret",
+    "reset_settings",
+    "set_setting",
+    "set_output_encoding",
+    "set_output_marker",
+    "set_respect_ignore_files",
+    "set_run_again_hotkey",
+    "set_script_hotkey",
+    "clear_script_hotkey",
+    "set_auto_chmod",
+    "set_auto_strip_quarantine",
+    "set_block_writable_scripts",
+    "set_capture_command",
+    "set_cancel_grace_ms",
+    "set_metrics_interval_ms",
+    "set_script_matchers",
+    "set_output_filter",
+    "set_log_cap",
+    "set_max_concurrent_runs",
+    "set_concurrency_policy",
+    "set_dangerous_trigger_overrides",
+    "set_run_conditions",
+    "set_confirm_timeout",
+    "set_allowed_script_paths",
+    "set_allowed_dirs",
+    "set_history_retention",
+    "set_notification_prefs",
+    "set_digest_settings",
+    "set_script_notify_threshold",
+    "set_script_notification_style",
+    "set_watch_debounce_ms",
+    "set_settings_auto_reload",
+    "set_log_level",
+    "set_update_settings",
+    "set_run_window_settings",
+    "install_update",
+    "set_tray_tags_menu",
+    "set_deep_link_confirm",
+    "set_editor_command",
+    "set_terminal_settings",
+    "set_webhook_settings",
+    "set_http_trigger",
+    "set_autostart",
+];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReadOnlyStatus {
+    pub active: bool,
+    // Stay on across restarts; otherwise every launch starts with the mode off
+    pub persist: bool,
+}
+
+pub fn is_active() -> bool {
+    READ_ONLY.load(Ordering::SeqCst)
+}
+
+pub fn error() -> String {
+    format!("{}: Read-only mode is active", READ_ONLY_ERROR)
+}
+
+// For entry points that don't go through IPC: webhook, deep links, the CLI, hotkeys and the tray
+pub fn guard() -> Result<(), String> {
+    if is_active() {
+        Err(error())
+    } else {
+        Ok(())
+    }
+}
+
+// Wraps the invoke handler so blocked commands are refused before they run. The store plugin's
+// own commands don't pass through it, so the frontend still has to honour the mode for the store.
+pub fn guard_commands(
+    handler: impl Fn(Invoke) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        if is_active() && BLOCKED_COMMANDS.contains(&invoke.message.command()) {
+            log::info!("Refused {} in read-only mode", invoke.message.command());
+            invoke.resolver.reject(error());
+            return true;
+        }
+        handler(invoke)
+    }
+}
+
+fn status(app: &AppHandle) -> ReadOnlyStatus {
+    let stored: ReadOnlyStatus = settings::get(app, READ_ONLY_KEY).unwrap_or_default();
+    ReadOnlyStatus {
+        active: is_active(),
+        persist: stored.persist,
+    }
+}
+
+fn apply(app: &AppHandle, active: bool) {
+    READ_ONLY.store(active, Ordering::SeqCst);
+    tray::refresh_status(app);
+    let _ = app.emit("read-only-changed", status(app));
+}
+
+// Called in setup; only a persisted mode comes back after a restart
+pub fn restore(app: &AppHandle) {
+    if status(app).persist {
+        apply(app, true);
+    }
+}
+
+// Tray entry point
+pub fn toggle(app: &AppHandle) {
+    let _ = set_read_only(app.clone(), !is_active(), None);
+}
+
+#[tauri::command]
+pub fn get_read_only(app: AppHandle) -> Result<ReadOnlyStatus, String> {
+    Ok(status(&app))
+}
+
+// `persist` keeps its previous value when omitted; leaving the mode always clears it
+#[tauri::command]
+pub fn set_read_only(
+    app: AppHandle,
+    enabled: bool,
+    persist: Option<bool>,
+) -> Result<ReadOnlyStatus, String> {
+    let persist = enabled && persist.unwrap_or_else(|| status(&app).persist);
+    if persist {
+        settings::set(
+            &app,
+            READ_ONLY_KEY,
+            &ReadOnlyStatus {
+                active: true,
+                persist,
+            },
+        )?;
+    } else {
+        settings::delete(&app, READ_ONLY_KEY)?;
+    }
+    apply(&app, enabled);
+    Ok(status(&app))
+}
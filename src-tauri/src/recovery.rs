@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::history::{self, HistoryEntry};
+use crate::runner::ActiveRuns;
+use crate::{ipc, paths};
+
+// Runs that started and haven't finished yet, so a crash leaves a trace of them
+const IN_FLIGHT_FILE: &str = "in-flight.json";
+// Prefix of the askpass, placeholder and sandbox dirs, which end in the run id
+const TEMP_PREFIX: &str = "sh-runner-";
+const TEMP_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+static IN_FLIGHT: Mutex<()> = Mutex::new(());
+// What the pass in setup found, for a window that loads after its event went out
+static STARTUP: Mutex<Option<RecoveryReport>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct InFlightRun {
+    pub run_id: String,
+    pub path: String,
+    pub started_at: u64,
+    pub pid: Option<u32>,
+    pub args: Vec<String>,
+    pub trigger: Option<String>,
+}
+
+// A run from before the restart whose process is still alive; it stays on record until it exits
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanedRun {
+    pub run_id: String,
+    pub path: String,
+    pub pid: u32,
+    pub started_at: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryReport {
+    // Run ids recorded in history as aborted
+    pub aborted_runs: Vec<String>,
+    pub orphaned_runs: Vec<OrphanedRun>,
+    pub removed_temp_dirs: Vec<String>,
+    pub removed_stale_socket: bool,
+    pub checked_at: u64,
+}
+
+impl RecoveryReport {
+    fn is_empty(&self) -> bool {
+        self.aborted_runs.is_empty()
+            && self.orphaned_runs.is_empty()
+            && self.removed_temp_dirs.is_empty()
+            && !self.removed_stale_socket
+    }
+}
+
+fn in_flight_file(app: &AppHandle) -> Option<PathBuf> {
+    paths::data_dir(app)
+        .ok()
+        .map(|dir| dir.join(IN_FLIGHT_FILE))
+}
+
+fn load(app: &AppHandle) -> HashMap<String, InFlightRun> {
+    in_flight_file(app)
+        .and_then(|file| fs::read_to_string(file).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn update(app: &AppHandle, change: impl FnOnce(&mut HashMap<String, InFlightRun>)) {
+    let Ok(_guard) = IN_FLIGHT.lock() else {
+        return;
+    };
+    let Some(file) = in_flight_file(app) else {
+        return;
+    };
+    let mut runs = load(app);
+    change(&mut runs);
+    let written = if runs.is_empty() {
+        fs::remove_file(&file).or_else(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => Ok(()),
+            _ => Err(e),
+        })
+    } else {
+        serde_json::to_string(&runs)
+            .map_err(std::io::Error::other)
+            .and_then(|json| fs::write(&file, json))
+    };
+    if let Err(e) = written {
+        log::warn!("Failed to update {}: {}", file.display(), e);
+    }
+}
+
+// Called by the runner once the process exists, and again when it's gone
+pub fn run_started(app: &AppHandle, run: InFlightRun) {
+    update(app, |runs| {
+        runs.insert(run.run_id.clone(), run);
+    });
+}
+
+pub fn run_finished(app: &AppHandle, run_id: &str) {
+    update(app, |runs| {
+        runs.remove(run_id);
+    });
+}
+
+#[cfg(unix)]
+fn is_alive(pid: u32) -> bool {
+    // SAFETY: signal 0 only checks that the process exists
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn is_alive(_pid: u32) -> bool {
+    false
+}
+
+fn reconcile_runs(app: &AppHandle, active: &[String], report: &mut RecoveryReport) {
+    let leftover: Vec<InFlightRun> = load(app)
+        .into_values()
+        .filter(|run| !active.contains(&run.run_id))
+        .collect();
+    for run in leftover {
+        if let Some(pid) = run.pid.filter(|pid| is_alive(*pid)) {
+            report.orphaned_runs.push(OrphanedRun {
+                run_id: run.run_id,
+                path: run.path,
+                pid,
+                started_at: run.started_at,
+            });
+            continue;
+        }
+        let recorded = history::record(
+            app,
+            HistoryEntry {
+                run_id: run.run_id.clone(),
+                path: run.path,
+                started_at: run.started_at,
+                args: run.args,
+                trigger: run.trigger,
+                aborted: true,
+                ..HistoryEntry::default()
+            },
+        );
+        if let Err(e) = recorded {
+            log::warn!("Failed to record aborted run {}: {}", run.run_id, e);
+            continue;
+        }
+        run_finished(app, &run.run_id);
+        report.aborted_runs.push(run.run_id);
+    }
+}
+
+fn reconcile_temp_dirs(active: &[String], report: &mut RecoveryReport) {
+    let Ok(entries) = fs::read_dir(std::env::temp_dir()) else {
+        return;
+    };
+    let now = SystemTime::now();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let in_use = active.iter().any(|id| name.ends_with(&format!("-{}", id)));
+        if !name.starts_with(TEMP_PREFIX) || in_use {
+            continue;
+        }
+        let old = entry
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .is_some_and(|age| age >= TEMP_MAX_AGE);
+        if old && entry.path().is_dir() && fs::remove_dir_all(entry.path()).is_ok() {
+            report
+                .removed_temp_dirs
+                .push(entry.path().to_string_lossy().to_string());
+        }
+    }
+}
+
+// The CLI socket of an instance that didn't shut down; only when no instance answers on it
+#[cfg(unix)]
+fn reconcile_socket(report: &mut RecoveryReport) {
+    let Some(path) = ipc::socket_path().filter(|path| path.exists()) else {
+        return;
+    };
+    if std::os::unix::net::UnixStream::connect(&path).is_err() && fs::remove_file(&path).is_ok() {
+        report.removed_stale_socket = true;
+    }
+}
+
+#[cfg(not(unix))]
+fn reconcile_socket(_report: &mut RecoveryReport) {}
+
+pub fn reconcile(app: &AppHandle) -> RecoveryReport {
+    let active: Vec<String> = app
+        .state::<ActiveRuns>()
+        .list()
+        .into_iter()
+        .map(|run| run.run_id)
+        .collect();
+    let mut report = RecoveryReport {
+        checked_at: history::now_ms(),
+        ..RecoveryReport::default()
+    };
+    reconcile_runs(app, &active, &mut report);
+    reconcile_temp_dirs(&active, &mut report);
+    if !report.is_empty() {
+        log::info!(
+            "Recovered {} aborted run(s), {} orphaned run(s), {} temp dir(s), stale socket: {}",
+            report.aborted_runs.len(),
+            report.orphaned_runs.len(),
+            report.removed_temp_dirs.len(),
+            report.removed_stale_socket
+        );
+    }
+    report
+}
+
+// Called in setup before the CLI socket starts; the rest runs off the main thread
+pub fn startup(app: &AppHandle) {
+    let mut socket = RecoveryReport::default();
+    reconcile_socket(&mut socket);
+    let app = app.clone();
+    thread::spawn(move || {
+        let mut report = reconcile(&app);
+        report.removed_stale_socket = socket.removed_stale_socket;
+        if !report.is_empty() {
+            let _ = app.emit("startup-recovery", &report);
+        }
+        if let Ok(mut startup) = STARTUP.lock() {
+            *startup = Some(report);
+        }
+    });
+}
+
+// The socket is left alone here: the running instance owns it
+#[tauri::command]
+pub fn run_health_check(app: AppHandle) -> Result<RecoveryReport, String> {
+    Ok(reconcile(&app))
+}
+
+// None until the startup pass is done
+#[tauri::command]
+pub fn get_startup_recovery() -> Result<Option<RecoveryReport>, String> {
+    Ok(STARTUP.lock().map_err(|e| e.to_string())?.clone())
+}
@@ -0,0 +1,637 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use tauri::AppHandle;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use crate::history::{self, HistoryEntry};
+use crate::notifications::ANSI;
+use crate::{settings, snapshots, templates};
+
+// Standard and bright colors of SGR 30-37 and 90-97, the xterm defaults
+const PALETTE: [&str; 16] = [
+    "#000000", "#cd0000", "#00cd00", "#cdcd00", "#0000ee", "#cd00cd", "#00cdcd", "#e5e5e5",
+    "#7f7f7f", "#ff0000", "#00ff00", "#ffff00", "#5c5cff", "#ff00ff", "#00ffff", "#ffffff",
+];
+
+enum Format {
+    Html,
+    Markdown,
+}
+
+// Each stream is shown up to this much; dir and zip reports carry the full logs next to it
+const MAX_INLINE_BYTES: u64 = 1024 * 1024;
+const REPORT_FILE: &str = "report.html";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+    // A folder with report.html and the run's log files
+    Dir,
+    // The same as a .zip archive
+    Zip,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Style {
+    bold: bool,
+    italic: bool,
+    underline: bool,
+    fg: Option<String>,
+    bg: Option<String>,
+}
+
+// 256-color index: the palette, a 6x6x6 cube, then a grey ramp
+fn indexed_color(index: u16) -> String {
+    match index {
+        0..=15 => PALETTE[index as usize].to_string(),
+        16..=231 => {
+            let level = |v: u16| if v == 0 { 0 } else { 55 + v * 40 };
+            let i = index - 16;
+            format!(
+                "#{:02x}{:02x}{:02x}",
+                level(i / 36),
+                level(i / 6 % 6),
+                level(i % 6)
+            )
+        }
+        _ => {
+            let grey = 8 + (index.min(255) - 232) * 10;
+            format!("#{:02x}{:02x}{:02x}", grey, grey, grey)
+        }
+    }
+}
+
+impl Style {
+    // Applies the parameters of one `ESC [ ... m` sequence
+    fn apply(&mut self, params: &str) {
+        let codes: Vec<u16> = params
+            .split(';')
+            .map(|code| code.parse().unwrap_or(0))
+            .collect();
+        let mut codes = codes.into_iter();
+        while let Some(code) = codes.next() {
+            match code {
+                0 => *self = Style::default(),
+                1 => self.bold = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                22 => self.bold = false,
+                23 => self.italic = false,
+                24 => self.underline = false,
+                30..=37 => self.fg = Some(PALETTE[(code - 30) as usize].to_string()),
+                90..=97 => self.fg = Some(PALETTE[(code - 82) as usize].to_string()),
+                39 => self.fg = None,
+                40..=47 => self.bg = Some(PALETTE[(code - 40) as usize].to_string()),
+                100..=107 => self.bg = Some(PALETTE[(code - 92) as usize].to_string()),
+                49 => self.bg = None,
+                38 | 48 => {
+                    let color = match codes.next() {
+                        Some(5) => codes.next().map(indexed_color),
+                        Some(2) => match (codes.next(), codes.next(), codes.next()) {
+                            (Some(r), Some(g), Some(b)) => Some(format!(
+                                "#{:02x}{:02x}{:02x}",
+                                r.min(255),
+                                g.min(255),
+                                b.min(255)
+                            )),
+                            _ => None,
+                        },
+                        _ => None,
+                    };
+                    if code == 38 {
+                        self.fg = color;
+                    } else {
+                        self.bg = color;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn css(&self) -> String {
+        let mut css = Vec::new();
+        if self.bold {
+            css.push("font-weight:bold".to_string());
+        }
+        if self.italic {
+            css.push("font-style:italic".to_string());
+        }
+        if self.underline {
+            css.push("text-decoration:underline".to_string());
+        }
+        if let Some(fg) = &self.fg {
+            css.push(format!("color:{}", fg));
+        }
+        if let Some(bg) = &self.bg {
+            css.push(format!("background-color:{}", bg));
+        }
+        css.join(";")
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn push_styled(html: &mut String, text: &str, style: &Style) {
+    if text.is_empty() {
+        return;
+    }
+    if *style == Style::default() {
+        html.push_str(&escape_html(text));
+    } else {
+        html.push_str(&format!(
+            "<span style=\"{}\">{}</span>",
+            style.css(),
+            escape_html(text)
+        ));
+    }
+}
+
+// Colors and text attributes become spans; other escape sequences are dropped
+fn ansi_to_html(text: &str) -> String {
+    let mut html = String::new();
+    push_ansi(&mut html, text, &mut Style::default());
+    html
+}
+
+// `style` carries over between calls, since a color set on one line lasts until reset
+fn push_ansi(html: &mut String, text: &str, style: &mut Style) {
+    let mut last = 0;
+    for sequence in ANSI.find_iter(text) {
+        push_styled(html, &text[last..sequence.start()], style);
+        let code = sequence.as_str();
+        if let Some(params) = code
+            .strip_prefix("\x1b[")
+            .and_then(|rest| rest.strip_suffix('m'))
+        {
+            style.apply(params);
+        }
+        last = sequence.end();
+    }
+    push_styled(html, &text[last..], style);
+}
+
+// Long enough that no run of backticks in the output closes the block early
+fn fence(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn timestamp(ms: u64) -> String {
+    let secs = ms / 1000 % 86_400;
+    format!(
+        "{} {:02}:{:02}:{:02} UTC",
+        templates::civil_date(ms),
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+fn duration(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{} ms", ms)
+    } else {
+        format!("{:.2} s", ms as f64 / 1000.0)
+    }
+}
+
+fn script_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+// Label/value rows shared by both formats
+fn summary(entry: &HistoryEntry) -> Vec<(&'static str, String)> {
+    let mut rows = vec![
+        ("Script", entry.path.clone()),
+        ("Arguments", entry.args.join(" ")),
+        (
+            "Exit code",
+            entry
+                .exit_code
+                .map_or_else(|| "none".to_string(), |code| code.to_string()),
+        ),
+        ("Duration", duration(entry.duration_ms)),
+        ("Started", timestamp(entry.started_at)),
+    ];
+    if entry.timed_out {
+        rows.push(("Timed out", "yes".to_string()));
+    }
+    if let Some(trigger) = &entry.trigger {
+        rows.push(("Trigger", trigger.clone()));
+    }
+    if let Some(label) = &entry.label {
+        rows.push(("Label", label.clone()));
+    }
+    rows
+}
+
+fn markdown(entry: &HistoryEntry, stdout: &str, stderr: &str) -> String {
+    let mut report = format!("# {}\n\n", script_name(&entry.path));
+    for (label, value) in summary(entry) {
+        report.push_str(&format!("- **{}:** `{}`\n", label, value));
+    }
+    for (title, output) in [("Output", stdout), ("Errors", stderr)] {
+        let clean = ANSI.replace_all(output, "");
+        if title == "Errors" && clean.trim().is_empty() {
+            continue;
+        }
+        let fence = fence(&clean);
+        report.push_str(&format!(
+            "\n## {}\n\n{}\n{}\n{}\n",
+            title,
+            fence,
+            clean.trim_end(),
+            fence
+        ));
+    }
+    report
+}
+
+fn html(entry: &HistoryEntry, stdout: &str, stderr: &str) -> String {
+    let name = escape_html(&script_name(&entry.path));
+    let rows: String = summary(entry)
+        .into_iter()
+        .map(|(label, value)| {
+            format!(
+                "<tr><th>{}</th><td><code>{}</code></td></tr>\n",
+                label,
+                escape_html(&value)
+            )
+        })
+        .collect();
+    let mut sections = format!(
+        "<h2>Output</h2>\n<pre>{}</pre>\n",
+        ansi_to_html(stdout.trim_end())
+    );
+    if !stderr.trim().is_empty() {
+        sections.push_str(&format!(
+            "<h2>Errors</h2>\n<pre class=\"stderr\">{}</pre>\n",
+            ansi_to_html(stderr.trim_end())
+        ));
+    }
+    format!(
+        "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>{name}</title>
+<style>
+body {{ font-family: -apple-system, system-ui, sans-serif; margin: 2rem; }}
+th {{ text-align: left; padding-right: 1rem; }}
+pre {{ background: #1e1e1e; color: #e5e5e5; padding: 1rem; overflow-x: auto; }}
+pre.stderr {{ border-left: 4px solid #cd0000; }}
+</style>
+</head>
+<body>
+<h1>{name}</h1>
+<table>
+{rows}</table>
+{sections}</body>
+</html>
+"
+    )
+}
+
+// `.html`/`.htm` or `.md`/`.markdown`, picked from the destination's extension
+#[tauri::command]
+pub fn save_run_report(app: AppHandle, run_id: String, dest: String) -> Result<(), String> {
+    let dest = settings::expand_path(&dest);
+    let extension = dest
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let format = match extension.as_str() {
+        "html" | "htm" => Format::Html,
+        "md" | "markdown" => Format::Markdown,
+        _ => return Err("Reports can be saved as .html or .md".to_string()),
+    };
+    let entry = history::find(&app, &run_id).ok_or_else(|| format!("Run not found: {}", run_id))?;
+    let (stdout, stderr) = history::read_output(&app, &run_id)
+        .ok_or_else(|| format!("No output was kept for run {}", run_id))?;
+    let report = match format {
+        Format::Html => html(&entry, &stdout, &stderr),
+        Format::Markdown => markdown(&entry, &stdout, &stderr),
+    };
+    fs::write(&dest, report).map_err(|e| format!("Failed to write {}: {}", dest.display(), e))
+}
+
+// What export_run_report adds to the summary rows
+fn details(entry: &HistoryEntry) -> Vec<(&'static str, String)> {
+    let mut rows = vec![
+        ("Finished", timestamp(entry.started_at + entry.duration_ms)),
+        (
+            "Content hash",
+            entry
+                .content_hash
+                .clone()
+                .unwrap_or_else(|| "not recorded".to_string()),
+        ),
+    ];
+    if let Some(source) = &entry.source {
+        let source = match &source.detail {
+            Some(detail) => format!("{} ({})", source.kind, detail),
+            None => source.kind.clone(),
+        };
+        rows.push(("Started by", source));
+    }
+    if entry.cancelled {
+        rows.push(("Cancelled", "yes".to_string()));
+    }
+    if entry.staged {
+        rows.push(("Staged edit", "yes".to_string()));
+    }
+    if let Some(cwd) = entry.rerun.as_ref().and_then(|r| r.cwd.clone()) {
+        rows.push(("Working directory", cwd));
+    }
+    // Only names were recorded, never values, so there is nothing to redact
+    if let Some(rerun) = entry.rerun.as_ref().filter(|r| !r.env_keys.is_empty()) {
+        rows.push((
+            "Environment",
+            format!("{} (values not included)", rerun.env_keys.join(", ")),
+        ));
+    }
+    if !entry.env_groups.is_empty() {
+        rows.push(("Environment groups", entry.env_groups.join(", ")));
+    }
+    if let Some(bytes) = entry.log_truncated_at_bytes {
+        rows.push(("Log capped at", format!("{} bytes", bytes)));
+    }
+    rows
+}
+
+// The script only when it still has the hash recorded for the run
+fn script_content(entry: &HistoryEntry) -> Option<String> {
+    let hash = entry.content_hash.as_deref()?;
+    let current = snapshots::read(Path::new(&entry.path)).ok()?;
+    (current.hash() == hash)
+        .then(|| current.content().map(str::to_string))
+        .flatten()
+}
+
+// Indented rather than fenced: the fence for a log can't be chosen before reading it all
+fn write_markdown_line(out: &mut dyn Write, line: &str) -> io::Result<()> {
+    writeln!(out, "    {}", ANSI.replace_all(line, ""))
+}
+
+// Copies a log into the report a line at a time, stopping after MAX_INLINE_BYTES. Returns
+// whether it stopped early.
+fn write_log(
+    out: &mut dyn Write,
+    path: &Path,
+    format: &Format,
+    style: &mut Style,
+) -> io::Result<bool> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut bytes = Vec::new();
+    let mut written: u64 = 0;
+    loop {
+        bytes.clear();
+        // Bounded, so a log without newlines isn't read whole either
+        let read = reader
+            .by_ref()
+            .take(MAX_INLINE_BYTES - written + 1)
+            .read_until(b'\n', &mut bytes)?;
+        if read == 0 {
+            return Ok(false);
+        }
+        written += read as u64;
+        if written > MAX_INLINE_BYTES {
+            return Ok(true);
+        }
+        let line = String::from_utf8_lossy(&bytes);
+        let line = line.trim_end_matches(['\r', '\n']);
+        match format {
+            Format::Markdown => write_markdown_line(out, line)?,
+            Format::Html => {
+                let mut html = String::new();
+                push_ansi(&mut html, line, style);
+                writeln!(out, "{}", html)?;
+            }
+        }
+    }
+}
+
+fn truncated_note(file: &str, attached: bool) -> String {
+    if attached {
+        format!(
+            "Shown up to {} MiB; the full log is in {}.",
+            MAX_INLINE_BYTES / (1024 * 1024),
+            file
+        )
+    } else {
+        format!(
+            "Shown up to {} MiB; export as a folder or zip to include the full log.",
+            MAX_INLINE_BYTES / (1024 * 1024)
+        )
+    }
+}
+
+fn write_report(
+    out: &mut dyn Write,
+    entry: &HistoryEntry,
+    logs: &[(&'static str, PathBuf)],
+    format: Format,
+    attached: bool,
+) -> io::Result<()> {
+    let name = script_name(&entry.path);
+    let rows: Vec<(&str, String)> = summary(entry).into_iter().chain(details(entry)).collect();
+    let script = script_content(entry);
+    match format {
+        Format::Markdown => {
+            writeln!(out, "# {}\n", name)?;
+            for (label, value) in rows {
+                writeln!(out, "- **{}:** `{}`", label, value)?;
+            }
+            writeln!(out, "\n## Script\n")?;
+            match &script {
+                Some(content) => {
+                    for line in content.lines() {
+                        write_markdown_line(out, line)?;
+                    }
+                }
+                None => writeln!(out, "Changed since this run; only its hash is included.")?,
+            }
+        }
+        Format::Html => {
+            let name = escape_html(&name);
+            write!(
+                out,
+                "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>{name}</title>
+<style>
+body {{ font-family: -apple-system, system-ui, sans-serif; margin: 2rem; }}
+th {{ text-align: left; padding-right: 1rem; }}
+pre {{ background: #1e1e1e; color: #e5e5e5; padding: 1rem; overflow-x: auto; }}
+pre.stderr {{ border-left: 4px solid #cd0000; }}
+p.note {{ color: #7f7f7f; font-style: italic; }}
+</style>
+</head>
+<body>
+<h1>{name}</h1>
+<table>
+"
+            )?;
+            for (label, value) in rows {
+                writeln!(
+                    out,
+                    "<tr><th>{}</th><td><code>{}</code></td></tr>",
+                    label,
+                    escape_html(&value)
+                )?;
+            }
+            writeln!(out, "</table>\n<h2>Script</h2>")?;
+            match &script {
+                Some(content) => writeln!(out, "<pre>{}</pre>", escape_html(content.trim_end()))?,
+                None => writeln!(
+                    out,
+                    "<p class=\"note\">Changed since this run; only its hash is included.</p>"
+                )?,
+            }
+        }
+    }
+
+    if !logs.iter().any(|(_, path)| path.is_file()) {
+        match format {
+            Format::Markdown => writeln!(out, "\nNo output was kept for this run.")?,
+            Format::Html => writeln!(
+                out,
+                "<p class=\"note\">No output was kept for this run.</p>"
+            )?,
+        }
+    }
+    for (stream, path) in logs {
+        let Ok(size) = fs::metadata(path).map(|m| m.len()) else {
+            continue;
+        };
+        if *stream == "stderr" && size == 0 {
+            continue;
+        }
+        let title = if *stream == "stderr" {
+            "Errors"
+        } else {
+            "Output"
+        };
+        let file = format!("{}.log", stream);
+        let mut style = Style::default();
+        match format {
+            Format::Markdown => {
+                writeln!(out, "\n## {}\n", title)?;
+                if write_log(out, path, &format, &mut style)? {
+                    writeln!(out, "\n_{}_", truncated_note(&file, attached))?;
+                }
+            }
+            Format::Html => {
+                let class = if *stream == "stderr" {
+                    " class=\"stderr\""
+                } else {
+                    ""
+                };
+                write!(out, "<h2>{}</h2>\n<pre{}>", title, class)?;
+                let truncated = write_log(out, path, &format, &mut style)?;
+                writeln!(out, "</pre>")?;
+                if truncated {
+                    writeln!(
+                        out,
+                        "<p class=\"note\">{}</p>",
+                        escape_html(&truncated_note(&file, attached))
+                    )?;
+                }
+            }
+        }
+    }
+    if matches!(format, Format::Html) {
+        writeln!(out, "</body>\n</html>")?;
+    }
+    Ok(())
+}
+
+fn export(
+    entry: &HistoryEntry,
+    logs: &[(&'static str, PathBuf)],
+    dest: &Path,
+    format: ReportFormat,
+) -> Result<(), String> {
+    let failed = |e: io::Error| format!("Failed to write {}: {}", dest.display(), e);
+    match format {
+        ReportFormat::Markdown | ReportFormat::Html => {
+            let file = File::create(dest).map_err(failed)?;
+            let mut out = BufWriter::new(file);
+            let inner = if format == ReportFormat::Html {
+                Format::Html
+            } else {
+                Format::Markdown
+            };
+            write_report(&mut out, entry, logs, inner, false).map_err(failed)?;
+            out.flush().map_err(failed)
+        }
+        ReportFormat::Dir => {
+            fs::create_dir_all(dest).map_err(failed)?;
+            let file = File::create(dest.join(REPORT_FILE)).map_err(failed)?;
+            let mut out = BufWriter::new(file);
+            write_report(&mut out, entry, logs, Format::Html, true).map_err(failed)?;
+            out.flush().map_err(failed)?;
+            for (stream, path) in logs {
+                if path.is_file() {
+                    fs::copy(path, dest.join(format!("{}.log", stream))).map_err(failed)?;
+                }
+            }
+            Ok(())
+        }
+        ReportFormat::Zip => {
+            let file = File::create(dest).map_err(failed)?;
+            let mut zip = ZipWriter::new(BufWriter::new(file));
+            let options =
+                SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+            zip.start_file(REPORT_FILE, options)
+                .map_err(|e| e.to_string())?;
+            write_report(&mut zip, entry, logs, Format::Html, true).map_err(failed)?;
+            for (stream, path) in logs {
+                let Ok(mut log) = File::open(path) else {
+                    continue;
+                };
+                zip.start_file(format!("{}.log", stream), options)
+                    .map_err(|e| e.to_string())?;
+                io::copy(&mut log, &mut zip).map_err(failed)?;
+            }
+            zip.finish().map_err(|e| e.to_string())?;
+            Ok(())
+        }
+    }
+}
+
+// Everything about one run in a file to hand to someone else. Logs are read a line at a
+// time, so large outputs don't have to fit in memory. Returns the path written.
+#[tauri::command]
+pub async fn export_run_report(
+    app: AppHandle,
+    run_id: String,
+    path: String,
+    format: ReportFormat,
+) -> Result<String, String> {
+    let entry = history::find(&app, &run_id).ok_or_else(|| format!("Run not found: {}", run_id))?;
+    let logs = history::log_files(&app, &run_id);
+    let dest = settings::expand_path(&path);
+    tauri::async_runtime::spawn_blocking(move || {
+        export(&entry, &logs, &dest, format)?;
+        Ok(dest.to_string_lossy().to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::{LazyLock, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::{scripts, watcher};
+
+// Error prefix for runs of a script whose scripts folder can't be reached
+pub const ROOT_UNAVAILABLE_ERROR: &str = "root-unavailable";
+// Long enough for a slow share, short enough that a dead one doesn't stall the list
+const OPERATION_TIMEOUT: Duration = Duration::from_secs(5);
+const FAILURES_BEFORE_UNAVAILABLE: u32 = 2;
+const PROBE_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Default)]
+struct RootState {
+    // From the last scan that finished in time, reported under the root as given
+    scripts: Vec<String>,
+    failures: u32,
+    unavailable: bool,
+    // What the last root-availability-changed event said
+    reported_unavailable: bool,
+    // A scan that timed out is still stuck in the filesystem; no new one starts meanwhile
+    stuck: bool,
+}
+
+static ROOTS: LazyLock<Mutex<HashMap<PathBuf, RootState>>> = LazyLock::new(Mutex::default);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RootAvailability {
+    pub root: String,
+    pub available: bool,
+}
+
+// Runs filesystem work on its own thread and gives up waiting after OPERATION_TIMEOUT; a
+// call stuck on a dead network volume is left behind rather than blocking the caller
+fn with_timeout<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> Option<T> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(work());
+    });
+    rx.recv_timeout(OPERATION_TIMEOUT).ok()
+}
+
+fn record_failure(root: &Path) {
+    let Ok(mut roots) = ROOTS.lock() else {
+        return;
+    };
+    let state = roots.entry(root.to_path_buf()).or_default();
+    state.failures += 1;
+    if state.failures >= FAILURES_BEFORE_UNAVAILABLE && !state.unavailable {
+        log::warn!("Scripts folder {} is unavailable", root.display());
+        state.unavailable = true;
+    }
+}
+
+fn set_stuck(root: &Path, stuck: bool) {
+    if let Ok(mut roots) = ROOTS.lock() {
+        roots.entry(root.to_path_buf()).or_default().stuck = stuck;
+    }
+}
+
+fn is_stuck(root: &Path) -> bool {
+    ROOTS
+        .lock()
+        .is_ok_and(|roots| roots.get(root).is_some_and(|state| state.stuck))
+}
+
+fn cached(root: &Path) -> Vec<String> {
+    ROOTS
+        .lock()
+        .ok()
+        .and_then(|roots| roots.get(root).map(|state| state.scripts.clone()))
+        .unwrap_or_default()
+}
+
+pub fn is_unavailable(root: &Path) -> bool {
+    ROOTS
+        .lock()
+        .is_ok_and(|roots| roots.get(root).is_some_and(|state| state.unavailable))
+}
+
+// Script ids in a cached listing of an unavailable root
+pub fn is_stale(path: &str) -> bool {
+    ROOTS.lock().is_ok_and(|roots| {
+        roots
+            .iter()
+            .any(|(root, state)| state.unavailable && Path::new(path).starts_with(root))
+    })
+}
+
+// Resolves and scans one root within the timeout. Returns the resolved path with the
+// scripts, or None and the last good listing when the root is unavailable or too slow.
+pub fn scan(root: &Path) -> (Option<PathBuf>, Vec<String>) {
+    if is_unavailable(root) || is_stuck(root) {
+        return (None, cached(root));
+    }
+    let configured = root.to_path_buf();
+    let result = with_timeout(move || {
+        let resolved = scripts::resolve_root(&configured);
+        let found = resolved.is_dir().then(|| {
+            scripts::collect_scripts(&resolved)
+                .iter()
+                .map(|p| {
+                    scripts::to_configured(p, &configured, &resolved)
+                        .to_string_lossy()
+                        .to_string()
+                })
+                .collect::<Vec<String>>()
+        });
+        set_stuck(&configured, false);
+        found.map(|found| (resolved, found))
+    });
+    if result.is_none() {
+        set_stuck(root, true);
+    }
+    match result.flatten() {
+        Some((resolved, found)) => {
+            if let Ok(mut roots) = ROOTS.lock() {
+                let state = roots.entry(root.to_path_buf()).or_default();
+                state.scripts = found.clone();
+                state.failures = 0;
+            }
+            (Some(resolved), found)
+        }
+        None => {
+            record_failure(root);
+            (None, cached(root))
+        }
+    }
+}
+
+// For the watcher: resolves the root within the timeout, None if it's not a reachable folder
+pub fn resolve(root: &Path) -> Option<PathBuf> {
+    if is_unavailable(root) {
+        return None;
+    }
+    let configured = root.to_path_buf();
+    let resolved = with_timeout(move || {
+        let resolved = scripts::resolve_root(&configured);
+        resolved.is_dir().then_some(resolved)
+    });
+    if resolved.is_none() {
+        record_failure(root);
+    }
+    resolved.flatten()
+}
+
+// Fails fast instead of letting the run pipeline block on the volume
+pub fn ensure_available(path: &str) -> Result<(), String> {
+    let unreachable = ROOTS.lock().ok().and_then(|roots| {
+        roots
+            .iter()
+            .find(|(root, state)| state.unavailable && Path::new(path).starts_with(root))
+            .map(|(root, _)| root.clone())
+    });
+    match unreachable {
+        Some(root) => Err(format!(
+            "{}: {} can't be reached, so {} can't run",
+            ROOT_UNAVAILABLE_ERROR,
+            root.display(),
+            path
+        )),
+        None => Ok(()),
+    }
+}
+
+fn probe(root: &Path) -> bool {
+    let root = root.to_path_buf();
+    with_timeout(move || std::fs::read_dir(&root).is_ok()).unwrap_or(false)
+}
+
+// Re-checks unavailable roots in the background and reports every change of availability
+// as a root-availability-changed event; roots that come back are watched and rescanned
+pub fn start_prober(app: &AppHandle) {
+    let app = app.clone();
+    thread::spawn(move || loop {
+        thread::sleep(PROBE_INTERVAL);
+        let unavailable: Vec<PathBuf> = ROOTS
+            .lock()
+            .map(|roots| {
+                roots
+                    .iter()
+                    .filter(|(_, state)| state.unavailable)
+                    .map(|(root, _)| root.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let back: Vec<PathBuf> = unavailable.into_iter().filter(|r| probe(r)).collect();
+        let changed: Vec<RootAvailability> = {
+            let Ok(mut roots) = ROOTS.lock() else {
+                continue;
+            };
+            for root in &back {
+                if let Some(state) = roots.get_mut(root) {
+                    log::info!("Scripts folder {} is available again", root.display());
+                    state.unavailable = false;
+                    state.failures = 0;
+                }
+            }
+            roots
+                .iter_mut()
+                .filter(|(_, state)| state.unavailable != state.reported_unavailable)
+                .map(|(root, state)| {
+                    state.reported_unavailable = state.unavailable;
+                    RootAvailability {
+                        root: root.to_string_lossy().to_string(),
+                        available: !state.unavailable,
+                    }
+                })
+                .collect()
+        };
+        for event in &changed {
+            let _ = app.emit("root-availability-changed", event);
+        }
+        if !back.is_empty() {
+            let _ = watcher::restart(&app);
+            scripts::notify_scripts_changed(&app, Vec::new());
+        }
+    });
+}
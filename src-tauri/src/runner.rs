@@ -0,0 +1,2402 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use encoding_rs::{Decoder, Encoding};
+use portable_pty::{native_pty_system, CommandBuilder, PtyPair, PtySize};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::history::{self, HistoryEntry, RerunSpec};
+use crate::limits::{self, ResourceLimits, ResourceUsage};
+use crate::logcap::{self, LogBudget};
+use crate::matchers::{self, MatcherHit, Matchers};
+use crate::outputfilter::{self, CompiledFilter, Highlight};
+use crate::recovery::InFlightRun;
+use crate::sandbox::{self, SandboxMode};
+use crate::screen::{Screen, Tee};
+use crate::snapshots::{self, ContentChange};
+use crate::{
+    askpass, audit, batch, concurrency, conditions, countdown, danger, deps, diagnostics,
+    envgroups, external, index, livestream, metadata, metrics, notifications, pause, placeholders,
+    profiles, readonly, recovery, roots, runstate, runwindows, scripts, secrets, settings, staging,
+    tray, triggers, workflows,
+};
+
+const OUTPUT_ENCODING_KEY: &str = "outputEncoding";
+const OUTPUT_MARKER_KEY: &str = "outputMarker";
+// The path is the first capture group, or the whole match for a pattern without one
+const DEFAULT_OUTPUT_MARKER: &str = r"^OUTPUT:\s*(.+?)\s*$";
+const AUTO_CHMOD_KEY: &str = "autoChmodScripts";
+const AUTO_STRIP_QUARANTINE_KEY: &str = "autoStripQuarantine";
+// How long cancel_script waits after SIGTERM before SIGKILL
+const CANCEL_GRACE_KEY: &str = "cancelGraceMs";
+const DEFAULT_CANCEL_GRACE_MS: u64 = 5000;
+// Refuse scripts that other accounts can write to
+const BLOCK_WRITABLE_KEY: &str = "blockWritableScripts";
+// Default for RunOptions.capture_command
+const CAPTURE_COMMAND_KEY: &str = "captureCommand";
+// Prefix of the run error for scripts missing their exec bit, so the UI can offer make_executable
+pub const NOT_EXECUTABLE_ERROR: &str = "not-executable";
+// Prefix of the run error for scripts marked `# @source: true`
+pub const SOURCE_ONLY_ERROR: &str = "source-only";
+// Prefix of the run error for scripts refused under BLOCK_WRITABLE_KEY
+pub const WRITABLE_BY_OTHERS_ERROR: &str = "writable-by-others";
+// Prefix of the run error for `trace` on a script whose interpreter has no xtrace
+pub const TRACE_UNSUPPORTED_ERROR: &str = "trace-unsupported";
+pub const UMASK_UNSUPPORTED_ERROR: &str = "umask-unsupported";
+// Prefix of the run_with_clipboard error when there is no text to pipe in
+pub const CLIPBOARD_EMPTY_ERROR: &str = "clipboard-empty";
+// Prefix of the preview_changes error for scripts without a `# @dry-run-flag`
+pub const NO_DRY_RUN_FLAG_ERROR: &str = "no-dry-run-flag";
+// Prefix of the confirm_after_preview error for an unknown token or a script edited since
+pub const PREVIEW_EXPIRED_ERROR: &str = "preview-expired";
+// Shells that have `set -x` and write each traced command behind PS4
+const TRACE_SHELLS: &[&str] = &["sh", "bash", "dash", "ksh", "mksh", "zsh"];
+// Marks trace lines so the readers can pull them out of stderr; bash repeats it per nesting level
+const TRACE_MARKER: char = '\u{1e}';
+const DEFAULT_RETRY_DELAY_MS: u64 = 1000;
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+// A collapsed line's count is sent at most this often while it keeps repeating
+const REPEAT_REPORT_INTERVAL: Duration = Duration::from_millis(500);
+const LAST_LINE_CHARS: usize = 200;
+const RESULT_FD: i32 = 3;
+const MAX_RESULT_BYTES: u64 = 4 * 1024 * 1024;
+// Clipboard text goes to a script's stdin in writes of this size
+const STDIN_CHUNK_BYTES: usize = 64 * 1024;
+const PTY_ROWS: u16 = 24;
+const PTY_COLS: u16 = 120;
+
+static RUN_COUNTER: AtomicU64 = AtomicU64::new(0);
+// Guards run-again against key repeat and double clicks
+static LAST_RUN_AGAIN: AtomicU64 = AtomicU64::new(0);
+const RUN_AGAIN_COOLDOWN_MS: u64 = 1000;
+// Preview token -> the script it was for and the hash of what the dry run ran
+static PREVIEWS: LazyLock<Mutex<HashMap<String, (String, String)>>> = LazyLock::new(Mutex::default);
+pub const RUN_AGAIN_TOO_SOON: &str = "Run again was triggered too quickly";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RunOptions {
+    pub args: Vec<String>,
+    pub env: HashMap<String, String>,
+    pub cwd: Option<String>,
+    pub timeout_secs: Option<u64>,
+    // Overrides the global output encoding for this run
+    pub encoding: Option<String>,
+    // Post a notification with the start of stdout even when the window is open
+    pub notify_with_output: bool,
+    // Give the script a pseudo-terminal instead of pipes, for `read` and curses UIs
+    pub run_in_pty: bool,
+    pub max_memory_mb: Option<u64>,
+    pub max_cpu_seconds: Option<u64>,
+    // Run from a throwaway temp dir, inside sandbox-exec or bwrap when available
+    pub sandbox: bool,
+    // Drop `# @progress: N` lines from the output once they've been reported
+    pub hide_progress_lines: bool,
+    // Stream a run of identical lines once, then report its count with script-output-repeated;
+    // the captured output still has every line
+    pub collapse_repeats: bool,
+    // Send stderr down the stdout pipe so both arrive in the order they were written; the
+    // result's stderr is then empty
+    pub merge_stderr: bool,
+    // Free-form tag stored with the history entry, e.g. the project the run was for
+    pub label: Option<String>,
+    // Point SUDO_ASKPASS at a helper that asks for the password in a native dialog
+    pub gui_sudo: bool,
+    // Keep only the last N KB of each stream in the result; events still carry every line
+    pub ring_buffer_kb: Option<usize>,
+    // Values for `{{NAME}}` tokens, substituted into a temp copy of the script
+    pub placeholders: HashMap<String, String>,
+    // Fail the run when a placeholder is left without a value
+    pub strict: bool,
+    // Required to start scripts marked dangerous, see danger.rs
+    pub confirmed: bool,
+    // Run even when the script's run conditions (network, host, hostname) don't hold
+    pub ignore_conditions: bool,
+    // Runs the script's staged edit instead of the file; only staging::run_staged sets it
+    #[serde(skip)]
+    pub staged: bool,
+    // Fail before starting when a `# @requires` program is missing or too old
+    pub check_dependencies: bool,
+    // Run again this many times after a non-zero exit; cancelled, timed out and killed runs
+    // are never retried
+    pub retries: Option<u32>,
+    pub retry_delay_ms: Option<u64>,
+    // Sample the process tree's CPU and memory into script-metrics events while it runs
+    pub metrics_enabled: bool,
+    // Open the file named on the script's marker line (`OUTPUT: /tmp/report.pdf` by default, see
+    // set_output_marker) with the default app, once the script exits with 0
+    pub auto_open_output: bool,
+    // Give the script a pipe as fd 3; whatever it writes there (`echo '{"ok":true}' >&3`) is
+    // parsed as JSON into the result's structured_result. Unix only, and not in a pty.
+    pub capture_fd3: bool,
+    // File mode creation mask for the script, 0 to 0o777; None inherits the app's. Unix only,
+    // and not in a pty.
+    pub umask: Option<u32>,
+    // Record how the script was started in the result's `invocation`; None uses the global setting
+    pub capture_command: Option<bool>,
+    // Run the script under its shell with `set -x` and collect each executed command into the result's trace
+    pub trace: bool,
+    // What started the run, e.g. "webhook"; set by the backend, never by the frontend
+    #[serde(skip)]
+    pub trigger: Option<String>,
+    // What exactly fired it, e.g. the hotkey's accelerator; see triggers::TriggerSource
+    #[serde(skip)]
+    pub trigger_detail: Option<String>,
+    // Started by the queue worker, which waits for a free slot instead of queueing again
+    #[serde(skip)]
+    pub from_queue: bool,
+    // Refused with CONCURRENCY_LIMIT_ERROR at the limit even under the Queue policy, for
+    // callers waiting on the result
+    #[serde(skip)]
+    pub no_queue: bool,
+    // Started by run_scripts; the batch gets one summary notification instead of one per run
+    #[serde(skip)]
+    pub batch_id: Option<String>,
+    // Draw the pty's output on a virtual screen and return what it shows at the end as
+    // `screen_snapshot`; needs run_in_pty
+    pub snapshot_tui: bool,
+    // Written to stdin, followed by EOF; set by run_with_clipboard
+    #[serde(skip)]
+    pub clipboard_stdin: Option<Arc<str>>,
+    // Set by preview_changes: the run takes no concurrency slot, keeps no history or logs,
+    // doesn't notify or move the script's snapshot, and is audited as a dry run
+    #[serde(skip)]
+    pub dry_run: bool,
+}
+
+impl RunOptions {
+    fn limits(&self) -> ResourceLimits {
+        ResourceLimits {
+            max_memory_mb: self.max_memory_mb,
+            max_cpu_seconds: self.max_cpu_seconds,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptResult {
+    pub run_id: String,
+    pub path: String,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration_ms: u64,
+    pub timed_out: bool,
+    // Set when undecodable bytes were replaced with U+FFFD
+    pub lossy_output: bool,
+    pub warnings: Vec<String>,
+    // "memory" or "cpu" when the run was ended by one of its resource limits
+    pub limit_exceeded: Option<String>,
+    // Isolation that was applied when the run asked for a sandbox
+    pub sandbox: Option<SandboxMode>,
+    // Set when the ring buffer dropped earlier output, so stdout/stderr are only the tail
+    pub output_truncated: bool,
+    // Set when the script differs from what its last successful run executed
+    pub content_change: Option<ContentChange>,
+    // Set when the script's content changed while it ran
+    pub modified_self: bool,
+    // Unix only, and not for runs in a pty
+    pub resource_usage: Option<ResourceUsage>,
+    // The commands xtrace printed, "+"-prefixed by nesting level; only for runs with `trace`
+    pub trace: Option<String>,
+    // The JSON the script wrote to fd 3, for runs with `capture_fd3`
+    pub structured_result: Option<serde_json::Value>,
+    // Stopped with cancel_script
+    pub cancelled: bool,
+    // Including the first run; above 1 only when `retries` kicked in
+    pub attempts: u32,
+    // Output matcher rules that fired, in order
+    pub matcher_hits: Vec<MatcherHit>,
+    // The file auto_open_output opened
+    pub opened_output: Option<String>,
+    // Where the run's log files stop, when its output passed the log cap
+    pub log_truncated_at_bytes: Option<u64>,
+    // The mask the script ran with, the app's own when the run didn't set one; None on Windows
+    pub umask: Option<u32>,
+    // Set when the run captured its command line
+    pub invocation: Option<Invocation>,
+    // The final screen of a snapshot_tui run, as plain text
+    pub screen_snapshot: Option<String>,
+}
+
+// Exactly how a script was started, with secret values replaced
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Invocation {
+    pub argv: Vec<String>,
+    pub cwd: Option<String>,
+    // From the shebang line, or the program the script was handed to
+    pub interpreter: String,
+    // Only the variables the run set on top of the app's own environment
+    pub env: BTreeMap<String, String>,
+}
+
+impl ScriptResult {
+    fn should_retry(&self) -> bool {
+        !self.success
+            && !self.cancelled
+            && !self.timed_out
+            && self.limit_exceeded.is_none()
+            && self.exit_code.is_some_and(|code| code != 0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveRun {
+    pub run_id: String,
+    pub path: String,
+    pub started_at: u64,
+    // Last `# @progress` marker the script printed
+    pub progress: Option<f64>,
+    // Also the id of the run's process group
+    pub pid: Option<u32>,
+    // Set by cancel_script while the run winds down
+    pub cancelled: bool,
+    // Most recent line streamed, cut to LAST_LINE_CHARS
+    pub last_line: Option<String>,
+    pub batch_id: Option<String>,
+}
+
+// Runs whose process is currently alive, keyed by run id
+#[derive(Default)]
+pub struct ActiveRuns(Mutex<HashMap<String, ActiveRun>>);
+
+impl ActiveRuns {
+    fn insert(&self, run: ActiveRun) {
+        if let Ok(mut runs) = self.0.lock() {
+            runs.insert(run.run_id.clone(), run);
+        }
+        runstate::changed();
+    }
+
+    fn update(&self, run_id: &str, update: impl FnOnce(&mut ActiveRun)) {
+        if let Ok(mut runs) = self.0.lock() {
+            if let Some(run) = runs.get_mut(run_id) {
+                update(run);
+            }
+        }
+        runstate::changed();
+    }
+
+    fn set_progress(&self, run_id: &str, percent: f64) {
+        self.update(run_id, |run| run.progress = Some(percent));
+    }
+
+    fn set_last_line(&self, run_id: &str, line: &str) {
+        let line = match line.char_indices().nth(LAST_LINE_CHARS) {
+            Some((end, _)) => &line[..end],
+            None => line,
+        };
+        self.update(run_id, |run| run.last_line = Some(line.to_string()));
+    }
+
+    fn remove(&self, run_id: &str) -> Option<ActiveRun> {
+        let run = self.0.lock().ok().and_then(|mut runs| runs.remove(run_id));
+        runstate::changed();
+        run
+    }
+
+    fn mark_cancelled(&self, run_id: &str) {
+        self.update(run_id, |run| run.cancelled = true);
+    }
+
+    pub fn pid(&self, run_id: &str) -> Option<u32> {
+        self.0
+            .lock()
+            .ok()
+            .and_then(|runs| runs.get(run_id).and_then(|run| run.pid))
+    }
+
+    pub fn list(&self) -> Vec<ActiveRun> {
+        let mut runs: Vec<ActiveRun> = self
+            .0
+            .lock()
+            .map(|runs| runs.values().cloned().collect())
+            .unwrap_or_default();
+        runs.sort_by_key(|run| run.started_at);
+        runs
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StartedEvent<'a> {
+    run_id: &'a str,
+    path: &'a str,
+}
+
+// script-output also carries the line's `seq`, added by livestream::output
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OutputEvent<'a> {
+    run_id: &'a str,
+    line: &'a str,
+    stream: &'a str,
+    // From the script's output filter; empty for other lines
+    highlights: Vec<Highlight>,
+}
+
+// Sent for the last streamed line while it repeats, and once more with its final count
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RepeatedEvent<'a> {
+    run_id: &'a str,
+    line: &'a str,
+    stream: &'a str,
+    // Including the streamed occurrence
+    repeat_count: u32,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunProgress {
+    run_id: String,
+    percent: f64,
+    elapsed_ms: u64,
+    // Linear extrapolation from the elapsed time; None until the first non-zero marker
+    eta_ms: Option<u64>,
+}
+
+impl RunProgress {
+    fn new(run_id: &str, percent: f64, started_at: u64) -> Self {
+        let elapsed_ms = history::now_ms().saturating_sub(started_at);
+        let eta_ms = (percent > 0.0)
+            .then(|| (elapsed_ms as f64 * (100.0 - percent) / percent).round() as u64);
+        Self {
+            run_id: run_id.to_string(),
+            percent,
+            elapsed_ms,
+            eta_ms,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExitedEvent<'a> {
+    run_id: &'a str,
+    path: &'a str,
+    exit_code: Option<i32>,
+    duration_ms: u64,
+    timed_out: bool,
+    // Removed from the queue before it ran; nothing else in the event applies
+    cancelled_before_start: bool,
+}
+
+// Sent before each retry, e.g. for "retrying (2/3)"
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RetryingEvent<'a> {
+    // The attempt that failed
+    run_id: &'a str,
+    // The attempt about to start, once the delay passes
+    next_run_id: &'a str,
+    path: &'a str,
+    exit_code: Option<i32>,
+    // 1 for the first retry
+    retry: u32,
+    retries: u32,
+    delay_ms: u64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WarningEvent<'a> {
+    pub run_id: &'a str,
+    pub message: &'a str,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum OutputDecoder {
+    Utf8Lossy,
+    Encoding(&'static Encoding),
+}
+
+impl OutputDecoder {
+    fn from_label(label: Option<&str>) -> Result<Self, String> {
+        let Some(label) = label.map(str::trim).filter(|l| !l.is_empty()) else {
+            return Ok(Self::Utf8Lossy);
+        };
+        let encoding = Encoding::for_label(label.as_bytes())
+            .ok_or_else(|| format!("Unknown output encoding: {}", label))?;
+        if encoding == encoding_rs::UTF_8 {
+            Ok(Self::Utf8Lossy)
+        } else {
+            Ok(Self::Encoding(encoding))
+        }
+    }
+
+    // Invalid input becomes replacement characters, as String::from_utf8_lossy does for UTF-8
+    fn decoder(self) -> Decoder {
+        match self {
+            Self::Utf8Lossy => encoding_rs::UTF_8.new_decoder_without_bom_handling(),
+            Self::Encoding(encoding) => encoding.new_decoder_without_bom_handling(),
+        }
+    }
+}
+
+// Decodes a stream as it arrives and splits the decoded text into lines, so encodings whose
+// newline isn't a lone 0x0a byte, such as UTF-16, still split where the text does
+struct LineReader<R> {
+    source: R,
+    decoder: Decoder,
+    // Decoded text not yet returned as a line
+    text: String,
+    done: bool,
+    // Whether replacement characters were inserted anywhere so far
+    lossy: bool,
+}
+
+impl<R: Read> LineReader<R> {
+    fn new(source: R, decoder: OutputDecoder) -> Self {
+        Self {
+            source,
+            decoder: decoder.decoder(),
+            text: String::new(),
+            done: false,
+            lossy: false,
+        }
+    }
+
+    // The next line without its line ending; None once the stream is over
+    fn next_line(&mut self) -> Option<String> {
+        let mut chunk = [0u8; 8192];
+        loop {
+            if let Some(end) = self.text.find('\n') {
+                let line: String = self.text.drain(..=end).collect();
+                return Some(line.trim_end_matches(['\n', '\r']).to_string());
+            }
+            if self.done {
+                if self.text.is_empty() {
+                    return None;
+                }
+                let line = std::mem::take(&mut self.text);
+                return Some(line.trim_end_matches('\r').to_string());
+            }
+            let read = match self.source.read(&mut chunk) {
+                Ok(read) => read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(_) => 0,
+            };
+            self.done = read == 0;
+            let needed = self
+                .decoder
+                .max_utf8_buffer_length(read)
+                .unwrap_or(read * 3 + 4);
+            self.text.reserve(needed);
+            let (_, _, replaced) =
+                self.decoder
+                    .decode_to_string(&chunk[..read], &mut self.text, self.done);
+            self.lossy |= replaced;
+        }
+    }
+}
+
+pub fn next_run_id() -> String {
+    let counter = RUN_COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("{}-{}", history::now_ms(), counter)
+}
+
+pub fn has_shebang(path: &Path) -> bool {
+    let mut prefix = [0u8; 2];
+    File::open(path)
+        .and_then(|mut f| f.read_exact(&mut prefix))
+        .map(|_| &prefix == b"#!")
+        .unwrap_or(false)
+}
+
+// The shebang line without its `#!`
+fn shebang(path: &Path) -> Option<String> {
+    let mut line = String::new();
+    BufReader::new(File::open(path).ok()?)
+        .read_line(&mut line)
+        .ok()?;
+    line.strip_prefix("#!").map(|rest| rest.trim().to_string())
+}
+
+// Program, arguments and working directory of a run, shared by both runners
+struct Launch {
+    argv: Vec<OsString>,
+    cwd: Option<PathBuf>,
+    // Set by the app on top of the run's own env vars
+    env: Vec<(OsString, OsString)>,
+}
+
+impl Launch {
+    fn new(script: &Path, options: &RunOptions) -> Self {
+        // Scripts with a shebang pick their own interpreter, everything else runs under bash
+        let mut argv: Vec<OsString> = if has_shebang(script) {
+            vec![script.into()]
+        } else {
+            vec!["bash".into(), script.into()]
+        };
+        argv.extend(options.args.iter().map(OsString::from));
+        let cwd = options
+            .cwd
+            .as_deref()
+            .map(PathBuf::from)
+            .or_else(|| script.parent().map(Path::to_path_buf));
+        Self {
+            argv,
+            cwd,
+            env: Vec::new(),
+        }
+    }
+
+    // Swaps whatever would start the script for its shell sourcing it under `set -x`. PS4 is set
+    // inside the shell because bash ignores it in the environment when running as root.
+    fn trace(&mut self, script: &Path, mut shell: Vec<OsString>) {
+        let start = self
+            .argv
+            .iter()
+            .position(|arg| Path::new(arg) == script)
+            .unwrap_or(0);
+        shell.push("-c".into());
+        shell.push(format!("PS4='{} '; set -x; . \"$0\"", TRACE_MARKER).into());
+        // The script path lands in $0 and the arguments in $@, as if it had been run directly
+        self.argv.splice(..start, shell);
+    }
+
+    // Names that look secret and values known to be secrets are both redacted
+    fn invocation(&self, script: &Path, options: &RunOptions, secrets: &[String]) -> Invocation {
+        let redact = |name: &str, value: String| {
+            if diagnostics::is_secret_name(name) || secrets.contains(&value) {
+                secrets::REDACTED.to_string()
+            } else {
+                value
+            }
+        };
+        let mut env: BTreeMap<String, String> = options
+            .env
+            .iter()
+            .map(|(name, value)| (name.clone(), redact(name, value.clone())))
+            .collect();
+        for (name, value) in &self.env {
+            let name = name.to_string_lossy().to_string();
+            let value = redact(&name, value.to_string_lossy().to_string());
+            env.insert(name, value);
+        }
+        let interpreter = match self.argv.first() {
+            Some(program) if Path::new(program) == script => shebang(script).unwrap_or_default(),
+            Some(program) => program.to_string_lossy().to_string(),
+            None => String::new(),
+        };
+        Invocation {
+            argv: self
+                .argv
+                .iter()
+                .map(|arg| secrets::redact(&arg.to_string_lossy(), secrets))
+                .collect(),
+            cwd: self
+                .cwd
+                .as_ref()
+                .map(|dir| dir.to_string_lossy().to_string()),
+            interpreter,
+            env,
+        }
+    }
+
+    fn command(&self, options: &RunOptions) -> Command {
+        let mut command = Command::new(&self.argv[0]);
+        command
+            .args(&self.argv[1..])
+            .envs(&options.env)
+            .envs(self.env.iter().map(|(key, value)| (key, value)));
+        if let Some(dir) = &self.cwd {
+            command.current_dir(dir);
+        }
+        // Its own group, so pausing stops the whole tree; pty children get a session anyway
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+        command
+    }
+
+    fn pty_command(&self, options: &RunOptions) -> CommandBuilder {
+        let mut command = CommandBuilder::from_argv(self.argv.clone());
+        for (key, value) in &options.env {
+            command.env(key, value);
+        }
+        for (key, value) in &self.env {
+            command.env(key, value);
+        }
+        if let Some(dir) = &self.cwd {
+            command.cwd(dir);
+        }
+        command
+    }
+}
+
+// What a reader kept of one stream
+#[derive(Default)]
+struct Captured {
+    text: String,
+    lossy: bool,
+    truncated: bool,
+    // Trace lines taken out of the stream, never cut back by the ring buffer
+    trace: String,
+}
+
+impl Captured {
+    // With a limit, the buffer may grow to twice its size before the front is cut
+    // back off, so long runs don't shift the whole buffer on every line
+    fn push_line(&mut self, line: &str, limit: Option<usize>) {
+        self.text.push_str(line);
+        self.text.push('\n');
+        if let Some(limit) = limit.filter(|limit| self.text.len() > limit * 2) {
+            self.trim_to(limit);
+        }
+    }
+
+    // Drops whole lines from the front where possible, otherwise cuts at a char boundary
+    fn trim_to(&mut self, limit: usize) {
+        if self.text.len() <= limit {
+            return;
+        }
+        let mut cut = self.text.len() - limit;
+        while !self.text.is_char_boundary(cut) {
+            cut += 1;
+        }
+        if let Some(newline) = self.text[cut..].find('\n') {
+            if newline + 1 < self.text.len() - cut {
+                cut += newline + 1;
+            }
+        }
+        self.text.drain(..cut);
+        self.truncated = true;
+    }
+}
+
+// How a reader decodes and keeps its stream, copied out of the run for each thread
+#[derive(Clone)]
+struct ReaderOptions {
+    decoder: OutputDecoder,
+    started_at: u64,
+    hide_progress_lines: bool,
+    collapse_repeats: bool,
+    ring_buffer_bytes: Option<usize>,
+    trace: bool,
+    // Secret values injected into the run, masked before output leaves the backend
+    redact: Arc<[String]>,
+    matchers: Option<Arc<Matchers>>,
+    filter: Option<Arc<CompiledFilter>>,
+    log_budget: Arc<LogBudget>,
+}
+
+// The last streamed line and how often it has been seen in a row
+struct Repeats {
+    line: String,
+    count: u32,
+    reported: u32,
+    reported_at: Instant,
+}
+
+impl Repeats {
+    fn new(line: &str) -> Self {
+        Self {
+            line: line.to_string(),
+            count: 1,
+            reported: 1,
+            reported_at: Instant::now(),
+        }
+    }
+
+    fn report(&mut self, app: &AppHandle, run_id: &str, stream: &str) {
+        if self.count == self.reported {
+            return;
+        }
+        livestream::emit_if_visible(
+            app,
+            "script-output-repeated",
+            RepeatedEvent {
+                run_id,
+                line: &self.line,
+                stream,
+                repeat_count: self.count,
+            },
+        );
+        self.reported = self.count;
+        self.reported_at = Instant::now();
+    }
+}
+
+fn spawn_reader<R: Read + Send + 'static>(
+    app: AppHandle,
+    run_id: String,
+    stream: &'static str,
+    source: Option<R>,
+    options: ReaderOptions,
+) -> JoinHandle<Captured> {
+    let ReaderOptions {
+        decoder,
+        started_at,
+        hide_progress_lines,
+        collapse_repeats,
+        ring_buffer_bytes,
+        trace,
+        redact,
+        matchers,
+        filter,
+        log_budget,
+    } = options;
+    thread::spawn(move || {
+        let mut output = Captured::default();
+        let Some(source) = source else {
+            return output;
+        };
+        let mut lines = LineReader::new(source, decoder);
+        let mut repeats: Option<Repeats> = None;
+        // Nesting of the script's top-level commands; bash counts the `.` that sources it
+        let mut trace_started = false;
+        let mut trace_base: Option<usize> = None;
+        while let Some(line) = lines.next_line() {
+            let line = if redact.is_empty() {
+                line
+            } else {
+                secrets::redact(&line, &redact)
+            };
+            // A traced command spanning several lines only has its first one marked
+            if trace && line.starts_with(TRACE_MARKER) {
+                let command = line.trim_start_matches(TRACE_MARKER);
+                let depth = (line.len() - command.len()) / TRACE_MARKER.len_utf8();
+                // The first one is the `.` that starts the script
+                if !trace_started {
+                    trace_started = true;
+                    continue;
+                }
+                let base = *trace_base.get_or_insert(depth);
+                let level = (depth + 1).saturating_sub(base).max(1);
+                let line = format!("{}{}", "+".repeat(level), command);
+                livestream::emit_if_visible(
+                    &app,
+                    "script-trace",
+                    OutputEvent {
+                        run_id: &run_id,
+                        line: &line,
+                        stream,
+                        highlights: Vec::new(),
+                    },
+                );
+                output.trace.push_str(&line);
+                output.trace.push('\n');
+                continue;
+            }
+            if let Some(matchers) = &matchers {
+                matchers.check(&app, &run_id, &line);
+            }
+            if let Some(percent) = metadata::parse_progress(&line) {
+                app.state::<ActiveRuns>().set_progress(&run_id, percent);
+                let _ = app.emit(
+                    "script-progress",
+                    RunProgress::new(&run_id, percent, started_at),
+                );
+                if hide_progress_lines {
+                    continue;
+                }
+            }
+            // Shown and kept without the stripped parts; matchers and progress saw the whole line
+            let line = match &filter {
+                Some(filter) => match filter.strip(&line) {
+                    Some(stripped) => stripped.into_owned(),
+                    None => continue,
+                },
+                None => line,
+            };
+            log_budget.add(&app, &run_id, stream, &line);
+            if collapse_repeats {
+                match repeats.as_mut() {
+                    Some(last) if last.line == line => {
+                        last.count += 1;
+                        if last.reported_at.elapsed() >= REPEAT_REPORT_INTERVAL {
+                            last.report(&app, &run_id, stream);
+                        }
+                        output.push_line(&line, ring_buffer_bytes);
+                        continue;
+                    }
+                    Some(last) => last.report(&app, &run_id, stream),
+                    None => {}
+                }
+                repeats = Some(Repeats::new(&line));
+            }
+            let event = OutputEvent {
+                run_id: &run_id,
+                line: &line,
+                stream,
+                highlights: filter
+                    .as_ref()
+                    .map(|f| f.highlights(&line))
+                    .unwrap_or_default(),
+            };
+            let payload = serde_json::to_value(event).unwrap_or_default();
+            livestream::output(&app, &run_id, stream, &line, payload);
+            app.state::<ActiveRuns>().set_last_line(&run_id, &line);
+            output.push_line(&line, ring_buffer_bytes);
+        }
+        output.lossy = lines.lossy;
+        if let Some(last) = repeats.as_mut() {
+            last.report(&app, &run_id, stream);
+        }
+        if let Some(limit) = ring_buffer_bytes {
+            output.trim_to(limit);
+        }
+        output
+    })
+}
+
+// What a finished process left behind, however it was run
+struct Outcome {
+    exit_code: Option<i32>,
+    signal: Option<i32>,
+    success: bool,
+    timed_out: bool,
+    stdout: Captured,
+    stderr: Captured,
+    // Everything written to fd 3, when it was captured
+    result_fd: Option<Vec<u8>>,
+    usage: Option<ResourceUsage>,
+    screen: Option<String>,
+}
+
+// Context shared by the piped and pty runners
+struct Run<'a> {
+    app: &'a AppHandle,
+    path: &'a str,
+    run_id: &'a str,
+    started_at: u64,
+    decoder: OutputDecoder,
+    deadline: Option<Instant>,
+    hide_progress_lines: bool,
+    collapse_repeats: bool,
+    ring_buffer_bytes: Option<usize>,
+    trace: bool,
+    redact: Arc<[String]>,
+    // Script runs are kept on disk while they run, so a crash can be reconciled later
+    in_flight: Option<InFlightRun>,
+    metrics: bool,
+    batch_id: Option<String>,
+    // The script's output matchers, compiled for this run
+    matchers: Option<Arc<Matchers>>,
+    filter: Option<Arc<CompiledFilter>>,
+    // Shared by both readers
+    log_budget: Arc<LogBudget>,
+}
+
+impl Run<'_> {
+    fn emit_started(&self, pid: Option<u32>) {
+        self.app.state::<ActiveRuns>().insert(ActiveRun {
+            run_id: self.run_id.to_string(),
+            path: self.path.to_string(),
+            started_at: self.started_at,
+            progress: None,
+            pid,
+            cancelled: false,
+            last_line: None,
+            batch_id: self.batch_id.clone(),
+        });
+        // cancel_batch came in between the slot and the start
+        if pid.is_some() && self.batch_id.as_deref().is_some_and(batch::is_cancelled) {
+            let _ = cancel_script(self.app.clone(), self.run_id.to_string(), None);
+        }
+        if let Some(run) = &self.in_flight {
+            recovery::run_started(self.app, InFlightRun { pid, ..run.clone() });
+        }
+        if let Some(pid) = pid {
+            pause::stop_if_paused(pid);
+            if self.metrics {
+                metrics::start(self.app, self.run_id, pid);
+            }
+        }
+        let _ = self.app.emit(
+            "script-started",
+            StartedEvent {
+                run_id: self.run_id,
+                path: self.path,
+            },
+        );
+    }
+
+    fn reader<R: Read + Send + 'static>(
+        &self,
+        stream: &'static str,
+        source: Option<R>,
+    ) -> JoinHandle<Captured> {
+        spawn_reader(
+            self.app.clone(),
+            self.run_id.to_string(),
+            stream,
+            source,
+            ReaderOptions {
+                decoder: self.decoder,
+                started_at: self.started_at,
+                hide_progress_lines: self.hide_progress_lines,
+                collapse_repeats: self.collapse_repeats,
+                ring_buffer_bytes: self.ring_buffer_bytes,
+                trace: self.trace,
+                redact: self.redact.clone(),
+                matchers: self.matchers.clone(),
+                filter: self.filter.clone(),
+                log_budget: self.log_budget.clone(),
+            },
+        )
+    }
+
+    fn spawn_failed(&self, error: impl std::fmt::Display) -> String {
+        log::error!(
+            "Failed to start run {} of {}: {}",
+            self.run_id,
+            self.path,
+            error
+        );
+        format!("Failed to start {}: {}", self.path, error)
+    }
+
+    // Polls until the child exits; `poll` is asked to kill it once the deadline passes
+    fn wait<S>(
+        &self,
+        mut poll: impl FnMut(bool) -> std::io::Result<Option<S>>,
+    ) -> Result<(S, bool), String> {
+        let mut timed_out = false;
+        loop {
+            let expired = !timed_out && self.deadline.is_some_and(|d| Instant::now() >= d);
+            timed_out |= expired;
+            if let Some(status) = poll(expired).map_err(|e| e.to_string())? {
+                return Ok((status, timed_out));
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn piped(&self, launch: &Launch, options: &RunOptions) -> Result<Outcome, String> {
+        let mut command = launch.command(options);
+        limits::apply(&mut command, options.limits())?;
+        command.stdin(if options.clipboard_stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        });
+        let merged = if options.merge_stderr {
+            let (reader, writer) = std::io::pipe().map_err(|e| e.to_string())?;
+            command
+                .stdout(writer.try_clone().map_err(|e| e.to_string())?)
+                .stderr(writer);
+            Some(reader)
+        } else {
+            command.stdout(Stdio::piped()).stderr(Stdio::piped());
+            None
+        };
+        if let Some(mask) = options.umask {
+            apply_umask(&mut command, mask)?;
+        }
+        let result_pipe = options
+            .capture_fd3
+            .then(|| attach_result_fd(&mut command))
+            .transpose()?;
+        let mut child = command.spawn().map_err(|e| self.spawn_failed(e))?;
+        // Our copies of the write end must go, or the reader never sees EOF
+        drop(command);
+        let result_fd = result_pipe.map(|(reader, writer)| {
+            drop(writer);
+            thread::spawn(move || {
+                let mut bytes = Vec::new();
+                let _ = reader.take(MAX_RESULT_BYTES).read_to_end(&mut bytes);
+                bytes
+            })
+        });
+        // Dropping the pipe once everything is written gives the script its EOF. A script that
+        // exits without reading it all just ends the writes early.
+        let stdin =
+            options
+                .clipboard_stdin
+                .clone()
+                .zip(child.stdin.take())
+                .map(|(text, mut pipe)| {
+                    thread::spawn(move || {
+                        for chunk in text.as_bytes().chunks(STDIN_CHUNK_BYTES) {
+                            if pipe.write_all(chunk).is_err() {
+                                break;
+                            }
+                        }
+                    })
+                });
+        self.emit_started(Some(child.id()));
+        let (stdout, stderr) = match merged {
+            Some(merged) => (
+                self.reader("stdout", Some(merged)),
+                self.reader("stderr", None::<std::process::ChildStderr>),
+            ),
+            None => (
+                self.reader("stdout", child.stdout.take()),
+                self.reader("stderr", child.stderr.take()),
+            ),
+        };
+        let ((status, usage), timed_out) = self.wait(|kill| {
+            if kill {
+                let _ = child.kill();
+            }
+            limits::try_wait(&mut child)
+        })?;
+        if let Some(stdin) = stdin {
+            let _ = stdin.join();
+        }
+        Ok(Outcome {
+            exit_code: status.code(),
+            signal: limits::exit_signal(&status),
+            success: status.success(),
+            timed_out,
+            stdout: stdout.join().unwrap_or_default(),
+            stderr: stderr.join().unwrap_or_default(),
+            result_fd: result_fd.map(|reader| reader.join().unwrap_or_default()),
+            usage,
+            screen: None,
+        })
+    }
+
+    // stdout and stderr share the terminal, so everything arrives on the "pty" stream
+    fn pty(&self, pair: PtyPair, launch: &Launch, options: &RunOptions) -> Result<Outcome, String> {
+        let mut child = pair
+            .slave
+            .spawn_command(launch.pty_command(options))
+            .map_err(|e| self.spawn_failed(e))?;
+        // The reader only sees EOF once no slave handle is left open
+        drop(pair.slave);
+        self.emit_started(child.process_id());
+        let source = pair.master.try_clone_reader().ok();
+        let screen = options
+            .snapshot_tui
+            .then(|| Arc::new(Mutex::new(Screen::new(PTY_ROWS, PTY_COLS))));
+        let output = match &screen {
+            Some(screen) => self.reader("pty", source.map(|s| Tee::new(s, screen.clone()))),
+            None => self.reader("pty", source),
+        };
+        let (status, timed_out) = self.wait(|kill| {
+            if kill {
+                let _ = child.kill();
+            }
+            child.try_wait()
+        })?;
+        let output = output.join().unwrap_or_default();
+        drop(pair.master);
+        Ok(Outcome {
+            exit_code: status.signal().is_none().then(|| status.exit_code() as i32),
+            signal: None,
+            success: status.success(),
+            timed_out,
+            stdout: output,
+            stderr: Captured::default(),
+            result_fd: None,
+            // portable-pty reaps the child itself, so there's no rusage to read
+            usage: None,
+            // Drawn from the raw pty bytes, so secrets are taken out here like in the output
+            screen: screen.and_then(|s| {
+                s.lock()
+                    .ok()
+                    .map(|s| secrets::redact(&s.text(), &self.redact))
+            }),
+        })
+    }
+}
+
+// The child gets the write end as fd 3; ours is closed by the caller once it has spawned
+#[cfg(unix)]
+fn attach_result_fd(
+    command: &mut Command,
+) -> Result<(std::io::PipeReader, std::io::PipeWriter), String> {
+    use std::os::fd::AsRawFd;
+    use std::os::unix::process::CommandExt;
+
+    let (reader, writer) = std::io::pipe().map_err(|e| e.to_string())?;
+    let fd = writer.as_raw_fd();
+    // SAFETY: the hook runs in the forked child and only calls dup2 and fcntl
+    unsafe {
+        command.pre_exec(move || {
+            // dup2 onto itself would keep close-on-exec, so that flag is cleared by hand
+            let result = if fd == RESULT_FD {
+                libc::fcntl(fd, libc::F_SETFD, 0)
+            } else {
+                libc::dup2(fd, RESULT_FD)
+            };
+            if result < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    Ok((reader, writer))
+}
+
+#[cfg(not(unix))]
+fn attach_result_fd(
+    _command: &mut Command,
+) -> Result<(std::io::PipeReader, std::io::PipeWriter), String> {
+    Err("capture_fd3 is not supported on this platform".to_string())
+}
+
+#[cfg(unix)]
+fn apply_umask(command: &mut Command, mask: u32) -> Result<(), String> {
+    use std::os::unix::process::CommandExt;
+
+    // SAFETY: the hook runs in the forked child and only calls umask
+    unsafe {
+        command.pre_exec(move || {
+            libc::umask(mask as libc::mode_t);
+            Ok(())
+        });
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn apply_umask(_command: &mut Command, _mask: u32) -> Result<(), String> {
+    Err(format!(
+        "{}: umask is not supported on this platform",
+        UMASK_UNSUPPORTED_ERROR
+    ))
+}
+
+// The mask scripts inherit, read without changing it where the platform allows
+#[cfg(target_os = "linux")]
+fn app_umask() -> Option<u32> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let value = status
+        .lines()
+        .find_map(|line| line.strip_prefix("Umask:"))?;
+    u32::from_str_radix(value.trim(), 8).ok()
+}
+
+// umask can only be read by setting it, so the old value goes straight back
+#[cfg(all(unix, not(target_os = "linux")))]
+fn app_umask() -> Option<u32> {
+    // SAFETY: umask can't fail; the original mask is restored right away
+    let mask = unsafe {
+        let mask = libc::umask(0o022);
+        libc::umask(mask);
+        mask
+    };
+    Some(mask as u32)
+}
+
+#[cfg(not(unix))]
+fn app_umask() -> Option<u32> {
+    None
+}
+
+// The last marker line's path, relative ones against the run's working directory
+fn marked_output_file(app: &AppHandle, stdout: &str, cwd: Option<&Path>) -> Option<PathBuf> {
+    let pattern = settings::get::<String>(app, OUTPUT_MARKER_KEY)
+        .unwrap_or_else(|| DEFAULT_OUTPUT_MARKER.to_string());
+    let marker = regex::Regex::new(&pattern).ok()?;
+    let path = stdout.lines().rev().find_map(|line| {
+        let captures = marker.captures(line)?;
+        let path = captures.get(1).or_else(|| captures.get(0))?.as_str().trim();
+        (!path.is_empty()).then(|| settings::expand_path(path))
+    })?;
+    match cwd {
+        Some(dir) if path.is_relative() => Some(dir.join(path)),
+        _ => Some(path),
+    }
+}
+
+// Blank output means the script didn't report anything; invalid JSON only warns
+fn parse_result_fd(
+    app: &AppHandle,
+    run_id: &str,
+    bytes: Option<Vec<u8>>,
+    warnings: &mut Vec<String>,
+) -> Option<serde_json::Value> {
+    let bytes = bytes.filter(|bytes| !bytes.iter().all(u8::is_ascii_whitespace))?;
+    match serde_json::from_slice(&bytes) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            let message = format!(
+                "Ignored what the script wrote to fd 3, it isn't JSON: {}",
+                e
+            );
+            warn(app, run_id, message, warnings);
+            None
+        }
+    }
+}
+
+fn open_pty() -> Result<PtyPair, String> {
+    native_pty_system()
+        .openpty(PtySize {
+            rows: PTY_ROWS,
+            cols: PTY_COLS,
+            pixel_width: 0,
+            pixel_height: 0,
+        })
+        .map_err(|e| e.to_string())
+}
+
+// How a helper command run through `stream_command` ended; stdout was already streamed
+pub struct CommandOutput {
+    pub success: bool,
+    pub stderr: String,
+}
+
+// Runs a helper program such as git through the same started/output/exited events as
+// scripts, without recording history or notifying; `label` stands in for the script path
+pub fn stream_command(
+    app: &AppHandle,
+    run_id: &str,
+    label: &str,
+    argv: Vec<OsString>,
+    options: &RunOptions,
+) -> Result<CommandOutput, String> {
+    let started_at = history::now_ms();
+    let started = Instant::now();
+    let run = Run {
+        app,
+        path: label,
+        run_id,
+        started_at,
+        decoder: OutputDecoder::Utf8Lossy,
+        deadline: options
+            .timeout_secs
+            .filter(|secs| *secs > 0)
+            .map(|secs| started + Duration::from_secs(secs)),
+        hide_progress_lines: false,
+        collapse_repeats: false,
+        ring_buffer_bytes: None,
+        trace: false,
+        redact: Arc::default(),
+        in_flight: None,
+        metrics: false,
+        batch_id: None,
+        matchers: None,
+        filter: None,
+        log_budget: Arc::new(LogBudget::unlimited()),
+    };
+    let launch = Launch {
+        argv,
+        cwd: options.cwd.as_deref().map(PathBuf::from),
+        env: Vec::new(),
+    };
+    let outcome = run.piped(&launch, options);
+    app.state::<ActiveRuns>().remove(run_id);
+    livestream::finished(run_id);
+    let outcome = outcome?;
+    let _ = app.emit(
+        "script-exited",
+        ExitedEvent {
+            run_id,
+            path: label,
+            exit_code: outcome.exit_code,
+            duration_ms: started.elapsed().as_millis() as u64,
+            timed_out: outcome.timed_out,
+            cancelled_before_start: false,
+        },
+    );
+    Ok(CommandOutput {
+        success: outcome.success && !outcome.timed_out,
+        stderr: outcome.stderr.text,
+    })
+}
+
+fn warn(app: &AppHandle, run_id: &str, message: String, warnings: &mut Vec<String>) {
+    let _ = app.emit(
+        "script-warning",
+        WarningEvent {
+            run_id,
+            message: &message,
+        },
+    );
+    warnings.push(message);
+}
+
+// Only shebang scripts are exec'd directly; the rest go through bash and don't need the bit
+fn ensure_executable(app: &AppHandle, script: &Path) -> Result<(), String> {
+    if !has_shebang(script) || scripts::is_executable(script) {
+        return Ok(());
+    }
+    if settings::get::<bool>(app, AUTO_CHMOD_KEY).unwrap_or(false) {
+        return scripts::set_user_executable(script)
+            .map_err(|e| format!("Failed to make {} executable: {}", script.display(), e));
+    }
+    Err(format!(
+        "{}: {} is not executable",
+        NOT_EXECUTABLE_ERROR,
+        script.display()
+    ))
+}
+
+// The shell command line for a traced run: the shebang's own, or bash without one
+fn trace_shell(script: &Path) -> Result<Vec<OsString>, String> {
+    if !has_shebang(script) {
+        return Ok(vec!["bash".into()]);
+    }
+    let mut line = String::new();
+    BufReader::new(File::open(script).map_err(|e| e.to_string())?)
+        .read_line(&mut line)
+        .map_err(|e| e.to_string())?;
+    let line = line.trim_end();
+    let interpreter = index::interpreter(Some(line));
+    if !TRACE_SHELLS.contains(&interpreter.as_str()) {
+        return Err(format!(
+            "{}: {} runs under {}, and only bash and sh-family shells can be traced",
+            TRACE_UNSUPPORTED_ERROR,
+            script.display(),
+            interpreter
+        ));
+    }
+    Ok(line
+        .trim_start_matches("#!")
+        .split_whitespace()
+        .map(OsString::from)
+        .collect())
+}
+
+fn check_writable(app: &AppHandle, script: &Path) -> Result<(), String> {
+    if !settings::get::<bool>(app, BLOCK_WRITABLE_KEY).unwrap_or(false)
+        || !scripts::is_writable_by_others(script)
+    {
+        return Ok(());
+    }
+    Err(format!(
+        "{}: {} can be modified by other users; remove group and world write permission to run it",
+        WRITABLE_BY_OTHERS_ERROR,
+        script.display()
+    ))
+}
+
+fn check_sourceable(script: &Path) -> Result<(), String> {
+    let Ok(content) = fs::read_to_string(script) else {
+        return Ok(());
+    };
+    if !metadata::parse(content.lines()).sourceable {
+        return Ok(());
+    }
+    Err(format!(
+        "{}: {} is meant to be sourced, so running it wouldn't change your shell; use `source {}` in a terminal instead",
+        SOURCE_ONLY_ERROR,
+        script.display(),
+        script.display()
+    ))
+}
+
+// Quarantined scripts get a warning, or lose the attribute when the user opted into that
+fn check_quarantine(app: &AppHandle, run_id: &str, script: &Path, warnings: &mut Vec<String>) {
+    if !scripts::is_quarantined(script) {
+        return;
+    }
+    if !settings::get::<bool>(app, AUTO_STRIP_QUARANTINE_KEY).unwrap_or(false) {
+        let message = format!(
+            "{} is quarantined and macOS may block it; remove the quarantine to run it without prompts",
+            script.display()
+        );
+        return warn(app, run_id, message, warnings);
+    }
+    match scripts::strip_quarantine(script) {
+        Ok(true) => {
+            let _ = audit::record(
+                app,
+                "quarantine-removed",
+                json!({ "path": script.to_string_lossy(), "runId": run_id }),
+            );
+        }
+        Ok(false) => {}
+        Err(e) => {
+            let message = format!(
+                "Failed to remove quarantine from {}: {}",
+                script.display(),
+                e
+            );
+            warn(app, run_id, message, warnings);
+        }
+    }
+}
+
+// Runs without a trigger were started from the app's own UI
+fn audit_run(
+    app: &AppHandle,
+    action: &str,
+    run_id: &str,
+    path: &str,
+    options: &RunOptions,
+    extra: serde_json::Value,
+) {
+    let mut details = json!({
+        "runId": run_id,
+        "path": path,
+        "args": options.args,
+        "trigger": options.trigger.as_deref().unwrap_or("manual"),
+        "user": audit::current_user(),
+    });
+    if options.dry_run {
+        details["dryRun"] = json!(true);
+    }
+    if let (Some(details), serde_json::Value::Object(extra)) = (details.as_object_mut(), extra) {
+        details.extend(extra);
+    }
+    let _ = audit::record(app, action, details);
+}
+
+pub fn execute(app: &AppHandle, path: &str, options: &RunOptions) -> Result<ScriptResult, String> {
+    execute_with_id(app, next_run_id(), path, options)
+}
+
+// For callers that need to hand out the run id before the run finishes. Each retry gets a
+// fresh run id, announced by script-retrying; only the final result notifies.
+pub fn execute_with_id(
+    app: &AppHandle,
+    run_id: String,
+    path: &str,
+    options: &RunOptions,
+) -> Result<ScriptResult, String> {
+    let retries = options.retries.unwrap_or(0);
+    let delay_ms = options.retry_delay_ms.unwrap_or(DEFAULT_RETRY_DELAY_MS);
+    let mut run_ids = vec![run_id.clone()];
+    // A run that would be refused is refused before the countdown announces it
+    gates(app, &run_id, path, options)?;
+    // Once per run rather than per attempt, so retries don't count down again
+    countdown::wait(app, &run_id, path, options)?;
+    let mut result = execute_attempt(app, run_id, path, options)?;
+    while result.attempts <= retries && result.should_retry() {
+        let attempt = result.attempts + 1;
+        let next_run_id = next_run_id();
+        log::info!(
+            "Retrying {} after exit {:?} ({}/{})",
+            path,
+            result.exit_code,
+            attempt - 1,
+            retries
+        );
+        let _ = app.emit(
+            "script-retrying",
+            RetryingEvent {
+                run_id: &result.run_id,
+                next_run_id: &next_run_id,
+                path,
+                exit_code: result.exit_code,
+                retry: attempt - 1,
+                retries,
+                delay_ms,
+            },
+        );
+        thread::sleep(Duration::from_millis(delay_ms));
+        run_ids.push(next_run_id.clone());
+        result = ScriptResult {
+            attempts: attempt,
+            ..execute_attempt(app, next_run_id, path, options)?
+        };
+    }
+    runwindows::runs_finished(app, &run_ids);
+    if options.batch_id.is_none() && !options.dry_run {
+        notifications::notify_run_finished(app, &result, options.notify_with_output);
+    }
+    Ok(result)
+}
+
+// Whether the script may start at all; returns its resolved path
+fn gates(
+    app: &AppHandle,
+    run_id: &str,
+    path: &str,
+    options: &RunOptions,
+) -> Result<PathBuf, String> {
+    readonly::guard()?;
+    roots::ensure_available(path)?;
+    let canonical = scripts::allowed_script(app, path)?;
+    danger::check(app, path, options)?;
+    conditions::check(app, run_id, path, options)?;
+    Ok(canonical)
+}
+
+fn execute_attempt(
+    app: &AppHandle,
+    run_id: String,
+    path: &str,
+    options: &RunOptions,
+) -> Result<ScriptResult, String> {
+    let script = Path::new(path);
+    // Checked again, since a countdown or retry delay can pass in between. Launched by its
+    // resolved path, so a symlink swapped after the check can't redirect the run.
+    let canonical = gates(app, &run_id, path, options)?;
+    let slot = (!options.dry_run)
+        .then(|| concurrency::acquire(app, path, options))
+        .transpose()?;
+    // A staged run launches the shadow copy, but keeps the script's path, settings and history
+    let shadow = options
+        .staged
+        .then(|| staging::require(script))
+        .transpose()?;
+    let source = shadow.as_deref().unwrap_or(&canonical);
+    let content = snapshots::read(&canonical)?;
+    let content_change = snapshots::check(app, path, &content, options.confirmed)?;
+    let content_hash = match shadow.as_deref() {
+        Some(shadow) => snapshots::read(shadow).ok().map(|c| c.hash().to_string()),
+        None => Some(content.hash().to_string()),
+    };
+    let encoding = options
+        .encoding
+        .clone()
+        .or_else(|| settings::get::<String>(app, OUTPUT_ENCODING_KEY));
+    let decoder = OutputDecoder::from_label(encoding.as_deref())?;
+    ensure_executable(app, script)?;
+    check_writable(app, script)?;
+    check_sourceable(source)?;
+    let trace_shell = options.trace.then(|| trace_shell(source)).transpose()?;
+    if options.check_dependencies {
+        deps::require(script)?;
+    }
+
+    if options.run_in_pty && !options.limits().is_empty() {
+        return Err("Resource limits can't be combined with run_in_pty".to_string());
+    }
+    if options.run_in_pty && options.capture_fd3 {
+        return Err("capture_fd3 can't be combined with run_in_pty".to_string());
+    }
+    if options.snapshot_tui && !options.run_in_pty {
+        return Err("snapshot_tui needs run_in_pty".to_string());
+    }
+    if options.run_in_pty && options.clipboard_stdin.is_some() {
+        return Err("Clipboard input can't be combined with run_in_pty".to_string());
+    }
+    if let Some(mask) = options.umask {
+        if cfg!(not(unix)) {
+            return Err(format!(
+                "{}: umask is not supported on this platform",
+                UMASK_UNSUPPORTED_ERROR
+            ));
+        }
+        if mask > 0o777 {
+            return Err(format!("Invalid umask {:o}, it must be 0 to 777", mask));
+        }
+        if options.run_in_pty {
+            return Err("umask can't be combined with run_in_pty".to_string());
+        }
+    }
+    // Resolved before anything starts so a missing secret fails the run up front
+    let secret_env = secrets::script_env(app, path)?;
+    let mut warnings = Vec::new();
+    if content_change.is_some() {
+        let message = format!("{} changed since its last successful run", path);
+        warn(app, &run_id, message, &mut warnings);
+    }
+    check_quarantine(app, &run_id, script, &mut warnings);
+    // Groups go underneath the script's own variables, which always win
+    let groups = envgroups::resolve(app, path);
+    for name in &groups.missing {
+        let message = format!("Environment group {} no longer exists, skipping it", name);
+        warn(app, &run_id, message, &mut warnings);
+    }
+    let rerun = rerun_spec(options);
+    let merged;
+    let options = if groups.vars.is_empty() {
+        options
+    } else {
+        let mut env = groups.vars;
+        env.extend(options.env.clone());
+        merged = RunOptions {
+            env,
+            ..options.clone()
+        };
+        &merged
+    };
+    let pty = match options.run_in_pty.then(open_pty) {
+        Some(Ok(pair)) => Some(pair),
+        Some(Err(error)) => {
+            let message = format!(
+                "Could not allocate a terminal, running without one: {}",
+                error
+            );
+            warn(app, &run_id, message, &mut warnings);
+            None
+        }
+        None => None,
+    };
+
+    let started_at = history::now_ms();
+    let started = Instant::now();
+    let run = Run {
+        app,
+        path,
+        run_id: &run_id,
+        started_at,
+        decoder,
+        deadline: options
+            .timeout_secs
+            .filter(|secs| *secs > 0)
+            .map(|secs| started + Duration::from_secs(secs)),
+        hide_progress_lines: options.hide_progress_lines,
+        collapse_repeats: options.collapse_repeats,
+        ring_buffer_bytes: options
+            .ring_buffer_kb
+            .filter(|kb| *kb > 0)
+            .map(|kb| kb * 1024),
+        trace: options.trace,
+        in_flight: Some(InFlightRun {
+            run_id: run_id.clone(),
+            path: path.to_string(),
+            started_at,
+            pid: None,
+            args: options.args.clone(),
+            trigger: options.trigger.clone(),
+        }),
+        metrics: options.metrics_enabled,
+        batch_id: options.batch_id.clone(),
+        matchers: matchers::compile(app, path),
+        filter: outputfilter::compile(app, path),
+        log_budget: Arc::new(if options.dry_run {
+            LogBudget::new(logcap::for_script(app, path))
+        } else {
+            LogBudget::with_logs(
+                logcap::for_script(app, path),
+                &history::log_files(app, &run_id),
+            )
+        }),
+        redact: secret_env.iter().map(|(_, value)| value.clone()).collect(),
+    };
+    let mut launch = Launch::new(source, options);
+    // A symlinked script still runs from the folder it's listed in
+    if options.cwd.is_none() && shadow.is_none() {
+        launch.cwd = script.parent().map(Path::to_path_buf);
+    }
+    if let Some(shell) = trace_shell {
+        launch.trace(source, shell);
+    }
+    launch.env.extend(
+        secret_env
+            .into_iter()
+            .map(|(var, value)| (var.into(), value.into())),
+    );
+    // The filled-in copy runs in place of the script, but from the script's own directory
+    let filled = placeholders::prepare(&run_id, source, &options.placeholders, options.strict)?;
+    let launched = match &filled {
+        Some(filled) => {
+            for arg in launch
+                .argv
+                .iter_mut()
+                .filter(|arg| Path::new(arg) == source)
+            {
+                *arg = filled.script.clone().into();
+            }
+            filled.script.as_path()
+        }
+        None => source,
+    };
+    let sandbox = if options.sandbox {
+        let sandbox = sandbox::prepare(&run_id, launched, &mut launch.argv)?;
+        launch.cwd = Some(sandbox.dir.clone());
+        Some(sandbox)
+    } else {
+        None
+    };
+    let askpass = options
+        .gui_sudo
+        .then(|| askpass::prepare(&run_id))
+        .transpose()?;
+    if let Some(askpass) = &askpass {
+        launch
+            .env
+            .push(("SUDO_ASKPASS".into(), askpass.helper.clone().into()));
+        if !askpass::has_prompt_tool() {
+            let message =
+                "No password dialog (osascript, zenity or kdialog) found, sudo will fail to prompt";
+            warn(app, &run_id, message.to_string(), &mut warnings);
+        }
+    }
+    let invocation = options
+        .capture_command
+        .or_else(|| settings::get(app, CAPTURE_COMMAND_KEY))
+        .unwrap_or(false)
+        .then(|| launch.invocation(launched, options, &run.redact));
+    audit_run(app, "run-started", &run_id, path, options, json!({}));
+    let outcome = match pty {
+        Some(pair) => run.pty(pair, &launch, options),
+        None => run.piped(&launch, options),
+    };
+    // Dropping the sandbox, askpass helper and filled-in copy removes their temp dirs
+    let sandbox = sandbox.map(|s| s.mode);
+    drop(askpass);
+    drop(filled);
+    recovery::run_finished(app, &run_id);
+    livestream::finished(&run_id);
+    let cancelled = app
+        .state::<ActiveRuns>()
+        .remove(&run_id)
+        .is_some_and(|run| run.cancelled);
+    if let Err(error) = &outcome {
+        audit_run(
+            app,
+            "run-failed",
+            &run_id,
+            path,
+            options,
+            json!({ "error": error }),
+        );
+    }
+
+    let Outcome {
+        exit_code,
+        signal,
+        success,
+        timed_out,
+        stdout,
+        stderr,
+        result_fd,
+        usage,
+        screen,
+    } = outcome?;
+    // Freed before script-exited, so the window's queue can start its next run right away
+    drop(slot);
+    let duration_ms = started.elapsed().as_millis() as u64;
+    let _ = app.emit(
+        "script-exited",
+        ExitedEvent {
+            run_id: &run_id,
+            path,
+            exit_code,
+            duration_ms,
+            timed_out,
+            cancelled_before_start: false,
+        },
+    );
+
+    audit_run(
+        app,
+        "run-finished",
+        &run_id,
+        path,
+        options,
+        json!({
+            "exitCode": exit_code,
+            "success": success && !timed_out,
+            "timedOut": timed_out,
+            "durationMs": duration_ms,
+        }),
+    );
+
+    let limit_exceeded = limits::exceeded(
+        options.limits(),
+        signal,
+        timed_out,
+        cancelled,
+        success,
+        &stderr.text,
+    );
+    let matcher_hits = run.matchers.as_ref().map(|m| m.hits()).unwrap_or_default();
+    let marked_failed = run.matchers.as_ref().is_some_and(|m| m.failed());
+    if marked_failed {
+        let message = format!("An output matcher marked run {} as failed", run_id);
+        warn(app, &run_id, message, &mut warnings);
+    }
+    let success = success && !timed_out && !marked_failed;
+    let log_truncated_at_bytes = run.log_budget.truncated_at();
+    // The cap's warning event went out while the run was streaming
+    if let Some(max) = log_truncated_at_bytes {
+        warnings.push(format!(
+            "Output passed {} bytes; the rest isn't logged",
+            max
+        ));
+    }
+    if run.log_budget.hit_hard_cap() {
+        let message = format!(
+            "Run {} was cancelled for passing its hard output cap",
+            run_id
+        );
+        warn(app, &run_id, message, &mut warnings);
+    }
+    let structured_result = parse_result_fd(app, &run_id, result_fd, &mut warnings);
+    let opened_output = if options.auto_open_output && success && exit_code == Some(0) {
+        match marked_output_file(app, &stdout.text, launch.cwd.as_deref()) {
+            Some(file) if file.exists() => {
+                let file = file.to_string_lossy().to_string();
+                match external::open_with_default_app(&file) {
+                    Ok(()) => Some(file),
+                    Err(e) => {
+                        let message = format!("Couldn't open {}: {}", file, e);
+                        warn(app, &run_id, message, &mut warnings);
+                        None
+                    }
+                }
+            }
+            Some(file) => {
+                let message = format!("The output file {} doesn't exist", file.display());
+                warn(app, &run_id, message, &mut warnings);
+                None
+            }
+            None => None,
+        }
+    } else {
+        None
+    };
+    // The script as it is now, so an edit it made to itself doesn't count as a change next time
+    let after = snapshots::read(script).ok();
+    let modified_self = after
+        .as_ref()
+        .is_some_and(|after| after.hash() != content.hash());
+    if modified_self {
+        let message = format!("{} modified itself while running", path);
+        warn(app, &run_id, message, &mut warnings);
+    }
+    if success && !options.dry_run {
+        let _ = snapshots::save(
+            app,
+            path,
+            after.filter(|_| modified_self).unwrap_or(content),
+        );
+    }
+    if !options.dry_run {
+        let _ = history::record(
+            app,
+            HistoryEntry {
+                run_id: run_id.clone(),
+                path: path.to_string(),
+                started_at,
+                duration_ms,
+                exit_code,
+                success,
+                timed_out,
+                cancelled,
+                args: options.args.clone(),
+                profile: profiles::active_name(app),
+                label: history::normalize_label(options.label.as_deref()),
+                trigger: options.trigger.clone(),
+                external: false,
+                aborted: false,
+                resource_usage: usage,
+                env_groups: groups.applied,
+                matcher_hits: matcher_hits.clone(),
+                batch_id: options.batch_id.clone(),
+                log_truncated_at_bytes,
+                condition_not_met: None,
+                staged: options.staged,
+                rerun: Some(rerun),
+                source: Some(triggers::source(options)),
+                content_hash,
+                clipboard_stdin_bytes: options.clipboard_stdin.as_ref().map(|s| s.len() as u64),
+                countdown_cancelled: false,
+            },
+        );
+        history::index_output(app, &run_id);
+        // Keeps the tray's "Run again" item pointing at this script
+        let _ = tray::rebuild_menu(app);
+    }
+
+    let result = ScriptResult {
+        run_id,
+        path: path.to_string(),
+        success,
+        exit_code,
+        stdout: stdout.text.trim_end().to_string(),
+        stderr: stderr.text.trim_end().to_string(),
+        duration_ms,
+        timed_out,
+        lossy_output: stdout.lossy || stderr.lossy,
+        warnings,
+        limit_exceeded,
+        sandbox,
+        output_truncated: stdout.truncated || stderr.truncated,
+        content_change,
+        modified_self,
+        resource_usage: usage,
+        trace: options.trace.then(|| {
+            format!("{}{}", stdout.trace, stderr.trace)
+                .trim_end()
+                .to_string()
+        }),
+        structured_result,
+        cancelled,
+        attempts: 1,
+        matcher_hits,
+        opened_output,
+        umask: options.umask.or_else(app_umask),
+        log_truncated_at_bytes,
+        invocation,
+        screen_snapshot: screen,
+    };
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn run_script(
+    app: AppHandle,
+    path: String,
+    options: Option<RunOptions>,
+) -> Result<ScriptResult, String> {
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || execute(&app, &path, &options))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// Asks for a folder, then runs the script in it with the folder as $1
+#[tauri::command]
+pub async fn run_script_in_picked_dir(
+    app: AppHandle,
+    path: String,
+) -> Result<ScriptResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let picked = app
+            .dialog()
+            .file()
+            .set_title("Run in folder")
+            .blocking_pick_folder()
+            .ok_or_else(|| "cancelled".to_string())?;
+        let dir = picked.into_path().map_err(|e| e.to_string())?;
+        let dir = dir.to_string_lossy().to_string();
+        let options = RunOptions {
+            args: vec![dir.clone()],
+            cwd: Some(dir),
+            env: settings::script_env(&app, &path).into_iter().collect(),
+            ..RunOptions::default()
+        };
+        execute(&app, &path, &options)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Pipes the clipboard's text into the script's stdin, for scripts marked `# @accepts: stdin`
+#[tauri::command]
+pub async fn run_with_clipboard(
+    app: AppHandle,
+    script_id: String,
+    args: Option<Vec<String>>,
+) -> Result<ScriptResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let text = external::read_clipboard()?;
+        if text.is_empty() {
+            return Err(format!(
+                "{}: the clipboard holds no text",
+                CLIPBOARD_EMPTY_ERROR
+            ));
+        }
+        let options = RunOptions {
+            args: args.unwrap_or_default(),
+            env: settings::script_env(&app, &script_id).into_iter().collect(),
+            clipboard_stdin: Some(text.into()),
+            ..RunOptions::default()
+        };
+        execute(&app, &script_id, &options)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Preview {
+    pub output: String,
+    // Handed back to confirm_after_preview to start the real run
+    pub token: String,
+}
+
+// The arguments saved for the script in the window, split the way bash splits them there
+fn saved_args(app: &AppHandle, path: &str) -> Result<Vec<String>, String> {
+    let args = settings::script_data(app, path)
+        .and_then(|data| data.get("args")?.as_str().map(str::to_string))
+        .unwrap_or_default();
+    shell_words::split(&args).map_err(|e| format!("Invalid saved arguments for {}: {}", path, e))
+}
+
+fn preview_token() -> Result<String, String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|e| e.to_string())?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// Runs the script with its saved arguments plus its `# @dry-run-flag` ones and returns stdout
+// and stderr as they were interleaved, so the UI can show what a real run would change. The
+// token starts that real run through confirm_after_preview. `confirmed` is needed for
+// scripts marked dangerous, as for run_script.
+#[tauri::command]
+pub async fn preview_changes(
+    app: AppHandle,
+    path: String,
+    confirmed: Option<bool>,
+) -> Result<Preview, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let canonical = scripts::allowed_script(&app, &path)?;
+        let content = fs::read_to_string(&canonical).map_err(|e| e.to_string())?;
+        let Some(flag) = metadata::parse(content.lines()).dry_run_flag else {
+            return Err(format!(
+                "{}: {} doesn't declare a `# @dry-run-flag`",
+                NO_DRY_RUN_FLAG_ERROR, path
+            ));
+        };
+        let mut args = saved_args(&app, &path)?;
+        args.extend(flag.split_whitespace().map(str::to_string));
+        let options = RunOptions {
+            args,
+            env: settings::script_env(&app, &path).into_iter().collect(),
+            merge_stderr: true,
+            trigger_detail: Some(format!("Dry run ({})", flag)),
+            confirmed: confirmed.unwrap_or(false),
+            dry_run: true,
+            ..RunOptions::default()
+        };
+        let result = execute(&app, &path, &options)?;
+        if !result.success {
+            let status = result
+                .exit_code
+                .map(|code| format!("exited with code {}", code))
+                .unwrap_or_else(|| "was stopped".to_string());
+            return Err(format!("The dry run {}:\n{}", status, result.stdout));
+        }
+        let token = preview_token()?;
+        if let Ok(mut previews) = PREVIEWS.lock() {
+            // Only the latest preview of a script can be confirmed
+            previews.retain(|_, (previewed, _)| *previewed != path);
+            previews.insert(token.clone(), (path, index::hash_bytes(content.as_bytes())));
+        }
+        Ok(Preview {
+            output: result.stdout,
+            token,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// The real run after a preview, without the dry-run flag. Refused unless the token came from
+// the latest preview of this script and the script hasn't changed since; each token works once.
+// A dangerous script needs `confirmed` here too.
+#[tauri::command]
+pub async fn confirm_after_preview(
+    app: AppHandle,
+    path: String,
+    token: String,
+    confirmed: Option<bool>,
+) -> Result<ScriptResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let previewed = PREVIEWS
+            .lock()
+            .ok()
+            .and_then(|mut previews| previews.remove(&token));
+        let unchanged = previewed.is_some_and(|(previewed, hash)| {
+            previewed == path
+                && fs::read(&path).is_ok_and(|content| index::hash_bytes(&content) == hash)
+        });
+        if !unchanged {
+            return Err(format!(
+                "{}: preview {} again before running it",
+                PREVIEW_EXPIRED_ERROR, path
+            ));
+        }
+        let options = RunOptions {
+            args: saved_args(&app, &path)?,
+            env: settings::script_env(&app, &path).into_iter().collect(),
+            trigger_detail: Some("Confirmed after a dry run".to_string()),
+            confirmed: confirmed.unwrap_or(false),
+            ..RunOptions::default()
+        };
+        execute(&app, &path, &options)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Taken before environment groups are merged in, since a rerun resolves them again
+fn rerun_spec(options: &RunOptions) -> RerunSpec {
+    let mut env_keys: Vec<String> = options.env.keys().cloned().collect();
+    env_keys.sort();
+    RerunSpec {
+        env_keys,
+        cwd: options.cwd.clone(),
+        timeout_secs: options.timeout_secs,
+        encoding: options.encoding.clone(),
+        run_in_pty: options.run_in_pty,
+        sandbox: options.sandbox,
+        merge_stderr: options.merge_stderr,
+        gui_sudo: options.gui_sudo,
+        umask: options.umask,
+        placeholders: options.placeholders.clone(),
+        trace: options.trace,
+    }
+}
+
+// Starts the run a history entry describes, with the same args, label and options. Entries
+// recorded before options were kept run with just their args, and say so in the warnings.
+#[tauri::command]
+pub async fn rerun_history_entry(app: AppHandle, run_id: String) -> Result<ScriptResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let entry =
+            history::find(&app, &run_id).ok_or_else(|| format!("No run {} in history", run_id))?;
+        if entry.path.starts_with(workflows::HISTORY_PREFIX) {
+            return Err("Workflow runs can't be re-run from their history entry".to_string());
+        }
+        if !Path::new(&entry.path).is_file() {
+            return Err(format!("{} no longer exists", entry.path));
+        }
+        let mut warnings = Vec::new();
+        let mut options = RunOptions {
+            args: entry.args,
+            label: entry.label,
+            ..RunOptions::default()
+        };
+        match entry.rerun {
+            Some(spec) => {
+                let saved: HashMap<String, String> = settings::script_env(&app, &entry.path)
+                    .into_iter()
+                    .collect();
+                for key in spec.env_keys {
+                    match saved.get(&key) {
+                        Some(value) => {
+                            options.env.insert(key, value.clone());
+                        }
+                        None => warnings.push(format!(
+                            "{} is no longer in the script's environment, running without it",
+                            key
+                        )),
+                    }
+                }
+                options.cwd = spec.cwd;
+                options.timeout_secs = spec.timeout_secs;
+                options.encoding = spec.encoding;
+                options.run_in_pty = spec.run_in_pty;
+                options.sandbox = spec.sandbox;
+                options.merge_stderr = spec.merge_stderr;
+                options.gui_sudo = spec.gui_sudo;
+                options.umask = spec.umask;
+                options.placeholders = spec.placeholders;
+                options.trace = spec.trace;
+            }
+            None => warnings.push(
+                "This run predates stored run options; rerunning with its args only".to_string(),
+            ),
+        }
+        for warning in &warnings {
+            log::warn!("Rerunning {}: {}", run_id, warning);
+        }
+        let mut result = execute(&app, &entry.path, &options)?;
+        result.warnings.splice(0..0, warnings);
+        Ok(result)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// How long until Run again is accepted again, 0 when it is now
+pub fn run_again_cooldown_ms() -> u64 {
+    let since = history::now_ms().saturating_sub(LAST_RUN_AGAIN.load(Ordering::Relaxed));
+    RUN_AGAIN_COOLDOWN_MS.saturating_sub(since)
+}
+
+// The script of the most recently started in-app run, skipping workflows and terminal hand-offs
+pub fn last_script_run(app: &AppHandle) -> Option<HistoryEntry> {
+    history::last_started(app, |e| {
+        !e.external && !e.path.starts_with(workflows::HISTORY_PREFIX)
+    })
+}
+
+// Re-runs the last script with the same args and label, and its current env vars.
+// Tray and hotkey runs (`trigger` set) confirm dangerous scripts in a native dialog
+fn run_last(app: AppHandle, trigger: Option<&str>, confirmed: bool) -> Result<String, String> {
+    readonly::guard()?;
+    let now = history::now_ms();
+    if now.saturating_sub(LAST_RUN_AGAIN.swap(now, Ordering::Relaxed)) < RUN_AGAIN_COOLDOWN_MS {
+        return Err(RUN_AGAIN_TOO_SOON.to_string());
+    }
+    let last = last_script_run(&app).ok_or_else(|| "No script has been run yet".to_string())?;
+    if !Path::new(&last.path).is_file() {
+        return Err(format!("{} no longer exists", last.path));
+    }
+    if app
+        .state::<ActiveRuns>()
+        .list()
+        .iter()
+        .any(|run| run.path == last.path)
+    {
+        return Err(format!("{} is already running", last.path));
+    }
+    let mut options = RunOptions {
+        args: last.args,
+        env: settings::script_env(&app, &last.path).into_iter().collect(),
+        label: last.label,
+        trigger: trigger.map(str::to_string),
+        trigger_detail: trigger.map(|_| triggers::RUN_AGAIN_DETAIL.to_string()),
+        confirmed,
+        ..RunOptions::default()
+    };
+    let ask = trigger.is_some() && !confirmed && danger::is_dangerous(&app, &last.path);
+    if !ask {
+        danger::check(&app, &last.path, &options)?;
+    }
+    let run_id = next_run_id();
+    let id = run_id.clone();
+    thread::spawn(move || {
+        if ask {
+            if !danger::confirm(&app, &last.path) {
+                return;
+            }
+            options.confirmed = true;
+        }
+        let _ = execute_with_id(&app, id, &last.path, &options);
+    });
+    Ok(run_id)
+}
+
+#[tauri::command]
+pub fn run_last_script(app: AppHandle, confirmed: Option<bool>) -> Result<String, String> {
+    run_last(app, None, confirmed.unwrap_or(false))
+}
+
+// Tray and hotkey entry point; failures other than the cooldown become a notification
+pub fn run_last_again(app: &AppHandle, trigger: &str) {
+    if let Err(error) = run_last(app.clone(), Some(trigger), false) {
+        if error != RUN_AGAIN_TOO_SOON {
+            notifications::show(app, "Can't run again", &error);
+        }
+    }
+}
+
+// Latest progress and ETA for a live run, for views opened after the events were sent
+#[tauri::command]
+pub fn get_run_progress(app: AppHandle, run_id: String) -> Result<Option<RunProgress>, String> {
+    Ok(app
+        .state::<ActiveRuns>()
+        .list()
+        .into_iter()
+        .find(|run| run.run_id == run_id)
+        .and_then(|run| {
+            run.progress
+                .map(|percent| RunProgress::new(&run.run_id, percent, run.started_at))
+        }))
+}
+
+// Same event a finished run sends, so the UI can drop a queued entry the same way
+pub fn emit_cancelled_before_start(app: &AppHandle, run_id: &str, path: &str) {
+    let _ = app.emit(
+        "script-exited",
+        ExitedEvent {
+            run_id,
+            path,
+            exit_code: None,
+            duration_ms: 0,
+            timed_out: false,
+            cancelled_before_start: true,
+        },
+    );
+}
+
+#[cfg(unix)]
+fn signal_group(pid: u32, signal: libc::c_int) -> bool {
+    // SAFETY: killpg only sends a signal
+    unsafe { libc::killpg(pid as libc::pid_t, signal) == 0 }
+}
+
+// SIGTERM to the run's process group, then SIGKILL once `grace_ms` passes unless it exited
+#[cfg(unix)]
+fn terminate(app: &AppHandle, run_id: &str, pid: u32, grace_ms: u64) -> Result<(), String> {
+    let signal = if grace_ms == 0 {
+        libc::SIGKILL
+    } else {
+        libc::SIGTERM
+    };
+    if !signal_group(pid, signal) {
+        return Err(format!("Failed to signal run {}", run_id));
+    }
+    if grace_ms > 0 {
+        // A paused group can't handle SIGTERM until it is continued
+        signal_group(pid, libc::SIGCONT);
+        let app = app.clone();
+        let run_id = run_id.to_string();
+        let deadline = Instant::now() + Duration::from_millis(grace_ms);
+        thread::spawn(move || {
+            // Checking the pid too, so a reused run id or pid is never killed
+            while app.state::<ActiveRuns>().pid(&run_id) == Some(pid) {
+                if Instant::now() >= deadline {
+                    signal_group(pid, libc::SIGKILL);
+                    return;
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+        });
+    }
+    Ok(())
+}
+
+// Windows has no SIGTERM to send, so the tree is ended right away
+#[cfg(not(unix))]
+fn terminate(_app: &AppHandle, run_id: &str, pid: u32, _grace_ms: u64) -> Result<(), String> {
+    let status = Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/T", "/F"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("Failed to stop run {}", run_id))
+    }
+}
+
+// `grace_ms` overrides the cancelGraceMs setting for this call; 0 kills immediately
+#[tauri::command]
+pub fn cancel_script(app: AppHandle, run_id: String, grace_ms: Option<u64>) -> Result<(), String> {
+    let pid = app
+        .state::<ActiveRuns>()
+        .pid(&run_id)
+        .ok_or_else(|| format!("Run {} is not running", run_id))?;
+    let grace_ms = grace_ms
+        .or_else(|| settings::get(&app, CANCEL_GRACE_KEY))
+        .unwrap_or(DEFAULT_CANCEL_GRACE_MS);
+    app.state::<ActiveRuns>().mark_cancelled(&run_id);
+    terminate(&app, &run_id, pid, grace_ms)
+}
+
+#[tauri::command]
+pub fn set_cancel_grace_ms(app: AppHandle, ms: u64) -> Result<(), String> {
+    settings::set(&app, CANCEL_GRACE_KEY, &ms)
+}
+
+#[tauri::command]
+pub fn set_auto_chmod(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set(&app, AUTO_CHMOD_KEY, &enabled)
+}
+
+#[tauri::command]
+pub fn set_capture_command(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set(&app, CAPTURE_COMMAND_KEY, &enabled)
+}
+
+#[tauri::command]
+pub fn set_block_writable_scripts(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set(&app, BLOCK_WRITABLE_KEY, &enabled)
+}
+
+#[tauri::command]
+pub fn set_auto_strip_quarantine(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set(&app, AUTO_STRIP_QUARANTINE_KEY, &enabled)
+}
+
+// None goes back to the default `OUTPUT: <path>` marker
+#[tauri::command]
+pub fn set_output_marker(app: AppHandle, pattern: Option<String>) -> Result<(), String> {
+    let Some(pattern) = pattern.filter(|p| !p.trim().is_empty()) else {
+        return settings::delete(&app, OUTPUT_MARKER_KEY);
+    };
+    if regex::Regex::new(&pattern).is_err() {
+        return Err(format!(
+            "Invalid output marker: {}",
+            matchers::describe_error(&pattern)
+        ));
+    }
+    settings::set(&app, OUTPUT_MARKER_KEY, &pattern)
+}
+
+#[tauri::command]
+pub fn set_output_encoding(app: AppHandle, encoding: Option<String>) -> Result<(), String> {
+    match OutputDecoder::from_label(encoding.as_deref())? {
+        OutputDecoder::Utf8Lossy => settings::delete(&app, OUTPUT_ENCODING_KEY),
+        OutputDecoder::Encoding(encoding) => {
+            settings::set(&app, OUTPUT_ENCODING_KEY, &encoding.name())
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::queue::{self, QueueStatus};
+use crate::runner::ActiveRuns;
+use crate::{history, pause, tray};
+
+// Bursts of output lines or queue moves become one runs-state event per interval
+const DEBOUNCE: Duration = Duration::from_millis(200);
+// Remaining times are refreshed this often even when nothing else changed
+const ESTIMATE_REFRESH: Duration = Duration::from_secs(5);
+
+static DIRTY: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    Running,
+    Paused,
+    // cancel_script was called and the process hasn't exited yet
+    Cancelling,
+    Queued,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunState {
+    // The queue item id while queued, the run id once started
+    pub id: String,
+    pub path: String,
+    pub name: String,
+    pub status: RunStatus,
+    pub progress: Option<f64>,
+    pub started_at: Option<u64>,
+    pub elapsed_ms: Option<u64>,
+    pub last_line: Option<String>,
+    // From the progress percentage when the script reports one, else from its usual duration
+    pub remaining_ms: Option<u64>,
+    // Position among the waiting items, 0 being next
+    pub queue_position: Option<usize>,
+    pub estimated_start_ms: Option<u64>,
+}
+
+// Everything the UI needs to draw running and queued work, so a reloaded webview can
+// pick up mid-run
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunsState {
+    pub runs: Vec<RunState>,
+    pub paused: bool,
+    pub taken_at: u64,
+}
+
+// Called wherever active runs or the queue change; the emitter thread picks it up
+pub fn changed() {
+    DIRTY.store(true, Ordering::Relaxed);
+}
+
+fn name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+// Progress is preferred; a run past its usual duration has no estimate
+fn remaining(app: &AppHandle, path: &str, progress: Option<f64>, elapsed_ms: u64) -> Option<u64> {
+    if let Some(percent) = progress.filter(|p| *p > 0.0 && *p < 100.0) {
+        return Some((elapsed_ms as f64 * (100.0 - percent) / percent) as u64);
+    }
+    history::typical_duration(app, path)
+        .and_then(|typical| typical.checked_sub(elapsed_ms))
+        .filter(|left| *left > 0)
+}
+
+// "about 2 minutes left", for the tray
+pub fn describe_remaining(ms: u64) -> String {
+    let minutes = (ms as f64 / 60_000.0).round() as u64;
+    match minutes {
+        0 => "less than a minute left".to_string(),
+        1 => "about a minute left".to_string(),
+        2..=59 => format!("about {} minutes left", minutes),
+        _ => match (minutes as f64 / 60.0).round() as u64 {
+            1 => "about an hour left".to_string(),
+            hours => format!("about {} hours left", hours),
+        },
+    }
+}
+
+pub fn snapshot(app: &AppHandle) -> Result<RunsState, String> {
+    let now = history::now_ms();
+    let paused = pause::is_paused();
+    let mut runs: Vec<RunState> = app
+        .state::<ActiveRuns>()
+        .list()
+        .into_iter()
+        .map(|run| RunState {
+            remaining_ms: remaining(
+                app,
+                &run.path,
+                run.progress,
+                now.saturating_sub(run.started_at),
+            ),
+            name: name(&run.path),
+            status: if run.cancelled {
+                RunStatus::Cancelling
+            } else if paused {
+                RunStatus::Paused
+            } else {
+                RunStatus::Running
+            },
+            progress: run.progress,
+            started_at: Some(run.started_at),
+            elapsed_ms: Some(now.saturating_sub(run.started_at)),
+            last_line: run.last_line,
+            queue_position: None,
+            estimated_start_ms: None,
+            id: run.run_id,
+            path: run.path,
+        })
+        .collect();
+    // The running queue item is already in the active runs under its run id
+    let queued = queue::get_queue(app.clone())?
+        .into_iter()
+        .filter(|item| item.status == QueueStatus::Pending);
+    runs.extend(queued.enumerate().map(|(position, item)| RunState {
+        id: item.id,
+        path: item.path,
+        name: item.name,
+        status: RunStatus::Queued,
+        progress: None,
+        started_at: None,
+        elapsed_ms: None,
+        last_line: None,
+        remaining_ms: None,
+        queue_position: Some(position),
+        estimated_start_ms: item.estimated_start_ms,
+    }));
+    Ok(RunsState {
+        runs,
+        paused,
+        taken_at: now,
+    })
+}
+
+// The runs with an estimate, e.g. "backup.sh: about 2 minutes left"; None when there are none
+fn tray_estimates(state: &RunsState) -> Option<String> {
+    let parts: Vec<String> = state
+        .runs
+        .iter()
+        .filter_map(|run| {
+            let remaining = run.remaining_ms?;
+            Some(format!("{}: {}", run.name, describe_remaining(remaining)))
+        })
+        .collect();
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
+
+pub fn start_emitter(app: &AppHandle) {
+    let app = app.clone();
+    thread::spawn(move || {
+        let mut refreshed = Instant::now();
+        // Whether the tray shows estimates, so it's only reset once they're gone
+        let mut estimating = false;
+        loop {
+            thread::sleep(DEBOUNCE);
+            let due = estimating && refreshed.elapsed() >= ESTIMATE_REFRESH;
+            if !DIRTY.swap(false, Ordering::Relaxed) && !due {
+                continue;
+            }
+            match snapshot(&app) {
+                Ok(state) => {
+                    let estimates = tray_estimates(&state);
+                    // Running scripts without an estimate still get the timer, until they finish
+                    estimating = state.runs.iter().any(|run| run.started_at.is_some());
+                    refreshed = Instant::now();
+                    tray::set_estimates(&app, estimates.as_deref());
+                    let _ = app.emit("runs-state", state);
+                }
+                Err(e) => log::warn!("Failed to build the runs state: {}", e),
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_runs_state(app: AppHandle) -> Result<RunsState, String> {
+    snapshot(&app)
+}
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{LazyLock, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, PhysicalPosition, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+
+use crate::runner::ActiveRuns;
+use crate::settings;
+
+const SETTINGS_KEY: &str = "runWindows";
+const POSITION_KEY: &str = "runWindowPosition";
+const LABEL_PREFIX: &str = "run-";
+const WIDTH: f64 = 420.0;
+const HEIGHT: f64 = 260.0;
+
+// Open run windows by run id, with the window's label
+static WINDOWS: LazyLock<Mutex<HashMap<String, String>>> = LazyLock::new(Mutex::default);
+// Where the last run window was moved to, saved when it closes
+static LAST_POSITION: Mutex<Option<PhysicalPosition<i32>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RunWindowSettings {
+    // Close the window once its run exits; off leaves it for the user to close
+    pub auto_close: bool,
+    pub close_delay_ms: u64,
+}
+
+impl Default for RunWindowSettings {
+    fn default() -> Self {
+        Self {
+            auto_close: true,
+            close_delay_ms: 5000,
+        }
+    }
+}
+
+fn window_settings(app: &AppHandle) -> RunWindowSettings {
+    settings::get(app, SETTINGS_KEY).unwrap_or_default()
+}
+
+// Labels only allow a few characters, run ids are digits and dashes anyway
+fn label(run_id: &str) -> String {
+    let id: String = run_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("{}{}", LABEL_PREFIX, id)
+}
+
+fn track(run_id: &str, label: &str) {
+    if let Ok(mut windows) = WINDOWS.lock() {
+        windows.insert(run_id.to_string(), label.to_string());
+    }
+}
+
+fn untrack(label: &str) {
+    if let Ok(mut windows) = WINDOWS.lock() {
+        windows.retain(|_, open| open != label);
+    }
+}
+
+pub fn window_for(run_id: &str) -> Option<String> {
+    WINDOWS.lock().ok()?.get(run_id).cloned()
+}
+
+fn tracked(run_ids: &[String]) -> Vec<String> {
+    WINDOWS
+        .lock()
+        .ok()
+        .map(|windows| {
+            run_ids
+                .iter()
+                .filter_map(|id| windows.get(id).cloned())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn close(app: &AppHandle, label: &str) {
+    if let Some(window) = app.get_webview_window(label) {
+        let _ = window.close();
+    }
+}
+
+// Called once a run and any retries of it are over
+pub fn runs_finished(app: &AppHandle, run_ids: &[String]) {
+    let labels = tracked(run_ids);
+    if labels.is_empty() {
+        return;
+    }
+    let prefs = window_settings(app);
+    if !prefs.auto_close {
+        return;
+    }
+    let app = app.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(prefs.close_delay_ms));
+        for label in labels {
+            close(&app, &label);
+        }
+    });
+}
+
+// On quit, so no window outlives the app's state
+pub fn close_all(app: &AppHandle) {
+    let labels: Vec<String> = WINDOWS
+        .lock()
+        .ok()
+        .map(|windows| windows.values().cloned().collect())
+        .unwrap_or_default();
+    for label in labels {
+        close(app, &label);
+    }
+}
+
+// An always-on-top window with only this run's output; the page reads the run id from the
+// `runWindow` query parameter, fetches the output so far with get_output_tail and then
+// follows run-window-output and script-exited. Opening it again for the same run focuses the
+// existing one. Returns the window's label. Async because
+// building a window from a sync command deadlocks on Windows.
+#[tauri::command]
+pub async fn open_run_window(app: AppHandle, run_id: String) -> Result<String, String> {
+    let label = label(&run_id);
+    if let Some(window) = app.get_webview_window(&label) {
+        let _ = window.show();
+        let _ = window.set_focus();
+        return Ok(label);
+    }
+    let run = app
+        .state::<ActiveRuns>()
+        .list()
+        .into_iter()
+        .find(|run| run.run_id == run_id)
+        .ok_or_else(|| format!("Run {} is not running", run_id))?;
+    let title = Path::new(&run.path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or(run.path);
+    let url = format!(
+        "index.html?runWindow={}",
+        percent_encoding::utf8_percent_encode(&run_id, percent_encoding::NON_ALPHANUMERIC)
+    );
+    let window = WebviewWindowBuilder::new(&app, &label, WebviewUrl::App(url.into()))
+        .title(title)
+        .inner_size(WIDTH, HEIGHT)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let stored = LAST_POSITION
+        .lock()
+        .ok()
+        .and_then(|position| *position)
+        .or_else(|| settings::get::<(i32, i32)>(&app, POSITION_KEY).map(PhysicalPosition::from));
+    if let Some(position) = stored {
+        let _ = window.set_position(position);
+    }
+    track(&run_id, &label);
+    let closed_app = app.clone();
+    let closed_label = label.clone();
+    window.on_window_event(move |event| match event {
+        WindowEvent::Moved(position) => {
+            if let Ok(mut last) = LAST_POSITION.lock() {
+                *last = Some(*position);
+            }
+        }
+        WindowEvent::Destroyed => {
+            untrack(&closed_label);
+            if let Some(position) = LAST_POSITION.lock().ok().and_then(|p| *p) {
+                let _ = settings::set(&closed_app, POSITION_KEY, &(position.x, position.y));
+            }
+        }
+        _ => {}
+    });
+    Ok(label)
+}
+
+#[tauri::command]
+pub fn close_run_window(app: AppHandle, run_id: String) -> Result<(), String> {
+    close(&app, &label(&run_id));
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_run_window_settings(app: AppHandle) -> Result<RunWindowSettings, String> {
+    Ok(window_settings(&app))
+}
+
+#[tauri::command]
+pub fn set_run_window_settings(app: AppHandle, prefs: RunWindowSettings) -> Result<(), String> {
+    settings::set(&app, SETTINGS_KEY, &prefs)
+}
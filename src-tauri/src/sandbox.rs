@@ -0,0 +1,174 @@
+use std::ffi::OsString;
+use std::fs::{self, DirBuilder};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+// How much isolation a sandboxed run actually got
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SandboxMode {
+    // macOS sandbox-exec: no network, writes only inside the temp dir, home unreadable
+    SandboxExec,
+    // Linux bubblewrap: same restrictions through fresh namespaces
+    Bubblewrap,
+    // No sandboxing tool was available, only the working directory is isolated
+    TempDir,
+}
+
+// A fresh working directory for one run, removed again when dropped
+pub struct Sandbox {
+    pub dir: PathBuf,
+    pub mode: SandboxMode,
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+pub fn find_program(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
+}
+
+fn sbpl_string(path: &Path) -> String {
+    format!(
+        "\"{}\"",
+        path.to_string_lossy()
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+    )
+}
+
+// Later rules win in SBPL, so the allowances come after the broad denials
+fn sandbox_exec_profile(dir: &Path, script: &Path, home: Option<&Path>) -> String {
+    let mut profile =
+        String::from("(version 1)\n(allow default)\n(deny network*)\n(deny file-write*)\n");
+    if let Some(home) = home {
+        profile.push_str(&format!(
+            "(deny file-read* (subpath {}))\n",
+            sbpl_string(home)
+        ));
+    }
+    profile.push_str(&format!(
+        "(allow file-read* (literal {}))\n(allow file-read* file-write* (subpath {}))\n\
+         (allow file-write* (literal \"/dev/null\") (literal \"/dev/tty\") (regex #\"^/dev/fd/\"))\n",
+        sbpl_string(script),
+        sbpl_string(dir)
+    ));
+    profile
+}
+
+fn bwrap_args(dir: &Path, script: &Path, home: Option<&Path>) -> Vec<OsString> {
+    let mut args: Vec<OsString> = [
+        "--ro-bind",
+        "/",
+        "/",
+        "--dev",
+        "/dev",
+        "--proc",
+        "/proc",
+        "--tmpfs",
+        "/tmp",
+    ]
+    .iter()
+    .map(OsString::from)
+    .collect();
+    if let Some(home) = home {
+        args.extend([OsString::from("--tmpfs"), home.into()]);
+    }
+    args.extend([
+        OsString::from("--ro-bind"),
+        script.into(),
+        script.into(),
+        OsString::from("--bind"),
+        dir.into(),
+        dir.into(),
+        OsString::from("--chdir"),
+        dir.into(),
+        OsString::from("--unshare-all"),
+        OsString::from("--die-with-parent"),
+        OsString::from("--"),
+    ]);
+    args
+}
+
+const DIR_ATTEMPTS: usize = 8;
+
+#[cfg(unix)]
+fn private_dir_builder() -> DirBuilder {
+    use std::os::unix::fs::DirBuilderExt;
+    let mut builder = DirBuilder::new();
+    builder.mode(0o700);
+    builder
+}
+
+#[cfg(not(unix))]
+fn private_dir_builder() -> DirBuilder {
+    DirBuilder::new()
+}
+
+// Like mkdtemp: a random name created in one step, so a path someone else put in the shared
+// temp dir beforehand (a symlink to their target, say) is never reused, bound or removed
+fn create_private_dir(run_id: &str) -> Result<PathBuf, String> {
+    // Only the system temp dir itself is resolved; the new directory can't be a link
+    let temp = std::env::temp_dir();
+    let temp = fs::canonicalize(&temp).unwrap_or(temp);
+    let builder = private_dir_builder();
+    for _ in 0..DIR_ATTEMPTS {
+        let mut bytes = [0u8; 8];
+        getrandom::getrandom(&mut bytes).map_err(|e| e.to_string())?;
+        let suffix: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        let dir = temp.join(format!("sh-runner-sandbox-{}-{}", run_id, suffix));
+        match builder.create(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("Failed to create sandbox directory: {}", e)),
+        }
+    }
+    Err("Failed to create sandbox directory: every name tried was taken".to_string())
+}
+
+// Wraps `argv` in the strongest sandbox available and creates the run's temp dir
+pub fn prepare(run_id: &str, script: &Path, argv: &mut Vec<OsString>) -> Result<Sandbox, String> {
+    // Already canonical, since both tools match rules against resolved paths
+    let dir = create_private_dir(run_id)?;
+    let resolved = fs::canonicalize(script).unwrap_or_else(|_| script.to_path_buf());
+    // Only the resolved script is mapped into the sandbox, so run it by that path
+    for arg in argv.iter_mut() {
+        if Path::new(arg) == script {
+            *arg = resolved.clone().into();
+        }
+    }
+    let script = resolved;
+    let home = dirs::home_dir().and_then(|h| fs::canonicalize(h).ok());
+
+    let mut wrapper: Vec<OsString> = Vec::new();
+    let mode = if cfg!(target_os = "macos") && Path::new("/usr/bin/sandbox-exec").is_file() {
+        wrapper.extend([
+            OsString::from("/usr/bin/sandbox-exec"),
+            OsString::from("-p"),
+            sandbox_exec_profile(&dir, &script, home.as_deref()).into(),
+        ]);
+        SandboxMode::SandboxExec
+    } else if let Some(bwrap) = cfg!(target_os = "linux")
+        .then(|| find_program("bwrap"))
+        .flatten()
+    {
+        wrapper.push(bwrap.into());
+        wrapper.extend(bwrap_args(&dir, &script, home.as_deref()));
+        SandboxMode::Bubblewrap
+    } else {
+        SandboxMode::TempDir
+    };
+    if !wrapper.is_empty() {
+        wrapper.append(argv);
+        *argv = wrapper;
+    }
+    Ok(Sandbox { dir, mode })
+}
@@ -0,0 +1,464 @@
+use std::io::{self, Read};
+use std::mem;
+use std::sync::{Arc, Mutex};
+
+// Parameters past this many in one CSI sequence are dropped
+const MAX_PARAMS: usize = 16;
+const TAB_WIDTH: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    // ESC ( and friends pick a character set; the next byte is skipped
+    Charset,
+    Csi,
+    Osc,
+    // ESC inside an OSC, normally the start of its ST terminator
+    OscEscape,
+}
+
+// A virtual terminal of a fixed size, enough of one to show what a TUI drew: cursor movement,
+// erasing, scroll regions and the alternate screen. Colors and other attributes are dropped.
+pub struct Screen {
+    rows: usize,
+    cols: usize,
+    grid: Vec<Vec<char>>,
+    row: usize,
+    col: usize,
+    // The cursor is past the last column and wraps on the next character
+    wrap_pending: bool,
+    saved: (usize, usize),
+    // Inclusive rows that scroll, set by CSI r
+    top: usize,
+    bottom: usize,
+    // The main screen while the alternate one is shown
+    main: Option<Vec<Vec<char>>>,
+    // The alternate screen as it was when the program last left it
+    last_alternate: Option<Vec<Vec<char>>>,
+    state: State,
+    params: String,
+    // Bytes of a UTF-8 character still being read
+    utf8: Vec<u8>,
+}
+
+impl Screen {
+    pub fn new(rows: u16, cols: u16) -> Self {
+        let (rows, cols) = (usize::from(rows.max(1)), usize::from(cols.max(1)));
+        Self {
+            rows,
+            cols,
+            grid: vec![vec![' '; cols]; rows],
+            row: 0,
+            col: 0,
+            wrap_pending: false,
+            saved: (0, 0),
+            top: 0,
+            bottom: rows - 1,
+            main: None,
+            last_alternate: None,
+            state: State::Ground,
+            params: String::new(),
+            utf8: Vec::new(),
+        }
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.byte(*byte);
+        }
+    }
+
+    // What's on screen, one line per row without trailing blanks. A program that drew on the
+    // alternate screen and switched back on exit is shown as it last was there.
+    pub fn text(&self) -> String {
+        let grid = match (&self.main, &self.last_alternate) {
+            (None, Some(alternate)) => alternate,
+            _ => &self.grid,
+        };
+        let mut lines: Vec<String> = grid
+            .iter()
+            .map(|row| row.iter().collect::<String>().trim_end().to_string())
+            .collect();
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        lines.join("\n")
+    }
+
+    fn byte(&mut self, byte: u8) {
+        match self.state {
+            State::Ground => self.ground(byte),
+            State::Escape => self.escape(byte),
+            State::Charset => self.state = State::Ground,
+            State::Csi => match byte {
+                0x20..=0x3f => {
+                    if self.params.len() < MAX_PARAMS * 6 {
+                        self.params.push(byte as char);
+                    }
+                }
+                0x40..=0x7e => {
+                    let params = mem::take(&mut self.params);
+                    self.state = State::Ground;
+                    self.csi(&params, byte as char);
+                }
+                0x1b => self.state = State::Escape,
+                // Control characters inside a sequence still take effect
+                _ => self.control(byte),
+            },
+            State::Osc => match byte {
+                0x07 => self.state = State::Ground,
+                0x1b => self.state = State::OscEscape,
+                _ => {}
+            },
+            State::OscEscape => {
+                self.state = State::Ground;
+                if byte != b'\\' {
+                    self.escape(byte);
+                }
+            }
+        }
+    }
+
+    fn ground(&mut self, byte: u8) {
+        if !self.utf8.is_empty() || byte >= 0x80 {
+            self.utf8.push(byte);
+            match std::str::from_utf8(&self.utf8) {
+                Ok(text) => {
+                    let c = text.chars().next().unwrap_or(char::REPLACEMENT_CHARACTER);
+                    self.utf8.clear();
+                    self.print(c);
+                }
+                Err(e) if e.error_len().is_some() || self.utf8.len() >= 4 => {
+                    self.utf8.clear();
+                    self.print(char::REPLACEMENT_CHARACTER);
+                }
+                // Incomplete, wait for the rest
+                Err(_) => {}
+            }
+            return;
+        }
+        match byte {
+            0x1b => self.state = State::Escape,
+            0x20..=0x7e => self.print(byte as char),
+            _ => self.control(byte),
+        }
+    }
+
+    fn control(&mut self, byte: u8) {
+        match byte {
+            b'\r' => {
+                self.col = 0;
+                self.wrap_pending = false;
+            }
+            b'\n' | 0x0b | 0x0c => self.line_feed(),
+            0x08 => {
+                self.col = self.col.saturating_sub(1);
+                self.wrap_pending = false;
+            }
+            b'\t' => {
+                self.col = ((self.col / TAB_WIDTH + 1) * TAB_WIDTH).min(self.cols - 1);
+            }
+            _ => {}
+        }
+    }
+
+    fn escape(&mut self, byte: u8) {
+        self.state = State::Ground;
+        match byte {
+            b'[' => {
+                self.params.clear();
+                self.state = State::Csi;
+            }
+            b']' => self.state = State::Osc,
+            b'(' | b')' | b'*' | b'+' => self.state = State::Charset,
+            b'7' => self.saved = (self.row, self.col),
+            b'8' => self.restore_cursor(),
+            b'D' => self.line_feed(),
+            b'E' => {
+                self.line_feed();
+                self.col = 0;
+            }
+            b'M' => {
+                if self.row == self.top {
+                    self.scroll_down(1);
+                } else {
+                    self.row = self.row.saturating_sub(1);
+                }
+            }
+            b'c' => {
+                let (rows, cols) = (self.rows as u16, self.cols as u16);
+                let last_alternate = self.last_alternate.take();
+                *self = Screen::new(rows, cols);
+                self.last_alternate = last_alternate;
+            }
+            _ => {}
+        }
+    }
+
+    fn print(&mut self, c: char) {
+        if self.wrap_pending {
+            self.col = 0;
+            self.line_feed();
+        }
+        self.grid[self.row][self.col] = c;
+        if self.col + 1 == self.cols {
+            self.wrap_pending = true;
+        } else {
+            self.col += 1;
+        }
+    }
+
+    fn line_feed(&mut self) {
+        self.wrap_pending = false;
+        if self.row == self.bottom {
+            self.scroll_up(1);
+        } else if self.row + 1 < self.rows {
+            self.row += 1;
+        }
+    }
+
+    fn blank_row(&self) -> Vec<char> {
+        vec![' '; self.cols]
+    }
+
+    fn scroll_up(&mut self, count: usize) {
+        for _ in 0..count.min(self.bottom - self.top + 1) {
+            self.grid.remove(self.top);
+            self.grid.insert(self.bottom, self.blank_row());
+        }
+    }
+
+    fn scroll_down(&mut self, count: usize) {
+        for _ in 0..count.min(self.bottom - self.top + 1) {
+            self.grid.remove(self.bottom);
+            self.grid.insert(self.top, self.blank_row());
+        }
+    }
+
+    fn restore_cursor(&mut self) {
+        (self.row, self.col) = self.saved;
+        self.row = self.row.min(self.rows - 1);
+        self.col = self.col.min(self.cols - 1);
+        self.wrap_pending = false;
+    }
+
+    fn erase(&mut self, row: usize, from: usize, to: usize) {
+        let to = to.min(self.cols);
+        for cell in &mut self.grid[row][from.min(to)..to] {
+            *cell = ' ';
+        }
+    }
+
+    fn set_alternate(&mut self, on: bool) {
+        match (on, self.main.is_some()) {
+            (true, false) => {
+                let blank = vec![self.blank_row(); self.rows];
+                self.main = Some(mem::replace(&mut self.grid, blank));
+            }
+            (false, true) => {
+                if let Some(main) = self.main.take() {
+                    self.last_alternate = Some(mem::replace(&mut self.grid, main));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn csi(&mut self, params: &str, command: char) {
+        let private = params.starts_with('?');
+        let values: Vec<usize> = params
+            .trim_start_matches(['?', '>', '='])
+            .split(';')
+            .take(MAX_PARAMS)
+            .map(|p| p.parse().unwrap_or(0))
+            .collect();
+        let arg = |index: usize, default: usize| match values.get(index) {
+            Some(0) | None => default,
+            Some(value) => *value,
+        };
+        if private {
+            if matches!(command, 'h' | 'l') && values.iter().any(|v| matches!(v, 47 | 1047 | 1049))
+            {
+                if command == 'h' && values.contains(&1049) {
+                    self.saved = (self.row, self.col);
+                }
+                self.set_alternate(command == 'h');
+                if command == 'l' && values.contains(&1049) {
+                    self.restore_cursor();
+                }
+            }
+            return;
+        }
+        let (rows, cols) = (self.rows, self.cols);
+        // Colors don't move the cursor, so a pending wrap survives them
+        if command != 'm' {
+            self.wrap_pending = false;
+        }
+        match command {
+            'A' => self.row = self.row.saturating_sub(arg(0, 1)),
+            // Counts come straight from the program, so the sums saturate
+            'B' | 'e' => self.row = self.row.saturating_add(arg(0, 1)).min(rows - 1),
+            'C' | 'a' => self.col = self.col.saturating_add(arg(0, 1)).min(cols - 1),
+            'D' => self.col = self.col.saturating_sub(arg(0, 1)),
+            'E' => {
+                self.row = self.row.saturating_add(arg(0, 1)).min(rows - 1);
+                self.col = 0;
+            }
+            'F' => {
+                self.row = self.row.saturating_sub(arg(0, 1));
+                self.col = 0;
+            }
+            'G' | '`' => self.col = (arg(0, 1) - 1).min(cols - 1),
+            'd' => self.row = (arg(0, 1) - 1).min(rows - 1),
+            'H' | 'f' => {
+                self.row = (arg(0, 1) - 1).min(rows - 1);
+                self.col = (arg(1, 1) - 1).min(cols - 1);
+            }
+            'J' => match values.first().copied().unwrap_or(0) {
+                0 => {
+                    self.erase(self.row, self.col, cols);
+                    for row in self.row + 1..rows {
+                        self.erase(row, 0, cols);
+                    }
+                }
+                1 => {
+                    for row in 0..self.row {
+                        self.erase(row, 0, cols);
+                    }
+                    self.erase(self.row, 0, self.col + 1);
+                }
+                _ => {
+                    for row in 0..rows {
+                        self.erase(row, 0, cols);
+                    }
+                }
+            },
+            'K' => match values.first().copied().unwrap_or(0) {
+                0 => self.erase(self.row, self.col, cols),
+                1 => self.erase(self.row, 0, self.col + 1),
+                _ => self.erase(self.row, 0, cols),
+            },
+            'X' => self.erase(self.row, self.col, self.col.saturating_add(arg(0, 1))),
+            'P' => {
+                let line = &mut self.grid[self.row];
+                for _ in 0..arg(0, 1).min(cols - self.col) {
+                    line.remove(self.col);
+                    line.push(' ');
+                }
+            }
+            '@' => {
+                let line = &mut self.grid[self.row];
+                for _ in 0..arg(0, 1).min(cols - self.col) {
+                    line.pop();
+                    line.insert(self.col, ' ');
+                }
+            }
+            'L' | 'M' if (self.top..=self.bottom).contains(&self.row) => {
+                let top = mem::replace(&mut self.top, self.row);
+                if command == 'L' {
+                    self.scroll_down(arg(0, 1));
+                } else {
+                    self.scroll_up(arg(0, 1));
+                }
+                self.top = top;
+            }
+            'S' => self.scroll_up(arg(0, 1)),
+            'T' => self.scroll_down(arg(0, 1)),
+            'r' => {
+                let top = arg(0, 1) - 1;
+                let bottom = arg(1, rows).min(rows) - 1;
+                if top < bottom {
+                    (self.top, self.bottom) = (top, bottom);
+                    (self.row, self.col) = (0, 0);
+                }
+            }
+            's' => self.saved = (self.row, self.col),
+            'u' => self.restore_cursor(),
+            _ => {}
+        }
+    }
+}
+
+// Passes a pty's output through unchanged while drawing it on `screen`
+pub struct Tee<R> {
+    inner: R,
+    screen: Arc<Mutex<Screen>>,
+}
+
+impl<R> Tee<R> {
+    pub fn new(inner: R, screen: Arc<Mutex<Screen>>) -> Self {
+        Self { inner, screen }
+    }
+}
+
+impl<R: Read> Read for Tee<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if let Ok(mut screen) = self.screen.lock() {
+            screen.feed(&buf[..read]);
+        }
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draw(rows: u16, cols: u16, output: &str) -> String {
+        let mut screen = Screen::new(rows, cols);
+        screen.feed(output.as_bytes());
+        screen.text()
+    }
+
+    #[test]
+    fn moves_the_cursor() {
+        assert_eq!(draw(3, 10, "\x1b[2;3Hx\x1b[Ay\x1b[2Dz"), "  zy\n  x");
+        assert_eq!(draw(3, 10, "a\x1b[Bb\x1b[Ec"), "a\n b\nc");
+        // Cursor movement stops at the edges
+        assert_eq!(draw(2, 4, "\x1b[9;9Hx\x1b[9Ay"), "   y\n   x");
+    }
+
+    #[test]
+    fn wraps_at_the_last_column() {
+        assert_eq!(draw(3, 3, "abcd"), "abc\nd");
+    }
+
+    #[test]
+    fn erases() {
+        assert_eq!(draw(2, 10, "abcdef\x1b[1;3H\x1b[K"), "ab");
+        assert_eq!(draw(2, 10, "abcdef\x1b[1;3H\x1b[1K"), "   def");
+        assert_eq!(draw(2, 10, "abcdef\x1b[1;2H\x1b[2X"), "a  def");
+        assert_eq!(draw(2, 10, "abc\r\ndef\x1b[1;2H\x1b[J"), "a");
+        assert_eq!(draw(2, 10, "abc\r\ndef\x1b[2J"), "");
+    }
+
+    #[test]
+    fn huge_counts_do_not_overflow() {
+        let max = usize::MAX;
+        assert_eq!(draw(2, 10, &format!("abc\x1b[1;2H\x1b[{}X", max)), "a");
+        assert_eq!(draw(2, 4, &format!("\x1b[1;2H\x1b[{}Bx", max)), "\n x");
+        assert_eq!(draw(2, 4, &format!("\x1b[{}Cx", max)), "   x");
+        assert_eq!(draw(2, 4, &format!("\x1b[{}Ex", max)), "\nx");
+    }
+
+    #[test]
+    fn scrolls_only_the_region() {
+        let output = "1\r\n2\r\n3\r\n4\x1b[2;3r\x1b[3;1H\n";
+        assert_eq!(draw(4, 10, output), "1\n3\n\n4");
+        // Without a region the whole screen scrolls
+        assert_eq!(draw(3, 10, "1\r\n2\r\n3\r\n4"), "2\n3\n4");
+    }
+
+    #[test]
+    fn shows_the_alternate_screen_after_it_is_left() {
+        let mut screen = Screen::new(3, 10);
+        screen.feed(b"main\x1b[?1049h\x1b[Halt");
+        assert_eq!(screen.text(), "alt");
+        screen.feed(b"\x1b[?1049l");
+        assert_eq!(screen.text(), "alt");
+        screen.feed(b"\x1b[?1049h");
+        assert_eq!(screen.text(), "");
+    }
+}
@@ -0,0 +1,943 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use regex::RegexBuilder;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::diagnostics::{self, VolumeKind};
+use crate::ignorefiles::IgnoreStack;
+use crate::metadata::{self, ArgSpec, ScriptMetadata};
+use crate::tags::{self, TagFilter};
+use crate::{
+    collections, history, hotkeys, index, profiles, roots, settings, snapshots, staging, tray,
+};
+
+const DEFAULT_GREP_MAX_FILE_BYTES: u64 = 1024 * 1024;
+// validate_scripts_path only looks this many folders deep, so huge trees stay quick
+const SHALLOW_SCAN_DEPTH: usize = 2;
+const LAST_SELECTED_KEY: &str = "lastSelectedScript";
+// Files or folders outside the scripts roots that may still be run
+const ALLOWED_PATHS_KEY: &str = "allowedScriptPaths";
+// Prefix of the error for paths outside every scripts root, so callers can tell it from not found
+pub const OUTSIDE_ROOTS_ERROR: &str = "outside-allowed-roots";
+// When set, the only directories scripts may run from, replacing the roots and exceptions
+const ALLOWED_DIRS_KEY: &str = "allowedDirs";
+pub const OUTSIDE_ALLOWED_DIRS_ERROR: &str = "script outside allowed directories";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrepHit {
+    pub path: String,
+    pub line_number: usize,
+    pub line: String,
+}
+
+pub fn is_script(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "sh")
+}
+
+#[cfg(unix)]
+pub fn set_user_executable(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_mode(permissions.mode() | 0o100);
+    fs::set_permissions(path, permissions)
+}
+
+#[cfg(not(unix))]
+pub fn set_user_executable(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+pub fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|m| m.permissions().mode() & 0o111 != 0)
+}
+
+// Windows has no exec bit; scripts are always launchable through the interpreter
+#[cfg(not(unix))]
+pub fn is_executable(_path: &Path) -> bool {
+    true
+}
+
+// Group or world write bit, so other accounts could change what runs next
+#[cfg(unix)]
+pub fn is_writable_by_others(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|m| m.permissions().mode() & 0o022 != 0)
+}
+
+#[cfg(not(unix))]
+pub fn is_writable_by_others(_path: &Path) -> bool {
+    false
+}
+
+#[cfg(target_os = "macos")]
+const QUARANTINE_ATTR: &str = "com.apple.quarantine";
+
+// Set by browsers on downloaded files; Gatekeeper may refuse or prompt before they run
+#[cfg(target_os = "macos")]
+pub fn is_quarantined(path: &Path) -> bool {
+    xattr::get(path, QUARANTINE_ATTR).is_ok_and(|value| value.is_some())
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn is_quarantined(_path: &Path) -> bool {
+    false
+}
+
+// Ok(false) when there was nothing to strip
+#[cfg(target_os = "macos")]
+pub fn strip_quarantine(path: &Path) -> std::io::Result<bool> {
+    if !is_quarantined(path) {
+        return Ok(false);
+    }
+    xattr::remove(path, QUARANTINE_ATTR).map(|_| true)
+}
+
+// Only macOS quarantines downloads, so elsewhere this never has anything to do
+#[cfg(not(target_os = "macos"))]
+pub fn strip_quarantine(_path: &Path) -> std::io::Result<bool> {
+    Ok(false)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptEntry {
+    pub path: String,
+    pub executable: bool,
+    pub quarantined: bool,
+    // Listed from the last scan of a scripts folder that can't be reached right now; the
+    // file isn't checked, so it is reported executable and not quarantined
+    pub stale: bool,
+    // Has an edit staged with stage_script_edit, waiting to be promoted or discarded
+    pub staged: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LastRunStatus {
+    Success,
+    Failure,
+    // Never run, shown as a neutral dot
+    None,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptStatusEntry {
+    #[serde(flatten)]
+    pub script: ScriptEntry,
+    pub status: LastRunStatus,
+    pub last_exit_code: Option<i32>,
+    // When the last run started
+    pub last_run_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptsPathInfo {
+    pub configured: String,
+    pub resolved: String,
+    pub is_symlink: bool,
+    pub exists: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptsPathCheck {
+    // After `~` expansion
+    pub path: String,
+    pub exists: bool,
+    pub is_dir: bool,
+    pub readable: bool,
+    pub writable: bool,
+    // Scripts within SHALLOW_SCAN_DEPTH levels, so a lower bound for deep trees
+    pub script_count: usize,
+    pub is_symlink: bool,
+    pub symlink_target: Option<String>,
+    pub volume: VolumeKind,
+    // What to fix before the path can be saved; empty when it is usable
+    pub problems: Vec<String>,
+}
+
+// Follows symlinks so synced folders are scanned and watched at their real location
+pub fn resolve_root(root: &Path) -> PathBuf {
+    fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf())
+}
+
+// Maps a path under a resolved root back under the configured root, which is
+// what script ids in the store are based on
+pub fn to_configured(path: &Path, configured: &Path, resolved: &Path) -> PathBuf {
+    path.strip_prefix(resolved)
+        .map(|rel| configured.join(rel))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+// Rejects absolute paths and anything that climbs out of the destination
+pub fn safe_relative(path: &Path) -> Option<PathBuf> {
+    let mut clean = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => clean.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!clean.as_os_str().is_empty()).then_some(clean)
+}
+
+// Recursively collects scripts the same way the frontend scanner does
+pub fn collect_scripts(dir: &Path) -> Vec<PathBuf> {
+    let mut scripts = Vec::new();
+    scan_dir(dir, &mut scripts, &mut IgnoreStack::default());
+    scripts.sort();
+    scripts
+}
+
+fn count_shallow(dir: &Path, depth: usize) -> usize {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| {
+            let path = entry.path();
+            if path.is_dir() {
+                if depth > 1 {
+                    count_shallow(&path, depth - 1)
+                } else {
+                    0
+                }
+            } else {
+                usize::from(is_script(&path))
+            }
+        })
+        .sum()
+}
+
+// Leaves out what the .gitignore and .shrunnerignore files along the way exclude
+fn scan_dir(dir: &Path, scripts: &mut Vec<PathBuf>, ignores: &mut IgnoreStack) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        // Skip directories we can't read
+        return;
+    };
+    ignores.enter(dir);
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_dir = path.is_dir();
+        if ignores.is_ignored(&path, is_dir) {
+            continue;
+        }
+        if is_dir {
+            scan_dir(&path, scripts, ignores);
+        } else if is_script(&path) {
+            scripts.push(path);
+        }
+    }
+    ignores.leave();
+}
+
+#[tauri::command]
+pub async fn grep_scripts(
+    app: AppHandle,
+    pattern: String,
+    dir: String,
+    regex: bool,
+    max_file_bytes: Option<u64>,
+    tags: Option<TagFilter>,
+) -> Result<Vec<GrepHit>, String> {
+    let source = if regex {
+        pattern
+    } else {
+        regex::escape(&pattern)
+    };
+    let matcher = RegexBuilder::new(&source)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let max_bytes = max_file_bytes.unwrap_or(DEFAULT_GREP_MAX_FILE_BYTES);
+    let root = settings::expand_path(&dir);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut hits = Vec::new();
+        let mut scripts: Vec<String> = all_scripts(vec![root]);
+        tags::retain(&app, &mut scripts, tags.as_ref(), |path| path);
+        for script in scripts {
+            if fs::metadata(&script)
+                .map(|m| m.len() > max_bytes)
+                .unwrap_or(true)
+            {
+                continue;
+            }
+            // Non-UTF-8 files fail here and are skipped
+            let Ok(content) = fs::read_to_string(&script) else {
+                continue;
+            };
+            for (index, line) in content.lines().enumerate() {
+                if matcher.is_match(line) {
+                    hits.push(GrepHit {
+                        path: script.clone(),
+                        line_number: index + 1,
+                        line: line.to_string(),
+                    });
+                }
+            }
+        }
+        hits
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+// Finds a script by file name, file stem or path relative to a root, e.g. "backup",
+// "backup.sh" or "tools/backup.sh"
+pub fn find_script(app: &AppHandle, name: &str) -> Option<String> {
+    let name = name.trim_matches('/');
+    settings::scripts_roots(app).into_iter().find_map(|root| {
+        let resolved = resolve_root(&root);
+        collect_scripts(&resolved).into_iter().find_map(|script| {
+            let relative = script.strip_prefix(&resolved).ok()?;
+            let matches = relative == Path::new(name)
+                || relative.with_extension("") == Path::new(name)
+                || script.file_name().is_some_and(|f| f == name)
+                || script.file_stem().is_some_and(|f| f == name);
+            matches.then(|| {
+                to_configured(&script, &root, &resolved)
+                    .to_string_lossy()
+                    .to_string()
+            })
+        })
+    })
+}
+
+#[tauri::command]
+pub fn get_script_metadata(path: String) -> Result<ScriptMetadata, String> {
+    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    Ok(metadata::parse_script(&content))
+}
+
+#[tauri::command]
+pub fn get_script_placeholders(path: String) -> Result<Vec<String>, String> {
+    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    Ok(metadata::placeholders(&content))
+}
+
+#[tauri::command]
+pub fn infer_script_args(path: String) -> Result<Vec<ArgSpec>, String> {
+    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    Ok(metadata::script_args(&content))
+}
+
+#[tauri::command]
+pub fn get_scripts_path(app: AppHandle) -> Result<String, String> {
+    settings::scripts_folder(&app)
+        .map(|p| resolve_root(&p).to_string_lossy().to_string())
+        .ok_or_else(|| "Could not determine home directory".to_string())
+}
+
+#[tauri::command]
+pub fn get_scripts_path_info(app: AppHandle) -> Result<ScriptsPathInfo, String> {
+    let configured = settings::scripts_folder(&app)
+        .ok_or_else(|| "Could not determine home directory".to_string())?;
+    let resolved = resolve_root(&configured);
+    Ok(ScriptsPathInfo {
+        is_symlink: fs::symlink_metadata(&configured).is_ok_and(|m| m.file_type().is_symlink()),
+        exists: resolved.is_dir(),
+        configured: configured.to_string_lossy().to_string(),
+        resolved: resolved.to_string_lossy().to_string(),
+    })
+}
+
+// Checks a scripts folder typed into settings before it is saved
+#[tauri::command]
+pub fn validate_scripts_path(path: String) -> Result<ScriptsPathCheck, String> {
+    let expanded = settings::expand_path(path.trim());
+    let link = fs::symlink_metadata(&expanded).ok();
+    let is_symlink = link.as_ref().is_some_and(|m| m.file_type().is_symlink());
+    let symlink_target = is_symlink
+        .then(|| fs::read_link(&expanded).ok())
+        .flatten()
+        .map(|target| target.to_string_lossy().to_string());
+    let resolved = resolve_root(&expanded);
+    let exists = resolved.exists();
+    let is_dir = resolved.is_dir();
+    let readable = is_dir && fs::read_dir(&resolved).is_ok();
+    let writable = exists && diagnostics::is_writable(&resolved);
+    let script_count = if readable {
+        count_shallow(&resolved, SHALLOW_SCAN_DEPTH)
+    } else {
+        0
+    };
+
+    let mut problems = Vec::new();
+    if path.trim().is_empty() {
+        problems.push("Enter a folder path".to_string());
+    } else if !expanded.is_absolute() {
+        problems.push("Use an absolute path, or one starting with ~".to_string());
+    } else if is_symlink && !exists {
+        problems.push(format!(
+            "The symlink points to {}, which does not exist",
+            symlink_target.as_deref().unwrap_or("a missing target")
+        ));
+    } else if !exists {
+        problems.push("The folder does not exist; create it to use it".to_string());
+    } else if !is_dir {
+        problems.push("This is a file; choose the folder that contains your scripts".to_string());
+    } else {
+        if !readable {
+            problems.push("The folder can't be read; check its permissions".to_string());
+        }
+        if !writable {
+            problems.push(
+                "The folder is read-only, so scripts can't be created or renamed here".to_string(),
+            );
+        }
+    }
+    Ok(ScriptsPathCheck {
+        path: expanded.to_string_lossy().to_string(),
+        exists,
+        is_dir,
+        readable,
+        writable,
+        script_count,
+        is_symlink,
+        symlink_target,
+        volume: if exists {
+            diagnostics::volume_kind(&resolved)
+        } else {
+            VolumeKind::Unknown
+        },
+        problems,
+    })
+}
+
+// Creates a missing scripts folder and its parents; returns it with `~` expanded
+#[tauri::command]
+pub fn create_scripts_path(path: String) -> Result<String, String> {
+    let expanded = settings::expand_path(path.trim());
+    if !expanded.is_absolute() {
+        return Err(format!("Not an absolute path: {}", path));
+    }
+    if expanded.exists() && !expanded.is_dir() {
+        return Err(format!("{} is a file", expanded.display()));
+    }
+    fs::create_dir_all(&expanded)
+        .map_err(|e| format!("Failed to create {}: {}", expanded.display(), e))?;
+    Ok(expanded.to_string_lossy().to_string())
+}
+
+// Scripts across every root, reported under the configured paths; roots that
+// resolve to the same target are only scanned once. A root that doesn't answer in time
+// contributes its last good listing.
+pub fn all_scripts(roots: Vec<PathBuf>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut scripts = Vec::new();
+    for root in roots {
+        let (resolved, found) = roots::scan(&root);
+        if !seen.insert(resolved.unwrap_or(root)) {
+            continue;
+        }
+        scripts.extend(found);
+    }
+    scripts
+}
+
+fn entry(path: String) -> ScriptEntry {
+    if roots::is_stale(&path) {
+        return ScriptEntry {
+            path,
+            executable: true,
+            quarantined: false,
+            stale: true,
+            staged: false,
+        };
+    }
+    ScriptEntry {
+        executable: is_executable(Path::new(&path)),
+        quarantined: is_quarantined(Path::new(&path)),
+        staged: staging::has_staged(Path::new(&path)),
+        path,
+        stale: false,
+    }
+}
+
+#[tauri::command]
+pub async fn list_scripts(
+    app: AppHandle,
+    tags: Option<TagFilter>,
+) -> Result<Vec<ScriptEntry>, String> {
+    let roots = settings::scripts_roots(&app);
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut scripts = all_scripts(roots);
+        tags::retain(&app, &mut scripts, tags.as_ref(), |path| path);
+        scripts.into_iter().map(entry).collect()
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+// list_scripts for one folder plus each script's last run, in one call instead of a history
+// lookup per script
+#[tauri::command]
+pub async fn list_scripts_with_status(
+    app: AppHandle,
+    dir: String,
+    tags: Option<TagFilter>,
+) -> Result<Vec<ScriptStatusEntry>, String> {
+    let latest = history::latest_by_path(&app);
+    let root = settings::expand_path(&dir);
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut scripts = all_scripts(vec![root]);
+        tags::retain(&app, &mut scripts, tags.as_ref(), |path| path);
+        scripts
+            .into_iter()
+            .map(|path| {
+                let last = latest.get(&path);
+                ScriptStatusEntry {
+                    status: match last {
+                        Some(entry) if entry.success => LastRunStatus::Success,
+                        Some(_) => LastRunStatus::Failure,
+                        None => LastRunStatus::None,
+                    },
+                    last_exit_code: last.and_then(|entry| entry.exit_code),
+                    last_run_ms: last.map(|entry| entry.started_at),
+                    script: entry(path),
+                }
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn make_executable(app: AppHandle, script_id: String) -> Result<(), String> {
+    let script = managed_script(&app, &script_id)?;
+    set_user_executable(&script).map_err(|e| e.to_string())?;
+    notify_scripts_changed(&app, vec![script_id]);
+    Ok(())
+}
+
+// Returns whether an attribute was removed; always false outside macOS
+#[tauri::command]
+pub fn remove_quarantine(app: AppHandle, script_id: String) -> Result<bool, String> {
+    let script = managed_script(&app, &script_id)?;
+    let removed = strip_quarantine(&script)
+        .map_err(|e| format!("Failed to remove quarantine from {}: {}", script_id, e))?;
+    if removed {
+        notify_scripts_changed(&app, vec![script_id]);
+    }
+    Ok(removed)
+}
+
+#[tauri::command]
+pub fn get_allowed_script_paths(app: AppHandle) -> Result<Vec<String>, String> {
+    Ok(settings::get(&app, ALLOWED_PATHS_KEY).unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn set_allowed_script_paths(app: AppHandle, paths: Vec<String>) -> Result<(), String> {
+    let paths: Vec<String> = paths
+        .iter()
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty())
+        .collect();
+    settings::set(&app, ALLOWED_PATHS_KEY, &paths)
+}
+
+#[tauri::command]
+pub fn get_allowed_dirs(app: AppHandle) -> Result<Vec<String>, String> {
+    Ok(settings::get(&app, ALLOWED_DIRS_KEY).unwrap_or_default())
+}
+
+// Stored canonical, so a later `..` or symlink in the list can't widen it. An empty list
+// goes back to the scripts roots and their exceptions.
+#[tauri::command]
+pub fn set_allowed_dirs(app: AppHandle, dirs: Vec<String>) -> Result<(), String> {
+    let mut canonical: Vec<String> = Vec::new();
+    for dir in dirs.iter().map(|d| d.trim()).filter(|d| !d.is_empty()) {
+        let path = fs::canonicalize(settings::expand_path(dir))
+            .ok()
+            .filter(|path| path.is_dir())
+            .ok_or_else(|| format!("Not a directory: {}", dir))?;
+        let path = path.to_string_lossy().to_string();
+        if !canonical.contains(&path) {
+            canonical.push(path);
+        }
+    }
+    if canonical.is_empty() {
+        settings::delete(&app, ALLOWED_DIRS_KEY)
+    } else {
+        settings::set(&app, ALLOWED_DIRS_KEY, &canonical)
+    }
+}
+
+#[tauri::command]
+pub fn set_last_selected(app: AppHandle, path: Option<String>) -> Result<(), String> {
+    match path {
+        Some(path) => settings::set(&app, LAST_SELECTED_KEY, &path),
+        None => settings::delete(&app, LAST_SELECTED_KEY),
+    }
+}
+
+// None when nothing was selected or the script has since been deleted
+#[tauri::command]
+pub fn get_last_selected(app: AppHandle) -> Result<Option<String>, String> {
+    Ok(settings::get::<String>(&app, LAST_SELECTED_KEY).filter(|path| Path::new(path).is_file()))
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScriptsChangedEvent {
+    paths: Vec<String>,
+}
+
+// Tells the frontend to rescan and refreshes the tray after scripts were added or removed
+pub fn notify_scripts_changed(app: &AppHandle, paths: Vec<String>) {
+    index::invalidate_metadata(&paths);
+    let _ = app.emit("scripts-changed", ScriptsChangedEvent { paths });
+    if let Err(e) = tray::rebuild_menu(app) {
+        log::warn!("Failed to rebuild the tray menu: {}", e);
+    }
+}
+
+fn outside_roots(id: &str) -> String {
+    format!(
+        "{}: {} is not inside a scripts folder",
+        OUTSIDE_ROOTS_ERROR, id
+    )
+}
+
+// Default macOS and Windows filesystems ignore case, so the prefix check does too
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+pub fn is_within(path: &Path, dir: &Path) -> bool {
+    let fold = |p: &Path| PathBuf::from(p.to_string_lossy().to_lowercase());
+    fold(path).starts_with(fold(dir))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub fn is_within(path: &Path, dir: &Path) -> bool {
+    path.starts_with(dir)
+}
+
+// Checks a path before it is executed: once `..` and symlinks are resolved it must lie in one
+// of the allowed directories, or without any in a scripts root or under one of the allowlisted
+// exceptions. Returns the canonical path.
+pub fn allowed_script(app: &AppHandle, id: &str) -> Result<PathBuf, String> {
+    let dirs: Vec<String> = settings::get(app, ALLOWED_DIRS_KEY).unwrap_or_default();
+    if !dirs.is_empty() {
+        let dirs: Vec<PathBuf> = dirs.iter().map(PathBuf::from).collect();
+        return allowed_in(id, &dirs, || OUTSIDE_ALLOWED_DIRS_ERROR.to_string());
+    }
+    let exceptions: Vec<String> = settings::get(app, ALLOWED_PATHS_KEY).unwrap_or_default();
+    let dirs: Vec<PathBuf> = settings::scripts_roots(app)
+        .into_iter()
+        .chain(exceptions.iter().map(|path| settings::expand_path(path)))
+        .collect();
+    allowed_in(id, &dirs, || outside_roots(id))
+}
+
+fn allowed_in(
+    id: &str,
+    dirs: &[PathBuf],
+    outside: impl FnOnce() -> String,
+) -> Result<PathBuf, String> {
+    let canonical = fs::canonicalize(id)
+        .ok()
+        .filter(|path| path.is_file())
+        .ok_or_else(|| format!("Script not found: {}", id))?;
+    let inside = dirs
+        .iter()
+        .filter_map(|dir| fs::canonicalize(dir).ok())
+        .any(|dir| is_within(&canonical, &dir));
+    if !inside {
+        return Err(outside());
+    }
+    Ok(canonical)
+}
+
+// Resolves a script id to its file, refusing anything that isn't a script inside a scripts root
+pub fn managed_script(app: &AppHandle, id: &str) -> Result<PathBuf, String> {
+    managed_in(id, &settings::scripts_roots(app))
+}
+
+// For a file about to be written below `root`: the nearest folder on its way that exists must
+// resolve inside the root, so a symlinked folder can't send the write somewhere else
+pub fn ensure_writable_under(root: &Path, target: &Path) -> Result<(), String> {
+    let resolved = resolve_root(root);
+    let inside = target
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.exists())
+        .and_then(|dir| dir.canonicalize().ok())
+        .is_some_and(|dir| is_within(&dir, &resolved));
+    if !inside {
+        return Err(outside_roots(&target.to_string_lossy()));
+    }
+    Ok(())
+}
+
+fn managed_in(id: &str, roots: &[PathBuf]) -> Result<PathBuf, String> {
+    let path = Path::new(id);
+    let inside = roots.iter().any(|root| {
+        let resolved = resolve_root(root);
+        let relative = path
+            .strip_prefix(root)
+            .or_else(|_| path.strip_prefix(&resolved));
+        // Symlinked folders must not lead out of the root either
+        relative.is_ok_and(|r| safe_relative(r).is_some())
+            && path
+                .parent()
+                .and_then(|dir| dir.canonicalize().ok())
+                .is_some_and(|dir| is_within(&dir, &resolved))
+    });
+    if !inside {
+        return Err(outside_roots(id));
+    }
+    if !is_script(path) || !fs::symlink_metadata(path).is_ok_and(|m| m.is_file()) {
+        return Err(format!("Not a script: {}", id));
+    }
+    Ok(path.to_path_buf())
+}
+
+// A sibling of `script` called `new_name`, keeping the extension when none is given
+fn sibling(script: &Path, new_name: &str) -> Result<PathBuf, String> {
+    let name = new_name.trim();
+    let mut components = Path::new(name).components();
+    let (Some(Component::Normal(_)), None) = (components.next(), components.next()) else {
+        return Err(format!("Invalid script name: {}", new_name));
+    };
+    let mut target = script.with_file_name(name);
+    if target.extension().is_none() {
+        if let Some(ext) = script.extension() {
+            target.set_extension(ext);
+        }
+    }
+    if !is_script(&target) {
+        return Err(format!("Invalid script name: {}", new_name));
+    }
+    if fs::symlink_metadata(&target).is_ok() {
+        return Err(format!("A file already exists at {}", target.display()));
+    }
+    Ok(target)
+}
+
+#[cfg(desktop)]
+fn move_to_trash(path: &Path) -> Result<(), String> {
+    trash::delete(path).map_err(|e| e.to_string())
+}
+
+#[cfg(mobile)]
+fn move_to_trash(_path: &Path) -> Result<(), String> {
+    Err("Moving to the trash is not supported on this platform".to_string())
+}
+
+// Settings, favorites, hotkeys and collections follow a script to its new id, past runs
+// included; with `to` None they're dropped and past runs are kept
+pub fn move_script_state(app: &AppHandle, id: &str, to: Option<&str>) -> Result<(), String> {
+    match to {
+        Some(new_id) => {
+            settings::rename_script_data(app, id, new_id)?;
+            history::rename_path(app, id, new_id)?;
+        }
+        None => settings::remove_script_data(app, id)?,
+    }
+    hotkeys::move_script_hotkey(app, id, to)?;
+    collections::move_script(app, id, to)?;
+    snapshots::move_snapshot(app, id, to)?;
+    profiles::update_favorites(app, |favorites| match to {
+        Some(new_id) => {
+            for favorite in favorites.iter_mut().filter(|f| *f == id) {
+                *favorite = new_id.to_string();
+            }
+        }
+        None => favorites.retain(|f| f != id),
+    })?;
+    if settings::get::<String>(app, LAST_SELECTED_KEY).as_deref() == Some(id) {
+        match to {
+            Some(new_id) => settings::set(app, LAST_SELECTED_KEY, &new_id)?,
+            None => settings::delete(app, LAST_SELECTED_KEY)?,
+        }
+    }
+    Ok(())
+}
+
+// Settings, favorites and past runs all follow the script to its new id
+#[tauri::command]
+pub fn rename_script(app: AppHandle, id: String, new_name: String) -> Result<String, String> {
+    let script = managed_script(&app, &id)?;
+    let target = sibling(&script, &new_name)?;
+    fs::rename(&script, &target).map_err(|e| e.to_string())?;
+    let new_id = target.to_string_lossy().to_string();
+
+    move_script_state(&app, &id, Some(&new_id))?;
+    notify_scripts_changed(&app, vec![id, new_id.clone()]);
+    Ok(new_id)
+}
+
+// The copy gets the original's settings and favorite status but starts without run history
+#[tauri::command]
+pub fn duplicate_script(app: AppHandle, id: String, new_name: String) -> Result<String, String> {
+    let script = managed_script(&app, &id)?;
+    let target = sibling(&script, &new_name)?;
+    // fs::copy carries the permission bits over, executable included
+    fs::copy(&script, &target).map_err(|e| e.to_string())?;
+    let new_id = target.to_string_lossy().to_string();
+
+    settings::copy_script_data(&app, &id, &new_id)?;
+    profiles::update_favorites(&app, |favorites| {
+        if favorites.contains(&id) && !favorites.contains(&new_id) {
+            favorites.push(new_id.clone());
+        }
+    })?;
+    notify_scripts_changed(&app, vec![new_id.clone()]);
+    Ok(new_id)
+}
+
+// Moves to the OS trash unless `to_trash` is explicitly false; run history is kept
+#[tauri::command]
+pub fn delete_script(app: AppHandle, id: String, to_trash: Option<bool>) -> Result<(), String> {
+    let script = managed_script(&app, &id)?;
+    if to_trash.unwrap_or(true) {
+        move_to_trash(&script)?;
+    } else {
+        fs::remove_file(&script).map_err(|e| e.to_string())?;
+    }
+
+    move_script_state(&app, &id, None)?;
+    notify_scripts_changed(&app, vec![id]);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fresh folder per test, removed when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "sh-runner-scripts-test-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(dir.join("root")).unwrap();
+            fs::create_dir_all(dir.join("outside")).unwrap();
+            fs::write(dir.join("root/ok.sh"), "echo ok\n").unwrap();
+            fs::write(dir.join("outside/evil.sh"), "echo evil\n").unwrap();
+            TempDir(dir)
+        }
+
+        fn root(&self) -> PathBuf {
+            self.0.join("root")
+        }
+
+        fn id(&self, relative: &str) -> String {
+            self.0.join(relative).to_string_lossy().to_string()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn outside() -> String {
+        "outside".to_string()
+    }
+
+    #[test]
+    fn scripts_inside_the_root_are_allowed() {
+        let dir = TempDir::new("inside");
+        let roots = [dir.root()];
+        let id = dir.id("root/ok.sh");
+        assert!(allowed_in(&id, &roots, outside).is_ok());
+        assert_eq!(managed_in(&id, &roots), Ok(PathBuf::from(&id)));
+    }
+
+    #[test]
+    fn parent_components_cannot_leave_the_root() {
+        let dir = TempDir::new("dotdot");
+        let roots = [dir.root()];
+        let id = dir.id("root/../outside/evil.sh");
+        assert_eq!(allowed_in(&id, &roots, outside), Err(outside()));
+        assert!(managed_in(&id, &roots)
+            .unwrap_err()
+            .starts_with(OUTSIDE_ROOTS_ERROR));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_cannot_leave_the_root() {
+        use std::os::unix::fs::symlink;
+
+        let dir = TempDir::new("symlink");
+        let roots = [dir.root()];
+        symlink(dir.0.join("outside/evil.sh"), dir.0.join("root/link.sh")).unwrap();
+        symlink(dir.0.join("outside"), dir.0.join("root/linked")).unwrap();
+        let file = dir.id("root/link.sh");
+        assert_eq!(allowed_in(&file, &roots, outside), Err(outside()));
+        assert!(managed_in(&file, &roots).is_err());
+        let folder = dir.id("root/linked/evil.sh");
+        assert_eq!(allowed_in(&folder, &roots, outside), Err(outside()));
+        assert!(managed_in(&folder, &roots)
+            .unwrap_err()
+            .starts_with(OUTSIDE_ROOTS_ERROR));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn writes_cannot_follow_a_symlinked_folder() {
+        use std::os::unix::fs::symlink;
+
+        let dir = TempDir::new("write");
+        let root = dir.root();
+        symlink(dir.0.join("outside"), root.join("linked")).unwrap();
+        assert!(ensure_writable_under(&root, &root.join("new/deep/x.sh")).is_ok());
+        assert!(ensure_writable_under(&root, &root.join("linked/x.sh"))
+            .unwrap_err()
+            .starts_with(OUTSIDE_ROOTS_ERROR));
+        assert!(ensure_writable_under(&root, &root.join("linked/new/x.sh")).is_err());
+    }
+
+    #[test]
+    fn is_within_matches_whole_components() {
+        assert!(is_within(
+            Path::new("/scripts/deploy.sh"),
+            Path::new("/scripts")
+        ));
+        assert!(!is_within(
+            Path::new("/scripts-old/deploy.sh"),
+            Path::new("/scripts")
+        ));
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    #[test]
+    fn is_within_ignores_case() {
+        assert!(is_within(
+            Path::new("/Users/Me/Scripts/deploy.sh"),
+            Path::new("/users/me/scripts")
+        ));
+        assert!(!is_within(
+            Path::new("/Users/Me/Scripts-Old/deploy.sh"),
+            Path::new("/users/me/scripts")
+        ));
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    #[test]
+    fn is_within_keeps_case() {
+        assert!(!is_within(
+            Path::new("/home/me/Scripts/deploy.sh"),
+            Path::new("/home/me/scripts")
+        ));
+    }
+}
@@ -0,0 +1,438 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::thread;
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::history::{self, HistoryEntry};
+use crate::startup::{self, Phase};
+
+// Error of a search stopped with cancel_output_search
+pub const SEARCH_CANCELLED_ERROR: &str = "search-cancelled";
+// Lowercased trigrams of a run's logs, written next to them once the run has finished
+const INDEX_FILE: &str = "trigrams.bin";
+const DEFAULT_CONTEXT_LINES: usize = 2;
+const MAX_CONTEXT_LINES: usize = 10;
+const DEFAULT_PAGE_RUNS: usize = 20;
+// More hits than this in one run are counted but not returned
+const MAX_MATCHES_PER_RUN: usize = 50;
+// Lines search_run_output returns at most
+const MAX_RUN_OUTPUT_MATCHES: usize = 1000;
+const CANCEL_CHECK_LINES: usize = 4096;
+
+// Searches in flight by the id their caller chose, flagged by cancel_output_search
+static SEARCHES: LazyLock<Mutex<HashMap<String, Arc<AtomicBool>>>> = LazyLock::new(Mutex::default);
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OutputSearchFilter {
+    // Treat the query as a regular expression rather than plain text
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub path: Option<String>,
+    pub from_ms: Option<u64>,
+    pub to_ms: Option<u64>,
+    // Lines shown before and after each hit, up to MAX_CONTEXT_LINES
+    pub context_lines: Option<usize>,
+    // Matching runs to skip, newest first; pass the previous page's next_offset
+    pub offset: usize,
+    pub limit: Option<usize>,
+    // Lets cancel_output_search stop this search
+    pub search_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputMatch {
+    // "stdout" or "stderr"
+    pub stream: &'static str,
+    pub line_number: usize,
+    pub line: String,
+    // Where the query matched, in characters
+    pub ranges: Vec<MatchRange>,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunMatches {
+    pub run: HistoryEntry,
+    pub matches: Vec<OutputMatch>,
+    // Every hit in the run, past the ones returned
+    pub total_matches: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputSearchPage {
+    pub runs: Vec<RunMatches>,
+    // None on the last page
+    pub next_offset: Option<usize>,
+    // Runs whose logs were read, after the index ruled the others out
+    pub scanned_runs: usize,
+}
+
+fn trigrams(bytes: &[u8], into: &mut BTreeSet<u32>) {
+    for window in bytes.windows(3) {
+        into.insert(u32::from_be_bytes([0, window[0], window[1], window[2]]));
+    }
+}
+
+fn build_index(files: &[(&'static str, PathBuf)]) -> std::io::Result<Vec<u8>> {
+    let mut set = BTreeSet::new();
+    for (_, path) in files {
+        let Ok(file) = File::open(path) else {
+            continue;
+        };
+        let mut reader = BufReader::new(file);
+        let mut line = Vec::new();
+        while reader.read_until(b'\n', &mut line)? > 0 {
+            line.make_ascii_lowercase();
+            trigrams(&line, &mut set);
+            line.clear();
+        }
+    }
+    Ok(set.into_iter().flat_map(u32::to_le_bytes).collect())
+}
+
+// Called once a run's logs are written; a run without an index is simply read in full
+pub fn index_in_background(dir: PathBuf, files: Vec<(&'static str, PathBuf)>) {
+    thread::spawn(move || match build_index(&files) {
+        Ok(index) => {
+            if let Err(e) = fs::write(dir.join(INDEX_FILE), index) {
+                log::warn!(
+                    "Failed to write the output index in {}: {}",
+                    dir.display(),
+                    e
+                );
+            }
+        }
+        Err(e) => log::warn!("Failed to index the output in {}: {}", dir.display(), e),
+    });
+}
+
+// False only when the run's index shows the text can't be in its logs
+fn may_contain(dir: &Path, needle: &BTreeSet<u32>) -> bool {
+    let Ok(bytes) = fs::read(dir.join(INDEX_FILE)) else {
+        return true;
+    };
+    let index: Vec<u32> = bytes
+        .chunks_exact(4)
+        .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect();
+    needle.iter().all(|gram| index.binary_search(gram).is_ok())
+}
+
+struct Search {
+    matcher: Regex,
+    // Trigrams of a plain ASCII query, checked against each run's index
+    needle: Option<BTreeSet<u32>>,
+    context: usize,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Search {
+    fn check_cancelled(&self) -> Result<(), String> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            Err(SEARCH_CANCELLED_ERROR.to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    // Reads line by line so large logs aren't loaded whole; returns the number of hits
+    fn scan(
+        &self,
+        stream: &'static str,
+        path: &Path,
+        matches: &mut Vec<OutputMatch>,
+    ) -> Result<usize, String> {
+        let Ok(file) = File::open(path) else {
+            return Ok(0);
+        };
+        let mut reader = BufReader::new(file);
+        let mut bytes = Vec::new();
+        let mut before: VecDeque<String> = VecDeque::new();
+        // Returned hits still collecting their `after` lines
+        let mut open: Vec<usize> = Vec::new();
+        let mut hits = 0;
+        let mut line_number = 0;
+        while reader
+            .read_until(b'\n', &mut bytes)
+            .map_err(|e| e.to_string())?
+            > 0
+        {
+            line_number += 1;
+            if line_number % CANCEL_CHECK_LINES == 0 {
+                self.check_cancelled()?;
+            }
+            let line = String::from_utf8_lossy(&bytes)
+                .trim_end_matches(['\r', '\n'])
+                .to_string();
+            bytes.clear();
+            open.retain(|index| {
+                let after = &mut matches[*index].after;
+                after.push(line.clone());
+                after.len() < self.context
+            });
+            if self.matcher.is_match(&line) {
+                hits += 1;
+                if matches.len() < MAX_MATCHES_PER_RUN {
+                    matches.push(OutputMatch {
+                        stream,
+                        line_number,
+                        ranges: ranges(&self.matcher, &line),
+                        line: line.clone(),
+                        before: before.iter().cloned().collect(),
+                        after: Vec::new(),
+                    });
+                    if self.context > 0 {
+                        open.push(matches.len() - 1);
+                    }
+                }
+            }
+            if self.context > 0 {
+                if before.len() == self.context {
+                    before.pop_front();
+                }
+                before.push_back(line);
+            }
+        }
+        Ok(hits)
+    }
+
+    fn run(&self, app: &AppHandle, entry: HistoryEntry) -> Result<Option<RunMatches>, String> {
+        let mut matches = Vec::new();
+        let mut total_matches = 0;
+        for (stream, path) in history::log_files(app, &entry.run_id) {
+            total_matches += self.scan(stream, &path, &mut matches)?;
+        }
+        Ok((total_matches > 0).then_some(RunMatches {
+            run: entry,
+            matches,
+            total_matches,
+        }))
+    }
+}
+
+fn ranges(matcher: &Regex, line: &str) -> Vec<MatchRange> {
+    let chars = |end: usize| line[..end].chars().count();
+    matcher
+        .find_iter(line)
+        .filter(|found| !found.is_empty())
+        .map(|found| MatchRange {
+            start: chars(found.start()),
+            end: chars(found.end()),
+        })
+        .collect()
+}
+
+fn matcher(query: &str, filter: &OutputSearchFilter) -> Result<Regex, String> {
+    let source = if filter.regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    RegexBuilder::new(&source)
+        .case_insensitive(!filter.case_sensitive)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+fn candidates(app: &AppHandle, filter: &OutputSearchFilter) -> Vec<HistoryEntry> {
+    let mut entries = Vec::new();
+    history::visit(app, |entry| {
+        let in_range = filter.from_ms.is_none_or(|from| entry.started_at >= from)
+            && filter.to_ms.is_none_or(|to| entry.started_at < to);
+        let on_path = filter.path.as_ref().is_none_or(|path| entry.path == *path);
+        if in_range && on_path && !entry.external && !entry.aborted {
+            entries.push(entry.clone());
+        }
+    });
+    entries.sort_by_key(|e| Reverse(e.started_at));
+    entries
+}
+
+fn search(
+    app: &AppHandle,
+    query: &str,
+    filter: &OutputSearchFilter,
+    cancelled: Arc<AtomicBool>,
+) -> Result<OutputSearchPage, String> {
+    let needle = (!filter.regex && query.len() >= 3 && query.is_ascii()).then(|| {
+        let mut set = BTreeSet::new();
+        trigrams(query.to_ascii_lowercase().as_bytes(), &mut set);
+        set
+    });
+    let search = Search {
+        matcher: matcher(query, filter)?,
+        needle,
+        context: filter
+            .context_lines
+            .unwrap_or(DEFAULT_CONTEXT_LINES)
+            .min(MAX_CONTEXT_LINES),
+        cancelled,
+    };
+    let limit = filter.limit.unwrap_or(DEFAULT_PAGE_RUNS).max(1);
+    let mut runs = Vec::new();
+    let mut skipped = 0;
+    let mut scanned_runs = 0;
+    let mut next_offset = None;
+    for entry in candidates(app, filter) {
+        search.check_cancelled()?;
+        if let (Some(needle), Some(dir)) = (&search.needle, history::run_dir(app, &entry.run_id)) {
+            if !may_contain(&dir, needle) {
+                continue;
+            }
+        }
+        scanned_runs += 1;
+        let Some(found) = search.run(app, entry)? else {
+            continue;
+        };
+        if skipped < filter.offset {
+            skipped += 1;
+            continue;
+        }
+        // One run past the page only tells there is another page
+        if runs.len() == limit {
+            next_offset = Some(filter.offset + limit);
+            break;
+        }
+        runs.push(found);
+    }
+    Ok(OutputSearchPage {
+        runs,
+        next_offset,
+        scanned_runs,
+    })
+}
+
+// Matching runs newest first. Live runs aren't in the history yet, so they aren't searched.
+#[tauri::command]
+pub async fn search_output(
+    app: AppHandle,
+    query: String,
+    filter: Option<OutputSearchFilter>,
+) -> Result<OutputSearchPage, String> {
+    startup::guard(Phase::History)?;
+    if query.is_empty() {
+        return Err("Nothing to search for".to_string());
+    }
+    let filter = filter.unwrap_or_default();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    if let Some(id) = &filter.search_id {
+        if let Ok(mut searches) = SEARCHES.lock() {
+            searches.insert(id.clone(), cancelled.clone());
+        }
+    }
+    let id = filter.search_id.clone();
+    let result =
+        tauri::async_runtime::spawn_blocking(move || search(&app, &query, &filter, cancelled))
+            .await
+            .map_err(|e| e.to_string());
+    if let Some(id) = id {
+        if let Ok(mut searches) = SEARCHES.lock() {
+            searches.remove(&id);
+        }
+    }
+    result?
+}
+
+fn line_match(
+    matcher: &Regex,
+    stream: &'static str,
+    line_number: usize,
+    line: &str,
+) -> OutputMatch {
+    OutputMatch {
+        stream,
+        line_number,
+        line: line.to_string(),
+        ranges: ranges(matcher, line),
+        before: Vec::new(),
+        after: Vec::new(),
+    }
+}
+
+// The run's logs read line by line; a live run's are written as it prints, see logcap
+fn search_run(app: &AppHandle, run_id: &str, matcher: &Regex) -> Result<Vec<OutputMatch>, String> {
+    let mut matches = Vec::new();
+    let files = history::log_files(app, run_id);
+    if !files.iter().any(|(_, path)| path.is_file()) {
+        return Err(format!("No captured output for run {}", run_id));
+    }
+    for (stream, path) in files {
+        let Ok(file) = File::open(&path) else {
+            continue;
+        };
+        let mut reader = BufReader::new(file);
+        let mut bytes = Vec::new();
+        let mut line_number = 0;
+        while reader
+            .read_until(b'\n', &mut bytes)
+            .map_err(|e| e.to_string())?
+            > 0
+        {
+            line_number += 1;
+            let line = String::from_utf8_lossy(&bytes);
+            let line = line.trim_end_matches(['\r', '\n']);
+            if matcher.is_match(line) {
+                matches.push(line_match(matcher, stream, line_number, line));
+                if matches.len() == MAX_RUN_OUTPUT_MATCHES {
+                    return Ok(matches);
+                }
+            }
+            bytes.clear();
+        }
+    }
+    Ok(matches)
+}
+
+// Matching lines of one run, case-insensitive, up to MAX_RUN_OUTPUT_MATCHES. A live run is
+// searched in the output it has logged so far, so the UI doesn't need the whole log.
+#[tauri::command]
+pub async fn search_run_output(
+    app: AppHandle,
+    run_id: String,
+    pattern: String,
+    regex: Option<bool>,
+) -> Result<Vec<OutputMatch>, String> {
+    if pattern.is_empty() {
+        return Err("Nothing to search for".to_string());
+    }
+    let filter = OutputSearchFilter {
+        regex: regex.unwrap_or(false),
+        ..OutputSearchFilter::default()
+    };
+    let matcher = matcher(&pattern, &filter)?;
+    tauri::async_runtime::spawn_blocking(move || search_run(&app, &run_id, &matcher))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn cancel_output_search(search_id: String) -> Result<(), String> {
+    if let Some(cancelled) = SEARCHES
+        .lock()
+        .ok()
+        .and_then(|s| s.get(&search_id).cloned())
+    {
+        cancelled.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use tauri::AppHandle;
+
+use crate::settings;
+
+// The keychain can't be listed portably, so the names are kept in the store; values never are
+const NAMES_KEY: &str = "secretNames";
+pub const REDACTED: &str = "[redacted]";
+
+fn load_names(app: &AppHandle) -> Vec<String> {
+    settings::get(app, NAMES_KEY).unwrap_or_default()
+}
+
+fn valid_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    {
+        return Err(format!(
+            "Invalid secret name \"{}\": use letters, digits, '_', '-' or '.'",
+            name
+        ));
+    }
+    Ok(name.to_string())
+}
+
+#[cfg(desktop)]
+fn entry(app: &AppHandle, name: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(&app.config().identifier, name).map_err(|e| e.to_string())
+}
+
+// Errors only ever name the secret, never its value
+#[cfg(desktop)]
+fn read(app: &AppHandle, name: &str) -> Result<Option<String>, String> {
+    match entry(app, name)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read secret \"{}\": {}", name, e)),
+    }
+}
+
+#[cfg(desktop)]
+fn write(app: &AppHandle, name: &str, value: &str) -> Result<(), String> {
+    entry(app, name)?
+        .set_password(value)
+        .map_err(|e| format!("Failed to store secret \"{}\": {}", name, e))
+}
+
+#[cfg(desktop)]
+fn remove(app: &AppHandle, name: &str) -> Result<(), String> {
+    match entry(app, name)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Failed to delete secret \"{}\": {}", name, e)),
+    }
+}
+
+#[cfg(mobile)]
+fn read(_app: &AppHandle, _name: &str) -> Result<Option<String>, String> {
+    Err("Secrets are not supported on this platform".to_string())
+}
+
+#[cfg(mobile)]
+fn write(_app: &AppHandle, _name: &str, _value: &str) -> Result<(), String> {
+    Err("Secrets are not supported on this platform".to_string())
+}
+
+#[cfg(mobile)]
+fn remove(_app: &AppHandle, _name: &str) -> Result<(), String> {
+    Err("Secrets are not supported on this platform".to_string())
+}
+
+// Env vars for a script's secret references, resolved from the keychain at spawn time
+pub fn script_env(app: &AppHandle, path: &str) -> Result<Vec<(String, String)>, String> {
+    settings::script_secret_refs(app, path)
+        .into_iter()
+        .map(|(var, name)| match read(app, &name)? {
+            Some(value) => Ok((var, value)),
+            None => Err(format!(
+                "Secret \"{}\" used for ${} is not in the keychain",
+                name, var
+            )),
+        })
+        .collect()
+}
+
+// Replaces every occurrence of a secret value so output can go to the webview
+pub fn redact(text: &str, values: &[String]) -> String {
+    values
+        .iter()
+        .filter(|value| !value.is_empty())
+        .fold(text.to_string(), |text, value| {
+            text.replace(value.as_str(), REDACTED)
+        })
+}
+
+#[tauri::command]
+pub fn set_secret(app: AppHandle, name: String, value: String) -> Result<(), String> {
+    let name = valid_name(&name)?;
+    if value.is_empty() {
+        return Err(format!("Secret \"{}\" cannot be empty", name));
+    }
+    write(&app, &name, &value)?;
+    let mut names = load_names(&app);
+    if !names.contains(&name) {
+        names.push(name);
+        names.sort();
+    }
+    settings::set(&app, NAMES_KEY, &names)
+}
+
+#[tauri::command]
+pub fn delete_secret(app: AppHandle, name: String) -> Result<(), String> {
+    let name = name.trim();
+    remove(&app, name)?;
+    let mut names = load_names(&app);
+    names.retain(|n| n != name);
+    settings::set(&app, NAMES_KEY, &names)
+}
+
+#[tauri::command]
+pub fn list_secret_names(app: AppHandle) -> Result<Vec<String>, String> {
+    Ok(load_names(&app))
+}
@@ -0,0 +1,391 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Map, Value};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_store::StoreExt;
+
+use crate::{history, hotkeys, paths, profiles, tray, watcher};
+
+// Shared with the frontend, see src/hooks/useStore.ts
+pub const STORE_FILE: &str = "scripts-state.json";
+
+// Saves of the store by this process still in progress, so the store watcher can tell them
+// apart from outside edits
+static WRITES_IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+pub fn writing() -> bool {
+    WRITES_IN_FLIGHT.load(Ordering::SeqCst) > 0
+}
+
+fn save(store: &tauri_plugin_store::Store<tauri::Wry>) -> Result<(), String> {
+    WRITES_IN_FLIGHT.fetch_add(1, Ordering::SeqCst);
+    let saved = store.save().map_err(|e| e.to_string());
+    WRITES_IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+    saved
+}
+
+pub fn get<T: DeserializeOwned>(app: &AppHandle, key: &str) -> Option<T> {
+    let store = app.store(paths::store_file(app)).ok()?;
+    store
+        .get(key)
+        .and_then(|value| serde_json::from_value(value).ok())
+}
+
+pub fn set<T: Serialize>(app: &AppHandle, key: &str, value: &T) -> Result<(), String> {
+    let store = app
+        .store(paths::store_file(app))
+        .map_err(|e| e.to_string())?;
+    let value = serde_json::to_value(value).map_err(|e| e.to_string())?;
+    store.set(key, value);
+    save(&store)
+}
+
+pub fn delete(app: &AppHandle, key: &str) -> Result<(), String> {
+    let store = app
+        .store(paths::store_file(app))
+        .map_err(|e| e.to_string())?;
+    store.delete(key);
+    save(&store)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SettingKind {
+    Bool,
+    String,
+    Object,
+    Array,
+}
+
+impl SettingKind {
+    fn matches(self, value: &Value) -> bool {
+        match self {
+            SettingKind::Bool => value.is_boolean(),
+            SettingKind::String => value.is_string(),
+            SettingKind::Object => value.is_object(),
+            SettingKind::Array => value.is_array(),
+        }
+    }
+}
+
+// Plain preferences that get_setting/set_setting may touch. Keys holding run state, the script
+// index, secret names, roots and other gates, or needing a side effect when they change (hotkeys,
+// watcher, profiles, webhook) go through their own commands instead.
+const GENERIC_SETTINGS: &[(&str, SettingKind)] = &[
+    ("autoChmodScripts", SettingKind::Bool),
+    ("autoStripQuarantine", SettingKind::Bool),
+    ("backupExcludes", SettingKind::Array),
+    ("deepLinkConfirm", SettingKind::Bool),
+    ("editorCommand", SettingKind::String),
+    ("notificationPrefs", SettingKind::Object),
+    ("showOnLaunch", SettingKind::Bool),
+    ("terminal", SettingKind::Object),
+    ("windowAnchor", SettingKind::String),
+    ("windowSize", SettingKind::Object),
+];
+
+fn generic_setting(key: &str) -> Result<SettingKind, String> {
+    GENERIC_SETTINGS
+        .iter()
+        .find(|(name, _)| *name == key)
+        .map(|(_, kind)| *kind)
+        .ok_or_else(|| format!("{} can't be accessed through get_setting/set_setting", key))
+}
+
+// An existing ~/scripts is kept so earlier Linux installs don't lose their folder
+#[cfg(target_os = "linux")]
+pub fn default_scripts_dir() -> Option<PathBuf> {
+    let legacy = dirs::home_dir().map(|p| p.join("scripts"));
+    match legacy {
+        Some(legacy) if legacy.is_dir() => Some(legacy),
+        _ => dirs::data_dir().map(|p| p.join("sh_runner").join("scripts")),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn default_scripts_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|p| p.join("scripts"))
+}
+
+pub fn expand_path(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+// Key of the object the frontend keeps its own settings in, see AppSettings in src/types
+pub const FRONTEND_KEY: &str = "settings";
+
+// One field of the frontend's settings object; Null when it isn't set
+pub fn frontend_field(app: &AppHandle, field: &str) -> Value {
+    let frontend: Value = get(app, FRONTEND_KEY).unwrap_or_default();
+    frontend[field].clone()
+}
+
+// Changes one field of the frontend's settings object and tells the window to read it again,
+// so its next save doesn't put back the old value
+pub fn set_frontend_field<T: Serialize>(
+    app: &AppHandle,
+    field: &str,
+    value: &T,
+) -> Result<(), String> {
+    let mut frontend: Map<String, Value> = get(app, FRONTEND_KEY).unwrap_or_default();
+    let value = serde_json::to_value(value).map_err(|e| e.to_string())?;
+    frontend.insert(field.to_string(), value);
+    set(app, FRONTEND_KEY, &frontend)?;
+    let _ = app.emit("settings-changed", field);
+    Ok(())
+}
+
+// The main scripts folder as configured in the frontend settings, before any symlink is resolved
+pub fn scripts_folder(app: &AppHandle) -> Option<PathBuf> {
+    let frontend: Value = get(app, "settings").unwrap_or_default();
+    match frontend["scriptsFolder"].as_str().filter(|s| !s.is_empty()) {
+        Some(folder) => Some(expand_path(folder)),
+        None => default_scripts_dir(),
+    }
+}
+
+// Roots of the active profile, falling back to the folders configured in the frontend settings
+pub fn scripts_roots(app: &AppHandle) -> Vec<PathBuf> {
+    if let Some(profile) = profiles::active_profile(app).filter(|p| !p.roots.is_empty()) {
+        return profile.roots.iter().map(|r| expand_path(r)).collect();
+    }
+    let frontend: Value = get(app, "settings").unwrap_or_default();
+    let mut roots: Vec<PathBuf> = scripts_folder(app).into_iter().collect();
+    if let Some(extra) = frontend["additionalFolders"].as_array() {
+        roots.extend(extra.iter().filter_map(Value::as_str).map(expand_path));
+    }
+    roots
+}
+
+// Looks up one of the active roots by its configured path, as the frontend knows it
+pub fn scripts_root(app: &AppHandle, root_id: &str) -> Result<PathBuf, String> {
+    let wanted = expand_path(root_id);
+    scripts_roots(app)
+        .into_iter()
+        .find(|root| *root == wanted)
+        .ok_or_else(|| format!("Not a scripts folder: {}", root_id))
+}
+
+#[tauri::command]
+pub fn list_settings_keys(app: AppHandle) -> Result<Vec<String>, String> {
+    let store = app
+        .store(paths::store_file(&app))
+        .map_err(|e| e.to_string())?;
+    let mut keys = store.keys();
+    keys.sort();
+    Ok(keys)
+}
+
+// None when the key is allowed but not set
+#[tauri::command]
+pub fn get_setting(app: AppHandle, key: String) -> Result<Option<Value>, String> {
+    generic_setting(&key)?;
+    Ok(get(&app, &key))
+}
+
+// A null value removes the key again so its default applies
+#[tauri::command]
+pub fn set_setting(app: AppHandle, key: String, value: Value) -> Result<(), String> {
+    let kind = generic_setting(&key)?;
+    if value.is_null() {
+        return delete(&app, &key);
+    }
+    if !kind.matches(&value) {
+        return Err(format!(
+            "{} expects a {} value",
+            key,
+            format!("{:?}", kind).to_lowercase()
+        ));
+    }
+    set(&app, &key, &value)
+}
+
+// Per-script fields the frontend keeps about past runs, preserved across a reset
+const SCRIPT_HISTORY_FIELDS: &[&str] = &[
+    "path",
+    "history",
+    "lastExecution",
+    "lastDuration",
+    "lastOutput",
+    "lastError",
+    "lastExitCode",
+    "lastTimedOut",
+    "runCount",
+];
+
+#[tauri::command]
+pub fn reset_settings(app: AppHandle, clear_history: Option<bool>) -> Result<(), String> {
+    let clear_history = clear_history.unwrap_or(false);
+    let store = app
+        .store(paths::store_file(&app))
+        .map_err(|e| e.to_string())?;
+    let scripts = store.get("scripts");
+    store.clear();
+
+    if clear_history {
+        history::clear(&app)?;
+    } else if let Some(Value::Array(scripts)) = scripts {
+        let kept: Vec<Value> = scripts
+            .into_iter()
+            .filter_map(|script| {
+                let Value::Object(fields) = script else {
+                    return None;
+                };
+                let kept: Map<String, Value> = fields
+                    .into_iter()
+                    .filter(|(key, _)| SCRIPT_HISTORY_FIELDS.contains(&key.as_str()))
+                    .collect();
+                Some(Value::Object(kept))
+            })
+            .collect();
+        store.set("scripts", kept);
+    }
+
+    let scripts_folder = default_scripts_dir()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    store.set("settings", json!({ "scriptsFolder": scripts_folder }));
+    store.save().map_err(|e| e.to_string())?;
+
+    hotkeys::register_toggle_hotkey(&app, hotkeys::DEFAULT_HOTKEY)?;
+    tray::reset_appearance(&app).map_err(|e| e.to_string())?;
+    watcher::restart(&app)?;
+    let _ = app.emit("settings-reset", clear_history);
+    Ok(())
+}
+
+// Mirrors defaultData in src/hooks/useStore.ts so entries created here look like the frontend's
+fn default_script_data(path: &str) -> Map<String, Value> {
+    let Value::Object(data) = json!({
+        "path": path,
+        "lastExecution": null,
+        "lastDuration": null,
+        "lastOutput": null,
+        "lastError": null,
+        "lastExitCode": null,
+        "lastTimedOut": false,
+        "favorite": false,
+        "icon": null,
+        "runCount": 0,
+        "envVars": {},
+        "args": "",
+        "timeoutSeconds": 0,
+        "tags": [],
+        "history": [],
+        "confirmBeforeRun": false,
+    }) else {
+        unreachable!()
+    };
+    data
+}
+
+pub fn script_data(app: &AppHandle, path: &str) -> Option<Map<String, Value>> {
+    let scripts: Vec<Value> = get(app, "scripts").unwrap_or_default();
+    scripts.into_iter().find_map(|script| match script {
+        Value::Object(data) if data.get("path").and_then(Value::as_str) == Some(path) => Some(data),
+        _ => None,
+    })
+}
+
+pub fn update_script_data(
+    app: &AppHandle,
+    path: &str,
+    update: impl FnOnce(&mut Map<String, Value>),
+) -> Result<(), String> {
+    let mut scripts: Vec<Value> = get(app, "scripts").unwrap_or_default();
+    let existing = scripts
+        .iter_mut()
+        .find(|s| s.get("path").and_then(Value::as_str) == Some(path));
+    match existing {
+        Some(Value::Object(data)) => update(data),
+        _ => {
+            let mut data = default_script_data(path);
+            update(&mut data);
+            scripts.push(Value::Object(data));
+        }
+    }
+    set(app, "scripts", &scripts)
+}
+
+// The env vars the frontend keeps for a script, as passed to its runs
+pub fn script_env(app: &AppHandle, path: &str) -> Vec<(String, String)> {
+    script_data(app, path)
+        .and_then(|data| data.get("envVars")?.as_object().cloned())
+        .map(|vars| {
+            vars.into_iter()
+                .filter_map(|(key, value)| Some((key, value.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// `secretEnv` maps env var names to keychain secret names; only the names live in the store
+pub fn script_secret_refs(app: &AppHandle, path: &str) -> Vec<(String, String)> {
+    script_data(app, path)
+        .and_then(|data| data.get("secretEnv")?.as_object().cloned())
+        .map(|refs| {
+            refs.into_iter()
+                .filter_map(|(var, name)| Some((var, name.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Run state that belongs to the original script and shouldn't follow a copy
+const RUN_STATE_KEYS: &[&str] = &[
+    "lastExecution",
+    "lastDuration",
+    "lastOutput",
+    "lastError",
+    "lastExitCode",
+    "lastTimedOut",
+    "runCount",
+    "history",
+];
+
+pub fn rename_script_data(app: &AppHandle, from: &str, to: &str) -> Result<(), String> {
+    let mut scripts: Vec<Value> = get(app, "scripts").unwrap_or_default();
+    scripts.retain(|s| s.get("path").and_then(Value::as_str) != Some(to));
+    for script in &mut scripts {
+        if let Value::Object(data) = script {
+            if data.get("path").and_then(Value::as_str) == Some(from) {
+                data.insert("path".to_string(), json!(to));
+            }
+        }
+    }
+    set(app, "scripts", &scripts)
+}
+
+// Copies settings such as args, env vars, tags and the favorite flag, but not run stats
+pub fn copy_script_data(app: &AppHandle, from: &str, to: &str) -> Result<(), String> {
+    let Some(source) = script_data(app, from) else {
+        return Ok(());
+    };
+    update_script_data(app, to, |data| {
+        let defaults = default_script_data(to);
+        *data = source;
+        data.insert("path".to_string(), json!(to));
+        for key in RUN_STATE_KEYS {
+            data.insert(key.to_string(), defaults[*key].clone());
+        }
+    })
+}
+
+// Every script the store keeps settings for, whether or not its file still exists
+pub fn script_data_paths(app: &AppHandle) -> Vec<String> {
+    let scripts: Vec<Value> = get(app, "scripts").unwrap_or_default();
+    scripts
+        .iter()
+        .filter_map(|s| s.get("path").and_then(Value::as_str).map(str::to_string))
+        .collect()
+}
+
+pub fn remove_script_data(app: &AppHandle, path: &str) -> Result<(), String> {
+    let mut scripts: Vec<Value> = get(app, "scripts").unwrap_or_default();
+    scripts.retain(|s| s.get("path").and_then(Value::as_str) != Some(path));
+    set(app, "scripts", &scripts)
+}
@@ -0,0 +1,220 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use similar::TextDiff;
+use tauri::AppHandle;
+
+use crate::{history, index, paths, scripts, settings};
+
+// `code` of the JSON error returned when a script with `verifyContent` changed since its last
+// successful run and was started without `confirmed`
+pub const CONTENT_CHANGED_ERROR: &str = "content-changed";
+const SNAPSHOTS_DIR: &str = "snapshots";
+
+// The script as it was when it last ran successfully
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Snapshot {
+    path: String,
+    hash: String,
+    mtime_ms: Option<u64>,
+    taken_at: u64,
+    // None for scripts that aren't valid UTF-8; the hash still catches changes
+    content: Option<String>,
+}
+
+// Read right before a run starts, saved only if the run succeeds
+pub struct Current {
+    hash: String,
+    mtime_ms: Option<u64>,
+    content: Option<String>,
+}
+
+impl Current {
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    pub fn content(&self) -> Option<&str> {
+        self.content.as_deref()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentChange {
+    pub old_hash: String,
+    pub new_hash: String,
+    pub old_mtime_ms: Option<u64>,
+    pub new_mtime_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ContentChanged<'a> {
+    code: &'static str,
+    path: &'a str,
+    #[serde(flatten)]
+    change: &'a ContentChange,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptDiff {
+    pub path: String,
+    // False when the script never ran successfully, so there is nothing to compare against
+    pub has_snapshot: bool,
+    pub changed: bool,
+    pub snapshot_taken_at: Option<u64>,
+    // Unified diff from the snapshot to the current content, empty when unchanged
+    pub diff: String,
+}
+
+fn snapshot_file(app: &AppHandle, path: &str) -> Result<PathBuf, String> {
+    let name = format!("{}.json", index::hash_bytes(path.as_bytes()));
+    paths::data_dir(app).map(|dir| dir.join(SNAPSHOTS_DIR).join(name))
+}
+
+fn load(app: &AppHandle, path: &str) -> Option<Snapshot> {
+    let content = fs::read_to_string(snapshot_file(app, path).ok()?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+pub fn mtime_ms(script: &Path) -> Option<u64> {
+    let modified = fs::metadata(script).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64)
+}
+
+pub fn read(script: &Path) -> Result<Current, String> {
+    let bytes =
+        fs::read(script).map_err(|e| format!("Failed to read {}: {}", script.display(), e))?;
+    Ok(Current {
+        hash: index::hash_bytes(&bytes),
+        mtime_ms: mtime_ms(script),
+        content: String::from_utf8(bytes).ok(),
+    })
+}
+
+// Set per script by the frontend; changed scripts then need `confirmed` to run
+fn verify_required(app: &AppHandle, path: &str) -> bool {
+    settings::script_data(app, path)
+        .and_then(|data| data.get("verifyContent").and_then(Value::as_bool))
+        .unwrap_or(false)
+}
+
+// None on the first run and whenever the content matches the last successful run
+pub fn check(
+    app: &AppHandle,
+    path: &str,
+    current: &Current,
+    confirmed: bool,
+) -> Result<Option<ContentChange>, String> {
+    let Some(snapshot) = load(app, path).filter(|s| s.hash != current.hash) else {
+        return Ok(None);
+    };
+    let change = ContentChange {
+        old_hash: snapshot.hash,
+        new_hash: current.hash.clone(),
+        old_mtime_ms: snapshot.mtime_ms,
+        new_mtime_ms: current.mtime_ms,
+    };
+    if !confirmed && verify_required(app, path) {
+        let error = ContentChanged {
+            code: CONTENT_CHANGED_ERROR,
+            path,
+            change: &change,
+        };
+        return Err(serde_json::to_string(&error).map_err(|e| e.to_string())?);
+    }
+    Ok(Some(change))
+}
+
+pub fn save(app: &AppHandle, path: &str, current: Current) -> Result<(), String> {
+    let file = snapshot_file(app, path)?;
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let snapshot = Snapshot {
+        path: path.to_string(),
+        hash: current.hash,
+        mtime_ms: current.mtime_ms,
+        taken_at: history::now_ms(),
+        content: current.content,
+    };
+    let json = serde_json::to_string(&snapshot).map_err(|e| e.to_string())?;
+    fs::write(file, json).map_err(|e| e.to_string())
+}
+
+// Keeps the snapshot with a renamed script, or drops it with `to` None on delete
+pub fn move_snapshot(app: &AppHandle, from: &str, to: Option<&str>) -> Result<(), String> {
+    let Some(mut snapshot) = load(app, from) else {
+        return Ok(());
+    };
+    let from_file = snapshot_file(app, from)?;
+    if let Some(to) = to {
+        snapshot.path = to.to_string();
+        let json = serde_json::to_string(&snapshot).map_err(|e| e.to_string())?;
+        fs::write(snapshot_file(app, to)?, json).map_err(|e| e.to_string())?;
+    }
+    fs::remove_file(from_file).map_err(|e| e.to_string())
+}
+
+// The scripts with a snapshot, whether or not their files still exist
+pub fn paths(app: &AppHandle) -> Result<Vec<String>, String> {
+    let dir = paths::data_dir(app)?.join(SNAPSHOTS_DIR);
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(Vec::new());
+    };
+    Ok(entries
+        .flatten()
+        .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+        .filter_map(|content| serde_json::from_str::<Snapshot>(&content).ok())
+        .map(|snapshot| snapshot.path)
+        .collect())
+}
+
+// Moves every snapshot whose script `rename` maps to a new path
+pub fn rename_paths(
+    app: &AppHandle,
+    rename: impl Fn(&str) -> Option<String>,
+) -> Result<(), String> {
+    for path in self::paths(app)? {
+        if let Some(to) = rename(&path) {
+            move_snapshot(app, &path, Some(&to))?;
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn show_script_diff(app: AppHandle, script_id: String) -> Result<ScriptDiff, String> {
+    let current = read(&scripts::allowed_script(&app, &script_id)?)?;
+    let Some(snapshot) = load(&app, &script_id) else {
+        return Ok(ScriptDiff {
+            path: script_id,
+            has_snapshot: false,
+            changed: false,
+            snapshot_taken_at: None,
+            diff: String::new(),
+        });
+    };
+    let changed = snapshot.hash != current.hash;
+    let diff = match (changed, &snapshot.content, &current.content) {
+        (false, _, _) => String::new(),
+        (true, Some(old), Some(new)) => TextDiff::from_lines(old, new)
+            .unified_diff()
+            .header("last successful run", "current")
+            .to_string(),
+        (true, _, _) => "Binary content differs".to_string(),
+    };
+    Ok(ScriptDiff {
+        path: script_id,
+        has_snapshot: true,
+        changed,
+        snapshot_taken_at: Some(snapshot.taken_at),
+        diff,
+    })
+}
@@ -0,0 +1,88 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tauri::AppHandle;
+
+use crate::runner::{self, RunOptions, ScriptResult};
+use crate::{editor, scripts};
+
+// Prefix of the error for scripts without a staged edit
+pub const NOTHING_STAGED_ERROR: &str = "nothing-staged";
+
+// `.name.sh.staged` next to the script: hidden, not a .sh, and on the same filesystem so
+// promoting it is a rename
+pub fn shadow_path(script: &Path) -> PathBuf {
+    let name = script
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    script.with_file_name(format!(".{}.staged", name))
+}
+
+pub fn has_staged(script: &Path) -> bool {
+    shadow_path(script).is_file()
+}
+
+pub fn require(script: &Path) -> Result<PathBuf, String> {
+    let shadow = shadow_path(script);
+    if !shadow.is_file() {
+        return Err(format!(
+            "{}: {} has no staged edit",
+            NOTHING_STAGED_ERROR,
+            script.display()
+        ));
+    }
+    Ok(shadow)
+}
+
+// The real file's permissions, so the shadow runs and promotes with its exec bit
+fn copy_permissions(script: &Path, shadow: &Path) -> Result<(), String> {
+    let permissions = fs::metadata(script)
+        .map_err(|e| e.to_string())?
+        .permissions();
+    fs::set_permissions(shadow, permissions).map_err(|e| e.to_string())
+}
+
+// Replaces any earlier staged edit; the script itself is left alone
+#[tauri::command]
+pub fn stage_script_edit(app: AppHandle, script_id: String, content: String) -> Result<(), String> {
+    let script = scripts::managed_script(&app, &script_id)?;
+    editor::check_content(&content)?;
+    let shadow = shadow_path(&script);
+    fs::write(&shadow, content)
+        .map_err(|e| format!("Failed to stage {}: {}", script.display(), e))?;
+    copy_permissions(&script, &shadow)?;
+    scripts::notify_scripts_changed(&app, vec![script_id]);
+    Ok(())
+}
+
+// Runs the staged copy like any other run of the script; only this command runs it
+#[tauri::command]
+pub async fn run_staged(app: AppHandle, script_id: String) -> Result<ScriptResult, String> {
+    require(Path::new(&script_id))?;
+    let options = RunOptions {
+        staged: true,
+        ..RunOptions::default()
+    };
+    runner::run_script(app, script_id, Some(options)).await
+}
+
+#[tauri::command]
+pub fn promote_staged(app: AppHandle, script_id: String) -> Result<(), String> {
+    let script = scripts::managed_script(&app, &script_id)?;
+    let shadow = require(&script)?;
+    copy_permissions(&script, &shadow)?;
+    fs::rename(&shadow, &script)
+        .map_err(|e| format!("Failed to promote {}: {}", script.display(), e))?;
+    scripts::notify_scripts_changed(&app, vec![script_id]);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn discard_staged(app: AppHandle, script_id: String) -> Result<(), String> {
+    let script = scripts::managed_script(&app, &script_id)?;
+    let shadow = require(&script)?;
+    fs::remove_file(&shadow).map_err(|e| e.to_string())?;
+    scripts::notify_scripts_changed(&app, vec![script_id]);
+    Ok(())
+}
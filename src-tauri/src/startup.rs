@@ -0,0 +1,159 @@
+use std::sync::{LazyLock, Mutex, OnceLock};
+use std::thread;
+use std::time::Instant;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+// Error prefix for commands that need a subsystem which is still loading
+pub const INITIALIZING_ERROR: &str = "initializing";
+
+static STARTED: OnceLock<Instant> = OnceLock::new();
+static PHASES: LazyLock<Mutex<Vec<PhaseTiming>>> = LazyLock::new(Mutex::default);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Phase {
+    Tray,
+    Window,
+    History,
+    Scripts,
+    LoginPath,
+    // The queue worker, Do Not Disturb expiry and the weekly digest
+    Scheduler,
+}
+
+impl Phase {
+    fn label(self) -> &'static str {
+        match self {
+            Phase::Tray => "The tray",
+            Phase::Window => "The window",
+            Phase::History => "Run history",
+            Phase::Scripts => "The scripts index",
+            Phase::LoginPath => "The login shell PATH",
+            Phase::Scheduler => "The run queue",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseTiming {
+    pub phase: Phase,
+    // Since the app process started
+    pub started_ms: u64,
+    // None while the phase is still running
+    pub duration_ms: Option<u64>,
+    // Ran after setup returned, off the main thread
+    pub background: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupTimings {
+    // In the order the phases started
+    pub phases: Vec<PhaseTiming>,
+    // Every phase has finished
+    pub ready: bool,
+    // From process start to the last phase finishing; None until then
+    pub total_ms: Option<u64>,
+}
+
+// Called first thing in run() so timings include plugin setup
+pub fn begin() {
+    STARTED.get_or_init(Instant::now);
+}
+
+fn elapsed_ms() -> u64 {
+    STARTED.get_or_init(Instant::now).elapsed().as_millis() as u64
+}
+
+fn start(phase: Phase, background: bool) {
+    if let Ok(mut phases) = PHASES.lock() {
+        phases.push(PhaseTiming {
+            phase,
+            started_ms: elapsed_ms(),
+            duration_ms: None,
+            background,
+        });
+    }
+}
+
+fn finish(phase: Phase) -> Option<PhaseTiming> {
+    let mut phases = PHASES.lock().ok()?;
+    let timing = phases.iter_mut().find(|t| t.phase == phase)?;
+    let duration = elapsed_ms().saturating_sub(timing.started_ms);
+    timing.duration_ms = Some(duration);
+    log::info!("Startup: {:?} took {} ms", phase, duration);
+    Some(timing.clone())
+}
+
+fn timings() -> StartupTimings {
+    let phases = PHASES.lock().map(|p| p.clone()).unwrap_or_default();
+    let ready = phases.iter().all(|t| t.duration_ms.is_some());
+    let total_ms = ready
+        .then(|| {
+            phases
+                .iter()
+                .filter_map(|t| t.duration_ms.map(|d| t.started_ms + d))
+                .max()
+        })
+        .flatten();
+    StartupTimings {
+        phases,
+        ready,
+        total_ms,
+    }
+}
+
+// Runs a phase of setup itself, on the main thread
+pub fn measure<T>(phase: Phase, work: impl FnOnce() -> T) -> T {
+    start(phase, false);
+    let result = work();
+    finish(phase);
+    result
+}
+
+// Runs a phase after setup on its own thread. A startup-phase-ready event with its timing
+// follows, and startup-ready with every timing once the last phase is done.
+pub fn defer(app: &AppHandle, phase: Phase, work: impl FnOnce(&AppHandle) + Send + 'static) {
+    start(phase, true);
+    let app = app.clone();
+    thread::spawn(move || {
+        work(&app);
+        if let Some(timing) = finish(phase) {
+            let _ = app.emit("startup-phase-ready", &timing);
+        }
+        let timings = timings();
+        if timings.ready {
+            let _ = app.emit("startup-ready", &timings);
+        }
+    });
+}
+
+// Phases never started count as ready, so a subsystem that wasn't deferred isn't held back
+pub fn is_ready(phase: Phase) -> bool {
+    PHASES.lock().is_ok_and(|phases| {
+        phases
+            .iter()
+            .find(|t| t.phase == phase)
+            .is_none_or(|t| t.duration_ms.is_some())
+    })
+}
+
+pub fn guard(phase: Phase) -> Result<(), String> {
+    if is_ready(phase) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{}: {} is still loading",
+            INITIALIZING_ERROR,
+            phase.label()
+        ))
+    }
+}
+
+#[tauri::command]
+pub fn get_startup_timings() -> Result<StartupTimings, String> {
+    Ok(timings())
+}
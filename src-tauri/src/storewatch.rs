@@ -0,0 +1,138 @@
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_store::StoreExt;
+
+use crate::{hotkeys, paths, settings, tray, watcher, webhook};
+
+const AUTO_RELOAD_KEY: &str = "settingsAutoReload";
+// Editors and sync tools often write a file in several steps
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Default)]
+pub struct StoreWatchState {
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+fn auto_reload(app: &AppHandle) -> bool {
+    settings::get(app, AUTO_RELOAD_KEY).unwrap_or(true)
+}
+
+// What's on disk, or None while it can't be read or is only partly written
+fn on_disk(path: &Path) -> Option<Map<String, Value>> {
+    serde_json::from_slice(&fs::read(path).ok()?).ok()
+}
+
+// Reads the store file again and re-applies the settings the backend acts on. False when the
+// file is unreadable or already matches what's loaded, as after the app's own saves.
+fn reload(app: &AppHandle) -> Result<bool, String> {
+    let path = paths::store_file(app);
+    let store = app.store(&path).map_err(|e| e.to_string())?;
+    let Some(stored) = on_disk(&path) else {
+        return Ok(false);
+    };
+    let loaded: Map<String, Value> = store.entries().into_iter().collect();
+    if stored == loaded {
+        return Ok(false);
+    }
+    let bindings = hotkeys::bindings(app);
+    store.reload_ignore_defaults().map_err(|e| e.to_string())?;
+    hotkeys::reapply(app, bindings);
+    tray::refresh_status(app);
+    if let Err(e) = watcher::restart(app) {
+        log::warn!(
+            "Scripts watcher not restarted after a settings reload: {}",
+            e
+        );
+    }
+    if let Err(e) = webhook::restart(app) {
+        log::warn!(
+            "Webhook server not restarted after a settings reload: {}",
+            e
+        );
+    }
+    let _ = app.emit("settings-reloaded", ());
+    Ok(true)
+}
+
+fn is_store_event(event: &Event, file_name: &str) -> bool {
+    !event.kind.is_access()
+        && event
+            .paths
+            .iter()
+            .any(|p| p.file_name().is_some_and(|n| n == file_name))
+}
+
+// Waits for the file to be quiet for DEBOUNCE before reloading, once per burst of writes
+fn debounce_events(app: AppHandle, rx: Receiver<notify::Result<Event>>) {
+    let file_name = settings::STORE_FILE;
+    let mut pending = false;
+    loop {
+        let received = if pending {
+            rx.recv_timeout(DEBOUNCE)
+        } else {
+            rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        };
+        match received {
+            Ok(Ok(event)) => {
+                // A save of ours shows up here while it's still being written
+                if is_store_event(&event, file_name) && !settings::writing() {
+                    pending = true;
+                }
+            }
+            Ok(Err(e)) => log::warn!("Settings watcher error: {}", e),
+            Err(RecvTimeoutError::Timeout) => {
+                pending = false;
+                match reload(&app) {
+                    Ok(true) => log::info!("Reloaded settings changed outside the app"),
+                    Ok(false) => {}
+                    Err(e) => log::warn!("Failed to reload settings: {}", e),
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
+
+// Watches the folder rather than the file, since editors and sync tools often replace it
+pub fn restart(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<StoreWatchState>();
+    let mut current = state.watcher.lock().map_err(|e| e.to_string())?;
+    // Dropping the previous watcher also ends its debounce thread
+    *current = None;
+    if !auto_reload(app) {
+        return Ok(());
+    }
+    let path = paths::store_file(app);
+    let Some(dir) = path.parent().filter(|dir| dir.is_dir()) else {
+        return Err(format!("Settings folder not found for {}", path.display()));
+    };
+    let (tx, rx) = mpsc::channel();
+    let mut store_watcher = notify::recommended_watcher(tx).map_err(|e| e.to_string())?;
+    store_watcher
+        .watch(dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch {}: {}", dir.display(), e))?;
+    *current = Some(store_watcher);
+    let app = app.clone();
+    thread::spawn(move || debounce_events(app, rx));
+    Ok(())
+}
+
+// True when the file differed from what was loaded
+#[tauri::command]
+pub fn reload_settings(app: AppHandle) -> Result<bool, String> {
+    reload(&app)
+}
+
+#[tauri::command]
+pub fn set_settings_auto_reload(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set(&app, AUTO_RELOAD_KEY, &enabled)?;
+    restart(&app)
+}
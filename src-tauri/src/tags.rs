@@ -0,0 +1,148 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+
+use crate::{index, settings, tray, window};
+
+// Show a "Tags" submenu in the tray
+const TRAY_TAGS_KEY: &str = "trayTagsMenu";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagMatch {
+    #[default]
+    Any,
+    All,
+}
+
+// Optional filter on the listing and search commands
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TagFilter {
+    pub tags: Vec<String>,
+    pub mode: TagMatch,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FilterEvent<'a> {
+    tag: &'a str,
+}
+
+// Tags compare trimmed and case-insensitively, and are reported lowercased
+pub fn normalize(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+// `@tags` from each script's header merged with the tags assigned in the app, by path
+pub fn tags_by_path(app: &AppHandle) -> HashMap<String, BTreeSet<String>> {
+    let mut tags: HashMap<String, BTreeSet<String>> = HashMap::new();
+    for root in settings::scripts_roots(app) {
+        for script in index::metadata_index(&root).scripts {
+            tags.entry(script.path)
+                .or_default()
+                .extend(script.tags.iter().map(|t| normalize(t)));
+        }
+    }
+    let stored: Vec<Value> = settings::get(app, "scripts").unwrap_or_default();
+    for data in stored {
+        let (Some(path), Some(assigned)) = (
+            data.get("path").and_then(Value::as_str),
+            data.get("tags").and_then(Value::as_array),
+        ) else {
+            continue;
+        };
+        // Settings can outlive their script; only scripts that exist are counted
+        if let Some(set) = tags.get_mut(path) {
+            set.extend(assigned.iter().filter_map(Value::as_str).map(normalize));
+        }
+    }
+    for set in tags.values_mut() {
+        set.remove("");
+    }
+    tags
+}
+
+impl TagFilter {
+    fn matches(&self, tags: Option<&BTreeSet<String>>) -> bool {
+        let wanted: Vec<String> = self
+            .tags
+            .iter()
+            .map(|t| normalize(t))
+            .filter(|t| !t.is_empty())
+            .collect();
+        if wanted.is_empty() {
+            return true;
+        }
+        let Some(tags) = tags else {
+            return false;
+        };
+        match self.mode {
+            TagMatch::Any => wanted.iter().any(|t| tags.contains(t)),
+            TagMatch::All => wanted.iter().all(|t| tags.contains(t)),
+        }
+    }
+}
+
+// Keeps only the paths whose tags pass `filter`; no filter keeps everything
+pub fn retain<T>(
+    app: &AppHandle,
+    items: &mut Vec<T>,
+    filter: Option<&TagFilter>,
+    path: impl Fn(&T) -> &str,
+) {
+    let Some(filter) = filter.filter(|f| !f.tags.is_empty()) else {
+        return;
+    };
+    let tags = tags_by_path(app);
+    items.retain(|item| filter.matches(tags.get(path(item))));
+}
+
+// Names for the tray submenu, or nothing when it is turned off
+pub fn tray_tags(app: &AppHandle) -> Vec<String> {
+    if !settings::get::<bool>(app, TRAY_TAGS_KEY).unwrap_or(false) {
+        return Vec::new();
+    }
+    let tags: BTreeSet<String> = tags_by_path(app).into_values().flatten().collect();
+    tags.into_iter().collect()
+}
+
+// Tray entry point: the window opens filtered to the tag
+pub fn show_filtered(app: &AppHandle, tag: &str) {
+    window::show_main_window(app);
+    let _ = app.emit("filter-by-tag", FilterEvent { tag });
+}
+
+// Most used first, then alphabetical
+#[tauri::command]
+pub async fn get_tags(app: AppHandle) -> Result<Vec<TagCount>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for tag in tags_by_path(&app).into_values().flatten() {
+            *counts.entry(tag).or_default() += 1;
+        }
+        let mut tags: Vec<TagCount> = counts
+            .into_iter()
+            .map(|(tag, count)| TagCount { tag, count })
+            .collect();
+        tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+        tags
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_tray_tags_menu(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set(&app, TRAY_TAGS_KEY, &enabled)?;
+    tray::rebuild_menu(&app).map_err(|e| e.to_string())
+}
@@ -0,0 +1,182 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tauri::AppHandle;
+
+use crate::{history, paths, scripts, settings};
+
+const BASH_TEMPLATE: &str = r#"#!/usr/bin/env bash
+# @name: {{name}}
+# Created {{date}}
+set -euo pipefail
+IFS=$'\n\t'
+
+main() {
+    echo "Hello from {{name}}"
+}
+
+main "$@"
+"#;
+
+const PYTHON_TEMPLATE: &str = r#"#!/usr/bin/env python3
+# @name: {{name}}
+# Created {{date}}
+import sys
+
+
+def main(args):
+    print("Hello from {{name}}")
+
+
+if __name__ == "__main__":
+    main(sys.argv[1:])
+"#;
+
+const NODE_TEMPLATE: &str = r#"#!/usr/bin/env node
+// @name: {{name}}
+// Created {{date}}
+const args = process.argv.slice(2);
+
+console.log("Hello from {{name}}");
+"#;
+
+const BUILT_IN: &[(&str, &str, &str)] = &[
+    ("bash", "Bash (strict mode)", BASH_TEMPLATE),
+    ("python", "Python 3", PYTHON_TEMPLATE),
+    ("node", "Node.js", NODE_TEMPLATE),
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptTemplate {
+    pub id: String,
+    pub name: String,
+    pub built_in: bool,
+}
+
+// User templates are plain files in <app config>/templates, identified by file name
+fn user_templates_dir(app: &AppHandle) -> Option<PathBuf> {
+    paths::config_dir(app)
+        .ok()
+        .map(|dir| dir.join(paths::TEMPLATES_DIR))
+}
+
+fn user_templates(app: &AppHandle) -> Vec<PathBuf> {
+    let Some(Ok(entries)) = user_templates_dir(app).map(fs::read_dir) else {
+        return Vec::new();
+    };
+    let mut templates: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+    templates.sort();
+    templates
+}
+
+fn template_content(app: &AppHandle, template_id: &str) -> Result<String, String> {
+    if let Some((_, _, content)) = BUILT_IN.iter().find(|(id, _, _)| *id == template_id) {
+        return Ok(content.to_string());
+    }
+    let path = user_templates(app)
+        .into_iter()
+        .find(|path| path.file_name().is_some_and(|name| name == template_id))
+        .ok_or_else(|| format!("Template not found: {}", template_id))?;
+    fs::read_to_string(&path).map_err(|e| e.to_string())
+}
+
+fn today() -> String {
+    civil_date(history::now_ms())
+}
+
+// UTC calendar date of an epoch timestamp in ms (Howard Hinnant's algorithm)
+pub fn civil_date(ms: u64) -> String {
+    let days = (ms / 86_400_000) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn render(template: &str, path: &Path) -> String {
+    let name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let filename = path
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    template
+        .replace("{{name}}", &name)
+        .replace("{{filename}}", &filename)
+        .replace("{{date}}", &today())
+}
+
+#[tauri::command]
+pub fn list_script_templates(app: AppHandle) -> Result<Vec<ScriptTemplate>, String> {
+    let built_in = BUILT_IN.iter().map(|(id, name, _)| ScriptTemplate {
+        id: id.to_string(),
+        name: name.to_string(),
+        built_in: true,
+    });
+    let user = user_templates(&app).into_iter().filter_map(|path| {
+        let id = path.file_name()?.to_string_lossy().to_string();
+        Some(ScriptTemplate {
+            name: id.clone(),
+            id,
+            built_in: false,
+        })
+    });
+    Ok(built_in.chain(user).collect())
+}
+
+// `root_id` is one of the scripts roots; a relative path without an extension gets `.sh`
+#[tauri::command]
+pub fn create_script(
+    app: AppHandle,
+    root_id: String,
+    relative_path: String,
+    template_id: String,
+) -> Result<Map<String, Value>, String> {
+    let root = settings::scripts_root(&app, &root_id)?;
+    let mut relative = scripts::safe_relative(Path::new(&relative_path))
+        .ok_or_else(|| format!("Invalid script path: {}", relative_path))?;
+    if relative.extension().is_none() {
+        relative.set_extension("sh");
+    }
+    let target = root.join(&relative);
+    let content = render(&template_content(&app, &template_id)?, &target);
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&target)
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => {
+                format!("A file already exists at {}", target.display())
+            }
+            _ => e.to_string(),
+        })?;
+    file.write_all(content.as_bytes())
+        .map_err(|e| e.to_string())?;
+    drop(file);
+    scripts::set_user_executable(&target).map_err(|e| e.to_string())?;
+
+    let path = target.to_string_lossy().to_string();
+    settings::update_script_data(&app, &path, |_| {})?;
+    scripts::notify_scripts_changed(&app, vec![path.clone()]);
+    settings::script_data(&app, &path).ok_or_else(|| format!("Failed to register {}", path))
+}
@@ -0,0 +1,301 @@
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_store::StoreExt;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::backup::{self, ArchiveFormat, ConflictPolicy};
+use crate::history::{self, HistoryEntry};
+use crate::migrate::{rebase, rewrite};
+use crate::{hotkeys, paths, scripts, settings, watcher};
+
+const FORMAT_VERSION: u32 = 1;
+const MANIFEST_FILE: &str = "manifest.json";
+const SETTINGS_FILE: &str = "settings.json";
+const HISTORY_FILE: &str = "history.json";
+const SCRIPTS_PREFIX: &str = "scripts";
+// Per-script data is merged script by script rather than as one key
+const SCRIPTS_KEY: &str = "scripts";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct Manifest {
+    format: u32,
+    // Where the scripts came from, so paths in settings and history can be moved to the new folder
+    scripts_folder: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub scripts_imported: usize,
+    pub settings_merged: usize,
+    pub history_imported: usize,
+    // Scripts, settings keys, settings fields, per-script entries and runs already here that
+    // were left alone
+    pub conflicts_skipped: usize,
+    // Archive entries that would have landed outside the scripts folder
+    pub rejected: Vec<String>,
+}
+
+fn write_json(zip: &mut ZipWriter<File>, name: &str, value: &impl Serialize) -> Result<(), String> {
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file(name, options).map_err(|e| e.to_string())?;
+    let json = serde_json::to_vec_pretty(value).map_err(|e| e.to_string())?;
+    zip.write_all(&json).map_err(|e| e.to_string())
+}
+
+fn write_bundle(app: &AppHandle, dest: &Path) -> Result<(), String> {
+    let folder = settings::scripts_folder(app)
+        .ok_or_else(|| "No scripts folder is configured".to_string())?;
+    let store = app
+        .store(paths::store_file(app))
+        .map_err(|e| e.to_string())?;
+    let stored: Map<String, Value> = store.entries().into_iter().collect();
+    let mut runs = Vec::new();
+    history::visit(app, |entry| runs.push(entry.clone()));
+
+    let file = File::create(dest).map_err(|e| e.to_string())?;
+    let mut zip = ZipWriter::new(file);
+    let manifest = Manifest {
+        format: FORMAT_VERSION,
+        scripts_folder: folder.to_string_lossy().to_string(),
+    };
+    write_json(&mut zip, MANIFEST_FILE, &manifest)?;
+    write_json(&mut zip, SETTINGS_FILE, &stored)?;
+    write_json(&mut zip, HISTORY_FILE, &runs)?;
+    if folder.is_dir() {
+        backup::append_folder(&mut zip, &folder, SCRIPTS_PREFIX)?;
+    }
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn read_json<T: DeserializeOwned>(
+    zip: &mut ZipArchive<File>,
+    name: &str,
+) -> Result<Option<T>, String> {
+    let Ok(mut entry) = zip.by_name(name) else {
+        return Ok(None);
+    };
+    let mut text = String::new();
+    entry.read_to_string(&mut text).map_err(|e| e.to_string())?;
+    serde_json::from_str(&text)
+        .map(Some)
+        .map_err(|e| format!("{}: {}", name, e))
+}
+
+fn script_path(data: &Value) -> Option<&str> {
+    data.get("path").and_then(Value::as_str)
+}
+
+// Adds scripts missing here and, with `overwrite`, replaces those that differ; returns how
+// many were taken and how many were left alone
+fn merge_script_data(
+    local: &mut Vec<Value>,
+    incoming: Vec<Value>,
+    overwrite: bool,
+) -> (usize, usize) {
+    let (mut merged, mut skipped) = (0, 0);
+    for data in incoming {
+        let Some(path) = script_path(&data).map(str::to_string) else {
+            continue;
+        };
+        match local
+            .iter_mut()
+            .find(|s| script_path(s) == Some(path.as_str()))
+        {
+            None => {
+                local.push(data);
+                merged += 1;
+            }
+            Some(existing) if *existing == data => {}
+            Some(existing) if overwrite => {
+                *existing = data;
+                merged += 1;
+            }
+            Some(_) => skipped += 1,
+        }
+    }
+    (merged, skipped)
+}
+
+// The frontend's settings object, field by field, so an import doesn't replace every preference
+// over one that differs; returns how many fields were taken and how many were left alone
+fn merge_fields(
+    local: &mut Map<String, Value>,
+    incoming: Map<String, Value>,
+    overwrite: bool,
+) -> (usize, usize) {
+    let (mut merged, mut skipped) = (0, 0);
+    for (field, value) in incoming {
+        match local.get(&field) {
+            Some(existing) if *existing == value => {}
+            Some(_) if !overwrite => skipped += 1,
+            _ => {
+                local.insert(field, value);
+                merged += 1;
+            }
+        }
+    }
+    (merged, skipped)
+}
+
+fn merge_settings(
+    app: &AppHandle,
+    bundled: Map<String, Value>,
+    overwrite: bool,
+    report: &mut ImportReport,
+) -> Result<(), String> {
+    let store = app
+        .store(paths::store_file(app))
+        .map_err(|e| e.to_string())?;
+    for (key, value) in bundled {
+        let existing = store.get(&key);
+        if key == SCRIPTS_KEY {
+            let (Value::Array(incoming), local) = (value, existing) else {
+                continue;
+            };
+            let mut local = match local {
+                Some(Value::Array(local)) => local,
+                _ => Vec::new(),
+            };
+            let (merged, skipped) = merge_script_data(&mut local, incoming, overwrite);
+            if merged > 0 {
+                store.set(SCRIPTS_KEY, local);
+                report.settings_merged += 1;
+            }
+            report.conflicts_skipped += skipped;
+            continue;
+        }
+        if key == settings::FRONTEND_KEY {
+            if let (Value::Object(incoming), None | Some(Value::Object(_))) = (&value, &existing) {
+                let mut local = match existing {
+                    Some(Value::Object(local)) => local,
+                    _ => Map::new(),
+                };
+                let (merged, skipped) = merge_fields(&mut local, incoming.clone(), overwrite);
+                if merged > 0 {
+                    store.set(settings::FRONTEND_KEY, local);
+                }
+                report.settings_merged += merged;
+                report.conflicts_skipped += skipped;
+                continue;
+            }
+        }
+        match existing {
+            Some(existing) if existing == value => {}
+            Some(_) if !overwrite => report.conflicts_skipped += 1,
+            _ => {
+                store.set(key, value);
+                report.settings_merged += 1;
+            }
+        }
+    }
+    store.save().map_err(|e| e.to_string())
+}
+
+fn import_bundle(app: &AppHandle, archive: &Path, overwrite: bool) -> Result<ImportReport, String> {
+    let file = File::open(archive).map_err(|e| e.to_string())?;
+    let mut zip = ZipArchive::new(file).map_err(|e| e.to_string())?;
+    let manifest: Manifest = read_json(&mut zip, MANIFEST_FILE)?
+        .ok_or_else(|| format!("{} is not a profile export", archive.display()))?;
+    if manifest.format > FORMAT_VERSION {
+        return Err(format!(
+            "{} was exported by a newer version of sh_runner",
+            archive.display()
+        ));
+    }
+    let mut bundled: Map<String, Value> = read_json(&mut zip, SETTINGS_FILE)?.unwrap_or_default();
+    let mut runs: Vec<HistoryEntry> = read_json(&mut zip, HISTORY_FILE)?.unwrap_or_default();
+    drop(zip);
+
+    let folder = settings::scripts_folder(app)
+        .ok_or_else(|| "No scripts folder is configured".to_string())?;
+    let to = folder.to_string_lossy().to_string();
+    let policy = if overwrite {
+        ConflictPolicy::Overwrite
+    } else {
+        ConflictPolicy::Skip
+    };
+    let placed = backup::import_zip_folder(archive, SCRIPTS_PREFIX, &folder, policy)?;
+    let mut report = ImportReport {
+        scripts_imported: placed.created.len(),
+        conflicts_skipped: placed.skipped.len(),
+        rejected: placed.rejected,
+        ..ImportReport::default()
+    };
+
+    // The folder is kept as configured here; paths under the old one are moved below it
+    let from: Vec<String> = Some(manifest.scripts_folder)
+        .filter(|old| !old.is_empty() && *old != to)
+        .into_iter()
+        .collect();
+    if !from.is_empty() {
+        for value in bundled.values_mut() {
+            rewrite(value, &from, &to);
+        }
+        for run in &mut runs {
+            if let Some(path) = rebase(&run.path, &from, &to) {
+                run.path = path;
+            }
+        }
+    }
+    merge_settings(app, bundled, overwrite, &mut report)?;
+    let (imported, skipped) = history::merge(app, runs, overwrite)?;
+    report.history_imported = imported;
+    report.conflicts_skipped += skipped;
+
+    let _ = watcher::restart(app);
+    hotkeys::restore_script_hotkeys(app);
+    hotkeys::restore_run_again_hotkey(app);
+    scripts::notify_scripts_changed(app, placed.created);
+    Ok(report)
+}
+
+// A zip of the main scripts folder, every stored setting and the run history, for
+// import_profile on another machine. Run logs aren't included.
+#[tauri::command]
+pub async fn export_profile(app: AppHandle, dest: String) -> Result<String, String> {
+    let dest = settings::expand_path(&dest);
+    if ArchiveFormat::from_path(&dest)? != ArchiveFormat::Zip {
+        return Err("A profile export must end in .zip".to_string());
+    }
+    let target = dest.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let written = write_bundle(&app, &target);
+        if written.is_err() {
+            let _ = fs::remove_file(&target);
+        }
+        written
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    Ok(dest.to_string_lossy().to_string())
+}
+
+// Scripts go into the configured scripts folder. Anything already here, whether a file, a
+// settings key, a script's stored data or a run, is kept unless `overwrite` is set.
+#[tauri::command]
+pub async fn import_profile(
+    app: AppHandle,
+    archive_path: String,
+    overwrite: Option<bool>,
+) -> Result<ImportReport, String> {
+    let archive = settings::expand_path(&archive_path);
+    let overwrite = overwrite.unwrap_or(false);
+    let worker = app.clone();
+    let report =
+        tauri::async_runtime::spawn_blocking(move || import_bundle(&worker, &archive, overwrite))
+            .await
+            .map_err(|e| e.to_string())??;
+    let _ = app.emit("profile-imported", &report);
+    Ok(report)
+}
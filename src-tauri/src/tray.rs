@@ -0,0 +1,267 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tauri::{
+    menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu},
+    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
+    AppHandle, Wry,
+};
+
+use crate::{
+    autostart, dnd, git, pause, profiles, readonly, runner, tags, triggers, update, window,
+};
+
+pub const TRAY_ID: &str = "main-tray";
+const DEFAULT_TOOLTIP: &str = "Scripts Runner";
+const PROFILE_PREFIX: &str = "profile:";
+const GIT_PULL_PREFIX: &str = "git-pull:";
+const TAG_PREFIX: &str = "tag:";
+const PAUSED_SUFFIX: &str = " (all scripts paused)";
+const READ_ONLY_SUFFIX: &str = " (read-only)";
+
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let menu = Menu::new(app)?;
+    menu.append(&MenuItem::with_id(
+        app,
+        "toggle",
+        "Show/Hide",
+        true,
+        None::<&str>,
+    )?)?;
+
+    let read_only = readonly::is_active();
+    if read_only {
+        menu.append(&MenuItem::with_id(
+            app,
+            "read-only",
+            "Exit Read-Only Mode",
+            true,
+            None::<&str>,
+        )?)?;
+        menu.append(&PredefinedMenuItem::separator(app)?)?;
+    }
+
+    if let Some(last) = runner::last_script_run(app) {
+        let path = Path::new(&last.path);
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| last.path.clone());
+        let exists = path.is_file();
+        let label = if exists {
+            format!("Run again: {}", name)
+        } else {
+            format!("Run again: {} (deleted)", name)
+        };
+        menu.append(&MenuItem::with_id(
+            app,
+            "run-again",
+            label,
+            exists && !read_only,
+            None::<&str>,
+        )?)?;
+    }
+
+    let profiles = profiles::load_profiles(app);
+    if !profiles.is_empty() {
+        let active = profiles::active_name(app);
+        let submenu = Submenu::new(app, "Profiles", !read_only)?;
+        for profile in &profiles {
+            let checked = active.as_deref() == Some(profile.name.as_str());
+            submenu.append(&CheckMenuItem::with_id(
+                app,
+                format!("{}{}", PROFILE_PREFIX, profile.name),
+                &profile.name,
+                true,
+                checked,
+                None::<&str>,
+            )?)?;
+        }
+        menu.append(&submenu)?;
+    }
+
+    let tags = tags::tray_tags(app);
+    if !tags.is_empty() {
+        let submenu = Submenu::new(app, "Tags", true)?;
+        for tag in &tags {
+            submenu.append(&MenuItem::with_id(
+                app,
+                format!("{}{}", TAG_PREFIX, tag),
+                tag,
+                true,
+                None::<&str>,
+            )?)?;
+        }
+        menu.append(&submenu)?;
+    }
+
+    // Only roots that are git checkouts get an update item, named after the folder when several are
+    let repos = git::repo_roots(app);
+    for root in &repos {
+        let label = match root.file_name() {
+            Some(name) if repos.len() > 1 => format!("Update {}", name.to_string_lossy()),
+            _ => "Update Scripts".to_string(),
+        };
+        menu.append(&MenuItem::with_id(
+            app,
+            format!("{}{}", GIT_PULL_PREFIX, root.display()),
+            label,
+            !read_only,
+            None::<&str>,
+        )?)?;
+    }
+
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    // SIGSTOP/SIGCONT have no Windows counterpart
+    if cfg!(unix) {
+        menu.append(&CheckMenuItem::with_id(
+            app,
+            "pause-all",
+            "Pause All Scripts",
+            true,
+            pause::is_paused(),
+            None::<&str>,
+        )?)?;
+    }
+    if !read_only {
+        menu.append(&MenuItem::with_id(
+            app,
+            "read-only",
+            "Read-Only Mode",
+            true,
+            None::<&str>,
+        )?)?;
+    }
+    menu.append(&CheckMenuItem::with_id(
+        app,
+        "dnd",
+        "Do Not Disturb",
+        true,
+        dnd::status(app).active,
+        None::<&str>,
+    )?)?;
+    menu.append(&CheckMenuItem::with_id(
+        app,
+        "autostart",
+        "Launch at Login",
+        true,
+        autostart::is_enabled(app),
+        None::<&str>,
+    )?)?;
+    if let Some(version) = update::available() {
+        menu.append(&MenuItem::with_id(
+            app,
+            "update",
+            format!("Update Available… ({})", version),
+            true,
+            None::<&str>,
+        )?)?;
+    }
+    menu.append(&MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?)?;
+    Ok(menu)
+}
+
+pub fn rebuild_menu(app: &AppHandle) -> tauri::Result<()> {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        tray.set_menu(Some(build_menu(app)?))?;
+    }
+    Ok(())
+}
+
+// Appends whichever of paused and read-only is on, to the frontend's tooltips too
+pub fn with_status(tooltip: String) -> String {
+    let mut tooltip = tooltip;
+    if pause::is_paused() {
+        tooltip.push_str(PAUSED_SUFFIX);
+    }
+    if readonly::is_active() {
+        tooltip.push_str(READ_ONLY_SUFFIX);
+    }
+    tooltip
+}
+
+// Remaining times of running scripts; None puts the default tooltip back once they're gone
+pub fn set_estimates(app: &AppHandle, estimates: Option<&str>) {
+    static SHOWN: AtomicBool = AtomicBool::new(false);
+    let shown = SHOWN.swap(estimates.is_some(), Ordering::Relaxed);
+    if estimates.is_none() && !shown {
+        return;
+    }
+    let tooltip = match estimates {
+        Some(estimates) => format!("{} — {}", DEFAULT_TOOLTIP, estimates),
+        None => DEFAULT_TOOLTIP.to_string(),
+    };
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(&with_status(tooltip)));
+    }
+}
+
+// Until the frontend sets its next tooltip, which keeps the suffixes
+pub fn refresh_status(app: &AppHandle) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(&with_status(DEFAULT_TOOLTIP.to_string())));
+    }
+    let _ = rebuild_menu(app);
+}
+
+pub fn reset_appearance(app: &AppHandle) -> tauri::Result<()> {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        tray.set_icon(app.default_window_icon().cloned())?;
+        tray.set_icon_as_template(true)?;
+        tray.set_tooltip(Some(&with_status(DEFAULT_TOOLTIP.to_string())))?;
+    }
+    rebuild_menu(app)
+}
+
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    match id {
+        "quit" => app.exit(0),
+        "toggle" => window::toggle_main_window(app),
+        "dnd" => {
+            let _ = dnd::set_dnd(app.clone(), !dnd::status(app).active, None);
+        }
+        "run-again" => runner::run_last_again(app, triggers::TRAY),
+        "pause-all" => pause::toggle(app),
+        "read-only" => readonly::toggle(app),
+        "update" => update::open_download(),
+        "autostart" => {
+            let _ = autostart::set_autostart(app.clone(), !autostart::is_enabled(app));
+        }
+        _ => {
+            if let Some(root) = id.strip_prefix(GIT_PULL_PREFIX) {
+                git::pull_in_background(app, PathBuf::from(root));
+            } else if let Some(tag) = id.strip_prefix(TAG_PREFIX) {
+                tags::show_filtered(app, tag);
+            } else if let Some(name) = id.strip_prefix(PROFILE_PREFIX) {
+                if profiles::switch_profile(app.clone(), name.to_string()).is_err() {
+                    let _ = rebuild_menu(app);
+                }
+            }
+        }
+    }
+}
+
+pub fn create_tray(app: &AppHandle) -> tauri::Result<()> {
+    let menu = build_menu(app)?;
+
+    let _tray = TrayIconBuilder::with_id(TRAY_ID)
+        .icon(app.default_window_icon().unwrap().clone())
+        .icon_as_template(true)
+        .tooltip(DEFAULT_TOOLTIP)
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id().as_ref()))
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                window::toggle_main_window(tray.app_handle());
+            }
+        })
+        .build(app)?;
+
+    Ok(())
+}
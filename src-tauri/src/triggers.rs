@@ -0,0 +1,168 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::runner::{self, RunOptions};
+use crate::{conditions, danger, history, hotkeys, pause, readonly, webhook, workflows};
+
+// What started a run, as stored in RunOptions.trigger and the history entry's `trigger`.
+// Runs started from the window have none.
+pub const TRAY: &str = "tray";
+pub const HOTKEY: &str = "hotkey";
+pub const WEBHOOK: &str = "webhook";
+pub const DEEP_LINK: &str = "deep-link";
+pub const CLI: &str = "cli";
+pub const BATCH: &str = "batch";
+pub const WORKFLOW: &str = "workflow";
+pub const TERMINAL: &str = "terminal";
+// Queued from the window
+pub const MANUAL: &str = "manual";
+// The kind recorded for runs without a trigger
+const APP: &str = "app";
+// Someone is at the keyboard for these, as for runs without a trigger
+const INTERACTIVE: &[&str] = &[TRAY, HOTKEY];
+pub const RUN_AGAIN_DETAIL: &str = "Run again";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TriggerSource {
+    // One of the trigger constants, or "app"
+    pub kind: String,
+    // e.g. the hotkey's accelerator, the webhook route or the workflow's name
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TriggerExplanation {
+    pub source: TriggerSource,
+    // None for every trigger the app has; none of them fire on a schedule
+    pub next_fire_ms: Option<u64>,
+    // The last run of the script this kind of trigger started
+    pub last_fired_ms: Option<u64>,
+    // Why firing it now wouldn't start the script; empty when it would
+    pub blocked_by: Vec<String>,
+}
+
+// Tray and hotkey runs, like runs from the window, have someone to ask
+pub fn is_unattended(trigger: Option<&str>) -> bool {
+    trigger.is_some_and(|trigger| !INTERACTIVE.contains(&trigger))
+}
+
+// Stamped on every history entry
+pub fn source(options: &RunOptions) -> TriggerSource {
+    TriggerSource {
+        kind: options.trigger.clone().unwrap_or_else(|| APP.to_string()),
+        detail: options.trigger_detail.clone(),
+    }
+}
+
+fn configured(app: &AppHandle, path: &str) -> Vec<TriggerSource> {
+    let trigger = |kind: &str, detail: String| TriggerSource {
+        kind: kind.to_string(),
+        detail: Some(detail),
+    };
+    let name = Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+    let mut found = Vec::new();
+    if let Some(accelerator) = hotkeys::script_hotkey(app, path) {
+        found.push(trigger(HOTKEY, accelerator));
+    }
+    if runner::last_script_run(app).is_some_and(|last| last.path == path) {
+        found.push(trigger(TRAY, RUN_AGAIN_DETAIL.to_string()));
+        if let Some(accelerator) = hotkeys::run_again_hotkey(app) {
+            found.push(trigger(
+                HOTKEY,
+                format!("{} ({})", RUN_AGAIN_DETAIL, accelerator),
+            ));
+        }
+    }
+    found.push(trigger(WEBHOOK, format!("POST /run/{}", name)));
+    found.push(trigger(DEEP_LINK, format!("sh-runner://run/{}", name)));
+    found.push(trigger(CLI, format!("run {}", name)));
+    for workflow in workflows::containing(app, path) {
+        found.push(trigger(WORKFLOW, workflow));
+    }
+    found
+}
+
+fn last_fired(app: &AppHandle, path: &str, source: &TriggerSource) -> Option<u64> {
+    let mut last = None;
+    history::visit(app, |entry| {
+        if entry.path != path || entry.trigger.as_deref() != Some(source.kind.as_str()) {
+            return;
+        }
+        // Entries from before sources were recorded only match on the kind
+        if entry
+            .source
+            .as_ref()
+            .is_some_and(|s| s.detail.is_some() && s.detail != source.detail)
+        {
+            return;
+        }
+        last = last.max(Some(entry.started_at));
+    });
+    last
+}
+
+// Every way the script can be started, with what would stop each of them right now
+#[tauri::command]
+pub async fn explain_triggers(
+    app: AppHandle,
+    script_id: String,
+) -> Result<Vec<TriggerExplanation>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = script_id;
+        let mut shared = Vec::new();
+        if readonly::is_active() {
+            shared.push("Read-only mode is on".to_string());
+        }
+        if pause::is_paused() {
+            shared.push("All scripts are paused, so a new run stops as it starts".to_string());
+        }
+        // Checked once: a reachability condition can take seconds
+        if let Some(unmet) = conditions::unmet_for(&app, &path) {
+            shared.push(format!("Run condition not met: {}", unmet));
+        }
+        let dangerous = danger::is_dangerous(&app, &path);
+        let webhook_on = webhook::is_enabled(&app);
+        let cooldown = runner::run_again_cooldown_ms();
+        let explanations = configured(&app, &path)
+            .into_iter()
+            .map(|source| {
+                let mut blocked_by = shared.clone();
+                if source.kind == WEBHOOK && !webhook_on {
+                    blocked_by.push("The HTTP server is off".to_string());
+                }
+                if dangerous
+                    && is_unattended(Some(&source.kind))
+                    && !danger::has_override(&app, &source.kind)
+                {
+                    blocked_by.push(format!(
+                        "Marked dangerous, and {} has no override",
+                        source.kind
+                    ));
+                }
+                let run_again = source
+                    .detail
+                    .as_deref()
+                    .is_some_and(|d| d.starts_with(RUN_AGAIN_DETAIL));
+                if run_again && cooldown > 0 {
+                    blocked_by.push(format!("Run again cools down for {} ms", cooldown));
+                }
+                TriggerExplanation {
+                    last_fired_ms: last_fired(&app, &path, &source),
+                    next_fire_ms: None,
+                    blocked_by,
+                    source,
+                }
+            })
+            .collect();
+        Ok(explanations)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,155 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::{roots, scripts, settings};
+
+const DEBOUNCE_KEY: &str = "watchDebounceMs";
+const DEFAULT_DEBOUNCE_MS: u64 = 300;
+
+#[derive(Default)]
+pub struct WatchState {
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    // Resolved directories the current watcher covers
+    watched: Mutex<Vec<PathBuf>>,
+    debounce_ms: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatcherStatus {
+    pub active: bool,
+    pub watched: Vec<String>,
+    pub debounce_ms: u64,
+}
+
+impl WatchState {
+    fn debounce(&self) -> Duration {
+        Duration::from_millis(self.debounce_ms.load(Ordering::Relaxed))
+    }
+}
+
+fn is_relevant(event: &Event, path: &Path) -> bool {
+    if event.kind.is_access() {
+        return false;
+    }
+    // Editor swap and backup files never show up as scripts
+    scripts::is_script(path) || path.is_dir() || event.kind.is_remove()
+}
+
+// Collects events until the tree has been quiet for the debounce window, then
+// emits a single scripts-changed with every affected path
+fn debounce_events(
+    app: AppHandle,
+    rx: Receiver<notify::Result<Event>>,
+    roots: Vec<(PathBuf, PathBuf)>,
+) {
+    let mut pending = BTreeSet::new();
+    loop {
+        let received = if pending.is_empty() {
+            rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            rx.recv_timeout(app.state::<WatchState>().debounce())
+        };
+        match received {
+            Ok(Ok(event)) => {
+                for path in &event.paths {
+                    if is_relevant(&event, path) {
+                        let path = roots
+                            .iter()
+                            .find(|(_, resolved)| path.starts_with(resolved))
+                            .map(|(root, resolved)| scripts::to_configured(path, root, resolved))
+                            .unwrap_or_else(|| path.clone());
+                        pending.insert(path.to_string_lossy().to_string());
+                    }
+                }
+            }
+            Ok(Err(e)) => log::warn!("Watcher error: {}", e),
+            Err(error) => {
+                if !pending.is_empty() {
+                    scripts::notify_scripts_changed(
+                        &app,
+                        std::mem::take(&mut pending).into_iter().collect(),
+                    );
+                }
+                if error == RecvTimeoutError::Disconnected {
+                    log::debug!("Watcher stopped");
+                    return;
+                }
+            }
+        }
+    }
+}
+
+// (Re)creates the watcher over the current scripts roots; the previous watcher is
+// dropped, which also ends its debounce thread
+pub fn restart(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<WatchState>();
+    state.debounce_ms.store(
+        settings::get(app, DEBOUNCE_KEY).unwrap_or(DEFAULT_DEBOUNCE_MS),
+        Ordering::Relaxed,
+    );
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(|e| {
+        log::error!("Failed to create the watcher: {}", e);
+        e.to_string()
+    })?;
+    // Watch resolved targets; events are reported under the configured roots
+    let mut roots = Vec::new();
+    for root in settings::scripts_roots(app) {
+        // Unreachable roots are skipped, and watched again once they come back
+        let Some(resolved) = roots::resolve(&root) else {
+            continue;
+        };
+        if !roots.iter().any(|(_, r)| r == &resolved) {
+            watcher
+                .watch(&resolved, RecursiveMode::Recursive)
+                .map_err(|e| {
+                    log::error!("Failed to watch {}: {}", root.display(), e);
+                    format!("Failed to watch {}: {}", root.display(), e)
+                })?;
+            roots.push((root, resolved));
+        }
+    }
+    *state.watcher.lock().map_err(|e| e.to_string())? = Some(watcher);
+    *state.watched.lock().map_err(|e| e.to_string())? =
+        roots.iter().map(|(_, resolved)| resolved.clone()).collect();
+    log::info!("Watching {} scripts folders", roots.len());
+    let app = app.clone();
+    thread::spawn(move || debounce_events(app, rx, roots));
+    Ok(())
+}
+
+pub fn status(app: &AppHandle) -> WatcherStatus {
+    let state = app.state::<WatchState>();
+    WatcherStatus {
+        active: state.watcher.lock().is_ok_and(|w| w.is_some()),
+        watched: state
+            .watched
+            .lock()
+            .map(|dirs| {
+                dirs.iter()
+                    .map(|dir| dir.to_string_lossy().to_string())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        debounce_ms: state.debounce_ms.load(Ordering::Relaxed),
+    }
+}
+
+#[tauri::command]
+pub fn set_watch_debounce_ms(app: AppHandle, ms: u64) -> Result<(), String> {
+    settings::set(&app, DEBOUNCE_KEY, &ms)?;
+    app.state::<WatchState>()
+        .debounce_ms
+        .store(ms, Ordering::Relaxed);
+    Ok(())
+}
@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::concurrency::CONCURRENCY_LIMIT_ERROR;
+use crate::runner::{self, RunOptions};
+use crate::{deeplink, ipc, prometheus, scripts, settings, triggers};
+
+const SETTINGS_KEY: &str = "webhook";
+const MAX_BODY_BYTES: u64 = 64 * 1024;
+const TOKEN_BYTES: usize = 32;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WebhookSettings {
+    pub enabled: bool,
+    pub port: u16,
+    // Callers must send `Authorization: Bearer <token>`
+    pub token: String,
+    // Synchronous runs beyond this many are turned away with 429
+    pub max_concurrent: usize,
+}
+
+impl Default for WebhookSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 7781,
+            token: String::new(),
+            max_concurrent: 2,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct WebhookState {
+    server: Mutex<Option<(Arc<Server>, JoinHandle<()>)>>,
+    in_flight: AtomicUsize,
+}
+
+// POST /run/<name> body; `sync` can also be passed as ?sync=true
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct RunBody {
+    args: Vec<String>,
+    // Passed as PARAM_<NAME>, like deep-link parameters
+    env: HashMap<String, String>,
+    label: Option<String>,
+    sync: bool,
+}
+
+// POST /run body; `path` is a script id or a name find_script resolves
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct RunPathBody {
+    path: String,
+    args: Vec<String>,
+}
+
+// Releases a concurrency slot when the run it was taken for finishes
+struct Slot<'a>(&'a AtomicUsize);
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn load(app: &AppHandle) -> WebhookSettings {
+    settings::get(app, SETTINGS_KEY).unwrap_or_default()
+}
+
+fn generate_token() -> Result<String, String> {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    getrandom::getrandom(&mut bytes).map_err(|e| e.to_string())?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// The stored settings, with a token generated and saved the first time one is needed
+fn with_token(app: &AppHandle) -> Result<WebhookSettings, String> {
+    let mut webhook = load(app);
+    if webhook.token.is_empty() {
+        webhook.token = generate_token()?;
+        settings::set(app, SETTINGS_KEY, &webhook)?;
+    }
+    Ok(webhook)
+}
+
+fn reply(request: Request, status: u16, body: Value) {
+    let header = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+        .expect("static header is valid");
+    let _ = request.respond(
+        Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(header),
+    );
+}
+
+fn reply_metrics(app: &AppHandle, request: Request) {
+    let header = Header::from_bytes(&b"Content-Type"[..], &b"text/plain; version=0.0.4"[..])
+        .expect("static header is valid");
+    let _ = request.respond(Response::from_string(prometheus::render(app)).with_header(header));
+}
+
+fn error(message: impl Into<String>) -> Value {
+    json!({ "ok": false, "error": message.into() })
+}
+
+// Compares every byte so the response time doesn't reveal how much of the token matched
+fn token_matches(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn authorized(request: &Request, token: &str) -> bool {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .and_then(|h| h.value.as_str().strip_prefix("Bearer "))
+        .is_some_and(|given| !token.is_empty() && token_matches(token, given.trim()))
+}
+
+fn wants_sync(query: &str) -> bool {
+    query
+        .split('&')
+        .any(|pair| matches!(pair, "sync" | "sync=1" | "sync=true"))
+}
+
+// An empty body reads as the default; on error the request has been answered
+fn read_body<T: Default + for<'de> Deserialize<'de>>(request: &mut Request) -> Result<T, Value> {
+    let mut body = String::new();
+    if request
+        .as_reader()
+        .take(MAX_BODY_BYTES)
+        .read_to_string(&mut body)
+        .is_err()
+    {
+        return Err(error("Could not read request body"));
+    }
+    if body.trim().is_empty() {
+        return Ok(T::default());
+    }
+    serde_json::from_str(&body).map_err(|e| error(format!("Invalid JSON body: {}", e)))
+}
+
+// Synchronous runs past max_concurrent, or past the app's own run limit, are turned away with
+// 429. They're never queued, since the caller is waiting for the result.
+fn run_sync(app: &AppHandle, request: Request, path: &str, options: RunOptions) {
+    let state = app.state::<WebhookState>();
+    let running = state.in_flight.fetch_add(1, Ordering::SeqCst);
+    let _slot = Slot(&state.in_flight);
+    if running >= load(app).max_concurrent.max(1) {
+        return reply(request, 429, error("Too many synchronous runs in progress"));
+    }
+    let options = RunOptions {
+        no_queue: true,
+        ..options
+    };
+    match runner::execute_with_id(app, runner::next_run_id(), path, &options) {
+        Ok(result) => reply(request, 200, json!({ "ok": true, "result": result })),
+        Err(message) if message.starts_with(CONCURRENCY_LIMIT_ERROR) => {
+            reply(request, 429, error(message))
+        }
+        Err(message) => reply(request, 500, error(message)),
+    }
+}
+
+fn run(app: &AppHandle, mut request: Request, name: &str, query: &str) {
+    let body: RunBody = match read_body(&mut request) {
+        Ok(body) => body,
+        Err(message) => return reply(request, 400, message),
+    };
+    let name = percent_decode_str(name).decode_utf8_lossy();
+    let Some(path) = scripts::find_script(app, &name) else {
+        return reply(
+            request,
+            404,
+            error(format!("No script matches \"{}\"", name)),
+        );
+    };
+    let options = RunOptions {
+        args: body.args,
+        env: body
+            .env
+            .into_iter()
+            .map(|(key, value)| (deeplink::param_env_name(&key), value))
+            .collect(),
+        label: body.label,
+        trigger: Some(triggers::WEBHOOK.to_string()),
+        trigger_detail: Some(format!("POST /run/{}", name)),
+        ..RunOptions::default()
+    };
+    if !(body.sync || wants_sync(query)) {
+        let run_id = runner::next_run_id();
+        let (app, id, script) = (app.clone(), run_id.clone(), path.clone());
+        thread::spawn(move || {
+            let _ = runner::execute_with_id(&app, id, &script, &options);
+        });
+        return reply(
+            request,
+            202,
+            json!({ "ok": true, "runId": run_id, "path": path }),
+        );
+    }
+    run_sync(app, request, &path, options)
+}
+
+// POST /run: always waits for the script and answers with its result
+fn run_path(app: &AppHandle, mut request: Request) {
+    let body: RunPathBody = match read_body(&mut request) {
+        Ok(body) => body,
+        Err(message) => return reply(request, 400, message),
+    };
+    if body.path.trim().is_empty() {
+        return reply(request, 400, error("The body needs a script path"));
+    }
+    // Ids outside the scripts roots are refused by the run pipeline itself
+    let path = scripts::find_script(app, &body.path).unwrap_or(body.path);
+    let options = RunOptions {
+        args: body.args,
+        trigger: Some(triggers::WEBHOOK.to_string()),
+        trigger_detail: Some("POST /run".to_string()),
+        ..RunOptions::default()
+    };
+    run_sync(app, request, &path, options)
+}
+
+fn handle(app: &AppHandle, request: Request) {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let method = request.method().clone();
+    // Health stays open so monitors don't need the token
+    if method == Method::Get && path == "/health" {
+        return reply(request, 200, json!({ "ok": true }));
+    }
+    if !authorized(&request, &load(app).token) {
+        return reply(request, 401, error("Missing or invalid bearer token"));
+    }
+    match (
+        method,
+        path.strip_prefix('/').unwrap_or(path).split_once('/'),
+    ) {
+        (Method::Post, None) if path == "/run" => run_path(app, request),
+        // Behind the token like the rest, since it lists script paths
+        (Method::Get, None) if path == "/metrics" => reply_metrics(app, request),
+        (Method::Post, Some(("run", name))) if !name.is_empty() => run(app, request, name, query),
+        (Method::Get, Some(("status", run_id))) if !run_id.is_empty() => {
+            let status = ipc::status(app, Some(run_id));
+            let code = if status["ok"] == true { 200 } else { 404 };
+            reply(request, code, status)
+        }
+        _ => reply(request, 404, error("Not found")),
+    }
+}
+
+fn stop(state: &WebhookState) {
+    let Ok(mut server) = state.server.lock() else {
+        return;
+    };
+    if let Some((server, accept_loop)) = server.take() {
+        // Ends the accept loop, which drops its handle and frees the port
+        server.unblock();
+        drop(server);
+        let _ = accept_loop.join();
+    }
+}
+
+// Applies the stored settings: stops any running listener and starts a new one when enabled
+pub fn restart(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<WebhookState>();
+    stop(&state);
+    let settings = load(app);
+    if !settings.enabled {
+        return Ok(());
+    }
+    if settings.token.trim().is_empty() {
+        return Err("The webhook server needs a bearer token".to_string());
+    }
+    // Localhost only; nothing else on the network can reach it
+    let server = Server::http(("127.0.0.1", settings.port))
+        .map(Arc::new)
+        .map_err(|e| format!("Failed to listen on 127.0.0.1:{}: {}", settings.port, e))?;
+    let listener = server.clone();
+    let app_handle = app.clone();
+    let accept_loop = thread::spawn(move || {
+        for request in listener.incoming_requests() {
+            let app = app_handle.clone();
+            thread::spawn(move || handle(&app, request));
+        }
+    });
+    *state.server.lock().map_err(|e| e.to_string())? = Some((server, accept_loop));
+    Ok(())
+}
+
+pub fn is_enabled(app: &AppHandle) -> bool {
+    load(app).enabled
+}
+
+pub fn shutdown(app: &AppHandle) {
+    stop(&app.state::<WebhookState>());
+}
+
+#[tauri::command]
+pub fn get_webhook_settings(app: AppHandle) -> Result<WebhookSettings, String> {
+    Ok(load(&app))
+}
+
+#[tauri::command]
+pub fn set_webhook_settings(app: AppHandle, webhook: WebhookSettings) -> Result<(), String> {
+    let webhook = WebhookSettings {
+        token: webhook.token.trim().to_string(),
+        ..webhook
+    };
+    if webhook.enabled && webhook.token.is_empty() {
+        return Err("The webhook server needs a bearer token".to_string());
+    }
+    settings::set(&app, SETTINGS_KEY, &webhook)?;
+    restart(&app)
+}
+
+// Turns the server on or off on `port`, generating a token the first time it's enabled
+#[tauri::command]
+pub fn set_http_trigger(app: AppHandle, enabled: bool, port: u16) -> Result<(), String> {
+    if port == 0 {
+        return Err("Port must be between 1 and 65535".to_string());
+    }
+    let mut webhook = if enabled {
+        with_token(&app)?
+    } else {
+        load(&app)
+    };
+    webhook.enabled = enabled;
+    webhook.port = port;
+    settings::set(&app, SETTINGS_KEY, &webhook)?;
+    restart(&app)
+}
+
+// The bearer token callers need, generated on first use
+#[tauri::command]
+pub fn get_http_token(app: AppHandle) -> Result<String, String> {
+    Ok(with_token(&app)?.token)
+}
@@ -0,0 +1,201 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, LogicalSize, Manager, PhysicalPosition, Position, WebviewWindow};
+
+use crate::{livestream, settings, tray};
+
+const SHOW_ON_LAUNCH_KEY: &str = "showOnLaunch";
+const WINDOW_SIZE_KEY: &str = "windowSize";
+const WINDOW_ANCHOR_KEY: &str = "windowAnchor";
+const MIN_WIDTH: f64 = 280.0;
+const MIN_HEIGHT: f64 = 240.0;
+
+// Logical pixels, so the size survives moving between displays with different scaling
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowSize {
+    pub width: f64,
+    pub height: f64,
+}
+
+// Set while a window shown without user action is waiting to receive focus,
+// so the blur handler doesn't hide it before the user has interacted with it
+static AWAITING_FOCUS: AtomicBool = AtomicBool::new(false);
+
+pub fn main_window(app: &AppHandle) -> Option<WebviewWindow> {
+    app.get_webview_window("main")
+}
+
+fn position_below_tray(app: &AppHandle, window: &WebviewWindow) {
+    let Some(tray) = app.tray_by_id(tray::TRAY_ID) else {
+        return;
+    };
+    // Position window below tray icon
+    if let Ok(Some(rect)) = tray.rect() {
+        let pos_x = match rect.position {
+            Position::Physical(p) => p.x,
+            Position::Logical(l) => l.x as i32,
+        };
+        let pos_y = match rect.position {
+            Position::Physical(p) => p.y,
+            Position::Logical(l) => l.y as i32,
+        };
+        let size_h = match rect.size {
+            tauri::Size::Physical(s) => s.height as i32,
+            tauri::Size::Logical(s) => s.height as i32,
+        };
+
+        let x = pos_x - 140;
+        let y = pos_y + size_h + 5;
+        let _ = window.set_position(Position::Physical(PhysicalPosition { x, y }));
+    }
+}
+
+// Keeps the window within the work area of its monitor, or the primary one while hidden
+fn clamp_size(window: &WebviewWindow, size: WindowSize) -> WindowSize {
+    let monitor = window
+        .current_monitor()
+        .ok()
+        .flatten()
+        .or_else(|| window.primary_monitor().ok().flatten());
+    let (max_width, max_height) = match monitor {
+        Some(monitor) => {
+            let area = monitor.work_area().size;
+            let scale = monitor.scale_factor();
+            (area.width as f64 / scale, area.height as f64 / scale)
+        }
+        None => (f64::MAX, f64::MAX),
+    };
+    WindowSize {
+        width: size.width.min(max_width).max(MIN_WIDTH),
+        height: size.height.min(max_height).max(MIN_HEIGHT),
+    }
+}
+
+fn resize(window: &WebviewWindow, size: WindowSize) -> Result<WindowSize, String> {
+    let size = clamp_size(window, size);
+    window
+        .set_size(LogicalSize::new(size.width, size.height))
+        .map_err(|e| e.to_string())?;
+    Ok(size)
+}
+
+// Runs in setup before the first show, so a stored size never flashes at the default
+pub fn apply_stored_size(app: &AppHandle, window: &WebviewWindow) {
+    if let Some(size) = settings::get::<WindowSize>(app, WINDOW_SIZE_KEY) {
+        let _ = resize(window, size);
+    }
+}
+
+// Where the window appears when shown from the tray, the hotkey or a second launch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WindowAnchor {
+    #[default]
+    Tray,
+    CursorMonitorCenter,
+}
+
+// Centers within the work area of the monitor under the cursor; false if it can't be found
+fn center_on_cursor_monitor(app: &AppHandle, window: &WebviewWindow) -> bool {
+    let Some(monitor) = app
+        .cursor_position()
+        .ok()
+        .and_then(|cursor| app.monitor_from_point(cursor.x, cursor.y).ok().flatten())
+    else {
+        return false;
+    };
+    let Ok(size) = window.outer_size() else {
+        return false;
+    };
+    let area = monitor.work_area();
+    let x = area.position.x + (area.size.width as i32 - size.width as i32) / 2;
+    let y = area.position.y + (area.size.height as i32 - size.height as i32) / 2;
+    window
+        .set_position(Position::Physical(PhysicalPosition { x, y }))
+        .is_ok()
+}
+
+fn position_window(app: &AppHandle, window: &WebviewWindow) {
+    let anchor = settings::get::<WindowAnchor>(app, WINDOW_ANCHOR_KEY).unwrap_or_default();
+    if anchor == WindowAnchor::CursorMonitorCenter && center_on_cursor_monitor(app, window) {
+        return;
+    }
+    position_below_tray(app, window);
+}
+
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = main_window(app) {
+        position_window(app, &window);
+        let _ = window.show();
+        let _ = window.set_focus();
+        livestream::set_visible(app, true);
+    }
+}
+
+pub fn toggle_main_window(app: &AppHandle) {
+    if let Some(window) = main_window(app) {
+        if window.is_visible().unwrap_or(false) {
+            let _ = window.hide();
+            livestream::set_visible(app, false);
+        } else {
+            show_main_window(app);
+        }
+    }
+}
+
+// Hide window when it loses focus
+pub fn install_auto_hide(window: &WebviewWindow) {
+    let window_clone = window.clone();
+    window.on_window_event(move |event| match event {
+        tauri::WindowEvent::Focused(true) => AWAITING_FOCUS.store(false, Ordering::Relaxed),
+        tauri::WindowEvent::Focused(false) if !AWAITING_FOCUS.load(Ordering::Relaxed) => {
+            let _ = window_clone.hide();
+            livestream::set_visible(window_clone.app_handle(), false);
+        }
+        _ => {}
+    });
+}
+
+pub fn show_on_launch_if_enabled(app: &AppHandle) {
+    if settings::get::<bool>(app, SHOW_ON_LAUNCH_KEY).unwrap_or(false) {
+        AWAITING_FOCUS.store(true, Ordering::Relaxed);
+        show_main_window(app);
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SecondInstanceEvent {
+    args: Vec<String>,
+    cwd: String,
+}
+
+// A second launch exits right away; the running instance surfaces instead. Deep links
+// in its arguments are forwarded to the deep-link handler by the plugin itself
+pub fn handle_second_instance(app: &AppHandle, args: Vec<String>, cwd: String) {
+    AWAITING_FOCUS.store(true, Ordering::Relaxed);
+    show_main_window(app);
+    let _ = app.emit("second-instance", SecondInstanceEvent { args, cwd });
+}
+
+// Returns the size actually applied after clamping
+#[tauri::command]
+pub fn set_window_size(app: AppHandle, width: f64, height: f64) -> Result<WindowSize, String> {
+    let window = main_window(&app).ok_or_else(|| "Main window not found".to_string())?;
+    let size = resize(&window, WindowSize { width, height })?;
+    settings::set(&app, WINDOW_SIZE_KEY, &size)?;
+    position_window(&app, &window);
+    Ok(size)
+}
+
+#[tauri::command]
+pub fn set_window_anchor(app: AppHandle, mode: WindowAnchor) -> Result<(), String> {
+    settings::set(&app, WINDOW_ANCHOR_KEY, &mode)
+}
+
+#[tauri::command]
+pub fn set_show_on_launch(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::set(&app, SHOW_ON_LAUNCH_KEY, &enabled)
+}
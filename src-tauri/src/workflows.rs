@@ -0,0 +1,259 @@
+use std::path::Path;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::history::{self, HistoryEntry};
+use crate::runner::{self, RunOptions};
+use crate::triggers::{self, TriggerSource};
+use crate::{profiles, settings};
+
+const WORKFLOWS_KEY: &str = "workflows";
+// Workflow runs share history with scripts, under a path no script can have
+pub const HISTORY_PREFIX: &str = "workflow:";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WorkflowStep {
+    pub path: String,
+    pub args: Vec<String>,
+    pub continue_on_error: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Workflow {
+    pub name: String,
+    pub steps: Vec<WorkflowStep>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepResult {
+    pub path: String,
+    // None for steps skipped after an earlier failure
+    pub run_id: Option<String>,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub skipped: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowResult {
+    pub run_id: String,
+    pub name: String,
+    pub success: bool,
+    pub duration_ms: u64,
+    pub steps: Vec<StepResult>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StepEvent<'a> {
+    workflow_run_id: &'a str,
+    name: &'a str,
+    index: usize,
+    total: usize,
+    // "started" or "finished"; the step's own output arrives as script-* events
+    state: &'a str,
+    step: &'a StepResult,
+}
+
+fn load(app: &AppHandle) -> Vec<Workflow> {
+    settings::get(app, WORKFLOWS_KEY).unwrap_or_default()
+}
+
+// The workflows with a step that runs `path`
+pub fn containing(app: &AppHandle, path: &str) -> Vec<String> {
+    load(app)
+        .into_iter()
+        .filter(|workflow| workflow.steps.iter().any(|step| step.path == path))
+        .map(|workflow| workflow.name)
+        .collect()
+}
+
+fn run_step(app: &AppHandle, workflow: &str, step: &WorkflowStep, run_id: String) -> StepResult {
+    let options = RunOptions {
+        args: step.args.clone(),
+        trigger: Some(triggers::WORKFLOW.to_string()),
+        trigger_detail: Some(workflow.to_string()),
+        ..RunOptions::default()
+    };
+    match runner::execute_with_id(app, run_id.clone(), &step.path, &options) {
+        Ok(result) => StepResult {
+            path: step.path.clone(),
+            run_id: Some(run_id),
+            success: result.success,
+            exit_code: result.exit_code,
+            skipped: false,
+            error: None,
+        },
+        Err(error) => StepResult {
+            path: step.path.clone(),
+            run_id: Some(run_id),
+            success: false,
+            exit_code: None,
+            skipped: false,
+            error: Some(error),
+        },
+    }
+}
+
+// Steps run one after another; a failure stops the workflow unless that step allows it
+fn run(app: &AppHandle, workflow: &Workflow) -> WorkflowResult {
+    let workflow_run_id = runner::next_run_id();
+    let started_at = history::now_ms();
+    let started = Instant::now();
+    let total = workflow.steps.len();
+    let emit = |index: usize, state: &str, step: &StepResult| {
+        let _ = app.emit(
+            "workflow-step",
+            StepEvent {
+                workflow_run_id: &workflow_run_id,
+                name: &workflow.name,
+                index,
+                total,
+                state,
+                step,
+            },
+        );
+    };
+
+    let mut results = Vec::with_capacity(total);
+    let mut stopped = false;
+    for (index, step) in workflow.steps.iter().enumerate() {
+        if stopped {
+            results.push(StepResult {
+                path: step.path.clone(),
+                run_id: None,
+                success: false,
+                exit_code: None,
+                skipped: true,
+                error: None,
+            });
+            continue;
+        }
+        let run_id = runner::next_run_id();
+        let pending = StepResult {
+            path: step.path.clone(),
+            run_id: Some(run_id.clone()),
+            success: false,
+            exit_code: None,
+            skipped: false,
+            error: None,
+        };
+        emit(index, "started", &pending);
+        let result = run_step(app, &workflow.name, step, run_id);
+        emit(index, "finished", &result);
+        stopped = !result.success && !step.continue_on_error;
+        results.push(result);
+    }
+
+    let success = !stopped && results.iter().all(|r| r.success);
+    let duration_ms = started.elapsed().as_millis() as u64;
+    let _ = history::record(
+        app,
+        HistoryEntry {
+            run_id: workflow_run_id.clone(),
+            path: format!("{}{}", HISTORY_PREFIX, workflow.name),
+            started_at,
+            duration_ms,
+            // The first failing step's code, so a tolerated failure still shows up
+            exit_code: results
+                .iter()
+                .find(|r| !r.success && !r.skipped)
+                .map_or(Some(0), |r| r.exit_code),
+            success,
+            timed_out: false,
+            cancelled: false,
+            args: Vec::new(),
+            profile: profiles::active_name(app),
+            label: None,
+            trigger: Some(triggers::WORKFLOW.to_string()),
+            external: false,
+            aborted: false,
+            resource_usage: None,
+            env_groups: Vec::new(),
+            matcher_hits: Vec::new(),
+            batch_id: None,
+            log_truncated_at_bytes: None,
+            condition_not_met: None,
+            staged: false,
+            rerun: None,
+            source: Some(TriggerSource {
+                kind: triggers::WORKFLOW.to_string(),
+                detail: Some(workflow.name.clone()),
+            }),
+            content_hash: None,
+            clipboard_stdin_bytes: None,
+            countdown_cancelled: false,
+        },
+    );
+    WorkflowResult {
+        run_id: workflow_run_id,
+        name: workflow.name.clone(),
+        success,
+        duration_ms,
+        steps: results,
+    }
+}
+
+#[tauri::command]
+pub fn list_workflows(app: AppHandle) -> Result<Vec<Workflow>, String> {
+    Ok(load(&app))
+}
+
+// Replaces a workflow with the same name
+#[tauri::command]
+pub fn save_workflow(app: AppHandle, name: String, steps: Vec<WorkflowStep>) -> Result<(), String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Workflow name cannot be empty".to_string());
+    }
+    if steps.is_empty() {
+        return Err("A workflow needs at least one step".to_string());
+    }
+    if let Some((index, step)) = steps
+        .iter()
+        .enumerate()
+        .find(|(_, step)| !Path::new(&step.path).is_file())
+    {
+        return Err(format!(
+            "Step {}: script not found: {}",
+            index + 1,
+            step.path
+        ));
+    }
+    let mut workflows = load(&app);
+    let workflow = Workflow { name, steps };
+    match workflows.iter_mut().find(|w| w.name == workflow.name) {
+        Some(existing) => *existing = workflow,
+        None => workflows.push(workflow),
+    }
+    settings::set(&app, WORKFLOWS_KEY, &workflows)
+}
+
+#[tauri::command]
+pub fn delete_workflow(app: AppHandle, name: String) -> Result<(), String> {
+    let mut workflows = load(&app);
+    let before = workflows.len();
+    workflows.retain(|w| w.name != name);
+    if workflows.len() == before {
+        return Err(format!("Workflow not found: {}", name));
+    }
+    settings::set(&app, WORKFLOWS_KEY, &workflows)
+}
+
+#[tauri::command]
+pub async fn run_workflow(app: AppHandle, name: String) -> Result<WorkflowResult, String> {
+    let workflow = load(&app)
+        .into_iter()
+        .find(|w| w.name == name)
+        .ok_or_else(|| format!("Workflow not found: {}", name))?;
+    tauri::async_runtime::spawn_blocking(move || run(&app, &workflow))
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -88,6 +88,11 @@ function App() {
     scriptsRef.current = scripts;
   }, [scripts]);
 
+  // set_category_collapsed in the backend changes the same setting
+  useEffect(() => {
+    setCollapsedFolders(new Set(settings.collapsedFolders || []));
+  }, [settings.collapsedFolders]);
+
 
 
   useEffect(() => {
@@ -413,7 +418,7 @@ function App() {
   const runScriptNow = useCallback(async (script: Script, mode: 'background' | 'terminal') => {
     if (mode === 'terminal') {
       try {
-        await runScriptInTerminal(script.path, script.envVars || {}, script.args || '');
+        await runScriptInTerminal(script.path, script.args || '');
         const entry: ExecutionEntry = {
           at: new Date().toISOString(),
           duration: 0,
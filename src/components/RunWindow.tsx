@@ -0,0 +1,96 @@
+import React, { useEffect, useRef, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { formatDuration } from '../lib/scripts';
+
+interface RunWindowProps {
+  runId: string;
+}
+
+interface OutputLine {
+  seq: number;
+  stream: string;
+  line: string;
+}
+
+interface OutputSnapshot {
+  runId: string;
+  lines: OutputLine[];
+  nextSeq: number;
+  skipped: number;
+}
+
+interface ExitedEvent {
+  runId: string;
+  exitCode: number | null;
+  durationMs: number;
+  timedOut: boolean;
+  cancelledBeforeStart: boolean;
+}
+
+function exitLabel(exited: ExitedEvent): string {
+  if (exited.cancelledBeforeStart) return 'Cancelled';
+  if (exited.timedOut) return `Timed out • ${formatDuration(exited.durationMs)}`;
+  const status = exited.exitCode === null ? 'Stopped' : `Exit ${exited.exitCode}`;
+  return `${status} • ${formatDuration(exited.durationMs)}`;
+}
+
+// Output-only view for the always-on-top windows opened per run, see runwindows.rs
+export const RunWindow: React.FC<RunWindowProps> = ({ runId }) => {
+  const [lines, setLines] = useState<OutputLine[]>([]);
+  const [exited, setExited] = useState<ExitedEvent | null>(null);
+  const bottomRef = useRef<HTMLDivElement>(null);
+
+  useEffect(() => {
+    let disposed = false;
+    // Lines can arrive both live and in the tail fetched below, so they're kept by seq
+    const append = (incoming: OutputLine[]) => {
+      setLines((current) => {
+        const last = current.length > 0 ? current[current.length - 1].seq : -1;
+        const fresh = incoming.filter((line) => line.seq > last);
+        return fresh.length > 0 ? [...current, ...fresh] : current;
+      });
+    };
+
+    const unlistenOutput = listen<OutputLine>('run-window-output', (event) => {
+      append([{ seq: event.payload.seq, stream: event.payload.stream, line: event.payload.line }]);
+    });
+    const unlistenExit = listen<ExitedEvent>('script-exited', (event) => {
+      if (event.payload.runId === runId) setExited(event.payload);
+    });
+    // Listening first, so nothing printed in between is lost
+    Promise.all([unlistenOutput, unlistenExit]).then(async () => {
+      const snapshot = await invoke<OutputSnapshot | null>('get_output_tail', { runId }).catch(() => null);
+      if (!disposed && snapshot) append(snapshot.lines);
+    });
+
+    return () => {
+      disposed = true;
+      unlistenOutput.then((unlisten) => unlisten());
+      unlistenExit.then((unlisten) => unlisten());
+    };
+  }, [runId]);
+
+  useEffect(() => {
+    bottomRef.current?.scrollIntoView({ block: 'end' });
+  }, [lines, exited]);
+
+  const exitClass = exited && exited.exitCode === 0 && !exited.timedOut
+    ? 'text-[color:var(--success)]'
+    : 'text-[color:var(--error)]';
+
+  return (
+    <div className="h-screen overflow-y-auto p-2">
+      <pre className="code-block text-xs whitespace-pre-wrap break-all font-mono">
+        {lines.map((line) => (
+          <div key={line.seq} className={line.stream === 'stderr' ? 'text-[color:var(--error)]' : undefined}>
+            {line.line}
+          </div>
+        ))}
+        {lines.length === 0 && !exited && <span className="text-secondary">Waiting for output…</span>}
+      </pre>
+      {exited && <div className={`text-xs mt-2 ${exitClass}`}>{exitLabel(exited)}</div>}
+      <div ref={bottomRef} />
+    </div>
+  );
+};
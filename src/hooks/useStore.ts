@@ -1,4 +1,6 @@
 import { useCallback, useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { load, Store } from '@tauri-apps/plugin-store';
 import type { AppSettings, ScriptData, SortOption, ExecutionEntry } from '../types';
 
@@ -22,9 +24,13 @@ const DEFAULT_SETTINGS: AppSettings = {
 
 let storeInstance: Store | null = null;
 
+// The backend decides where the store lives (XDG config dir on Linux), so ask it for the path
 async function getStore(): Promise<Store> {
   if (!storeInstance) {
-    storeInstance = await load(STORE_FILE);
+    const path = await invoke<{ storeFile: string }>('get_app_paths')
+      .then(paths => paths.storeFile)
+      .catch(() => STORE_FILE);
+    storeInstance = await load(path);
   }
   return storeInstance;
 }
@@ -34,7 +40,7 @@ export function useStore() {
   const [settings, setSettings] = useState<AppSettings>(DEFAULT_SETTINGS);
   const [scriptsData, setScriptsData] = useState<Map<string, ScriptData>>(new Map());
 
-  // Load initial data from store
+  // Load initial data from store, and again whenever the backend changes it
   useEffect(() => {
     async function loadStore() {
       try {
@@ -61,6 +67,13 @@ export function useStore() {
     }
 
     loadStore();
+    // Otherwise the next saveSettings would write the stale object back over the change
+    const unlisteners = ['settings-changed', 'settings-reloaded'].map(event =>
+      listen(event, () => { loadStore(); })
+    );
+    return () => {
+      unlisteners.forEach(unlisten => unlisten.then(fn => fn()));
+    };
   }, []);
 
   // Save settings
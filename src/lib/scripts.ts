@@ -1,6 +1,7 @@
 import { readDir, exists, watch, readTextFile, writeTextFile } from '@tauri-apps/plugin-fs';
-import { Command, Child } from '@tauri-apps/plugin-shell';
+import { Command } from '@tauri-apps/plugin-shell';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 
 export async function getDefaultScriptsPath(): Promise<string> {
   try {
@@ -188,22 +189,57 @@ export interface ExecutionResult {
   timedOut: boolean;
 }
 
-// Store running processes for cancellation
-const runningProcesses = new Map<string, Child>();
-
-function buildEnvString(envVars: Record<string, string>): string {
-  return Object.entries(envVars)
-    .map(([k, v]) => `export ${k}="${v.replace(/\"/g, '\\"')}"`)
-    .join('; ');
+interface ScriptResult {
+  runId: string;
+  success: boolean;
+  exitCode: number | null;
+  stdout: string;
+  stderr: string;
+  durationMs: number;
+  timedOut: boolean;
 }
 
-function buildCommand(scriptPath: string, envVars: Record<string, string>, args: string): string {
-  const envString = buildEnvString(envVars);
-  const argString = args.trim();
-  const base = `bash "${scriptPath}"${argString ? ` ${argString}` : ''}`;
-  return envString ? `${envString}; ${base}` : base;
+// Run ids of the scripts running from the window, for cancellation
+const runningRuns = new Map<string, string>();
+
+// Splits saved args the way bash did when they were passed through `bash -c`: on
+// whitespace, keeping quoted parts together and dropping the quotes
+export function splitArgs(args: string): string[] {
+  const words: string[] = [];
+  let word = '';
+  let inWord = false;
+  let quote: '"' | "'" | null = null;
+  for (let i = 0; i < args.length; i++) {
+    const c = args[i];
+    if (quote) {
+      if (c === quote) {
+        quote = null;
+      } else if (c === '\\' && quote === '"' && i + 1 < args.length) {
+        word += args[++i];
+      } else {
+        word += c;
+      }
+    } else if (c === '"' || c === "'") {
+      quote = c;
+      inWord = true;
+    } else if (c === '\\' && i + 1 < args.length) {
+      word += args[++i];
+      inWord = true;
+    } else if (/\s/.test(c)) {
+      if (inWord) words.push(word);
+      word = '';
+      inWord = false;
+    } else {
+      word += c;
+      inWord = true;
+    }
+  }
+  if (inWord) words.push(word);
+  return words;
 }
 
+// Runs through the backend's run_script, so read-only mode, the allowed roots and the
+// danger and condition checks apply to the window's runs too
 export async function executeScript(
   scriptPath: string,
   envVars: Record<string, string> = {},
@@ -212,72 +248,37 @@ export async function executeScript(
   onOutput?: (line: string, isError: boolean) => void
 ): Promise<ExecutionResult> {
   const startTime = Date.now();
-
-  // First make sure it's executable
-  await makeExecutable(scriptPath);
-
-  try {
-    const fullCommand = buildCommand(scriptPath, envVars, args);
-    const command = Command.create('bash', ['-c', fullCommand]);
-
-    let stdout = '';
-    let stderr = '';
-
-    command.stdout.on('data', (line) => {
-      stdout += line + '\n';
-      onOutput?.(line, false);
-    });
-
-    command.stderr.on('data', (line) => {
-      stderr += line + '\n';
-      onOutput?.(line, true);
-    });
-
-    const child = await command.spawn();
-    runningProcesses.set(scriptPath, child);
-
-    // Setup timeout
-    let timedOut = false;
-    let timeoutId: ReturnType<typeof setTimeout> | null = null;
-
-    if (timeoutSeconds > 0) {
-      timeoutId = setTimeout(async () => {
-        timedOut = true;
-        try {
-          await child.kill();
-        } catch {
-          // Ignore kill errors
-        }
-      }, timeoutSeconds * 1000);
+  let runId: string | null = null;
+  const unlistenStarted = await listen<{ runId: string; path: string }>('script-started', (event) => {
+    if (event.payload.path === scriptPath && runId === null) {
+      runId = event.payload.runId;
+      runningRuns.set(scriptPath, runId);
     }
-
-    // Wait for completion
-    const result = await new Promise<{ code: number }>((resolve) => {
-      command.on('close', (data) => {
-        resolve({ code: data.code ?? -1 });
-      });
-      command.on('error', () => {
-        resolve({ code: -1 });
-      });
-    });
-
-    if (timeoutId) {
-      clearTimeout(timeoutId);
+  });
+  const unlistenOutput = await listen<{ runId: string; line: string; stream: string }>('script-output', (event) => {
+    if (event.payload.runId === runId) {
+      onOutput?.(event.payload.line, event.payload.stream === 'stderr');
     }
+  });
 
-    runningProcesses.delete(scriptPath);
-    const duration = Date.now() - startTime;
-
+  try {
+    const result = await invoke<ScriptResult>('run_script', {
+      path: scriptPath,
+      options: {
+        args: splitArgs(args),
+        env: envVars,
+        timeoutSecs: timeoutSeconds > 0 ? timeoutSeconds : null,
+      },
+    });
     return {
-      success: result.code === 0 && !timedOut,
-      exitCode: result.code,
-      stdout: stdout.trim(),
-      stderr: stderr.trim(),
-      duration,
-      timedOut,
+      success: result.success,
+      exitCode: result.exitCode ?? -1,
+      stdout: result.stdout.trim(),
+      stderr: result.stderr.trim(),
+      duration: result.durationMs,
+      timedOut: result.timedOut,
     };
   } catch (error) {
-    runningProcesses.delete(scriptPath);
     const errorMessage = error instanceof Error ? error.message : String(error);
     return {
       success: false,
@@ -287,19 +288,15 @@ export async function executeScript(
       duration: Date.now() - startTime,
       timedOut: false,
     };
+  } finally {
+    unlistenStarted();
+    unlistenOutput();
+    runningRuns.delete(scriptPath);
   }
 }
 
-export async function runScriptInTerminal(
-  scriptPath: string,
-  envVars: Record<string, string> = {},
-  args: string = ''
-): Promise<void> {
-  await makeExecutable(scriptPath);
-  const fullCommand = buildCommand(scriptPath, envVars, args);
-  const osaScript = `tell application "Terminal" to do script ${JSON.stringify(fullCommand)}`;
-  const command = Command.create('osascript', ['-e', osaScript]);
-  await command.execute();
+export async function runScriptInTerminal(scriptPath: string, args: string = ''): Promise<void> {
+  await invoke('run_in_terminal', { scriptId: scriptPath, args: splitArgs(args) });
 }
 
 export async function revealInFinder(scriptPath: string): Promise<void> {
@@ -318,11 +315,10 @@ export async function openInEditor(scriptPath: string, editorApp?: string): Prom
 }
 
 export async function cancelScript(scriptPath: string): Promise<boolean> {
-  const child = runningProcesses.get(scriptPath);
-  if (child) {
+  const runId = runningRuns.get(scriptPath);
+  if (runId) {
     try {
-      await child.kill();
-      runningProcesses.delete(scriptPath);
+      await invoke('cancel_script', { runId });
       return true;
     } catch {
       return false;
@@ -332,7 +328,7 @@ export async function cancelScript(scriptPath: string): Promise<boolean> {
 }
 
 export function isScriptRunning(scriptPath: string): boolean {
-  return runningProcesses.has(scriptPath);
+  return runningRuns.has(scriptPath);
 }
 
 export function formatTimestamp(isoString: string | null): string {
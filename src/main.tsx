@@ -1,10 +1,14 @@
 import React from 'react';
 import ReactDOM from 'react-dom/client';
 import App from './App';
+import { RunWindow } from './components/RunWindow';
 import './index.css';
 
+// Run windows load this page with ?runWindow=<run id>, see runwindows.rs
+const runWindow = new URLSearchParams(window.location.search).get('runWindow');
+
 ReactDOM.createRoot(document.getElementById('root')!).render(
   <React.StrictMode>
-    <App />
+    {runWindow ? <RunWindow runId={runWindow} /> : <App />}
   </React.StrictMode>
 );